@@ -1,7 +1,7 @@
 #version 150 core
 
-in vec4 a_Pos;
-in vec3 a_Color;
+in vec2 a_Pos;
+in vec4 a_Color;
 
  uniform Transform {
 
@@ -14,6 +14,6 @@ in vec3 a_Color;
 out vec4 v_Color;
 
 void main() {
-    v_Color = vec4(a_Color, 1.0);
-    gl_Position = model_Transform * view_Transform * projection_Transform * a_Pos;
+    v_Color = a_Color;
+    gl_Position = projection_Transform * view_Transform * model_Transform * vec4(a_Pos, 0.0, 1.0);
 }
\ No newline at end of file
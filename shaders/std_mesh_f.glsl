@@ -8,10 +8,6 @@
 
     void main() {
 
-        vec4 tex = texture(t_Texture, v_Uv);
-
-        float blend = dot(v_Uv-vec2(0.5,0.5), v_Uv-vec2(0.5,0.5));
-
-        Target0 = mix(tex, vec4(0.0,0.0,0.0,0.0), blend*1.0);
+        Target0 = texture(t_Texture, v_Uv);
 
     }
\ No newline at end of file
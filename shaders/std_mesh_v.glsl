@@ -18,6 +18,6 @@
 
         v_Uv = a_Uv;
 
-        gl_Position = model_Transform * view_Transform * projection_Transform * vec4(a_Pos, 1.0);
+        gl_Position = projection_Transform * view_Transform * model_Transform * vec4(a_Pos, 1.0);
 
     }
\ No newline at end of file
@@ -0,0 +1,31 @@
+#version 150 core
+
+uniform sampler2D t_Input;
+uniform sampler2D t_Secondary;
+
+uniform Post {
+
+    vec4 u_Param0;
+    vec4 u_Param1;
+    vec4 u_Param2;
+    vec4 u_Param3;
+    vec2 u_Resolution;
+    float u_Time;
+    float u_Padding;
+
+};
+
+in vec2 v_Uv;
+out vec4 Target0;
+
+// u_Param0: direction in pixels between taps. A 9 tap gaussian along that direction.
+void main() {
+    float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+    vec2 offset = u_Param0.xy / u_Resolution;
+    vec4 color = texture(t_Input, v_Uv) * weights[0];
+    for (int i = 1; i < 5; i++) {
+        color += texture(t_Input, v_Uv + offset * float(i)) * weights[i];
+        color += texture(t_Input, v_Uv - offset * float(i)) * weights[i];
+    }
+    Target0 = color;
+}
@@ -0,0 +1,25 @@
+#version 150 core
+
+uniform sampler2D t_Input;
+uniform sampler2D t_Secondary;
+
+uniform Post {
+
+    vec4 u_Param0;
+    vec4 u_Param1;
+    vec4 u_Param2;
+    vec4 u_Param3;
+    vec2 u_Resolution;
+    float u_Time;
+    float u_Padding;
+
+};
+
+in vec2 v_Uv;
+out vec4 Target0;
+
+// t_Secondary: the base image. u_Param0: intensity of the input added on top.
+void main() {
+    vec4 base = texture(t_Secondary, v_Uv);
+    Target0 = vec4(base.rgb + texture(t_Input, v_Uv).rgb * u_Param0.x, base.a);
+}
@@ -0,0 +1,23 @@
+#version 150 core
+
+uniform sampler2D t_Input;
+uniform sampler2D t_Secondary;
+
+uniform Post {
+
+    vec4 u_Param0;
+    vec4 u_Param1;
+    vec4 u_Param2;
+    vec4 u_Param3;
+    vec2 u_Resolution;
+    float u_Time;
+    float u_Padding;
+
+};
+
+in vec2 v_Uv;
+out vec4 Target0;
+
+void main() {
+    Target0 = texture(t_Input, v_Uv);
+}
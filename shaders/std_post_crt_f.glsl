@@ -0,0 +1,34 @@
+#version 150 core
+
+uniform sampler2D t_Input;
+uniform sampler2D t_Secondary;
+
+uniform Post {
+
+    vec4 u_Param0;
+    vec4 u_Param1;
+    vec4 u_Param2;
+    vec4 u_Param3;
+    vec2 u_Resolution;
+    float u_Time;
+    float u_Padding;
+
+};
+
+in vec2 v_Uv;
+out vec4 Target0;
+
+// u_Param0: scanline strength, curvature, vignette.
+void main() {
+    vec2 centered = v_Uv * 2.0 - 1.0;
+    centered += centered * dot(centered, centered) * u_Param0.y;
+    if (abs(centered.x) > 1.0 || abs(centered.y) > 1.0) {
+        Target0 = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+    vec2 uv = centered * 0.5 + 0.5;
+    vec4 color = texture(t_Input, uv);
+    float scanline = 0.5 + 0.5 * sin(uv.y * u_Resolution.y * 3.14159265);
+    float shade = mix(1.0, scanline, u_Param0.x) * (1.0 - u_Param0.z * dot(centered, centered) * 0.5);
+    Target0 = vec4(color.rgb * shade, color.a);
+}
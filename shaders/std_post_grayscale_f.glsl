@@ -0,0 +1,26 @@
+#version 150 core
+
+uniform sampler2D t_Input;
+uniform sampler2D t_Secondary;
+
+uniform Post {
+
+    vec4 u_Param0;
+    vec4 u_Param1;
+    vec4 u_Param2;
+    vec4 u_Param3;
+    vec2 u_Resolution;
+    float u_Time;
+    float u_Padding;
+
+};
+
+in vec2 v_Uv;
+out vec4 Target0;
+
+// u_Param0: amount.
+void main() {
+    vec4 color = texture(t_Input, v_Uv);
+    float luma = dot(color.rgb, vec3(0.299, 0.587, 0.114));
+    Target0 = vec4(mix(color.rgb, vec3(luma), u_Param0.x), color.a);
+}
@@ -0,0 +1,39 @@
+#version 150 core
+
+uniform sampler2D t_Input;
+uniform sampler2D t_Secondary;
+
+uniform Post {
+
+    vec4 u_Param0;
+    vec4 u_Param1;
+    vec4 u_Param2;
+    vec4 u_Param3;
+    vec2 u_Resolution;
+    float u_Time;
+    float u_Padding;
+
+};
+
+in vec2 v_Uv;
+out vec4 Target0;
+
+// t_Secondary: a LUT strip of N slices of NxN, with red along x, green along y and blue across the slices.
+// u_Param0: amount, N.
+vec3 lookup(vec3 color) {
+    float n = u_Param0.y;
+    float slice = color.b * (n - 1.0);
+    float s0 = floor(slice);
+    float s1 = min(s0 + 1.0, n - 1.0);
+    float u = color.r * (n - 1.0) + 0.5;
+    float v = (color.g * (n - 1.0) + 0.5) / n;
+    vec3 a = texture(t_Secondary, vec2((s0 * n + u) / (n * n), v)).rgb;
+    vec3 b = texture(t_Secondary, vec2((s1 * n + u) / (n * n), v)).rgb;
+    return mix(a, b, slice - s0);
+}
+
+void main() {
+    vec4 color = texture(t_Input, v_Uv);
+    vec3 graded = lookup(clamp(color.rgb, 0.0, 1.0));
+    Target0 = vec4(mix(color.rgb, graded, u_Param0.x), color.a);
+}
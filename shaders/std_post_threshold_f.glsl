@@ -0,0 +1,27 @@
+#version 150 core
+
+uniform sampler2D t_Input;
+uniform sampler2D t_Secondary;
+
+uniform Post {
+
+    vec4 u_Param0;
+    vec4 u_Param1;
+    vec4 u_Param2;
+    vec4 u_Param3;
+    vec2 u_Resolution;
+    float u_Time;
+    float u_Padding;
+
+};
+
+in vec2 v_Uv;
+out vec4 Target0;
+
+// u_Param0: threshold. Keeps only the part of each colour that is brighter than the threshold.
+void main() {
+    vec4 color = texture(t_Input, v_Uv);
+    float brightness = max(color.r, max(color.g, color.b));
+    float keep = max(brightness - u_Param0.x, 0.0) / max(brightness, 0.0001);
+    Target0 = vec4(color.rgb * keep, 1.0);
+}
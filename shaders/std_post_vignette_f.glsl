@@ -0,0 +1,27 @@
+#version 150 core
+
+uniform sampler2D t_Input;
+uniform sampler2D t_Secondary;
+
+uniform Post {
+
+    vec4 u_Param0;
+    vec4 u_Param1;
+    vec4 u_Param2;
+    vec4 u_Param3;
+    vec2 u_Resolution;
+    float u_Time;
+    float u_Padding;
+
+};
+
+in vec2 v_Uv;
+out vec4 Target0;
+
+// u_Param0: strength, radius, softness.
+void main() {
+    vec4 color = texture(t_Input, v_Uv);
+    float d = distance(v_Uv, vec2(0.5));
+    float vignette = smoothstep(u_Param0.y, u_Param0.y + u_Param0.z, d) * u_Param0.x;
+    Target0 = vec4(color.rgb * (1.0 - vignette), color.a);
+}
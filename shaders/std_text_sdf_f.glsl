@@ -0,0 +1,43 @@
+#version 150 core
+
+uniform sampler2D t_Texture;
+
+uniform Effects {
+
+    vec4 u_Param0;
+    vec4 u_Param1;
+    vec4 u_Param2;
+    vec4 u_Param3;
+
+};
+
+in vec2 v_Uv;
+in vec4 v_Color;
+out vec4 Target0;
+
+// Puts a colour with straight alpha over another.
+vec4 over(vec4 top, vec4 bottom) {
+    float a = top.a + bottom.a * (1.0 - top.a);
+    if (a <= 0.0) {
+        return vec4(0.0);
+    }
+    return vec4((top.rgb * top.a + bottom.rgb * bottom.a * (1.0 - top.a)) / a, a);
+}
+
+// u_Param0: outline colour. u_Param1: glow colour.
+// u_Param2: outline width, glow width, distance range and softness in texels. u_Param3.x: ignores the texture colour.
+void main() {
+    vec4 texel = texture(t_Texture, v_Uv);
+    // The distance to the edge in texels, positive inside the glyph.
+    float d = (texel.a - 0.5) * u_Param2.z;
+    float width = max(length(vec2(dFdx(d), dFdy(d))), 0.0001) + u_Param2.w;
+
+    vec4 fill = v_Color * vec4(mix(texel.rgb, vec3(1.0), u_Param3.x), 1.0);
+    fill.a *= clamp(d / width + 0.5, 0.0, 1.0);
+    vec4 outline = u_Param0;
+    outline.a *= clamp((d + u_Param2.x) / width + 0.5, 0.0, 1.0);
+    vec4 glow = u_Param1;
+    glow.a *= u_Param2.y > 0.0 ? pow(clamp(1.0 + (d + u_Param2.x) / u_Param2.y, 0.0, 1.0), 2.0) : 0.0;
+
+    Target0 = over(over(fill, outline), glow);
+}
\ No newline at end of file
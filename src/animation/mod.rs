@@ -0,0 +1,474 @@
+use super::*;
+
+use self::atlas::{AtlasRegion, TextureAtlas};
+use self::node::*;
+use self::render::Sprite;
+use std::collections::HashMap;
+
+/**
+What a clip does when it reaches its last frame.
+*/
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PlayMode {
+
+    /**
+    Starts again from the first frame.
+    */
+    Loop,
+    /**
+    Plays backwards to the first frame, then forwards again.
+    */
+    PingPong,
+    /**
+    Stops on the last frame.
+    */
+    Once,
+
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationFrame {
+
+    pub region: AtlasRegion,
+    /**
+    Seconds the frame is shown for at a speed of 1.
+    */
+    pub duration: f32,
+    /**
+    Reported by `Animator::update` whenever the frame is entered.
+    */
+    pub event: Option<String>,
+    /**
+    The origin of the sprite while the frame is shown, for frames that are trimmed or have their own pivot. The origin is left alone if None.
+    */
+    pub origin: Option<Vector2f>,
+
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+
+    pub name: String,
+    pub frames: Vec<AnimationFrame>,
+    pub mode: PlayMode,
+
+}
+
+impl AnimationClip {
+
+    pub fn new(name: &str, mode: PlayMode) -> AnimationClip {
+
+        return AnimationClip { name: name.to_string(), frames: Vec::new(), mode };
+
+    }
+
+    /**
+    A clip showing the regions in order, each for the same duration.
+    */
+    pub fn from_regions(name: &str, regions: &[AtlasRegion], frame_duration: f32, mode: PlayMode) -> AnimationClip {
+
+        let mut clip = AnimationClip::new(name, mode);
+        for region in regions {
+            clip.add_frame(*region, frame_duration);
+        }
+        return clip;
+
+    }
+
+    /**
+    A clip showing the named textures of an atlas in order, each for the same duration.
+    */
+    pub fn from_atlas(name: &str, atlas: &TextureAtlas, textures: &[&str], frame_duration: f32, mode: PlayMode) -> error::Result<AnimationClip> {
+
+        let mut clip = AnimationClip::new(name, mode);
+        for texture in textures {
+            match atlas.get(texture) {
+                Some(region) => clip.add_frame(region, frame_duration),
+                None => return Err(Error::Texture(format!("The atlas has no texture named {}.", texture))),
+            }
+        }
+        return Ok(clip);
+
+    }
+
+    pub fn add_frame(&mut self, region: AtlasRegion, duration: f32) {
+
+        self.frames.push(AnimationFrame { region, duration, event: None, origin: None });
+
+    }
+
+    /**
+    Names the event reported when the frame is entered. Does nothing if there is no such frame.
+    */
+    pub fn set_event(&mut self, frame: usize, event: &str) {
+
+        if let Some(frame) = self.frames.get_mut(frame) {
+            frame.event = Some(event.to_string());
+        }
+
+    }
+
+    /**
+    The time it takes to play every frame once, at a speed of 1.
+    */
+    pub fn get_duration(&self) -> f32 {
+
+        return self.frames.iter().map(|f| f.duration.max(0.0)).sum();
+
+    }
+
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum AnimationEvent {
+
+    /**
+    A frame with an event was entered.
+    */
+    Frame { clip: String, frame: usize, event: String },
+    /**
+    A clip played with `PlayMode::Once` reached the end of its last frame.
+    */
+    Finished { clip: String },
+
+}
+
+/**
+Plays named clips, advanced with the delta time of the game loop. It only decides which frame is shown, see `AnimatedSprite` for drawing it.
+*/
+pub struct Animator {
+
+    clips: HashMap<String, AnimationClip>,
+    current: Option<String>,
+    frame: usize,
+    /**
+    Time spent on the current frame.
+    */
+    time: f32,
+    /**
+    1 when playing forwards, -1 when a ping-pong clip plays backwards.
+    */
+    direction: i32,
+    playing: bool,
+    finished: bool,
+    /**
+    Multiplies the delta time. Negative speeds are treated as 0.
+    */
+    pub speed: f32,
+    events: Vec<AnimationEvent>,
+
+}
+
+impl Animator {
+
+    pub fn new() -> Animator {
+
+        return Animator { clips: HashMap::new(), current: None, frame: 0, time: 0.0, direction: 1, playing: false, finished: false, speed: 1.0, events: Vec::new() };
+
+    }
+
+    /**
+    Adds a clip, replacing any clip with the same name.
+    */
+    pub fn add_clip(&mut self, clip: AnimationClip) {
+
+        self.clips.insert(clip.name.clone(), clip);
+
+    }
+
+    pub fn get_clip(&self, name: &str) -> Option<&AnimationClip> {
+        return self.clips.get(name);
+    }
+
+    /**
+    Plays a clip from its first frame, unless it is already playing. Returns false if there is no clip with the name.
+    */
+    pub fn play(&mut self, name: &str) -> bool {
+
+        if self.playing && self.current.as_ref().map(|c| c == name).unwrap_or(false) {
+            return true;
+        }
+        return self.restart(name);
+
+    }
+
+    /**
+    Plays a clip from its first frame, even if it is already playing.
+    */
+    pub fn restart(&mut self, name: &str) -> bool {
+
+        if !self.clips.contains_key(name) {
+            return false;
+        }
+
+        self.current = Some(name.to_string());
+        self.frame = 0;
+        self.time = 0.0;
+        self.direction = 1;
+        self.playing = true;
+        self.finished = false;
+        self.enter_frame();
+        return true;
+
+    }
+
+    /**
+    Stops advancing, keeping the current frame.
+    */
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn resume(&mut self) {
+        if self.current.is_some() && !self.finished {
+            self.playing = true;
+        }
+    }
+
+    /**
+    Stops and goes back to the first frame of the clip, which `resume` plays again even if it had finished.
+    */
+    pub fn stop(&mut self) {
+
+        self.playing = false;
+        self.finished = false;
+        self.frame = 0;
+        self.time = 0.0;
+        self.direction = 1;
+
+    }
+
+    pub fn is_playing(&self) -> bool {
+        return self.playing;
+    }
+
+    /**
+    Whether a clip played with `PlayMode::Once` has reached its end.
+    */
+    pub fn is_finished(&self) -> bool {
+        return self.finished;
+    }
+
+    pub fn get_clip_name(&self) -> Option<&str> {
+        return self.current.as_ref().map(|c| c.as_str());
+    }
+
+    pub fn get_frame_index(&self) -> usize {
+        return self.frame;
+    }
+
+    pub fn get_frame(&self) -> Option<&AnimationFrame> {
+        return self.current.as_ref().and_then(|c| self.clips.get(c)).and_then(|c| c.frames.get(self.frame));
+    }
+
+    pub fn get_region(&self) -> Option<AtlasRegion> {
+        return self.get_frame().map(|f| f.region);
+    }
+
+    /**
+    Advances the current clip, returning the events of the frames that were entered (including the first frame of a clip started since the last update), in order.
+    Several frames may be passed in one update if the delta time is long.
+    */
+    pub fn update(&mut self, dt: f32) -> Vec<AnimationEvent> {
+
+        if self.playing {
+
+            let duration = self.current.as_ref().and_then(|c| self.clips.get(c)).map(|c| c.get_duration()).unwrap_or(0.0);
+
+            // A clip without any duration would never leave the loop below.
+            if duration > 0.0 {
+                self.time += dt * self.speed.max(0.0);
+                while self.playing && self.time >= self.get_frame().map(|f| f.duration.max(0.0)).unwrap_or(0.0) {
+                    self.time -= self.get_frame().map(|f| f.duration.max(0.0)).unwrap_or(0.0);
+                    self.advance();
+                }
+            }
+
+        }
+
+        return self.events.drain(..).collect();
+
+    }
+
+    fn advance(&mut self) {
+
+        let (count, mode, name) = match self.current.as_ref().and_then(|c| self.clips.get(c)) {
+            Some(clip) => (clip.frames.len(), clip.mode, clip.name.clone()),
+            None => return,
+        };
+
+        match mode {
+            PlayMode::Loop => self.frame = (self.frame + 1) % count.max(1),
+            PlayMode::Once => {
+                if self.frame + 1 < count {
+                    self.frame += 1;
+                } else {
+                    self.playing = false;
+                    self.finished = true;
+                    self.time = 0.0;
+                    self.events.push(AnimationEvent::Finished { clip: name });
+                    return;
+                }
+            },
+            PlayMode::PingPong => {
+                if count > 1 {
+                    let next = self.frame as i32 + self.direction;
+                    if next < 0 || next >= count as i32 {
+                        self.direction = -self.direction;
+                    }
+                    self.frame = (self.frame as i32 + self.direction) as usize;
+                }
+            },
+        }
+
+        self.enter_frame();
+
+    }
+
+    fn enter_frame(&mut self) {
+
+        let event = match self.get_frame().and_then(|f| f.event.clone()) {
+            Some(event) => event,
+            None => return,
+        };
+        let clip = self.current.clone().unwrap();
+        self.events.push(AnimationEvent::Frame { clip, frame: self.frame, event });
+
+    }
+
+}
+
+/**
+A sprite showing the frames of an animator. All the regions of its clips have to come from the atlas it was created with, which `add_clip` checks.
+Frames on other pages, of clips added to the animator directly, are not shown.
+*/
+pub struct AnimatedSprite {
+
+    pub sprite: Sprite,
+    pub animator: Animator,
+    /**
+    The texture of each atlas page.
+    */
+    pages: Vec<core::TextureId>,
+    shown: Option<(AtlasRegion, Option<Vector2f>)>,
+
+}
+
+impl AnimatedSprite {
+
+    /**
+    The atlas has to be uploaded first.
+    */
+    pub fn new(atlas: &TextureAtlas) -> error::Result<AnimatedSprite> {
+
+        let mut pages: Vec<core::TextureId> = Vec::new();
+        for page in 0..atlas.get_page_count() {
+            match atlas.get_texture(page) {
+                Some(texture) => pages.push(texture),
+                None => return Err(Error::Texture(format!("Page {} of the atlas has not been uploaded.", page))),
+            }
+        }
+
+        return Ok(AnimatedSprite { sprite: Sprite::new(), animator: Animator::new(), pages, shown: None });
+
+    }
+
+    /**
+    Adds a clip to the animator, unless one of its frames is on an atlas page the sprite was not created with.
+    */
+    pub fn add_clip(&mut self, clip: AnimationClip) -> error::Result<()> {
+
+        if let Some(frame) = clip.frames.iter().find(|f| f.region.page >= self.pages.len()) {
+            return Err(Error::Texture(format!("A frame of the clip {} is on atlas page {}, but the sprite was created with {} pages.", clip.name, frame.region.page, self.pages.len())));
+        }
+        self.animator.add_clip(clip);
+        return Ok(());
+
+    }
+
+    /**
+    See `Animator::play`.
+    */
+    pub fn play(&mut self, name: &str) -> bool {
+
+        let found = self.animator.play(name);
+        self.show_frame();
+        return found;
+
+    }
+
+    /**
+    Advances the animation and shows the new frame. See `Animator::update` for the events.
+    */
+    pub fn update(&mut self, dt: f32) -> Vec<AnimationEvent> {
+
+        let events = self.animator.update(dt);
+        self.show_frame();
+        return events;
+
+    }
+
+    fn show_frame(&mut self) {
+
+        let (region, origin) = match self.animator.get_frame() {
+            Some(frame) => (frame.region, frame.origin),
+            None => return,
+        };
+
+        if self.shown != Some((region, origin)) {
+            match self.pages.get(region.page) {
+                Some(&texture) => self.sprite.set_region(texture, &region),
+                None => return,
+            }
+            if let Some(origin) = origin {
+                self.sprite.set_origin(origin);
+            }
+            self.shown = Some((region, origin));
+        }
+
+    }
+
+}
+
+impl Node2D for AnimatedSprite {
+
+    fn get_node_obj_mut(&mut self) -> &mut NodeObject2D {
+        return &mut self.sprite.node;
+    }
+
+    fn get_node_obj(&self) -> &NodeObject2D {
+        return &self.sprite.node;
+    }
+
+}
+
+impl SizedNode2D for AnimatedSprite {
+
+    fn get_fixed_size(&self) -> Vector2f {
+        return self.sprite.get_fixed_size();
+    }
+
+}
+
+impl core::Drawable for AnimatedSprite {
+
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        self.show_frame();
+        return core::Drawable::load(&mut self.sprite, engine);
+
+    }
+
+    fn render(&mut self, engine: &mut core::FlatEngine) {
+
+        core::Drawable::render(&mut self.sprite, engine);
+
+    }
+
+    fn destroy(&mut self, engine: &mut core::FlatEngine) {
+
+        core::Drawable::destroy(&mut self.sprite, engine);
+
+    }
+
+}
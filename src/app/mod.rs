@@ -0,0 +1,267 @@
+use super::*;
+
+use std::time::Instant;
+
+/**
+A game driven by `Runner`. Logic that must be deterministic (physics, movement) goes in `fixed_update`, which is always called with the same step.
+*/
+pub trait App {
+
+    /**
+    Called for every window event before the frame is updated.
+    */
+    fn handle_event(&mut self, _event: &glutin::Event, _engine: &mut core::FlatEngine) {}
+
+    /**
+    Called zero or more times per frame, each time with the fixed step of the runner.
+    */
+    fn fixed_update(&mut self, _dt: f32, _engine: &mut core::FlatEngine) {}
+
+    /**
+    Called once per frame with the real time since the last frame, after the fixed updates.
+    */
+    fn update(&mut self, _dt: f32, _engine: &mut core::FlatEngine) {}
+
+    /**
+    Draws the frame. `alpha` is how far (0 to 1) the current time is between the last fixed update and the next one, used to interpolate between the previous and current state.
+    The runner swaps the buffers afterwards.
+    */
+    fn render(&mut self, alpha: f32, engine: &mut core::FlatEngine);
+
+    /**
+    Called about once per second with the statistics of the last second.
+    */
+    fn stats_updated(&mut self, _stats: &FrameStats) {}
+
+    /**
+    The runner stops once this returns false, or when the window is closed.
+    */
+    fn is_running(&self) -> bool {
+        return true;
+    }
+
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct RunnerSettings {
+
+    /**
+    The time in seconds between fixed updates.
+    */
+    pub fixed_step: f64,
+    /**
+    Frames longer than this (in seconds) are treated as this long, so a stall (like dragging the window) does not have to be caught up.
+    */
+    pub max_frame_time: f64,
+    /**
+    The most fixed updates run in one frame. Time that would need more is dropped, otherwise slow updates would fall further behind every frame.
+    */
+    pub max_fixed_updates: u32,
+
+}
+
+impl RunnerSettings {
+
+    pub fn new() -> RunnerSettings {
+
+        return RunnerSettings { fixed_step: 1.0 / 60.0, max_frame_time: 0.25, max_fixed_updates: 5 };
+
+    }
+
+    pub fn with_fixed_rate(rate: f64) -> RunnerSettings {
+
+        let mut settings = RunnerSettings::new();
+        settings.fixed_step = 1.0 / rate;
+        return settings;
+
+    }
+
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct FrameStats {
+
+    pub fps: f32,
+    /**
+    Average, shortest and longest frame time over the last second, in milliseconds.
+    */
+    pub frame_time: f32,
+    pub min_frame_time: f32,
+    pub max_frame_time: f32,
+    /**
+    Totals since the runner was created.
+    */
+    pub frames: u64,
+    pub fixed_updates: u64,
+    /**
+    Time in seconds that was not simulated because a frame hit `max_frame_time` or `max_fixed_updates`.
+    */
+    pub dropped_time: f64,
+
+}
+
+impl FrameStats {
+
+    pub fn new() -> FrameStats {
+
+        return FrameStats { fps: 0.0, frame_time: 0.0, min_frame_time: 0.0, max_frame_time: 0.0, frames: 0, fixed_updates: 0, dropped_time: 0.0 };
+
+    }
+
+}
+
+/**
+Owns the game loop: polls events, runs fixed updates from an accumulator, then updates and renders once per frame.
+*/
+pub struct Runner {
+
+    pub settings: RunnerSettings,
+    accumulator: f64,
+    running: bool,
+    stats: FrameStats,
+    // The frames of the current one second window the stats are computed from.
+    window_time: f64,
+    window_frames: u32,
+    window_min: f64,
+    window_max: f64,
+
+}
+
+impl Runner {
+
+    pub fn new(settings: RunnerSettings) -> Runner {
+
+        return Runner {
+            settings,
+            accumulator: 0.0,
+            running: true,
+            stats: FrameStats::new(),
+            window_time: 0.0,
+            window_frames: 0,
+            window_min: ::std::f64::MAX,
+            window_max: 0.0,
+        };
+
+    }
+
+    /**
+    Runs frames until the app stops or the window is closed, and returns the final statistics.
+    */
+    pub fn run(&mut self, engine: &mut core::FlatEngine, app: &mut App) -> FrameStats {
+
+        self.running = true;
+        let mut last = Instant::now();
+
+        while self.running && app.is_running() {
+
+            let now = Instant::now();
+            let elapsed = now.duration_since(last);
+            last = now;
+
+            self.step(engine, app, elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000.0);
+
+        }
+
+        return self.stats;
+
+    }
+
+    /**
+    Runs a single frame that took `frame_time` seconds. Exposed so that the loop can be driven with a fake clock.
+    */
+    pub fn step(&mut self, engine: &mut core::FlatEngine, app: &mut App, frame_time: f64) {
+
+        // The events are collected first as the app needs the engine, which polling borrows.
+        let mut events: Vec<glutin::Event> = Vec::new();
+        engine.poll_events(|event| events.push(event.clone()));
+
+        for event in events.iter() {
+            app.handle_event(event, engine);
+            if let glutin::Event::WindowEvent { event: glutin::WindowEvent::CloseRequested, .. } = *event {
+                self.running = false;
+            }
+        }
+
+        if frame_time > self.settings.max_frame_time {
+            self.stats.dropped_time += frame_time - self.settings.max_frame_time;
+        }
+        self.accumulator += frame_time.min(self.settings.max_frame_time);
+
+        let step = self.settings.fixed_step;
+        let mut updates = 0;
+        while self.accumulator >= step && updates < self.settings.max_fixed_updates {
+            app.fixed_update(step as f32, engine);
+            self.accumulator -= step;
+            updates += 1;
+        }
+        self.stats.fixed_updates += updates as u64;
+
+        // Spiral of death: drop the whole steps that did not fit in this frame, keeping the remainder for interpolation.
+        if self.accumulator >= step {
+            let remainder = self.accumulator % step;
+            self.stats.dropped_time += self.accumulator - remainder;
+            self.accumulator = remainder;
+        }
+
+        app.update(frame_time as f32, engine);
+        app.render(self.get_alpha(), engine);
+        engine.swap_buffers();
+
+        self.record_frame(frame_time, app);
+
+    }
+
+    fn record_frame(&mut self, frame_time: f64, app: &mut App) {
+
+        self.stats.frames += 1;
+        self.window_frames += 1;
+        self.window_time += frame_time;
+        self.window_min = self.window_min.min(frame_time);
+        self.window_max = self.window_max.max(frame_time);
+
+        if self.window_time >= 1.0 {
+
+            self.stats.fps = (self.window_frames as f64 / self.window_time) as f32;
+            self.stats.frame_time = (self.window_time / self.window_frames as f64 * 1000.0) as f32;
+            self.stats.min_frame_time = (self.window_min * 1000.0) as f32;
+            self.stats.max_frame_time = (self.window_max * 1000.0) as f32;
+
+            self.window_time = 0.0;
+            self.window_frames = 0;
+            self.window_min = ::std::f64::MAX;
+            self.window_max = 0.0;
+
+            app.stats_updated(&self.stats);
+
+        }
+
+    }
+
+    /**
+    How far the accumulated time is into the next fixed step, from 0 to 1.
+    */
+    pub fn get_alpha(&self) -> f32 {
+        return (self.accumulator / self.settings.fixed_step) as f32;
+    }
+
+    pub fn get_stats(&self) -> FrameStats {
+        return self.stats;
+    }
+
+    /**
+    Stops the loop after the current frame.
+    */
+    pub fn quit(&mut self) {
+        self.running = false;
+    }
+
+}
+
+/**
+Runs the app with the default settings (60 fixed updates per second) until it stops or the window is closed.
+*/
+pub fn run(engine: &mut core::FlatEngine, app: &mut App) -> FrameStats {
+
+    return Runner::new(RunnerSettings::new()).run(engine, app);
+
+}
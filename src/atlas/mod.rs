@@ -0,0 +1,421 @@
+use super::*;
+
+use self::render::{Sprite, Texture};
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Debug)]
+struct SkylineSegment {
+
+    x: u32,
+    y: u32,
+    width: u32,
+
+}
+
+/**
+Packs rectangles into a fixed size area with the skyline bottom-left algorithm.
+Positions have their origin at the top left. Only the outline of the used area is tracked, so packing is fast but space under overhangs is never reused.
+*/
+pub struct RectPacker {
+
+    width: u32,
+    height: u32,
+    skyline: Vec<SkylineSegment>,
+    used_area: u64,
+
+}
+
+impl RectPacker {
+
+    pub fn new(width: u32, height: u32) -> RectPacker {
+
+        return RectPacker { width, height, skyline: vec![SkylineSegment { x: 0, y: 0, width }], used_area: 0 };
+
+    }
+
+    pub fn get_size(&self) -> Vector2u {
+        return Vector2u::new(self.width, self.height);
+    }
+
+    /**
+    The fraction of the area that has been packed, from 0 to 1.
+    */
+    pub fn get_occupancy(&self) -> f32 {
+        return (self.used_area as f64 / (self.width as f64 * self.height as f64).max(1.0)) as f32;
+    }
+
+    pub fn clear(&mut self) {
+
+        self.skyline = vec![SkylineSegment { x: 0, y: 0, width: self.width }];
+        self.used_area = 0;
+
+    }
+
+    /**
+    Finds room for a rectangle, returning the position of its top left corner, or None if it does not fit.
+    */
+    pub fn pack(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+
+        if width == 0 || height == 0 {
+            return Some((0, 0));
+        }
+
+        // Choose the lowest position, and the narrowest segment among equally low ones to waste less space.
+        let mut best: Option<(usize, u32)> = None;
+        for i in 0..self.skyline.len() {
+            if let Some(y) = self.fit(i, width, height) {
+                let better = match best {
+                    Some((best_i, best_y)) => y < best_y || (y == best_y && self.skyline[i].width < self.skyline[best_i].width),
+                    None => true,
+                };
+                if better {
+                    best = Some((i, y));
+                }
+            }
+        }
+
+        let (index, y) = best?;
+        let x = self.skyline[index].x;
+        self.place(index, x, y, width, height);
+        self.used_area += width as u64 * height as u64;
+        return Some((x, y));
+
+    }
+
+    /**
+    The y a rectangle would be placed at if its left edge is at the start of the segment.
+    */
+    fn fit(&self, index: usize, width: u32, height: u32) -> Option<u32> {
+
+        let x = self.skyline[index].x;
+        if x + width > self.width {
+            return None;
+        }
+
+        let mut y = 0;
+        let mut remaining = width as i64;
+        let mut i = index;
+        while remaining > 0 {
+            let segment = self.skyline.get(i)?;
+            y = y.max(segment.y);
+            if y + height > self.height {
+                return None;
+            }
+            remaining -= segment.width as i64;
+            i += 1;
+        }
+        return Some(y);
+
+    }
+
+    fn place(&mut self, index: usize, x: u32, y: u32, width: u32, height: u32) {
+
+        self.skyline.insert(index, SkylineSegment { x, y: y + height, width });
+
+        // Cut the segments that are now covered by the new one.
+        let end = x + width;
+        let i = index + 1;
+        while i < self.skyline.len() && self.skyline[i].x < end {
+            let overlap = end - self.skyline[i].x;
+            if self.skyline[i].width <= overlap {
+                self.skyline.remove(i);
+            } else {
+                self.skyline[i].x += overlap;
+                self.skyline[i].width -= overlap;
+                break;
+            }
+        }
+
+        // Merge neighbours of the same height.
+        let mut i = 0;
+        while i + 1 < self.skyline.len() {
+            if self.skyline[i].y == self.skyline[i + 1].y {
+                self.skyline[i].width += self.skyline[i + 1].width;
+                self.skyline.remove(i + 1);
+            } else {
+                i += 1;
+            }
+        }
+
+    }
+
+}
+
+/**
+A packed texture within an atlas.
+*/
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AtlasRegion {
+
+    /**
+    The index of the page the texture was packed into.
+    */
+    pub page: usize,
+    /**
+    The area of the page in pixels, from the top left.
+    */
+    pub rect: Rect,
+    /**
+    The same area in texture coordinates, as used by `Sprite::uv_rect`.
+    */
+    pub uv: Rect,
+
+}
+
+struct AtlasPage {
+
+    texture: Texture,
+    packer: RectPacker,
+    /**
+    Created by `upload`.
+    */
+    texture_id: Option<core::TextureId>,
+    dirty: bool,
+
+}
+
+/**
+Packs many textures into a few large pages, so that sprites using them share textures and can be batched.
+Textures are packed on the CPU as they are added, and `upload` sends the pages that changed to the renderer.
+*/
+pub struct TextureAtlas {
+
+    page_size: Vector2u,
+    /**
+    Pixels around each texture, filled with copies of its edges so that filtering does not bleed in neighbouring textures.
+    */
+    padding: u32,
+    pages: Vec<AtlasPage>,
+    regions: HashMap<String, AtlasRegion>,
+
+}
+
+impl TextureAtlas {
+
+    pub fn new(page_size: Vector2u, padding: u32) -> TextureAtlas {
+
+        return TextureAtlas { page_size, padding, pages: Vec::new(), regions: HashMap::new() };
+
+    }
+
+    /**
+    Packs a texture under a name, starting a new page if it does not fit in any of the existing ones.
+    Adding a texture under a name that is already used packs it again and replaces the old region.
+    */
+    pub fn add(&mut self, name: &str, texture: &Texture) -> error::Result<AtlasRegion> {
+
+        let width = texture.dimensions.x as u32;
+        let height = texture.dimensions.y as u32;
+        let padded = (width + self.padding * 2, height + self.padding * 2);
+
+        if padded.0 > self.page_size.x || padded.1 > self.page_size.y || self.page_size.x > u16::max_value() as u32 || self.page_size.y > u16::max_value() as u32 {
+            return Err(Error::Texture(format!("The texture {} ({}x{} with padding) does not fit into an atlas page of {}x{}.", name, padded.0, padded.1, self.page_size.x, self.page_size.y)));
+        }
+
+        let mut found: Option<(usize, (u32, u32))> = None;
+        for (i, page) in self.pages.iter_mut().enumerate() {
+            if let Some(pos) = page.packer.pack(padded.0, padded.1) {
+                found = Some((i, pos));
+                break;
+            }
+        }
+
+        let (page, pos) = match found {
+            Some(found) => found,
+            None => {
+                let mut packer = RectPacker::new(self.page_size.x, self.page_size.y);
+                let pos = packer.pack(padded.0, padded.1).unwrap();
+                let data = vec![0; self.page_size.x as usize * self.page_size.y as usize * 4];
+                self.pages.push(AtlasPage { texture: Texture::from_data(&data, self.page_size.x as u16, self.page_size.y as u16), packer, texture_id: None, dirty: true });
+                (self.pages.len() - 1, pos)
+            },
+        };
+
+        let x = pos.0 + self.padding;
+        let y = pos.1 + self.padding;
+        self.blit(page, texture, x, y);
+
+        let region = self.make_region(page, Rect::new(x as f32, y as f32, width as f32, height as f32));
+        self.regions.insert(name.to_string(), region);
+
+        return Ok(region);
+
+    }
+
+    /**
+    Adds an image that is already packed, such as a sprite sheet, as a page of its own. Its regions are added with `add_region`.
+    The page can have any size, and textures added with `add` never go into it.
+    */
+    pub fn add_page(&mut self, texture: Texture) -> usize {
+
+        // A packer without any room, so that the page is skipped when packing.
+        let packer = RectPacker::new(0, 0);
+        self.pages.push(AtlasPage { texture, packer, texture_id: None, dirty: true });
+        return self.pages.len() - 1;
+
+    }
+
+    /**
+    Names an area of a page, in pixels from its top left.
+    */
+    pub fn add_region(&mut self, name: &str, page: usize, rect: Rect) -> error::Result<AtlasRegion> {
+
+        let size = match self.pages.get(page) {
+            Some(page) => page.texture.dimensions,
+            None => return Err(Error::Texture(format!("The atlas has no page {}.", page))),
+        };
+
+        if rect.x < 0.0 || rect.y < 0.0 || rect.width < 0.0 || rect.height < 0.0 || rect.x + rect.width > size.x as f32 || rect.y + rect.height > size.y as f32 {
+            return Err(Error::Texture(format!("The region {} ({}, {}, {}x{}) is outside of its {}x{} page.", name, rect.x, rect.y, rect.width, rect.height, size.x, size.y)));
+        }
+
+        let region = self.make_region(page, rect);
+        self.regions.insert(name.to_string(), region);
+        return Ok(region);
+
+    }
+
+    fn make_region(&self, page: usize, rect: Rect) -> AtlasRegion {
+
+        let size = self.pages[page].texture.dimensions;
+        let (width, height) = (size.x as f32, size.y as f32);
+        return AtlasRegion {
+            page,
+            rect,
+            uv: Rect::new(rect.x / width, rect.y / height, rect.width / width, rect.height / height),
+        };
+
+    }
+
+    /**
+    Adds many textures at once. They are packed tallest first, which wastes less space than packing them in any order.
+    The regions are returned in the order of the textures.
+    */
+    pub fn add_all(&mut self, textures: &[(&str, &Texture)]) -> error::Result<Vec<AtlasRegion>> {
+
+        let mut order: Vec<usize> = (0..textures.len()).collect();
+        order.sort_by(|&a, &b| textures[b].1.dimensions.y.cmp(&textures[a].1.dimensions.y));
+
+        let mut regions = vec![None; textures.len()];
+        for i in order {
+            regions[i] = Some(self.add(textures[i].0, textures[i].1)?);
+        }
+        return Ok(regions.into_iter().map(|r| r.unwrap()).collect());
+
+    }
+
+    /**
+    Loads an image file and adds it under its path.
+    */
+    pub fn add_from_path(&mut self, path: &str) -> error::Result<AtlasRegion> {
+
+        let texture = Texture::load_from_path(path)?;
+        return self.add(path, &texture);
+
+    }
+
+    pub fn get(&self, name: &str) -> Option<AtlasRegion> {
+        return self.regions.get(name).cloned();
+    }
+
+    pub fn get_page_count(&self) -> usize {
+        return self.pages.len();
+    }
+
+    pub fn get_page(&self, page: usize) -> Option<&Texture> {
+        return self.pages.get(page).map(|p| &p.texture);
+    }
+
+    /**
+    The size of the pages created by `add`.
+    */
+    pub fn get_page_size(&self) -> Vector2u {
+        return self.page_size;
+    }
+
+    /**
+    The renderer texture of a page, once it has been uploaded.
+    */
+    pub fn get_texture(&self, page: usize) -> Option<core::TextureId> {
+        return self.pages.get(page).and_then(|p| p.texture_id);
+    }
+
+    /**
+    Creates the textures of new pages and updates those of pages that changed since the last upload.
+    Page textures keep their ids, so sprites created before more textures were added stay valid.
+    */
+    pub fn upload(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        for page in self.pages.iter_mut().filter(|p| p.dirty) {
+            match page.texture_id {
+                Some(id) => engine.renderer.update_texture(id, &page.texture)?,
+                None => page.texture_id = Some(engine.renderer.create_texture(&page.texture)?),
+            }
+            page.dirty = false;
+        }
+        return Ok(());
+
+    }
+
+    /**
+    Creates a sprite showing the texture with the given name. The atlas has to be uploaded first.
+    */
+    pub fn create_sprite(&self, name: &str) -> error::Result<Sprite> {
+
+        let region = match self.get(name) {
+            Some(region) => region,
+            None => return Err(Error::Texture(format!("The atlas has no texture named {}.", name))),
+        };
+        let texture = match self.get_texture(region.page) {
+            Some(texture) => texture,
+            None => return Err(Error::Texture(format!("The atlas page of {} has not been uploaded.", name))),
+        };
+        return Ok(Sprite::from_region(texture, &region));
+
+    }
+
+    /**
+    Frees the page textures. Sprites showing them must not be rendered afterwards.
+    */
+    pub fn destroy(&mut self, engine: &mut core::FlatEngine) {
+
+        for page in self.pages.iter_mut() {
+            if let Some(id) = page.texture_id.take() {
+                engine.renderer.destroy_texture(id);
+            }
+            page.dirty = true;
+        }
+
+    }
+
+    /**
+    Copies a texture into a page, extruding its edges into the padding around it.
+    */
+    fn blit(&mut self, page: usize, texture: &Texture, x: u32, y: u32) {
+
+        let padding = self.padding as i64;
+        let width = texture.dimensions.x as i64;
+        let height = texture.dimensions.y as i64;
+        let page = &mut self.pages[page];
+        let page_width = page.texture.dimensions.x as i64;
+
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        for ty in -padding..height + padding {
+            for tx in -padding..width + padding {
+                let sx = tx.max(0).min(width - 1);
+                let sy = ty.max(0).min(height - 1);
+                let src = ((sy * width + sx) * 4) as usize;
+                let dst = (((y as i64 + ty) * page_width + x as i64 + tx) * 4) as usize;
+                if src + 4 <= texture.data.len() {
+                    page.texture.data[dst..dst + 4].copy_from_slice(&texture.data[src..src + 4]);
+                }
+            }
+        }
+        page.dirty = true;
+
+    }
+
+}
@@ -0,0 +1,261 @@
+use super::*;
+
+use self::types::*;
+
+/**
+A 2D camera that looks at `pos` in the world. It builds the view used by `core::Camera`, so apply it with `engine.apply_camera(&mut camera)` after updating it.
+Screen positions are in view coordinates, which are the same as window coordinates unless the engine uses a scaling mode (see `FlatEngine::window_to_view`).
+*/
+pub struct Camera2D {
+
+    /**
+    The world position shown at the anchor.
+    */
+    pub pos: Vector2f,
+    /**
+    Values above 1 zoom in.
+    */
+    pub zoom: f32,
+    /**
+    Rotation of the view in radians. The world appears rotated by the opposite angle.
+    */
+    pub rotation: f32,
+    /**
+    Where on the screen `pos` appears, from (0, 0) at the bottom left to (1, 1) at the top right.
+    */
+    pub anchor: Vector2f,
+    /**
+    The size of the screen area the camera renders to, in view coordinates. Set by `FlatEngine::apply_camera`.
+    */
+    pub size: Vector2f,
+    /**
+    How quickly `follow` catches up with the target, per second. 0 snaps to the target.
+    */
+    pub follow_speed: f32,
+    /**
+    Half the size of the area around `pos` (in world units) the follow target can move in without moving the camera.
+    */
+    pub deadzone: Vector2f,
+    /**
+    The area the visible rect is kept inside of.
+    */
+    pub bounds: Option<Rect>,
+    shake_intensity: f32,
+    shake_duration: f32,
+    shake_remaining: f32,
+    shake_offset: Vector2f,
+    time: f32,
+
+}
+
+impl Camera2D {
+
+    pub fn new(size: Vector2f) -> Camera2D {
+
+        return Camera2D {
+            pos: Vector2f::new(0.0, 0.0),
+            zoom: 1.0,
+            rotation: 0.0,
+            anchor: Vector2f::new(0.5, 0.5),
+            size,
+            follow_speed: 0.0,
+            deadzone: Vector2f::new(0.0, 0.0),
+            bounds: None,
+            shake_intensity: 0.0,
+            shake_duration: 0.0,
+            shake_remaining: 0.0,
+            shake_offset: Vector2f::new(0.0, 0.0),
+            time: 0.0,
+        };
+
+    }
+
+    pub fn set_pos(&mut self, pos: Vector2f) {
+        self.pos = pos;
+    }
+
+    pub fn get_pos(&self) -> Vector2f {
+        return self.pos;
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+    }
+
+    pub fn get_zoom(&self) -> f32 {
+        return self.zoom;
+    }
+
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+    }
+
+    pub fn get_rotation(&self) -> f32 {
+        return self.rotation;
+    }
+
+    pub fn set_size(&mut self, size: Vector2f) {
+        self.size = size;
+    }
+
+    /**
+    Shakes the camera by up to `intensity` world units, fading out over `duration` seconds. A stronger shake replaces a weaker one.
+    */
+    pub fn shake(&mut self, intensity: f32, duration: f32) {
+
+        if intensity >= self.get_shake_strength() {
+            self.shake_intensity = intensity;
+            self.shake_duration = duration;
+            self.shake_remaining = duration;
+        }
+
+    }
+
+    fn get_shake_strength(&self) -> f32 {
+
+        if self.shake_remaining <= 0.0 || self.shake_duration <= 0.0 {
+            return 0.0;
+        }
+        return self.shake_intensity * self.shake_remaining / self.shake_duration;
+
+    }
+
+    /**
+    Moves the camera towards the target, unless the target is inside the deadzone.
+    */
+    pub fn follow(&mut self, target: Vector2f, dt: f32) {
+
+        let mut desired = self.pos;
+        let offset = target - self.pos;
+
+        if offset.x.abs() > self.deadzone.x {
+            desired.x = target.x - self.deadzone.x * offset.x.signum();
+        }
+        if offset.y.abs() > self.deadzone.y {
+            desired.y = target.y - self.deadzone.y * offset.y.signum();
+        }
+
+        if self.follow_speed <= 0.0 {
+            self.pos = desired;
+        } else {
+            // Frame rate independent exponential smoothing.
+            let t = 1.0 - (-self.follow_speed * dt).exp();
+            self.pos += (desired - self.pos) * t;
+        }
+
+        self.clamp_to_bounds();
+
+    }
+
+    /**
+    Advances the shake and keeps the camera inside its bounds. Call once per frame.
+    */
+    pub fn update(&mut self, dt: f32) {
+
+        self.time += dt;
+        self.shake_remaining = (self.shake_remaining - dt).max(0.0);
+
+        let strength = self.get_shake_strength();
+        if strength > 0.0 {
+            // Incommensurate frequencies give a jittery but deterministic motion without a random number generator.
+            self.shake_offset = Vector2f::new((self.time * 47.0).sin() * (self.time * 13.0).cos(), (self.time * 53.0 + 1.3).sin() * (self.time * 17.0).cos()) * strength;
+        } else {
+            self.shake_offset = Vector2f::new(0.0, 0.0);
+        }
+
+        self.clamp_to_bounds();
+
+    }
+
+    fn clamp_to_bounds(&mut self) {
+
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        // The shake is left out, so that it does not move the camera away from a bound it rests at.
+        let visible = self.get_visible_rect_at(self.pos);
+        // The distance from the camera position to the edges of the visible rect.
+        let left = self.pos.x - visible.x;
+        let bottom = self.pos.y - visible.y;
+        let right = visible.x + visible.width - self.pos.x;
+        let top = visible.y + visible.height - self.pos.y;
+
+        if visible.width >= bounds.width {
+            self.pos.x = bounds.x + bounds.width / 2.0 - (right - left) / 2.0;
+        } else {
+            self.pos.x = self.pos.x.max(bounds.x + left).min(bounds.x + bounds.width - right);
+        }
+
+        if visible.height >= bounds.height {
+            self.pos.y = bounds.y + bounds.height / 2.0 - (top - bottom) / 2.0;
+        } else {
+            self.pos.y = self.pos.y.max(bounds.y + bottom).min(bounds.y + bounds.height - top);
+        }
+
+    }
+
+    /**
+    The view of a camera looking at `eye`, without the shake.
+    */
+    fn get_view_at(&self, eye: Vector2f) -> Matrix4f {
+
+        let anchor = Vector3f::new(self.anchor.x * self.size.x, self.anchor.y * self.size.y, 0.0);
+
+        return Matrix4f::from_translation(anchor)
+            * Matrix4f::from_scale(self.zoom)
+            * Matrix4f::from_angle_z(Rad(-self.rotation))
+            * Matrix4f::from_translation(Vector3f::new(-eye.x, -eye.y, 0.0));
+
+    }
+
+    pub fn get_view(&self) -> Matrix4f {
+        return self.get_view_at(self.pos + self.shake_offset);
+    }
+
+    pub fn get_projection(&self) -> Matrix4f {
+        return cgmath::ortho(0.0, self.size.x, 0.0, self.size.y, 100.0, -100.0);
+    }
+
+    pub fn get_camera(&self) -> core::Camera {
+        return core::Camera { projection: self.get_projection(), view: self.get_view() };
+    }
+
+    /**
+    Converts a position in view coordinates (from the top left) to world coordinates. Window positions go through `FlatEngine::window_to_view` first.
+    */
+    pub fn screen_to_world(&self, screen: Vector2f) -> Vector2f {
+
+        let inverse = self.get_view().invert().unwrap_or(Matrix4f::identity());
+        let world = inverse * Vector4f::new(screen.x, self.size.y - screen.y, 0.0, 1.0);
+        return Vector2f::new(world.x, world.y);
+
+    }
+
+    /**
+    Converts a world position to view coordinates (from the top left).
+    */
+    pub fn world_to_screen(&self, world: Vector2f) -> Vector2f {
+
+        let screen = self.get_view() * Vector4f::new(world.x, world.y, 0.0, 1.0);
+        return Vector2f::new(screen.x, self.size.y - screen.y);
+
+    }
+
+    fn get_visible_rect_at(&self, pos: Vector2f) -> Rect {
+
+        let inverse = self.get_view_at(pos).invert().unwrap_or(Matrix4f::identity());
+        let screen = Rect::new(0.0, 0.0, self.size.x, self.size.y);
+        return OrientedRect::from_rect(&screen, &inverse).get_bounds();
+
+    }
+
+    /**
+    The world area visible through the camera. When rotated this is the bounding box of the rotated view.
+    */
+    pub fn get_visible_rect(&self) -> Rect {
+        return self.get_visible_rect_at(self.pos + self.shake_offset);
+    }
+
+}
@@ -4,6 +4,8 @@ use self::types::*;
 use gfx::traits::FactoryExt;
 use glutin::dpi::*;
 use self::gfx::Device;
+use self::gfx::Factory as GfxFactory;
+use self::gfx::memory::Typed;
 use self::gfx_device_gl::{Factory};
 use gfx_window_glutin;
 use self::glutin::{GlContext, GlRequest};
@@ -11,19 +13,1036 @@ use self::glutin::Api::OpenGl;
 use self::glutin::GlWindow;
 
 use self::cgmath::Matrix4;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+pub type ShaderId = usize;
+pub type TextureId = usize;
+pub type BufferId = usize;
+pub type RenderTargetId = usize;
+
+/**
+The kind of pipeline a shader pair is compiled for. This decides the vertex layout and which uniforms the shader receives.
+*/
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PipelineKind {
+
+    Texture,
+    Geometry,
+    Mesh,
+    /**
+    Glyph quads tinted by a vertex colour.
+    */
+    Text,
+    /**
+    Glyph quads shaded from a distance field, drawn with `draw_distance_text`.
+    */
+    DistanceText,
+    /**
+    A full-screen pass, drawn with `draw_post_process`.
+    */
+    PostProcess(post::PostShader),
+
+}
+
+/**
+Vertex data handed to a renderer backend. Each variant matches one of the pipeline kinds.
+*/
+pub enum Vertices<'a> {
+
+    Uv2f(&'a [render::UvVertex2f]),
+    Color(&'a [geometry::Vertex]),
+    Uv3f(&'a [spatial::UvVertex3f]),
+    Text(&'a [text::TextVertex]),
+
+}
+
+impl<'a> Vertices<'a> {
+
+    pub fn len(&self) -> usize {
+        match *self {
+            Vertices::Uv2f(v) => v.len(),
+            Vertices::Color(v) => v.len(),
+            Vertices::Uv3f(v) => v.len(),
+            Vertices::Text(v) => v.len(),
+        }
+    }
+
+}
+
+/**
+A single draw of a vertex buffer with a shader (and optionally a texture).
+*/
+#[derive(Copy, Clone)]
+pub struct DrawCall {
+
+    pub shader: ShaderId,
+    pub vertices: BufferId,
+    pub texture: Option<TextureId>,
+    pub model: Matrix4f,
+    pub view: Matrix4f,
+    pub projection: Matrix4f,
+    /**
+    The range of vertices to draw (start, end). The whole buffer is drawn if this is None.
+    */
+    pub range: Option<(u32, u32)>,
+
+}
+
+/**
+A draw of text vertices with a distance field shader, along with the parameters of its effects (see `text::TextEffects`).
+*/
+#[derive(Copy, Clone)]
+pub struct DistanceTextCall {
+
+    pub draw: DrawCall,
+    /**
+    The outline colour, the glow colour, then the outline width, glow width, distance range and softness in texels of the distance field.
+    Param 3 x ignores the colour of the texture, which shadows use.
+    */
+    pub params: [[f32; 4]; 4],
+
+}
+
+/**
+A full-screen pass over the bound render target (or the viewport of the window).
+*/
+#[derive(Copy, Clone)]
+pub struct PostProcessCall {
+
+    pub shader: ShaderId,
+    pub input: TextureId,
+    pub secondary: Option<TextureId>,
+    pub params: [[f32; 4]; 4],
+    pub time: f32,
+
+}
+
+/**
+The renderer abstraction that all drawables go through.
+Resources are created through the renderer and referred to by id, so the drawables never touch backend specific types.
+*/
+pub trait Renderer {
+
+    fn get_dimensions(&self) -> Vector2f;
+
+    /**
+    Resizes the framebuffer to the current window size.
+    */
+    fn update_size(&mut self);
+
+    /**
+    The number of framebuffer pixels per unit of `get_dimensions`.
+    */
+    fn get_hidpi_factor(&self) -> f32;
+
+    /**
+    Restricts drawing to a rect in the units of `get_dimensions`, with the origin at the bottom left. None draws to the whole framebuffer.
+    Clearing is not affected.
+    */
+    fn set_viewport(&mut self, viewport: Option<Rect>);
+
+    fn clear(&mut self, color: Color);
+
+    fn flush(&mut self);
+
+    fn swap_buffers(&mut self);
+
+    /**
+    Reads back the frame drawn since the last call to `swap_buffers`, top row first. Call it before presenting the frame,
+    as the contents of a presented frame are undefined on many drivers.
+    */
+    fn read_pixels(&mut self) -> error::Result<render::Texture>;
+
+    /**
+    Compiles a pipeline for the given shaders. Drawables go through `FlatEngine::create_shader`, which compiles each pair of shaders once.
+    */
+    fn create_shader(&mut self, kind: PipelineKind, v_shader: &[u8], f_shader: &[u8]) -> error::Result<ShaderId>;
+
+    fn create_texture(&mut self, texture: &render::Texture) -> error::Result<TextureId>;
+
+    fn update_texture(&mut self, id: TextureId, texture: &render::Texture) -> error::Result<()>;
+
+    fn destroy_texture(&mut self, id: TextureId);
+
+    /**
+    Creates an off-screen target with its own color and depth buffers, so that depth-tested drawing into it does not use the depth buffer of the window. The color buffer can be drawn like any other texture through the returned texture id.
+    */
+    fn create_render_target(&mut self, width: u32, height: u32) -> error::Result<(RenderTargetId, TextureId)>;
+
+    /**
+    Makes clear and draw go to the render target, or back to the window if None.
+    The texture of a render target is only guaranteed to be up to date once another target (or the window) has been bound.
+    */
+    fn set_render_target(&mut self, target: Option<RenderTargetId>);
+
+    /**
+    Reads back the contents of a render target, top row first.
+    */
+    fn read_render_target(&mut self, target: RenderTargetId) -> error::Result<render::Texture>;
+
+    /**
+    Destroys the render target along with its texture.
+    */
+    fn destroy_render_target(&mut self, target: RenderTargetId);
+
+    fn create_vertex_buffer(&mut self, vertices: Vertices) -> BufferId;
+
+    /**
+    Creates a vertex buffer for data that changes every frame. Updates are written in place and the buffer grows when it runs out of room.
+    */
+    fn create_dynamic_vertex_buffer(&mut self, vertices: Vertices) -> error::Result<BufferId>;
+
+    /**
+    Replaces the vertices of a buffer. If this fails the buffer is left empty until it is updated again, so it must not be drawn.
+    */
+    fn update_vertex_buffer(&mut self, id: BufferId, vertices: Vertices) -> error::Result<()>;
+
+    fn destroy_vertex_buffer(&mut self, id: BufferId);
+
+    fn draw(&mut self, call: &DrawCall);
+
+    /**
+    Draws text vertices with a `PipelineKind::DistanceText` shader, blending with the output like `draw`.
+    */
+    fn draw_distance_text(&mut self, call: &DistanceTextCall);
+
+    /**
+    Draws a post process pass, replacing the contents of the output instead of blending with them.
+    */
+    fn draw_post_process(&mut self, call: &PostProcessCall);
+
+}
+
+/**
+Simple slot storage used by the backends to hand out resource ids. Freed slots are reused.
+*/
+pub struct ResourceStore<T> {
+
+    items: Vec<Option<T>>,
+    free: Vec<usize>,
+
+}
+
+impl<T> ResourceStore<T> {
+
+    pub fn new() -> ResourceStore<T> {
+
+        return ResourceStore { items: Vec::new(), free: Vec::new() };
+
+    }
+
+    pub fn insert(&mut self, item: T) -> usize {
+
+        if let Some(id) = self.free.pop() {
+            self.items[id] = Some(item);
+            return id;
+        }
+
+        self.items.push(Some(item));
+        return self.items.len() - 1;
+
+    }
+
+    pub fn get(&self, id: usize) -> Option<&T> {
+        return self.items.get(id).and_then(|i| i.as_ref());
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut T> {
+        return self.items.get_mut(id).and_then(|i| i.as_mut());
+    }
+
+    pub fn set(&mut self, id: usize, item: T) {
+        if id < self.items.len() {
+            self.items[id] = Some(item);
+        }
+    }
+
+    /**
+    Takes an item out without releasing its id, so that it can be put back with `set`.
+    */
+    pub fn take(&mut self, id: usize) -> Option<T> {
+        return self.items.get_mut(id).and_then(|i| i.take());
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<T> {
+
+        let item = self.items.get_mut(id).and_then(|i| i.take());
+        if item.is_some() {
+            self.free.push(id);
+        }
+        return item;
+
+    }
+
+}
+
+/**
+Maps shader sources and pipeline kinds to already compiled shaders, so every drawable using the same shaders shares one pipeline.
+The engine keeps one for its renderer, see `FlatEngine::create_shader`.
+*/
+pub struct ShaderCache {
+
+    // Sources are keyed by their hash and length rather than copied.
+    shaders: HashMap<(PipelineKind, u64, usize, u64, usize), ShaderId>,
+
+}
+
+impl ShaderCache {
+
+    pub fn new() -> ShaderCache {
+
+        return ShaderCache { shaders: HashMap::new() };
+
+    }
+
+    fn key(kind: PipelineKind, v_shader: &[u8], f_shader: &[u8]) -> (PipelineKind, u64, usize, u64, usize) {
+
+        let hash = |source: &[u8]| {
+            let mut hasher = DefaultHasher::new();
+            source.hash(&mut hasher);
+            hasher.finish()
+        };
+        return (kind, hash(v_shader), v_shader.len(), hash(f_shader), f_shader.len());
+
+    }
+
+    pub fn get(&self, kind: PipelineKind, v_shader: &[u8], f_shader: &[u8]) -> Option<ShaderId> {
+        return self.shaders.get(&ShaderCache::key(kind, v_shader, f_shader)).cloned();
+    }
+
+    pub fn insert(&mut self, kind: PipelineKind, v_shader: &[u8], f_shader: &[u8], id: ShaderId) {
+        self.shaders.insert(ShaderCache::key(kind, v_shader, f_shader), id);
+    }
+
+    pub fn len(&self) -> usize {
+        return self.shaders.len();
+    }
+
+}
+
+/**
+Shares the uploads of identical textures, so that sprites made from the same image draw with the same texture and can be batched together.
+Each upload counts its users and is destroyed when the last one releases it. The engine keeps one for its renderer, see `FlatEngine::create_texture`.
+*/
+pub struct TextureCache {
+
+    /**
+    Uploads by the size of their texture and the hash of some of its pixels. Textures with the same key are told apart by all of their pixels.
+    */
+    textures: HashMap<(u64, u16, u16), Vec<CachedTexture>>,
+    keys: HashMap<TextureId, (u64, u16, u16)>,
+
+}
+
+struct CachedTexture {
+
+    id: TextureId,
+    users: usize,
+    data: Vec<u8>,
+
+}
+
+/**
+The number of pixels hashed for the key of a texture. Hashing all of them would make every upload as slow as a large one.
+*/
+const TEXTURE_KEY_SAMPLES: usize = 256;
+
+impl TextureCache {
+
+    pub fn new() -> TextureCache {
+
+        return TextureCache { textures: HashMap::new(), keys: HashMap::new() };
+
+    }
+
+    fn key(texture: &render::Texture) -> (u64, u16, u16) {
+
+        let mut hasher = DefaultHasher::new();
+        let pixels = texture.data.len() / 4;
+        texture.data.len().hash(&mut hasher);
+        for i in (0..pixels).step_by((pixels / TEXTURE_KEY_SAMPLES).max(1)) {
+            texture.data[i * 4..i * 4 + 4].hash(&mut hasher);
+        }
+        return (hasher.finish(), texture.dimensions.x, texture.dimensions.y);
+
+    }
+
+    /**
+    Returns the upload of an identical texture, counting one more user of it.
+    */
+    pub fn acquire(&mut self, texture: &render::Texture) -> Option<TextureId> {
+
+        let cached = self.textures.get_mut(&TextureCache::key(texture))?.iter_mut().find(|c| c.data == texture.data)?;
+        cached.users += 1;
+        return Some(cached.id);
+
+    }
+
+    /**
+    Adds a new upload with one user. Its pixels are kept to compare other textures with.
+    */
+    pub fn insert(&mut self, texture: &render::Texture, id: TextureId) {
+
+        let key = TextureCache::key(texture);
+        self.textures.entry(key).or_insert_with(Vec::new).push(CachedTexture { id, users: 1, data: texture.data.clone() });
+        self.keys.insert(id, key);
+
+    }
+
+    /**
+    Counts one user less, returning true if the texture is no longer used and can be destroyed. Textures that are not in the cache are never shared.
+    */
+    pub fn release(&mut self, id: TextureId) -> bool {
+
+        let key = match self.keys.get(&id) {
+            Some(&key) => key,
+            None => return true,
+        };
+
+        let bucket = self.textures.get_mut(&key).unwrap();
+        let index = bucket.iter().position(|c| c.id == id).unwrap();
+        bucket[index].users -= 1;
+        if bucket[index].users > 0 {
+            return false;
+        }
+
+        bucket.remove(index);
+        if bucket.is_empty() {
+            self.textures.remove(&key);
+        }
+        self.keys.remove(&id);
+        return true;
+
+    }
+
+    pub fn len(&self) -> usize {
+        return self.keys.len();
+    }
+
+}
+
+enum GlPipeline {
+
+    Texture(gfx::PipelineState<ResourceType, render::pipe::Meta>),
+    Geometry(gfx::PipelineState<ResourceType, geometry::pipe::Meta>),
+    Mesh(gfx::PipelineState<ResourceType, spatial::pipe::Meta>),
+    Text(gfx::PipelineState<ResourceType, text::pipe::Meta>),
+    DistanceText(gfx::PipelineState<ResourceType, text::distance_pipe::Meta>),
+    Post(gfx::PipelineState<ResourceType, post::pipe::Meta>),
+
+}
+
+enum GlVertexData {
+
+    Uv2f(gfx::handle::Buffer<ResourceType, render::UvVertex2f>),
+    Color(gfx::handle::Buffer<ResourceType, geometry::Vertex>),
+    Uv3f(gfx::handle::Buffer<ResourceType, spatial::UvVertex3f>),
+    Text(gfx::handle::Buffer<ResourceType, text::TextVertex>),
+
+}
+
+struct GlVertexBuffer {
+
+    data: GlVertexData,
+    slice: gfx::Slice<ResourceType>,
+    /**
+    Only dynamic buffers have a capacity, static buffers are recreated on every update.
+    */
+    capacity: Option<usize>,
+
+}
+
+type GlTextureView = gfx::handle::ShaderResourceView<ResourceType, [f32; 4]>;
+
+struct GlRenderTarget {
+
+    texture: gfx::handle::Texture<ResourceType, gfx::format::R8_G8_B8_A8>,
+    color: gfx::handle::RenderTargetView<ResourceType, ColorFormat>,
+    depth: gfx::handle::DepthStencilView<ResourceType, DepthFormat>,
+    texture_id: TextureId,
+
+}
+
+/**
+The OpenGL backend. Owns the window and the gfx device.
+*/
+pub struct GlRenderer {
+
+    pub window: GlWindow,
+    pub factory: Factory,
+    pub encoder: gfx::Encoder<ResourceType, gfx_device_gl::CommandBuffer>,
+    pub device: Box<gfx_device_gl::Device>,
+    pub render_view: gfx::handle::RenderTargetView<ResourceType, (gfx::format::R8_G8_B8_A8, gfx::format::Unorm)>,
+    pub depth_view: gfx::handle::DepthStencilView<ResourceType, (gfx::format::D24_S8, gfx::format::Unorm)>,
+
+    sampler: gfx::handle::Sampler<ResourceType>,
+    blank_texture: GlTextureView,
+    texture_trans: gfx::handle::Buffer<ResourceType, render::GeometryTransform>,
+    geometry_trans: gfx::handle::Buffer<ResourceType, geometry::GeometryTransform>,
+    mesh_trans: gfx::handle::Buffer<ResourceType, spatial::MeshTransform>,
+    text_trans: gfx::handle::Buffer<ResourceType, text::TextTransform>,
+    distance_params: gfx::handle::Buffer<ResourceType, text::DistanceParams>,
+    post_params: gfx::handle::Buffer<ResourceType, post::PostParams>,
+    /**
+    Full-screen quads for drawing post process passes to the window and to render targets.
+    */
+    post_quad: (gfx::handle::Buffer<ResourceType, render::UvVertex2f>, gfx::Slice<ResourceType>),
+    post_quad_flipped: (gfx::handle::Buffer<ResourceType, render::UvVertex2f>, gfx::Slice<ResourceType>),
+
+    pipelines: ResourceStore<GlPipeline>,
+    textures: ResourceStore<GlTextureView>,
+    buffers: ResourceStore<GlVertexBuffer>,
+    viewport: Option<Rect>,
+    targets: ResourceStore<GlRenderTarget>,
+    bound_target: Option<RenderTargetId>,
+
+}
+
+impl GlRenderer {
+
+    pub fn new(window: GlWindow, device: gfx_device_gl::Device, mut factory: Factory, render_view: gfx::handle::RenderTargetView<ResourceType, ColorFormat>, depth_view: gfx::handle::DepthStencilView<ResourceType, DepthFormat>) -> error::Result<GlRenderer> {
+
+        let encoder: gfx::Encoder<ResourceType, gfx_device_gl::CommandBuffer> = factory.create_command_buffer().into();
+        let sampler = factory.create_sampler_linear();
+        let blank_texture = GlRenderer::create_texture_view(&mut factory, &render::Texture::new())?;
+        let texture_trans = factory.create_constant_buffer(1);
+        let geometry_trans = factory.create_constant_buffer(1);
+        let mesh_trans = factory.create_constant_buffer(1);
+        let text_trans = factory.create_constant_buffer(1);
+        let distance_params = factory.create_constant_buffer(1);
+        let post_params = factory.create_constant_buffer(1);
+        let post_quad = factory.create_vertex_buffer_with_slice(&post::fullscreen_quad(false), ());
+        let post_quad_flipped = factory.create_vertex_buffer_with_slice(&post::fullscreen_quad(true), ());
+
+        return Ok(GlRenderer {
+            window,
+            factory,
+            encoder,
+            device: Box::new(device),
+            render_view,
+            depth_view,
+            sampler,
+            blank_texture,
+            texture_trans,
+            geometry_trans,
+            mesh_trans,
+            text_trans,
+            distance_params,
+            post_params,
+            post_quad,
+            post_quad_flipped,
+            pipelines: ResourceStore::new(),
+            textures: ResourceStore::new(),
+            buffers: ResourceStore::new(),
+            viewport: None,
+            targets: ResourceStore::new(),
+            bound_target: None,
+        });
+
+    }
+
+    fn create_texture_view(factory: &mut Factory, texture: &render::Texture) -> error::Result<GlTextureView> {
+        let kind = gfx::texture::Kind::D2(texture.dimensions.x, texture.dimensions.y, gfx::texture::AaMode::Single);
+        let (_, view) = factory.create_texture_immutable_u8::<gfx::format::Rgba8>(kind, gfx::texture::Mipmap::Provided, &[texture.data.as_ref()])
+            .map_err(|e| Error::Texture(format!("{}", e)))?;
+        return Ok(view);
+    }
+
+    fn create_gl_vertex_buffer(factory: &mut Factory, vertices: Vertices) -> GlVertexBuffer {
+
+        let (data, slice) = match vertices {
+            Vertices::Uv2f(v) => {
+                let (buffer, slice) = factory.create_vertex_buffer_with_slice(v, ());
+                (GlVertexData::Uv2f(buffer), slice)
+            },
+            Vertices::Color(v) => {
+                let (buffer, slice) = factory.create_vertex_buffer_with_slice(v, ());
+                (GlVertexData::Color(buffer), slice)
+            },
+            Vertices::Uv3f(v) => {
+                let (buffer, slice) = factory.create_vertex_buffer_with_slice(v, ());
+                (GlVertexData::Uv3f(buffer), slice)
+            },
+            Vertices::Text(v) => {
+                let (buffer, slice) = factory.create_vertex_buffer_with_slice(v, ());
+                (GlVertexData::Text(buffer), slice)
+            },
+        };
+
+        return GlVertexBuffer { data, slice, capacity: None };
+
+    }
+
+    fn upload_dynamic<T: gfx::traits::Pod>(factory: &mut Factory, encoder: &mut gfx::Encoder<ResourceType, gfx_device_gl::CommandBuffer>, buffer: Option<gfx::handle::Buffer<ResourceType, T>>, vertices: &[T], capacity: usize) -> error::Result<gfx::handle::Buffer<ResourceType, T>> {
+
+        let buffer = match buffer {
+            Some(buffer) => buffer,
+            None => match factory.create_buffer(capacity, gfx::buffer::Role::Vertex, gfx::memory::Usage::Dynamic, gfx::memory::Bind::empty()) {
+                Ok(buffer) => buffer,
+                Err(e) => return Err(Error::Renderer(format!("A vertex buffer for {} vertices could not be created: {:?}", capacity, e))),
+            },
+        };
+        if let Err(e) = encoder.update_buffer(&buffer, vertices, 0) {
+            return Err(Error::Renderer(format!("{} vertices could not be written to a vertex buffer: {:?}", vertices.len(), e)));
+        }
+        return Ok(buffer);
+
+    }
+
+    /**
+    Writes vertices into a dynamic buffer, reusing the existing one if it is large enough.
+    */
+    fn write_dynamic_vertex_buffer(&mut self, existing: Option<GlVertexBuffer>, vertices: Vertices) -> error::Result<GlVertexBuffer> {
+
+        let count = vertices.len();
+        let capacity = existing.as_ref().and_then(|b| b.capacity).unwrap_or(0);
+        let reuse = count <= capacity;
+        let capacity = if reuse { capacity } else { count.next_power_of_two().max(64) };
+        let existing = existing.map(|b| b.data).filter(|_| reuse);
+
+        let factory = &mut self.factory;
+        let encoder = &mut self.encoder;
+
+        let data = match (existing, vertices) {
+            (Some(GlVertexData::Uv2f(b)), Vertices::Uv2f(v)) => GlVertexData::Uv2f(GlRenderer::upload_dynamic(factory, encoder, Some(b), v, capacity)?),
+            (_, Vertices::Uv2f(v)) => GlVertexData::Uv2f(GlRenderer::upload_dynamic(factory, encoder, None, v, capacity)?),
+            (Some(GlVertexData::Color(b)), Vertices::Color(v)) => GlVertexData::Color(GlRenderer::upload_dynamic(factory, encoder, Some(b), v, capacity)?),
+            (_, Vertices::Color(v)) => GlVertexData::Color(GlRenderer::upload_dynamic(factory, encoder, None, v, capacity)?),
+            (Some(GlVertexData::Uv3f(b)), Vertices::Uv3f(v)) => GlVertexData::Uv3f(GlRenderer::upload_dynamic(factory, encoder, Some(b), v, capacity)?),
+            (_, Vertices::Uv3f(v)) => GlVertexData::Uv3f(GlRenderer::upload_dynamic(factory, encoder, None, v, capacity)?),
+            (Some(GlVertexData::Text(b)), Vertices::Text(v)) => GlVertexData::Text(GlRenderer::upload_dynamic(factory, encoder, Some(b), v, capacity)?),
+            (_, Vertices::Text(v)) => GlVertexData::Text(GlRenderer::upload_dynamic(factory, encoder, None, v, capacity)?),
+        };
+
+        let slice = gfx::Slice { start: 0, end: count as u32, base_vertex: 0, instances: None, buffer: gfx::IndexBuffer::Auto };
+
+        return Ok(GlVertexBuffer { data, slice, capacity: Some(capacity) });
+
+    }
+
+    fn get_target_view(&self) -> gfx::handle::RenderTargetView<ResourceType, ColorFormat> {
+
+        match self.bound_target.and_then(|id| self.targets.get(id)) {
+            Some(target) => target.color.clone(),
+            None => self.render_view.clone(),
+        }
+
+    }
+
+    /**
+    Copies an RGBA8 texture into memory, returning its pixels and size. The rows are in the order OpenGL stores them.
+    */
+    fn download(&mut self, texture: &gfx::handle::RawTexture<ResourceType>) -> error::Result<(Vec<u8>, u16, u16)> {
+
+        let info = texture.get_info().to_raw_image_info(gfx::format::ChannelType::Unorm, 0);
+        let (width, height) = (info.width, info.height);
+
+        let buffer = self.factory.create_download_buffer::<[u8; 4]>(width as usize * height as usize).map_err(|e| Error::Texture(format!("{}", e)))?;
+        self.encoder.copy_texture_to_buffer_raw(texture, None, info, buffer.raw(), 0).map_err(|e| Error::Texture(format!("{:?}", e)))?;
+        self.encoder.flush(self.device.as_mut());
+
+        let mut data: Vec<u8> = Vec::with_capacity(width as usize * height as usize * 4);
+        let reader = self.factory.read_mapping(&buffer).map_err(|e| Error::Texture(format!("{}", e)))?;
+        for pixel in reader.iter() {
+            data.extend_from_slice(pixel);
+        }
+
+        return Ok((data, width, height));
+
+    }
+
+    /**
+    The viewport in framebuffer pixels, which gfx expects with the origin at the bottom left.
+    */
+    fn get_scissor(&self) -> gfx::Rect {
+
+        // The viewport only applies to the window.
+        if let Some(target) = self.bound_target.and_then(|id| self.targets.get(id)) {
+            let (width, height, _, _) = target.color.get_dimensions();
+            return gfx::Rect { x: 0, y: 0, w: width, h: height };
+        }
+
+        let (width, height, _, _) = self.render_view.get_dimensions();
+        let viewport = match self.viewport {
+            Some(viewport) => viewport,
+            None => return gfx::Rect { x: 0, y: 0, w: width, h: height },
+        };
+
+        let factor = self.get_hidpi_factor();
+        let x = (viewport.x * factor).round().max(0.0).min(width as f32);
+        let y = (viewport.y * factor).round().max(0.0).min(height as f32);
+        let w = (viewport.width * factor).round().max(0.0).min(width as f32 - x);
+        let h = (viewport.height * factor).round().max(0.0).min(height as f32 - y);
+        return gfx::Rect { x: x as u16, y: y as u16, w: w as u16, h: h as u16 };
+
+    }
+
+}
+
+impl Renderer for GlRenderer {
+
+    fn get_dimensions(&self) -> Vector2f {
+
+        // The window no longer has a size once it has been closed, in which case the last known framebuffer size is used.
+        match self.window.get_inner_size() {
+            Some(size) => Vector2f { x: size.width as f32, y: size.height as f32 },
+            None => {
+                let (width, height, _, _) = self.render_view.get_dimensions();
+                Vector2f { x: width as f32, y: height as f32 }
+            },
+        }
+
+    }
+
+    fn update_size(&mut self) {
+
+        // The context has to be resized along with the window on some platforms.
+        if let Some(size) = self.window.get_inner_size() {
+            self.window.resize(size.to_physical(self.window.get_hidpi_factor()));
+        }
+        gfx_window_glutin::update_views(&self.window, &mut self.render_view, &mut self.depth_view);
+
+    }
+
+    fn get_hidpi_factor(&self) -> f32 {
+        return self.window.get_hidpi_factor() as f32;
+    }
+
+    fn set_viewport(&mut self, viewport: Option<Rect>) {
+        self.viewport = viewport;
+    }
+
+    fn clear(&mut self, color: Color) {
+
+        self.encoder.clear(&self.get_target_view(), color.to_raw_color()); //clear the framebuffer with a color(color needs to be an array of 4 f32s, RGBa)
+        let targets = &self.targets;
+        if let Some(target) = self.bound_target.and_then(|id| targets.get(id)) {
+            self.encoder.clear_depth(&target.depth, 1.0);
+        }
+
+    }
+
+    fn flush(&mut self) {
+        self.encoder.flush(self.device.as_mut());
+    }
+
+    fn swap_buffers(&mut self) {
+        self.window.swap_buffers().unwrap();
+        self.device.cleanup();
+    }
+
+    fn read_pixels(&mut self) -> error::Result<render::Texture> {
+
+        // Copying from the window's surface reads its back buffer, which holds the frame until it is presented.
+        let surface = self.render_view.raw().get_texture().clone();
+        let (data, width, height) = self.download(&surface)?;
+
+        // OpenGL returns the bottom row first.
+        let row = width as usize * 4;
+        let mut flipped: Vec<u8> = Vec::with_capacity(data.len());
+        for y in (0..height as usize).rev() {
+            flipped.extend_from_slice(&data[y * row..(y + 1) * row]);
+        }
+
+        return Ok(render::Texture::from_data(&flipped, width, height));
+
+    }
+
+    fn create_shader(&mut self, kind: PipelineKind, v_shader: &[u8], f_shader: &[u8]) -> error::Result<ShaderId> {
+
+        let pipeline = match kind {
+            PipelineKind::Texture => GlPipeline::Texture(self.factory.create_pipeline_simple(v_shader, f_shader, render::pipe::new()).map_err(|e| Error::Shader(format!("{}", e)))?),
+            PipelineKind::Geometry => GlPipeline::Geometry(self.factory.create_pipeline_simple(v_shader, f_shader, geometry::pipe::new()).map_err(|e| Error::Shader(format!("{}", e)))?),
+            PipelineKind::Mesh => GlPipeline::Mesh(self.factory.create_pipeline_simple(v_shader, f_shader, spatial::pipe::new()).map_err(|e| Error::Shader(format!("{}", e)))?),
+            PipelineKind::Text => GlPipeline::Text(self.factory.create_pipeline_simple(v_shader, f_shader, text::pipe::new()).map_err(|e| Error::Shader(format!("{}", e)))?),
+            PipelineKind::DistanceText => GlPipeline::DistanceText(self.factory.create_pipeline_simple(v_shader, f_shader, text::distance_pipe::new()).map_err(|e| Error::Shader(format!("{}", e)))?),
+            PipelineKind::PostProcess(_) => GlPipeline::Post(self.factory.create_pipeline_simple(v_shader, f_shader, post::pipe::new()).map_err(|e| Error::Shader(format!("{}", e)))?),
+        };
 
-pub struct Renderer {
+        return Ok(self.pipelines.insert(pipeline));
 
-    pub factory: Factory,
-    pub encoder: gfx::Encoder<ResourceType, gfx_device_gl::CommandBuffer>,
-    pub device: Box<gfx_device_gl::Device>,
-    pub render_view: gfx::handle::RenderTargetView<ResourceType, (gfx::format::R8_G8_B8_A8, gfx::format::Unorm)>,
-    pub depth_view: gfx::handle::DepthStencilView<ResourceType, (gfx::format::D24_S8, gfx::format::Unorm)>,
+    }
 
-    pub camera: Camera,
+    fn create_texture(&mut self, texture: &render::Texture) -> error::Result<TextureId> {
+        let view = GlRenderer::create_texture_view(&mut self.factory, texture)?;
+        return Ok(self.textures.insert(view));
+    }
+
+    fn update_texture(&mut self, id: TextureId, texture: &render::Texture) -> error::Result<()> {
+        // Textures are immutable on the GPU side, so a new one is uploaded in place of the old one.
+        let view = GlRenderer::create_texture_view(&mut self.factory, texture)?;
+        self.textures.set(id, view);
+        return Ok(());
+    }
+
+    fn destroy_texture(&mut self, id: TextureId) {
+        self.textures.remove(id);
+    }
+
+    fn create_render_target(&mut self, width: u32, height: u32) -> error::Result<(RenderTargetId, TextureId)> {
+
+        if width == 0 || height == 0 || width > u16::max_value() as u32 || height > u16::max_value() as u32 {
+            return Err(Error::Texture(format!("Invalid render target size {}x{}.", width, height)));
+        }
+
+        let kind = gfx::texture::Kind::D2(width as u16, height as u16, gfx::texture::AaMode::Single);
+        // TRANSFER_SRC is needed to read the target back.
+        let bind = gfx::memory::Bind::RENDER_TARGET | gfx::memory::Bind::SHADER_RESOURCE | gfx::memory::Bind::TRANSFER_SRC;
+        let texture = self.factory.create_texture::<gfx::format::R8_G8_B8_A8>(kind, 1, bind, gfx::memory::Usage::Data, Some(gfx::format::ChannelType::Unorm))
+            .map_err(|e| Error::Texture(format!("{}", e)))?;
+        let view = self.factory.view_texture_as_shader_resource::<ColorFormat>(&texture, (0, 0), gfx::format::Swizzle::new())
+            .map_err(|e| Error::Texture(format!("{}", e)))?;
+        let color = self.factory.view_texture_as_render_target::<ColorFormat>(&texture, 0, None)
+            .map_err(|e| Error::Texture(format!("{}", e)))?;
+        let depth = self.factory.create_depth_stencil_view_only::<DepthFormat>(width as u16, height as u16)
+            .map_err(|e| Error::Texture(format!("{}", e)))?;
+
+        let texture_id = self.textures.insert(view);
+        let id = self.targets.insert(GlRenderTarget { texture, color, depth, texture_id });
+        return Ok((id, texture_id));
+
+    }
+
+    fn set_render_target(&mut self, target: Option<RenderTargetId>) {
+        self.encoder.flush(self.device.as_mut());
+        self.bound_target = target;
+    }
+
+    fn read_render_target(&mut self, target: RenderTargetId) -> error::Result<render::Texture> {
+
+        let texture = match self.targets.get(target) {
+            Some(target) => target.texture.clone(),
+            None => return Err(Error::Texture(String::from("The render target does not exist."))),
+        };
+
+        // Targets are drawn upside down (see draw), so the rows are already top first.
+        let (data, width, height) = self.download(texture.raw())?;
+        return Ok(render::Texture::from_data(&data, width, height));
+
+    }
+
+    fn destroy_render_target(&mut self, target: RenderTargetId) {
+
+        if self.bound_target == Some(target) {
+            self.bound_target = None;
+        }
+        if let Some(target) = self.targets.remove(target) {
+            self.textures.remove(target.texture_id);
+        }
+
+    }
+
+    fn create_vertex_buffer(&mut self, vertices: Vertices) -> BufferId {
+        let buffer = GlRenderer::create_gl_vertex_buffer(&mut self.factory, vertices);
+        return self.buffers.insert(buffer);
+    }
+
+    fn create_dynamic_vertex_buffer(&mut self, vertices: Vertices) -> error::Result<BufferId> {
+        let buffer = self.write_dynamic_vertex_buffer(None, vertices)?;
+        return Ok(self.buffers.insert(buffer));
+    }
+
+    fn update_vertex_buffer(&mut self, id: BufferId, vertices: Vertices) -> error::Result<()> {
+
+        let existing = self.buffers.take(id);
+
+        let buffer = if existing.as_ref().map(|b| b.capacity.is_some()).unwrap_or(false) {
+            self.write_dynamic_vertex_buffer(existing, vertices)?
+        } else {
+            GlRenderer::create_gl_vertex_buffer(&mut self.factory, vertices)
+        };
+
+        self.buffers.set(id, buffer);
+        return Ok(());
+
+    }
+
+    fn destroy_vertex_buffer(&mut self, id: BufferId) {
+        self.buffers.remove(id);
+    }
+
+    fn draw(&mut self, call: &DrawCall) {
+
+        let scissor = self.get_scissor();
+        let out = self.get_target_view();
+
+        let texture = match call.texture.and_then(|id| self.textures.get(id)) {
+            Some(view) => view.clone(),
+            None => self.blank_texture.clone(),
+        };
+
+        let model = call.model.get_data();
+        let view = call.view.get_data();
+        // Render targets are drawn upside down so that their first row is the top of the image, as with loaded textures.
+        let projection = match self.bound_target {
+            Some(_) => (Matrix4f::from_nonuniform_scale(1.0, -1.0, 1.0) * call.projection).get_data(),
+            None => call.projection.get_data(),
+        };
+
+        let buffer = match self.buffers.get(call.vertices) {
+            Some(buffer) => buffer,
+            None => panic!("The draw call refers to a missing vertex buffer!"),
+        };
+
+        let mut slice = buffer.slice.clone();
+        if let Some((start, end)) = call.range {
+            slice.start = start;
+            slice.end = end;
+        }
+        let slice = &slice;
+
+        match (self.pipelines.get(call.shader), &buffer.data) {
+            (Some(&GlPipeline::Texture(ref pso)), &GlVertexData::Uv2f(ref vbuf)) => {
+                let data = render::pipe::Data {
+                    vbuf: vbuf.clone(),
+                    tex: (texture, self.sampler.clone()),
+                    trans: self.texture_trans.clone(),
+                    out: out.clone(),
+                    scissor,
+                };
+                self.encoder.update_buffer(&data.trans, &[render::GeometryTransform { model, view, projection }], 0).unwrap(); //update buffers
+                self.encoder.draw(slice, pso, &data); // draw commands with buffer data and attached pso
+            },
+            (Some(&GlPipeline::Geometry(ref pso)), &GlVertexData::Color(ref vbuf)) => {
+                let data = geometry::pipe::Data {
+                    vbuf: vbuf.clone(),
+                    trans: self.geometry_trans.clone(),
+                    out: out.clone(),
+                    scissor,
+                };
+                self.encoder.update_buffer(&data.trans, &[geometry::GeometryTransform { model, view, projection }], 0).unwrap();
+                self.encoder.draw(slice, pso, &data);
+            },
+            (Some(&GlPipeline::Mesh(ref pso)), &GlVertexData::Uv3f(ref vbuf)) => {
+                let data = spatial::pipe::Data {
+                    vbuf: vbuf.clone(),
+                    tex: (texture, self.sampler.clone()),
+                    trans: self.mesh_trans.clone(),
+                    out: out.clone(),
+                    scissor,
+                };
+                self.encoder.update_buffer(&data.trans, &[spatial::MeshTransform { model, view, projection }], 0).unwrap();
+                self.encoder.draw(slice, pso, &data);
+            },
+            (Some(&GlPipeline::Text(ref pso)), &GlVertexData::Text(ref vbuf)) => {
+                let data = text::pipe::Data {
+                    vbuf: vbuf.clone(),
+                    tex: (texture, self.sampler.clone()),
+                    trans: self.text_trans.clone(),
+                    out: out.clone(),
+                    scissor,
+                };
+                self.encoder.update_buffer(&data.trans, &[text::TextTransform { model, view, projection }], 0).unwrap();
+                self.encoder.draw(slice, pso, &data);
+            },
+            _ => panic!("The draw call refers to a missing shader, or the shader and vertex buffer pipeline kinds do not match!"),
+        }
+
+    }
+
+    fn draw_distance_text(&mut self, call: &DistanceTextCall) {
+
+        let scissor = self.get_scissor();
+        let out = self.get_target_view();
+        let draw = &call.draw;
+
+        let texture = match draw.texture.and_then(|id| self.textures.get(id)) {
+            Some(view) => view.clone(),
+            None => self.blank_texture.clone(),
+        };
+
+        let model = draw.model.get_data();
+        let view = draw.view.get_data();
+        // Same as in draw, targets are drawn upside down.
+        let projection = match self.bound_target {
+            Some(_) => (Matrix4f::from_nonuniform_scale(1.0, -1.0, 1.0) * draw.projection).get_data(),
+            None => draw.projection.get_data(),
+        };
+
+        let buffer = match self.buffers.get(draw.vertices) {
+            Some(buffer) => buffer,
+            None => panic!("The draw call refers to a missing vertex buffer!"),
+        };
+
+        let mut slice = buffer.slice.clone();
+        if let Some((start, end)) = draw.range {
+            slice.start = start;
+            slice.end = end;
+        }
+
+        match (self.pipelines.get(draw.shader), &buffer.data) {
+            (Some(&GlPipeline::DistanceText(ref pso)), &GlVertexData::Text(ref vbuf)) => {
+                let data = text::distance_pipe::Data {
+                    vbuf: vbuf.clone(),
+                    tex: (texture, self.sampler.clone()),
+                    trans: self.text_trans.clone(),
+                    params: self.distance_params.clone(),
+                    out,
+                    scissor,
+                };
+                let params = text::DistanceParams { param0: call.params[0], param1: call.params[1], param2: call.params[2], param3: call.params[3] };
+                self.encoder.update_buffer(&data.trans, &[text::TextTransform { model, view, projection }], 0).unwrap();
+                self.encoder.update_buffer(&data.params, &[params], 0).unwrap();
+                self.encoder.draw(&slice, pso, &data);
+            },
+            _ => panic!("The draw call refers to a missing shader, or one that is not a distance field text shader!"),
+        }
+
+    }
+
+    fn draw_post_process(&mut self, call: &PostProcessCall) {
+
+        let scissor = self.get_scissor();
+        let out = self.get_target_view();
+        let (width, height, _, _) = out.get_dimensions();
+
+        let input = match self.textures.get(call.input) {
+            Some(view) => view.clone(),
+            None => self.blank_texture.clone(),
+        };
+        let secondary = match call.secondary.and_then(|id| self.textures.get(id)) {
+            Some(view) => view.clone(),
+            None => self.blank_texture.clone(),
+        };
+
+        // Same as in draw, targets are drawn upside down.
+        let &(ref vbuf, ref slice) = match self.bound_target {
+            Some(_) => &self.post_quad_flipped,
+            None => &self.post_quad,
+        };
+
+        let pso = match self.pipelines.get(call.shader) {
+            Some(&GlPipeline::Post(ref pso)) => pso,
+            _ => panic!("The post process call refers to a missing shader, or one that is not a post process shader!"),
+        };
+
+        let data = post::pipe::Data {
+            vbuf: vbuf.clone(),
+            input: (input, self.sampler.clone()),
+            secondary: (secondary, self.sampler.clone()),
+            params: self.post_params.clone(),
+            out,
+            scissor,
+        };
+        let params = post::PostParams {
+            param0: call.params[0],
+            param1: call.params[1],
+            param2: call.params[2],
+            param3: call.params[3],
+            resolution: [width as f32, height as f32],
+            time: call.time,
+            padding: 0.0,
+        };
+        self.encoder.update_buffer(&data.params, &[params], 0).unwrap();
+        self.encoder.draw(slice, pso, &data);
+
+    }
 
 }
 
+#[derive(Copy, Clone)]
 pub struct Camera {
 
     pub projection: Matrix4f,
@@ -56,13 +1075,170 @@ impl Camera {
 
     }
 
+    /**
+    Converts a position in window coordinates (from the top left corner) to world coordinates.
+    */
+    pub fn screen_to_world(&self, screen: Vector2f, dimensions: Vector2f) -> Vector2f {
+
+        let ndc = Vector4f::new(screen.x / dimensions.x * 2.0 - 1.0, 1.0 - screen.y / dimensions.y * 2.0, 0.0, 1.0);
+        let inverse = match (self.projection * self.view).invert() {
+            Some(inverse) => inverse,
+            None => return screen,
+        };
+        let world = inverse * ndc;
+        return Vector2f::new(world.x / world.w, world.y / world.w);
+
+    }
+
+    /**
+    The bounding box of the world area that is drawn to the output, including any area outside of the viewport.
+    */
+    pub fn get_visible_rect(&self) -> Rect {
+
+        let inverse = match (self.projection * self.view).invert() {
+            Some(inverse) => inverse,
+            None => return Rect::new(0.0, 0.0, 0.0, 0.0),
+        };
+
+        let corners: Vec<Vector2f> = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].iter().map(|&(x, y)| {
+            let world = inverse * Vector4f::new(x, y, 0.0, 1.0);
+            Vector2f::new(world.x / world.w, world.y / world.w)
+        }).collect();
+
+        let min_x = corners.iter().fold(::std::f32::INFINITY, |m, c| m.min(c.x));
+        let min_y = corners.iter().fold(::std::f32::INFINITY, |m, c| m.min(c.y));
+        let max_x = corners.iter().fold(::std::f32::NEG_INFINITY, |m, c| m.max(c.x));
+        let max_y = corners.iter().fold(::std::f32::NEG_INFINITY, |m, c| m.max(c.y));
+        return Rect::new(min_x, min_y, max_x - min_x, max_y - min_y);
+
+    }
+
+}
+
+/**
+How the world is mapped to the window. All modes except `Window` take the design resolution the game is made for, which is always fully visible.
+*/
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScalingMode {
+
+    /**
+    One world unit per window unit. Resizing the window shows more or less of the world.
+    */
+    Window,
+    /**
+    The design resolution fills the window, distorting it if the aspect ratio differs.
+    */
+    Stretch(Vector2f),
+    /**
+    The design resolution is scaled as much as it fits while keeping its aspect ratio, with bars on the sides.
+    */
+    Letterbox(Vector2f),
+    /**
+    Like letterbox, but only scaled by whole numbers so that pixel art stays sharp.
+    */
+    PixelPerfect(Vector2f),
+    /**
+    The design resolution is scaled as much as it fits while keeping its aspect ratio, and the rest of the window shows more of the world instead of bars.
+    */
+    Expand(Vector2f),
+
+}
+
+/**
+The result of applying a scaling mode to a window size.
+*/
+#[derive(Copy, Clone, Debug)]
+pub struct Scaling {
+
+    /**
+    The area of the window that is drawn to, with the origin at the bottom left.
+    */
+    pub viewport: Rect,
+    /**
+    The size of the world area shown in the viewport.
+    */
+    pub view_size: Vector2f,
+
+}
+
+impl ScalingMode {
+
+    pub fn compute(&self, window: Vector2f) -> Scaling {
+
+        let full = Rect::new(0.0, 0.0, window.x, window.y);
+
+        let fit = |design: Vector2f| -> f32 {
+            return (window.x / design.x).min(window.y / design.y);
+        };
+
+        let centered = |size: Vector2f| -> Rect {
+            return Rect::new(((window.x - size.x) / 2.0).floor(), ((window.y - size.y) / 2.0).floor(), size.x, size.y);
+        };
+
+        match *self {
+            ScalingMode::Window => Scaling { viewport: full, view_size: window },
+            ScalingMode::Stretch(design) => Scaling { viewport: full, view_size: design },
+            ScalingMode::Letterbox(design) => Scaling { viewport: centered(design * fit(design)), view_size: design },
+            ScalingMode::PixelPerfect(design) => {
+                // Windows smaller than the design resolution still show it at 1x, cropped.
+                let scale = fit(design).floor().max(1.0);
+                Scaling { viewport: centered(design * scale), view_size: design }
+            },
+            ScalingMode::Expand(design) => Scaling { viewport: full, view_size: window / fit(design) },
+        }
+
+    }
+
+}
+
+impl Scaling {
+
+    /**
+    The projection that maps the view (from (0, 0) to `view_size`) onto the viewport of a window of the given size.
+    */
+    pub fn get_projection(&self, window: Vector2f) -> Matrix4f {
+
+        let v = self.viewport;
+        let to_viewport = Matrix4f::from_translation(Vector3f::new((v.x + v.width / 2.0) / window.x * 2.0 - 1.0, (v.y + v.height / 2.0) / window.y * 2.0 - 1.0, 0.0))
+            * Matrix4f::from_nonuniform_scale(v.width / window.x, v.height / window.y, 1.0);
+
+        return to_viewport * cgmath::ortho(-self.view_size.x / 2.0, self.view_size.x / 2.0, -self.view_size.y / 2.0, self.view_size.y / 2.0, 100.0, -100.0)
+            * Matrix4f::from_translation(Vector3f::new(-self.view_size.x / 2.0, -self.view_size.y / 2.0, 0.0));
+
+    }
+
 }
 
 pub struct FlatEngine {
 
-    pub renderer: Renderer,
-    pub window: GlWindow,
-    pub events_loop: glutin::EventsLoop,
+    pub renderer: Box<Renderer>,
+    pub camera: Camera,
+    pub input: input::Input,
+    /**
+    Change with `set_scaling_mode`, so that the projection is updated.
+    */
+    pub scaling_mode: ScalingMode,
+    render_target: Option<RenderTargetId>,
+    /**
+    Immediate mode shapes, queued during the frame and drawn with `draw_shapes`.
+    */
+    pub shapes: geometry::ShapeRenderer,
+    /**
+    The glyphs of all `Text` nodes, packed into one shared texture.
+    */
+    pub glyphs: text::GlyphCache,
+    /**
+    The shaders compiled by `create_shader`.
+    */
+    pub shaders: ShaderCache,
+    /**
+    The textures uploaded by `create_texture`.
+    */
+    pub textures: TextureCache,
+    /**
+    Only present when the engine was created with a window.
+    */
+    pub events_loop: Option<glutin::EventsLoop>,
 
 }
 
@@ -71,53 +1247,228 @@ impl FlatEngine {
     /**
     Initialises the flat engine instance.
     */
-    pub fn init(window_builder: glutin::WindowBuilder) -> FlatEngine {
+    pub fn init(window_builder: glutin::WindowBuilder) -> error::Result<FlatEngine> {
+
+        let events_loop = glutin::EventsLoop::new();
+
+        // Same pixel format gfx_window_glutin::init asks for with Rgba8 colour and D24S8 depth.
+        let contextbuilder = glutin::ContextBuilder::new()
+            .with_gl(GlRequest::Specific(OpenGl,(3,2)))
+            .with_vsync(true)
+            .with_depth_buffer(24)
+            .with_stencil_buffer(8)
+            .with_pixel_format(24, 8)
+            .with_srgb(false);
+
+        let window = GlWindow::new(window_builder, contextbuilder, &events_loop).map_err(|e| Error::Window(format!("{}", e)))?;
+        unsafe { window.make_current() }.map_err(|e| Error::Window(format!("{:?}", e)))?;
+
+        if window.get_inner_size().is_none() {
+            return Err(Error::Window(String::from("The window was closed before the engine was initialised.")));
+        }
+
+        let (device, factory, color_view, depth_view) = gfx_window_glutin::init_existing::<ColorFormat, DepthFormat>(&window);
+
+        let renderer = GlRenderer::new(window, device, factory, color_view, depth_view)?;
+
+        return Ok(FlatEngine::from_renderer(Box::new(renderer), Some(events_loop)));
 
-        let mut events_loop = glutin::EventsLoop::new();
+    }
+
+    /**
+    Initialises a flat engine instance without a window, rendering on the CPU into an in-memory framebuffer.
+    */
+    pub fn headless(size: Vector2u) -> FlatEngine {
 
-        let contextbuilder = glutin::ContextBuilder::new().with_gl(GlRequest::Specific(OpenGl,(3,2))).with_vsync(true);
+        return FlatEngine::from_renderer(Box::new(software::SoftwareRenderer::new(size.x, size.y)), None);
 
-        let (window, mut device, mut factory, color_view, mut depth_view) = gfx_window_glutin::init::<ColorFormat, DepthFormat>(window_builder, contextbuilder, &events_loop);
+    }
 
-        let window_size: Vector2f = Vector2f { x: window.get_inner_size().unwrap().width as f32, y: window.get_inner_size().unwrap().height as f32 };
+    pub fn from_renderer(renderer: Box<Renderer>, events_loop: Option<glutin::EventsLoop>) -> FlatEngine {
 
-        // Put at the start of your file, outside of the loop
-        let mut encoder: gfx::Encoder<ResourceType, gfx_device_gl::CommandBuffer> = factory.create_command_buffer().into();
+        let window_size: Vector2f = renderer.get_dimensions();
 
         return FlatEngine {
-            renderer: Renderer { factory: factory, encoder: encoder, device: Box::new(device), render_view: color_view, depth_view: depth_view, camera: Camera::ortho(window_size) },
-            window: window,
-            events_loop: events_loop
+            renderer,
+            camera: Camera::ortho(window_size),
+            input: input::Input::new(),
+            scaling_mode: ScalingMode::Window,
+            render_target: None,
+            shapes: geometry::ShapeRenderer::new(),
+            glyphs: text::GlyphCache::new(1024, 1024),
+            shaders: ShaderCache::new(),
+            textures: TextureCache::new(),
+            events_loop
         };
 
     }
 
     pub fn clear(&mut self, color: Color) {
 
-        self.renderer.encoder.clear(&self.renderer.render_view, color.to_raw_color()); //clear the framebuffer with a color(color needs to be an array of 4 f32s, RGBa)
+        self.renderer.clear(color);
+
+    }
+
+    /**
+    Returns the compiled pipeline for the given shaders. Shaders are compiled once and shared between all callers.
+    */
+    pub fn create_shader(&mut self, kind: PipelineKind, v_shader: &[u8], f_shader: &[u8]) -> error::Result<ShaderId> {
+
+        if let Some(id) = self.shaders.get(kind, v_shader, f_shader) {
+            return Ok(id);
+        }
+
+        let id = self.renderer.create_shader(kind, v_shader, f_shader)?;
+        self.shaders.insert(kind, v_shader, f_shader, id);
+        return Ok(id);
+
+    }
+
+    /**
+    Uploads a texture, or returns the upload of an identical texture that is still in use. Free it with `destroy_texture`.
+    Shared uploads must not be changed with `Renderer::update_texture`, as that would change the texture for all of its users.
+    */
+    pub fn create_texture(&mut self, texture: &render::Texture) -> error::Result<TextureId> {
+
+        if let Some(id) = self.textures.acquire(texture) {
+            return Ok(id);
+        }
+
+        let id = self.renderer.create_texture(texture)?;
+        self.textures.insert(texture, id);
+        return Ok(id);
+
+    }
+
+    /**
+    Releases a texture from `create_texture`, destroying it once it has no users left.
+    */
+    pub fn destroy_texture(&mut self, id: TextureId) {
+
+        if self.textures.release(id) {
+            self.renderer.destroy_texture(id);
+        }
 
     }
 
     pub fn swap_buffers(&mut self) {
-        self.window.swap_buffers().unwrap();
-        self.renderer.device.cleanup();
+
+        self.renderer.swap_buffers();
 
     }
 
     pub fn flush(&mut self) {
-        self.renderer.encoder.flush(self.renderer.device.as_mut());
+        self.renderer.flush();
+    }
+
+    pub fn read_pixels(&mut self) -> error::Result<render::Texture> {
+
+        return self.renderer.read_pixels();
+
     }
 
+    /**
+    Resizes the framebuffer to the window and recomputes the projection for the scaling mode. Called automatically when the window is resized.
+    */
     pub fn update_size(&mut self) {
 
-        gfx_window_glutin::update_views(&self.window, &mut self.renderer.render_view, &mut self.renderer.depth_view);
+        self.renderer.update_size();
+        self.apply_scaling();
+
+    }
+
+    pub fn set_scaling_mode(&mut self, mode: ScalingMode) {
+
+        self.scaling_mode = mode;
+        self.apply_scaling();
+
+    }
+
+    pub fn get_scaling(&self) -> Scaling {
+
+        return self.scaling_mode.compute(self.get_dimensions());
+
+    }
+
+    fn apply_scaling(&mut self) {
+
+        let window = self.get_dimensions();
+        let scaling = self.scaling_mode.compute(window);
+
+        self.renderer.set_viewport(if self.scaling_mode == ScalingMode::Window { None } else { Some(scaling.viewport) });
+        self.camera.projection = scaling.get_projection(window);
+
+    }
+
+    /**
+    The size of the world area visible in the window, without any camera transform.
+    */
+    pub fn get_view_size(&self) -> Vector2f {
+
+        return self.get_scaling().view_size;
+
+    }
+
+    /**
+    Converts a position in window coordinates (from the top left corner) to view coordinates (from the top left corner of the viewport, in world units), as used by `Camera2D`.
+    */
+    pub fn window_to_view(&self, pos: Vector2f) -> Vector2f {
+
+        let window = self.get_dimensions();
+        let scaling = self.get_scaling();
+        let v = scaling.viewport;
+        let top = window.y - v.y - v.height;
+        return Vector2f::new((pos.x - v.x) / v.width * scaling.view_size.x, (pos.y - top) / v.height * scaling.view_size.y);
+
+    }
+
+    /**
+    Makes the engine render through a 2D camera, sizing the camera to the visible area.
+    */
+    pub fn apply_camera(&mut self, camera: &mut camera::Camera2D) {
+
+        camera.set_size(self.get_view_size());
+        self.camera.view = camera.get_view();
+
+    }
+
+    /**
+    Polls the window events, updating `input` before passing each event on to the callback.
+    This should be called once per frame, as it also resets the just pressed and just released state of the input.
+    */
+    pub fn poll_events<F>(&mut self, mut callback: F) where F: FnMut(&glutin::Event) {
+
+        self.input.begin_frame();
+
+        let mut resized = false;
+
+        if let Some(ref mut events_loop) = self.events_loop {
+            let input = &mut self.input;
+            events_loop.poll_events(|event| {
+                if let glutin::Event::WindowEvent { ref event, .. } = event {
+                    match *event {
+                        glutin::WindowEvent::Resized(_) | glutin::WindowEvent::HiDpiFactorChanged(_) => resized = true,
+                        _ => (),
+                    }
+                }
+                input.handle_event(&event);
+                callback(&event);
+            });
+        }
+
+        // Resize before the callback's frame is drawn, so it is never drawn with the old size.
+        if resized {
+            self.update_size();
+        }
+
+        let dimensions = self.get_dimensions();
+        self.input.update_cursor_world_pos(&self.camera, dimensions);
 
     }
 
     pub fn get_dimensions(&self) -> Vector2f {
 
-        let size = self.window.get_inner_size().unwrap();
-        return Vector2f { x: size.width as f32, y: size.height as f32 };
+        return self.renderer.get_dimensions();
 
     }
 
@@ -139,9 +1490,65 @@ impl FlatEngine {
 
     }
 
-    pub fn load(&mut self, drawable: &mut Drawable) {
+    /**
+    Makes clear and draw go to a render target, or back to the window if None. Unlike `render_to`, the camera is left as it is.
+    */
+    pub fn set_render_target(&mut self, target: Option<RenderTargetId>) {
+
+        self.render_target = target;
+        self.renderer.set_render_target(target);
+
+    }
+
+    pub fn get_render_target(&self) -> Option<RenderTargetId> {
+
+        return self.render_target;
+
+    }
+
+    /**
+    Draws into a render target instead of the window for the duration of the closure.
+    The camera is replaced by one that covers the target (one unit per pixel), and restored afterwards.
+    */
+    pub fn render_to<F>(&mut self, target: &render::RenderTarget, draw: F) where F: FnOnce(&mut FlatEngine) {
+
+        let camera = self.camera;
+        let previous = self.render_target;
+        let size = target.get_size();
+
+        self.camera = Camera::ortho(Vector2f::new(size.x as f32, size.y as f32));
+        self.set_render_target(Some(target.get_id()));
+
+        draw(self);
+
+        self.set_render_target(previous);
+        self.camera = camera;
+
+    }
+
+    /**
+    Draws the shapes queued in `shapes` with the current camera and clears them.
+    */
+    pub fn draw_shapes(&mut self) -> error::Result<()> {
+
+        // The shape renderer needs the engine to draw, so it is taken out while it does.
+        let mut shapes = ::std::mem::replace(&mut self.shapes, geometry::ShapeRenderer::new());
+        if !shapes.is_loaded() {
+            if let Err(e) = shapes.load(self) {
+                self.shapes = shapes;
+                return Err(e);
+            }
+        }
+        shapes.render(self);
+        self.shapes = shapes;
+
+        return Ok(());
 
-        drawable.load(self)
+    }
+
+    pub fn load(&mut self, drawable: &mut Drawable) -> error::Result<()> {
+
+        return drawable.load(self);
 
     }
 
@@ -161,10 +1568,10 @@ impl FlatEngine {
 
 pub trait Drawable {
 
-    fn load(&mut self, engine: &mut FlatEngine);
+    fn load(&mut self, engine: &mut FlatEngine) -> error::Result<()>;
 
     fn render(&mut self, engine: &mut FlatEngine);
 
     fn destroy(&mut self, engine: &mut FlatEngine);
 
-}
\ No newline at end of file
+}
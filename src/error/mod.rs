@@ -0,0 +1,117 @@
+use super::*;
+
+use std::error;
+use std::fmt;
+use std::io;
+
+/**
+The error type for everything in the engine that can fail at runtime, such as loading assets or creating the window.
+*/
+#[derive(Debug)]
+pub enum Error {
+
+    Io(io::Error),
+    Image(image::ImageError),
+    /**
+    A shader failed to compile or the program failed to link.
+    */
+    Shader(String),
+    Font(String),
+    Window(String),
+    /**
+    A texture could not be created on the GPU, for example because it is too large.
+    */
+    Texture(String),
+    /**
+    A config or data file is malformed.
+    */
+    Parse(String),
+    /**
+    The renderer could not create or write another resource, such as a vertex buffer.
+    */
+    Renderer(String),
+
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref e) => write!(f, "I/O error: {}", e),
+            Error::Image(ref e) => write!(f, "Image error: {}", e),
+            Error::Shader(ref e) => write!(f, "Shader error: {}", e),
+            Error::Font(ref e) => write!(f, "Font error: {}", e),
+            Error::Window(ref e) => write!(f, "Window error: {}", e),
+            Error::Texture(ref e) => write!(f, "Texture error: {}", e),
+            Error::Parse(ref e) => write!(f, "Parse error: {}", e),
+            Error::Renderer(ref e) => write!(f, "Renderer error: {}", e),
+        }
+    }
+
+}
+
+impl error::Error for Error {
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            Error::Io(ref e) => Some(e),
+            Error::Image(ref e) => Some(e),
+            _ => None,
+        }
+    }
+
+}
+
+impl From<io::Error> for Error {
+
+    fn from(e: io::Error) -> Error {
+        return Error::Io(e);
+    }
+
+}
+
+impl From<image::ImageError> for Error {
+
+    fn from(e: image::ImageError) -> Error {
+        // The image crate wraps I/O errors, keep them as such.
+        match e {
+            image::ImageError::IoError(e) => Error::Io(e),
+            e => Error::Image(e),
+        }
+    }
+
+}
+
+impl From<serde_json::Error> for Error {
+
+    fn from(e: serde_json::Error) -> Error {
+        return Error::Parse(format!("{}", e));
+    }
+
+}
+
+impl From<xml::reader::Error> for Error {
+
+    fn from(e: xml::reader::Error) -> Error {
+        return Error::Parse(format!("{}", e));
+    }
+
+}
+
+impl From<base64::DecodeError> for Error {
+
+    fn from(e: base64::DecodeError) -> Error {
+        return Error::Parse(format!("{}", e));
+    }
+
+}
+
+impl From<rusttype::Error> for Error {
+
+    fn from(e: rusttype::Error) -> Error {
+        return Error::Font(format!("{}", e));
+    }
+
+}
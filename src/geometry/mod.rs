@@ -3,15 +3,8 @@ use super::*;
 use self::node::*;
 
 use self::types::*;
-use gfx::traits::FactoryExt;
-use glutin::dpi::*;
-use gfx_window_glutin;
-use self::gfx::Device;
-use self::gfx::{Factory};
-use self::glutin::{GlContext, GlRequest};
-use self::glutin::Api::OpenGl;
-use self::glutin::GlWindow;
-use std::convert::AsMut;
+use std::cmp::Ordering;
+use std::f32::consts::PI;
 
 gfx_defines!{
 
@@ -31,51 +24,41 @@ gfx_defines!{
     pipeline pipe {
         vbuf: gfx::VertexBuffer<Vertex> = (),
         trans: gfx::ConstantBuffer<GeometryTransform> = "Transform",
-        out: gfx::RenderTarget<ColorFormat> = "Target0",
+        out: gfx::BlendTarget<ColorFormat> = ("Target0", gfx::state::ColorMask::all(), gfx::preset::blend::ALPHA),
+        scissor: gfx::Scissor = (),
     }
 }
 
 pub struct GeometryRenderer {
 
-    data: geometry::pipe::Data<ResourceType>,
-    slice: gfx::Slice<ResourceType>,
-    pipeline_state: gfx::PipelineState<ResourceType, geometry::pipe::Meta>
+    shader: core::ShaderId,
+    vertices: core::BufferId,
 
 }
 
 impl GeometryRenderer {
 
-    pub fn new(data: geometry::pipe::Data<ResourceType>, slice: gfx::Slice<ResourceType>, pipeline_state: gfx::PipelineState<ResourceType, geometry::pipe::Meta>) -> GeometryRenderer {
-        return GeometryRenderer { data, slice, pipeline_state };
+    pub fn new(shader: core::ShaderId, vertices: core::BufferId) -> GeometryRenderer {
+        return GeometryRenderer { shader, vertices };
     }
 
-    pub fn from_vertices(vertices: &[Vertex], v_shader: &[u8], f_shader: &[u8], engine: &mut core::FlatEngine) -> GeometryRenderer {
+    pub fn from_vertices(vertices: &[Vertex], v_shader: &[u8], f_shader: &[u8], engine: &mut core::FlatEngine) -> error::Result<GeometryRenderer> {
         // Load shaders.
-        let pipeline_state = engine.renderer.factory
-            .create_pipeline_simple(
-                v_shader,
-                f_shader,
-                pipe::new(),
-            )
-            .unwrap();
-
-        let (vertex_buffer, slice) = engine.renderer.factory.create_vertex_buffer_with_slice(vertices, ());
-        let trans_buffer = engine.renderer.factory.create_constant_buffer(1);
-        let data = pipe::Data {
-            vbuf: vertex_buffer,
-            trans: trans_buffer,
-            out: engine.renderer.render_view.clone(),
-        };
+        let shader = engine.create_shader(core::PipelineKind::Geometry, v_shader, f_shader)?;
+        let vertices = engine.renderer.create_vertex_buffer(core::Vertices::Color(vertices));
 
-        return GeometryRenderer::new(data, slice, pipeline_state);
+        return Ok(GeometryRenderer::new(shader, vertices));
 
     }
 
     // Automatically applies global Matrix4f to the render.
     pub fn render(&mut self, model_trans: Matrix4f, view_trans: Matrix4f, projection_trans: Matrix4f, engine: &mut core::FlatEngine) {
-        engine.renderer.encoder.update_buffer(&self.data.trans, &[GeometryTransform { model: model_trans.get_data(), view: view_trans.get_data(), projection: projection_trans.get_data() }], 0); //update buffers
-        engine.renderer.encoder.draw(&self.slice, &mut self.pipeline_state, &self.data); // draw commands with buffer data and attached pso
-        engine.renderer.encoder.flush(engine.renderer.device.as_mut()); // execute draw commands
+        engine.renderer.draw(&core::DrawCall { shader: self.shader, vertices: self.vertices, texture: None, model: model_trans, view: view_trans, projection: projection_trans, range: None }); // draw commands with buffer data and attached pso
+        engine.renderer.flush(); // execute draw commands
+    }
+
+    pub fn destroy(&mut self, renderer: &mut core::Renderer) {
+        renderer.destroy_vertex_buffer(self.vertices);
     }
 
 }
@@ -84,7 +67,6 @@ pub struct Triangle {
 
     node: NodeObject2D,
     vertices: [Vertex; 3],
-    color: Color,
     geometry_renderer: Option<GeometryRenderer>
 
 
@@ -101,7 +83,6 @@ impl Triangle {
                 Vertex { pos: [  0.5, -0.5 ], color: color.to_raw_color() },
                 Vertex { pos: [  0.0,  0.5], color: color.to_raw_color() },
             ],
-            color: color,
             geometry_renderer: None
 
         };
@@ -112,15 +93,16 @@ impl Triangle {
 
 impl core::Drawable for Triangle {
 
-    fn load(&mut self, engine: &mut core::FlatEngine) {
-        self.geometry_renderer = Some(GeometryRenderer::from_vertices(&self.vertices, include_bytes!("../../shaders/std_geom_v.glsl"), include_bytes!("../../shaders/std_geom_f.glsl"), engine));
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+        self.geometry_renderer = Some(GeometryRenderer::from_vertices(&self.vertices, include_bytes!("../../shaders/std_geom_v.glsl"), include_bytes!("../../shaders/std_geom_f.glsl"), engine)?);
+        return Ok(());
     }
 
     fn render(&mut self, engine: &mut core::FlatEngine) {
 
         // Check if all neccessary parts have been initialized.
         if self.geometry_renderer.is_some() {
-            self.geometry_renderer.as_mut().unwrap().render(self.node.get_trans(), engine.renderer.camera.view, engine.renderer.camera.projection, engine);
+            self.geometry_renderer.as_mut().unwrap().render(self.node.get_world_trans(), engine.camera.view, engine.camera.projection, engine);
         } else {
             panic!("The triangle object is being drawn before it has been initialized!");
         }
@@ -129,6 +111,10 @@ impl core::Drawable for Triangle {
 
     fn destroy(&mut self, engine: &mut core::FlatEngine) {
 
+        if let Some(mut geometry_renderer) = self.geometry_renderer.take() {
+            geometry_renderer.destroy(engine.renderer.as_mut());
+        }
+
     }
 
 }
@@ -144,3 +130,1415 @@ impl Node2D for Triangle {
     }
 
 }
+
+/**
+How the segments of a stroked line are connected.
+*/
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LineJoin {
+
+    /**
+    Extends the edges until they meet, falling back to a bevel for very sharp corners.
+    */
+    Miter,
+    /**
+    Cuts the corner off.
+    */
+    Bevel,
+    Round,
+
+}
+
+/**
+How the ends of an open stroked line are drawn.
+*/
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LineCap {
+
+    /**
+    Ends exactly at the end points.
+    */
+    Butt,
+    /**
+    Extends past the end points by half the thickness.
+    */
+    Square,
+    Round,
+
+}
+
+/**
+How far a miter may reach from the corner, in multiples of half the thickness, before it is beveled.
+*/
+const MITER_LIMIT: f32 = 4.0;
+
+fn cross(o: Vector2f, a: Vector2f, b: Vector2f) -> f32 {
+    return (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+}
+
+/**
+Twice the signed area of a polygon, positive when its points are counter-clockwise.
+*/
+fn signed_area(points: &[Vector2f]) -> f32 {
+
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        let (a, b) = (points[i], points[(i + 1) % points.len()]);
+        area += a.x * b.y - b.x * a.y;
+    }
+    return area;
+
+}
+
+/**
+The number of segments a full circle is drawn with so that no segment is further than the tolerance from the circle.
+*/
+fn circle_segments(radius: f32, tolerance: f32) -> usize {
+
+    if radius <= tolerance {
+        return 8;
+    }
+    let step = 2.0 * (1.0 - tolerance / radius).acos();
+    return ((2.0 * PI / step).ceil() as usize).max(8).min(1024);
+
+}
+
+/**
+Splits a simple polygon, convex or concave, into triangles by ear clipping. The triangles index into `points` and are counter-clockwise.
+Self-intersecting polygons are triangulated as far as possible and the rest is filled as a fan.
+*/
+pub fn triangulate(points: &[Vector2f]) -> Vec<[usize; 3]> {
+
+    let mut triangles: Vec<[usize; 3]> = Vec::new();
+    if points.len() < 3 {
+        return triangles;
+    }
+
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    if signed_area(points) < 0.0 {
+        remaining.reverse();
+    }
+
+    while remaining.len() > 3 {
+
+        let n = remaining.len();
+        let mut clipped = false;
+
+        for i in 0..n {
+
+            let (prev, current, next) = (remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]);
+            let (a, b, c) = (points[prev], points[current], points[next]);
+
+            // Reflex corners are not ears.
+            if cross(a, b, c) <= 0.0 {
+                continue;
+            }
+
+            // Neither is a corner with another point of the polygon inside it.
+            let contains_point = remaining.iter().any(|&j| {
+                if j == prev || j == current || j == next {
+                    return false;
+                }
+                let p = points[j];
+                return cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0;
+            });
+
+            if !contains_point {
+                triangles.push([prev, current, next]);
+                remaining.remove(i);
+                clipped = true;
+                break;
+            }
+
+        }
+
+        if !clipped {
+            break;
+        }
+
+    }
+
+    for i in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+    }
+    return triangles;
+
+}
+
+/**
+Draws filled and outlined shapes in immediate mode. Shapes are added during the frame in world space and drawn together from one dynamic vertex buffer when the renderer is rendered, which clears them.
+The engine has one in `FlatEngine::shapes`, drawn with `FlatEngine::draw_shapes`.
+Angles are in radians, counter-clockwise from the x axis.
+*/
+pub struct ShapeRenderer {
+
+    vertices: Vec<Vertex>,
+    /**
+    How far curves may be from the polygons they are drawn as, in world units. Lower values use more vertices.
+    */
+    pub tolerance: f32,
+    shader: Option<core::ShaderId>,
+    buffer: Option<core::BufferId>,
+
+}
+
+impl ShapeRenderer {
+
+    pub fn new() -> ShapeRenderer {
+
+        return ShapeRenderer { vertices: Vec::new(), tolerance: 0.25, shader: None, buffer: None };
+
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        return self.shader.is_some();
+    }
+
+    /**
+    The number of vertices queued for the next render.
+    */
+    pub fn get_vertex_count(&self) -> usize {
+        return self.vertices.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    /**
+    The number of segments a full circle of the radius is drawn with.
+    */
+    fn get_segments(&self, radius: f32) -> usize {
+        return circle_segments(radius, self.tolerance);
+    }
+
+    fn push_vertex(&mut self, pos: Vector2f, color: [f32; 4]) {
+        self.vertices.push(Vertex { pos: [pos.x, pos.y], color });
+    }
+
+    fn push_triangle(&mut self, points: [Vector2f; 3], colors: [[f32; 4]; 3]) {
+
+        for i in 0..3 {
+            self.push_vertex(points[i], colors[i]);
+        }
+
+    }
+
+    /**
+    Points along an elliptical arc, including both ends.
+    */
+    fn arc_points(&self, center: Vector2f, radii: Vector2f, start: f32, end: f32) -> Vec<Vector2f> {
+
+        let sweep = end - start;
+        let count = ((self.get_segments(radii.x.max(radii.y)) as f32 * sweep.abs() / (2.0 * PI)).ceil() as usize).max(1);
+        return (0..count + 1).map(|i| {
+            let angle = start + sweep * i as f32 / count as f32;
+            return Vector2f::new(center.x + radii.x * angle.cos(), center.y + radii.y * angle.sin());
+        }).collect();
+
+    }
+
+    fn ellipse_points(&self, center: Vector2f, radii: Vector2f) -> Vec<Vector2f> {
+
+        let mut points = self.arc_points(center, radii, 0.0, 2.0 * PI);
+        points.pop();
+        return points;
+
+    }
+
+    fn rounded_rect_points(&self, rect: Rect, radius: f32) -> Vec<Vector2f> {
+
+        let r = radius.min(rect.width / 2.0).min(rect.height / 2.0).max(0.0);
+        let (left, bottom, right, top) = (rect.x, rect.y, rect.x + rect.width, rect.y + rect.height);
+        if r <= 0.0 {
+            return vec![Vector2f::new(left, bottom), Vector2f::new(right, bottom), Vector2f::new(right, top), Vector2f::new(left, top)];
+        }
+
+        let corners = [(right - r, bottom + r), (right - r, top - r), (left + r, top - r), (left + r, bottom + r)];
+        let mut points: Vec<Vector2f> = Vec::new();
+        for (i, &(x, y)) in corners.iter().enumerate() {
+            let start = -PI / 2.0 + i as f32 * PI / 2.0;
+            points.extend(self.arc_points(Vector2f::new(x, y), Vector2f::new(r, r), start, start + PI / 2.0));
+        }
+        return points;
+
+    }
+
+    /**
+    A fan of triangles from a center to the outline of a convex shape.
+    */
+    fn fill_fan(&mut self, center: Vector2f, outline: &[Vector2f], color: [f32; 4], closed: bool) {
+
+        let count = if closed { outline.len() } else { outline.len().saturating_sub(1) };
+        for i in 0..count {
+            self.push_triangle([center, outline[i], outline[(i + 1) % outline.len()]], [color; 3]);
+        }
+
+    }
+
+    /**
+    A round end or join, as a fan around a point from one angle sweeping to another.
+    */
+    fn fill_round(&mut self, center: Vector2f, radius: f32, start: f32, sweep: f32, color: [f32; 4]) {
+
+        let points = self.arc_points(center, Vector2f::new(radius, radius), start, start + sweep);
+        self.fill_fan(center, &points, color, false);
+
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+
+        self.fill_rect_colors(rect, [color; 4]);
+
+    }
+
+    /**
+    A rectangle with a color for each corner, starting at the bottom left and going counter-clockwise.
+    */
+    pub fn fill_rect_colors(&mut self, rect: Rect, colors: [Color; 4]) {
+
+        let corners = [Vector2f::new(rect.x, rect.y), Vector2f::new(rect.x + rect.width, rect.y), Vector2f::new(rect.x + rect.width, rect.y + rect.height), Vector2f::new(rect.x, rect.y + rect.height)];
+        let colors = [colors[0].to_raw_color(), colors[1].to_raw_color(), colors[2].to_raw_color(), colors[3].to_raw_color()];
+        self.push_triangle([corners[0], corners[1], corners[2]], [colors[0], colors[1], colors[2]]);
+        self.push_triangle([corners[0], corners[2], corners[3]], [colors[0], colors[2], colors[3]]);
+
+    }
+
+    /**
+    The outline of a rectangle, centered on its edges.
+    */
+    pub fn stroke_rect(&mut self, rect: Rect, thickness: f32, color: Color) {
+
+        let points = self.rounded_rect_points(rect, 0.0);
+        self.stroke_polygon(&points, thickness, LineJoin::Miter, color);
+
+    }
+
+    pub fn fill_rounded_rect(&mut self, rect: Rect, radius: f32, color: Color) {
+
+        let points = self.rounded_rect_points(rect, radius);
+        self.fill_fan(Vector2f::new(rect.x + rect.width / 2.0, rect.y + rect.height / 2.0), &points, color.to_raw_color(), true);
+
+    }
+
+    pub fn stroke_rounded_rect(&mut self, rect: Rect, radius: f32, thickness: f32, color: Color) {
+
+        let points = self.rounded_rect_points(rect, radius);
+        self.stroke_polygon(&points, thickness, LineJoin::Miter, color);
+
+    }
+
+    pub fn fill_circle(&mut self, center: Vector2f, radius: f32, color: Color) {
+
+        self.fill_ellipse(center, Vector2f::new(radius, radius), color);
+
+    }
+
+    pub fn stroke_circle(&mut self, center: Vector2f, radius: f32, thickness: f32, color: Color) {
+
+        self.stroke_ellipse(center, Vector2f::new(radius, radius), thickness, color);
+
+    }
+
+    pub fn fill_ellipse(&mut self, center: Vector2f, radii: Vector2f, color: Color) {
+
+        let points = self.ellipse_points(center, radii);
+        self.fill_fan(center, &points, color.to_raw_color(), true);
+
+    }
+
+    pub fn stroke_ellipse(&mut self, center: Vector2f, radii: Vector2f, thickness: f32, color: Color) {
+
+        let points = self.ellipse_points(center, radii);
+        self.stroke_polygon(&points, thickness, LineJoin::Miter, color);
+
+    }
+
+    /**
+    A pie slice from the start angle to the end angle.
+    */
+    pub fn fill_arc(&mut self, center: Vector2f, radius: f32, start: f32, end: f32, color: Color) {
+
+        let points = self.arc_points(center, Vector2f::new(radius, radius), start, end);
+        self.fill_fan(center, &points, color.to_raw_color(), false);
+
+    }
+
+    pub fn stroke_arc(&mut self, center: Vector2f, radius: f32, start: f32, end: f32, thickness: f32, color: Color) {
+
+        let points = self.arc_points(center, Vector2f::new(radius, radius), start, end);
+        self.polyline(&points, thickness, LineJoin::Miter, LineCap::Butt, color);
+
+    }
+
+    pub fn line(&mut self, from: Vector2f, to: Vector2f, thickness: f32, color: Color) {
+
+        self.polyline(&[from, to], thickness, LineJoin::Miter, LineCap::Butt, color);
+
+    }
+
+    pub fn polyline(&mut self, points: &[Vector2f], thickness: f32, join: LineJoin, cap: LineCap, color: Color) {
+
+        self.stroke(points, &[color.to_raw_color()], thickness, join, cap, false);
+
+    }
+
+    /**
+    The outline of a polygon, closed back to its first point.
+    */
+    pub fn stroke_polygon(&mut self, points: &[Vector2f], thickness: f32, join: LineJoin, color: Color) {
+
+        self.stroke(points, &[color.to_raw_color()], thickness, join, LineCap::Butt, true);
+
+    }
+
+    /**
+    A polyline with a color for each point, blended along the segments.
+    */
+    pub fn polyline_colors(&mut self, points: &[Vector2f], colors: &[Color], thickness: f32, join: LineJoin, cap: LineCap, closed: bool) {
+
+        let colors: Vec<[f32; 4]> = colors.iter().map(|c| c.to_raw_color()).collect();
+        self.stroke(points, &colors, thickness, join, cap, closed);
+
+    }
+
+    /**
+    Fills a simple polygon, convex or concave, in either winding order.
+    */
+    pub fn fill_polygon(&mut self, points: &[Vector2f], color: Color) {
+
+        self.fill_polygon_colors(points, &[color]);
+
+    }
+
+    /**
+    A polygon with a color for each point. If there are fewer colors than points the last color is repeated.
+    */
+    pub fn fill_polygon_colors(&mut self, points: &[Vector2f], colors: &[Color]) {
+
+        if colors.is_empty() {
+            return;
+        }
+        let color = |i: usize| colors[i.min(colors.len() - 1)].to_raw_color();
+        for triangle in triangulate(points) {
+            self.push_triangle([points[triangle[0]], points[triangle[1]], points[triangle[2]]], [color(triangle[0]), color(triangle[1]), color(triangle[2])]);
+        }
+
+    }
+
+    pub fn fill_triangle(&mut self, points: [Vector2f; 3], colors: [Color; 3]) {
+
+        self.push_triangle(points, [colors[0].to_raw_color(), colors[1].to_raw_color(), colors[2].to_raw_color()]);
+
+    }
+
+    pub fn fill_path(&mut self, path: &Path, rule: FillRule, color: Color) {
+
+        let vertices = path.fill(rule, self.tolerance, color);
+        self.vertices.extend(vertices);
+
+    }
+
+    pub fn stroke_path(&mut self, path: &Path, style: &StrokeStyle, color: Color) {
+
+        let color = color.to_raw_color();
+        for contour in path.flatten(self.tolerance) {
+            if style.dashes.iter().any(|&d| d < 0.0) || style.dashes.iter().sum::<f32>() <= 0.0 {
+                self.stroke(&contour.points, &[color], style.thickness, style.join, style.cap, contour.closed);
+            } else {
+                for dash in contour.dash(&style.dashes, style.dash_offset) {
+                    self.stroke(&dash, &[color], style.thickness, style.join, style.cap, false);
+                }
+            }
+        }
+
+    }
+
+    /**
+    Builds a stroke as a quad per segment, with triangles filling the outside of each corner.
+    On the inside of a corner the quads end where their edges cross, so that translucent strokes do not overlap themselves.
+    Only corners too sharp for the length of their segments still overlap there.
+    `colors` has a color per point, with the last one repeated if there are fewer.
+    */
+    fn stroke(&mut self, points: &[Vector2f], colors: &[[f32; 4]], thickness: f32, join: LineJoin, cap: LineCap, closed: bool) {
+
+        if colors.is_empty() || thickness <= 0.0 {
+            return;
+        }
+
+        // Repeated points have no direction to offset the stroke by.
+        let mut line: Vec<(Vector2f, [f32; 4])> = Vec::new();
+        for (i, &point) in points.iter().enumerate() {
+            if line.last().map(|l| (l.0 - point).magnitude2() > 1e-12).unwrap_or(true) {
+                line.push((point, colors[i.min(colors.len() - 1)]));
+            }
+        }
+        if closed && line.len() > 2 && (line[0].0 - line[line.len() - 1].0).magnitude2() <= 1e-12 {
+            line.pop();
+        }
+
+        let h = thickness / 2.0;
+        let n = line.len();
+        if n < 2 {
+            // A single point only shows with a round or square cap.
+            if let Some(&(point, color)) = line.first() {
+                match cap {
+                    LineCap::Round => self.fill_round(point, h, 0.0, 2.0 * PI, color),
+                    LineCap::Square => self.fill_rect_colors(Rect::new(point.x - h, point.y - h, thickness, thickness), [Color { r: color[0], g: color[1], b: color[2], a: color[3] }; 4]),
+                    LineCap::Butt => (),
+                }
+            }
+            return;
+        }
+
+        // The side of each corner that is on the outside of the turn (1 for the left), and where the edges on the inside cross.
+        let mut turns: Vec<Option<(f32, Option<Vector2f>)>> = vec![None; n];
+        let corners = if closed { 0..n } else { 1..n - 1 };
+        for i in corners.clone() {
+
+            let (prev, point, next) = (line[(i + n - 1) % n].0, line[i].0, line[(i + 1) % n].0);
+            let d0 = (point - prev).normalize();
+            let d1 = (next - point).normalize();
+            let turn = d0.x * d1.y - d0.y * d1.x;
+            if turn.abs() < 1e-6 && d0.dot(d1) > 0.0 {
+                continue;
+            }
+
+            // The gap to fill is on the outside of the turn, to the right of a left turn.
+            let side = if turn > 0.0 { -1.0 } else { 1.0 };
+            let n0 = Vector2f::new(-d0.y, d0.x) * h * side;
+            let n1 = Vector2f::new(-d1.y, d1.x) * h * side;
+
+            // The inner edges cross h * tan(half the turn) along each segment. Half of a segment is left for the corner at its other end.
+            let bisector = n0 + n1;
+            let cos = if bisector.magnitude2() > 1e-12 { bisector.normalize().dot(n0) / h } else { 0.0 };
+            let reach = (point - prev).magnitude().min((next - point).magnitude()) / 2.0;
+            let inner = if cos > 1e-6 && h * (1.0 - cos * cos).sqrt() / cos <= reach {
+                Some(point - bisector.normalize() * (h / cos))
+            } else {
+                None
+            };
+            turns[i] = Some((side, inner));
+
+        }
+
+        let segments = if closed { n } else { n - 1 };
+        for i in 0..segments {
+
+            let (mut a, a_color) = line[i];
+            let (mut b, b_color) = line[(i + 1) % n];
+            let dir = (b - a).normalize();
+            let normal = Vector2f::new(-dir.y, dir.x) * h;
+
+            if !closed && cap == LineCap::Square {
+                if i == 0 {
+                    a = a - dir * h;
+                }
+                if i == segments - 1 {
+                    b = b + dir * h;
+                }
+            }
+
+            // The right and left corners of each end, moved to where the edges cross on the inside of a turn.
+            let mut start = [a - normal, a + normal];
+            let mut end = [b - normal, b + normal];
+            if let Some((side, Some(inner))) = turns[i] {
+                start[if side > 0.0 { 0 } else { 1 }] = inner;
+            }
+            if let Some((side, Some(inner))) = turns[(i + 1) % n] {
+                end[if side > 0.0 { 0 } else { 1 }] = inner;
+            }
+
+            self.push_triangle([start[0], end[0], end[1]], [a_color, b_color, b_color]);
+            self.push_triangle([start[0], end[1], start[1]], [a_color, b_color, a_color]);
+
+        }
+
+        for i in corners {
+
+            let (side, inner) = match turns[i] {
+                Some(turn) => turn,
+                None => continue,
+            };
+
+            let (prev, (point, color), next) = (line[(i + n - 1) % n].0, line[i], line[(i + 1) % n].0);
+            let d0 = (point - prev).normalize();
+            let d1 = (next - point).normalize();
+            let n0 = Vector2f::new(-d0.y, d0.x) * h * side;
+            let n1 = Vector2f::new(-d1.y, d1.x) * h * side;
+
+            // The quads end at the inner crossing, which leaves the triangles between it and the point to fill.
+            if let Some(inner) = inner {
+                self.push_triangle([inner, point + n0, point], [color; 3]);
+                self.push_triangle([inner, point, point + n1], [color; 3]);
+            }
+
+            match join {
+                LineJoin::Round => {
+                    let start = n0.y.atan2(n0.x);
+                    let mut sweep = n1.y.atan2(n1.x) - start;
+                    while sweep > PI {
+                        sweep -= 2.0 * PI;
+                    }
+                    while sweep < -PI {
+                        sweep += 2.0 * PI;
+                    }
+                    self.fill_round(point, h, start, sweep, color);
+                },
+                LineJoin::Miter | LineJoin::Bevel => {
+                    let bisector = n0 + n1;
+                    let cos = if bisector.magnitude2() > 1e-12 { bisector.normalize().dot(n0) / h } else { 0.0 };
+                    if join == LineJoin::Miter && cos > 1.0 / MITER_LIMIT {
+                        let miter = point + bisector.normalize() * (h / cos);
+                        self.push_triangle([point, point + n0, miter], [color; 3]);
+                        self.push_triangle([point, miter, point + n1], [color; 3]);
+                    } else {
+                        self.push_triangle([point, point + n0, point + n1], [color; 3]);
+                    }
+                },
+            }
+
+        }
+
+        if !closed && cap == LineCap::Round {
+            let (start, start_color) = line[0];
+            let (end, end_color) = line[n - 1];
+            let d0 = (line[1].0 - start).normalize();
+            let d1 = (end - line[n - 2].0).normalize();
+            self.fill_round(start, h, d0.y.atan2(d0.x) + PI / 2.0, PI, start_color);
+            self.fill_round(end, h, d1.y.atan2(d1.x) - PI / 2.0, PI, end_color);
+        }
+
+    }
+
+}
+
+impl core::Drawable for ShapeRenderer {
+
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        self.shader = Some(engine.create_shader(core::PipelineKind::Geometry, include_bytes!("../../shaders/std_geom_v.glsl"), include_bytes!("../../shaders/std_geom_f.glsl"))?);
+        self.buffer = Some(engine.renderer.create_dynamic_vertex_buffer(core::Vertices::Color(&[]))?);
+
+        return Ok(());
+
+    }
+
+    fn render(&mut self, engine: &mut core::FlatEngine) {
+
+        let (shader, buffer) = match (self.shader, self.buffer) {
+            (Some(shader), Some(buffer)) => (shader, buffer),
+            _ => panic!("The shape renderer is being drawn before it has been initialized!"),
+        };
+
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        // The shapes of a frame whose vertices cannot be written are skipped.
+        if engine.renderer.update_vertex_buffer(buffer, core::Vertices::Color(&self.vertices)).is_err() {
+            self.vertices.clear();
+            return;
+        }
+
+        // Shapes are already in world space.
+        engine.renderer.draw(&core::DrawCall { shader, vertices: buffer, texture: None, model: Matrix4f::identity(), view: engine.camera.view, projection: engine.camera.projection, range: None });
+        engine.renderer.flush();
+
+        self.vertices.clear();
+
+    }
+
+    fn destroy(&mut self, engine: &mut core::FlatEngine) {
+
+        if let Some(buffer) = self.buffer.take() {
+            engine.renderer.destroy_vertex_buffer(buffer);
+        }
+        self.shader = None;
+        self.vertices.clear();
+
+    }
+
+}
+
+/**
+Decides which parts of a path are inside it when it is filled, for paths that overlap themselves or have holes.
+*/
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FillRule {
+
+    /**
+    Inside where the contours wind around the point any number of times other than 0, so holes have to wind the other way.
+    */
+    NonZero,
+    /**
+    Inside where a ray from the point crosses the contours an odd number of times, so any overlap is a hole.
+    */
+    EvenOdd,
+
+}
+
+#[derive(Clone, Debug)]
+pub struct StrokeStyle {
+
+    pub thickness: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /**
+    Alternating lengths of dashes and gaps, repeated along the stroke. Empty for a solid stroke.
+    */
+    pub dashes: Vec<f32>,
+    /**
+    How far into the dash pattern the stroke starts.
+    */
+    pub dash_offset: f32,
+
+}
+
+impl StrokeStyle {
+
+    pub fn new(thickness: f32) -> StrokeStyle {
+        return StrokeStyle { thickness, join: LineJoin::Miter, cap: LineCap::Butt, dashes: Vec::new(), dash_offset: 0.0 };
+    }
+
+    pub fn with_join(mut self, join: LineJoin) -> StrokeStyle {
+        self.join = join;
+        return self;
+    }
+
+    pub fn with_cap(mut self, cap: LineCap) -> StrokeStyle {
+        self.cap = cap;
+        return self;
+    }
+
+    pub fn with_dashes(mut self, dashes: &[f32], offset: f32) -> StrokeStyle {
+        self.dashes = dashes.to_vec();
+        self.dash_offset = offset;
+        return self;
+    }
+
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PathCommand {
+
+    MoveTo(Vector2f),
+    LineTo(Vector2f),
+    /**
+    A quadratic bezier curve through a control point.
+    */
+    QuadTo(Vector2f, Vector2f),
+    /**
+    A cubic bezier curve through two control points.
+    */
+    CubicTo(Vector2f, Vector2f, Vector2f),
+    /**
+    A circular arc around a center, from the start angle to the end angle. It is connected to the current point with a line.
+    */
+    Arc { center: Vector2f, radius: f32, start: f32, end: f32 },
+    /**
+    An elliptical arc to a point, as in SVG. The rotation of the ellipse is in radians.
+    */
+    ArcTo { radii: Vector2f, rotation: f32, large_arc: bool, sweep: bool, to: Vector2f },
+    Close,
+
+}
+
+/**
+A path flattened into points.
+*/
+#[derive(Clone, Debug)]
+pub struct Contour {
+
+    pub points: Vec<Vector2f>,
+    /**
+    Whether the path was closed back to its first point, which is not repeated at the end.
+    */
+    pub closed: bool,
+
+}
+
+impl Contour {
+
+    /**
+    Splits the contour into the pieces covered by a dash pattern.
+    */
+    fn dash(&self, pattern: &[f32], offset: f32) -> Vec<Vec<Vector2f>> {
+
+        let mut points = self.points.clone();
+        if self.closed && !points.is_empty() {
+            let first = points[0];
+            points.push(first);
+        }
+
+        // An odd number of lengths is repeated to make it even, as in SVG.
+        let pattern: Vec<f32> = if pattern.len() % 2 == 1 { pattern.iter().chain(pattern.iter()).cloned().collect() } else { pattern.to_vec() };
+        let total: f32 = pattern.iter().sum();
+        let mut index = 0;
+        let mut remaining = offset % total;
+        if remaining < 0.0 {
+            remaining += total;
+        }
+        // Skip to where the offset is within the pattern.
+        while remaining >= pattern[index] {
+            remaining -= pattern[index];
+            index = (index + 1) % pattern.len();
+        }
+        remaining = pattern[index] - remaining;
+
+        let mut dashes: Vec<Vec<Vector2f>> = Vec::new();
+        let mut current: Vec<Vector2f> = Vec::new();
+        if index % 2 == 0 && !points.is_empty() {
+            current.push(points[0]);
+        }
+
+        for i in 1..points.len() {
+
+            let (mut a, b) = (points[i - 1], points[i]);
+            let mut length = (b - a).magnitude();
+
+            while length >= remaining {
+                let split = a + (b - a) * (remaining / length);
+                if index % 2 == 0 {
+                    current.push(split);
+                    dashes.push(current);
+                    current = Vec::new();
+                } else {
+                    current.push(split);
+                }
+                length -= remaining;
+                a = split;
+                index = (index + 1) % pattern.len();
+                remaining = pattern[index];
+            }
+
+            remaining -= length;
+            if index % 2 == 0 {
+                current.push(b);
+            }
+
+        }
+
+        if index % 2 == 0 && current.len() > 1 {
+            dashes.push(current);
+        }
+        return dashes;
+
+    }
+
+}
+
+/**
+A vector shape made of straight lines, bezier curves and arcs, which can be filled and stroked into triangles.
+Paths are built with commands that chain, or parsed from the `d` attribute of an SVG path.
+SVG coordinates point down, so shapes from SVG files appear upside down unless they are flipped, for example with a y scale of -1.
+*/
+#[derive(Clone, Debug)]
+pub struct Path {
+
+    pub commands: Vec<PathCommand>,
+
+}
+
+impl Path {
+
+    pub fn new() -> Path {
+        return Path { commands: Vec::new() };
+    }
+
+    pub fn move_to(&mut self, to: Vector2f) -> &mut Path {
+        self.commands.push(PathCommand::MoveTo(to));
+        return self;
+    }
+
+    pub fn line_to(&mut self, to: Vector2f) -> &mut Path {
+        self.commands.push(PathCommand::LineTo(to));
+        return self;
+    }
+
+    pub fn quad_to(&mut self, control: Vector2f, to: Vector2f) -> &mut Path {
+        self.commands.push(PathCommand::QuadTo(control, to));
+        return self;
+    }
+
+    pub fn cubic_to(&mut self, control1: Vector2f, control2: Vector2f, to: Vector2f) -> &mut Path {
+        self.commands.push(PathCommand::CubicTo(control1, control2, to));
+        return self;
+    }
+
+    /**
+    A circular arc, counter-clockwise if the end angle is greater than the start angle.
+    */
+    pub fn arc(&mut self, center: Vector2f, radius: f32, start: f32, end: f32) -> &mut Path {
+        self.commands.push(PathCommand::Arc { center, radius, start, end });
+        return self;
+    }
+
+    /**
+    An elliptical arc to a point, with the same parameters as the SVG arc command.
+    */
+    pub fn arc_to(&mut self, radii: Vector2f, rotation: f32, large_arc: bool, sweep: bool, to: Vector2f) -> &mut Path {
+        self.commands.push(PathCommand::ArcTo { radii, rotation, large_arc, sweep, to });
+        return self;
+    }
+
+    pub fn close(&mut self) -> &mut Path {
+        self.commands.push(PathCommand::Close);
+        return self;
+    }
+
+    pub fn rect(&mut self, rect: Rect) -> &mut Path {
+
+        return self.move_to(Vector2f::new(rect.x, rect.y))
+            .line_to(Vector2f::new(rect.x + rect.width, rect.y))
+            .line_to(Vector2f::new(rect.x + rect.width, rect.y + rect.height))
+            .line_to(Vector2f::new(rect.x, rect.y + rect.height))
+            .close();
+
+    }
+
+    pub fn circle(&mut self, center: Vector2f, radius: f32) -> &mut Path {
+
+        self.move_to(Vector2f::new(center.x + radius, center.y));
+        return self.arc(center, radius, 0.0, 2.0 * PI).close();
+
+    }
+
+    /**
+    Parses the `d` attribute of an SVG path element.
+    */
+    pub fn from_svg(d: &str) -> error::Result<Path> {
+
+        let mut parser = SvgParser { chars: d.chars().collect(), pos: 0 };
+        let mut path = Path::new();
+
+        let mut current = Vector2f::new(0.0, 0.0);
+        let mut start = current;
+        // The last control point, for reflecting in smooth curves, and whether it was cubic.
+        let mut last_control: Option<(Vector2f, bool)> = None;
+        let mut command: Option<char> = None;
+
+        loop {
+
+            parser.skip_separators();
+            let c = match parser.peek() {
+                Some(c) => c,
+                None => break,
+            };
+
+            if c.is_alphabetic() {
+                command = Some(c);
+                parser.pos += 1;
+            } else if command.is_none() {
+                return Err(Error::Parse(format!("The SVG path does not start with a command: {}", d)));
+            }
+
+            let letter = command.unwrap();
+            let relative = letter.is_lowercase();
+            let origin = if relative { current } else { Vector2f::new(0.0, 0.0) };
+            let mut control: Option<(Vector2f, bool)> = None;
+
+            match letter.to_ascii_uppercase() {
+                'M' => {
+                    current = origin + parser.point()?;
+                    start = current;
+                    path.move_to(current);
+                    // Further points after a move are lines.
+                    command = Some(if relative { 'l' } else { 'L' });
+                },
+                'L' => {
+                    current = origin + parser.point()?;
+                    path.line_to(current);
+                },
+                'H' => {
+                    current.x = origin.x + parser.number()?;
+                    path.line_to(current);
+                },
+                'V' => {
+                    current.y = origin.y + parser.number()?;
+                    path.line_to(current);
+                },
+                'C' | 'S' => {
+                    let control1 = if letter.to_ascii_uppercase() == 'C' {
+                        origin + parser.point()?
+                    } else {
+                        match last_control {
+                            Some((point, true)) => current * 2.0 - point,
+                            _ => current,
+                        }
+                    };
+                    let control2 = origin + parser.point()?;
+                    current = origin + parser.point()?;
+                    path.cubic_to(control1, control2, current);
+                    control = Some((control2, true));
+                },
+                'Q' | 'T' => {
+                    let control1 = if letter.to_ascii_uppercase() == 'Q' {
+                        origin + parser.point()?
+                    } else {
+                        match last_control {
+                            Some((point, false)) => current * 2.0 - point,
+                            _ => current,
+                        }
+                    };
+                    current = origin + parser.point()?;
+                    path.quad_to(control1, current);
+                    control = Some((control1, false));
+                },
+                'A' => {
+                    let radii = Vector2f::new(parser.number()?, parser.number()?);
+                    let rotation = parser.number()?.to_radians();
+                    let large_arc = parser.flag()?;
+                    let sweep = parser.flag()?;
+                    current = origin + parser.point()?;
+                    path.arc_to(radii, rotation, large_arc, sweep, current);
+                },
+                'Z' => {
+                    path.close();
+                    current = start;
+                    command = None;
+                },
+                other => return Err(Error::Parse(format!("Unknown SVG path command {}.", other))),
+            }
+
+            last_control = control;
+
+        }
+
+        return Ok(path);
+
+    }
+
+    /**
+    Flattens the curves of the path into points no further than the tolerance from them.
+    */
+    pub fn flatten(&self, tolerance: f32) -> Vec<Contour> {
+
+        let tolerance = tolerance.max(0.0001);
+        let mut contours: Vec<Contour> = Vec::new();
+        let mut points: Vec<Vector2f> = Vec::new();
+        let mut current = Vector2f::new(0.0, 0.0);
+
+        fn finish(contours: &mut Vec<Contour>, points: &mut Vec<Vector2f>, closed: bool) {
+            if closed && points.len() > 1 && (points[0] - points[points.len() - 1]).magnitude2() < 1e-12 {
+                points.pop();
+            }
+            if points.len() > 1 {
+                contours.push(Contour { points: points.clone(), closed });
+            }
+            points.clear();
+        }
+
+        for command in self.commands.iter() {
+
+            // Commands other than moves continue from the current point.
+            if points.is_empty() {
+                match *command {
+                    PathCommand::MoveTo(_) | PathCommand::Close => (),
+                    _ => points.push(current),
+                }
+            }
+
+            match *command {
+                PathCommand::MoveTo(to) => {
+                    finish(&mut contours, &mut points, false);
+                    points.push(to);
+                    current = to;
+                },
+                PathCommand::LineTo(to) => {
+                    points.push(to);
+                    current = to;
+                },
+                PathCommand::QuadTo(control, to) => {
+                    let dd = (current - control * 2.0 + to).magnitude();
+                    let count = ((dd / (4.0 * tolerance)).sqrt().ceil() as usize).max(1).min(1000);
+                    for i in 1..count + 1 {
+                        let t = i as f32 / count as f32;
+                        let u = 1.0 - t;
+                        points.push(current * (u * u) + control * (2.0 * u * t) + to * (t * t));
+                    }
+                    current = to;
+                },
+                PathCommand::CubicTo(control1, control2, to) => {
+                    let dd = (current - control1 * 2.0 + control2).magnitude().max((control1 - control2 * 2.0 + to).magnitude());
+                    let count = ((3.0 * dd / (4.0 * tolerance)).sqrt().ceil() as usize).max(1).min(1000);
+                    for i in 1..count + 1 {
+                        let t = i as f32 / count as f32;
+                        let u = 1.0 - t;
+                        points.push(current * (u * u * u) + control1 * (3.0 * u * u * t) + control2 * (3.0 * u * t * t) + to * (t * t * t));
+                    }
+                    current = to;
+                },
+                PathCommand::Arc { center, radius, start, end } => {
+                    let count = ((circle_segments(radius, tolerance) as f32 * (end - start).abs() / (2.0 * PI)).ceil() as usize).max(1);
+                    for i in 0..count + 1 {
+                        let angle = start + (end - start) * i as f32 / count as f32;
+                        points.push(Vector2f::new(center.x + radius * angle.cos(), center.y + radius * angle.sin()));
+                    }
+                    current = points[points.len() - 1];
+                },
+                PathCommand::ArcTo { radii, rotation, large_arc, sweep, to } => {
+                    flatten_svg_arc(current, to, radii, rotation, large_arc, sweep, tolerance, &mut points);
+                    current = to;
+                },
+                PathCommand::Close => {
+                    if let Some(&first) = points.first() {
+                        finish(&mut contours, &mut points, true);
+                        current = first;
+                    }
+                },
+            }
+
+            // Points closer together than a rounding error would give segments without a direction.
+            points.dedup_by(|a, b| (*a - *b).magnitude2() < 1e-12);
+
+        }
+
+        finish(&mut contours, &mut points, false);
+        return contours;
+
+    }
+
+    /**
+    Tessellates the inside of the path into triangles. Open contours are closed with a straight line.
+    */
+    pub fn fill(&self, rule: FillRule, tolerance: f32, color: Color) -> Vec<Vertex> {
+
+        // Edges with the direction they wind in, ignoring horizontal edges, which never cross a scanline.
+        let mut edges: Vec<(Vector2f, Vector2f, i32)> = Vec::new();
+        for contour in self.flatten(tolerance) {
+            let n = contour.points.len();
+            for i in 0..n {
+                let (a, b) = (contour.points[i], contour.points[(i + 1) % n]);
+                if a.y < b.y {
+                    edges.push((a, b, 1));
+                } else if a.y > b.y {
+                    edges.push((b, a, -1));
+                }
+            }
+        }
+
+        // The path is cut into horizontal slabs at every point and crossing, so within a slab the edges keep their order.
+        let mut ys: Vec<f32> = Vec::new();
+        for (i, edge) in edges.iter().enumerate() {
+            ys.push(edge.0.y);
+            ys.push(edge.1.y);
+            for other in edges[i + 1..].iter() {
+                if let Some(y) = crossing_y(edge, other) {
+                    ys.push(y);
+                }
+            }
+        }
+        ys.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        ys.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+
+        let color = color.to_raw_color();
+        let mut vertices: Vec<Vertex> = Vec::new();
+        let mut crossing: Vec<(f32, f32, f32, i32)> = Vec::new();
+
+        for slab in ys.windows(2) {
+
+            let (bottom, top) = (slab[0], slab[1]);
+            let middle = (bottom + top) / 2.0;
+
+            crossing.clear();
+            for &(a, b, winding) in edges.iter().filter(|e| e.0.y <= middle && e.1.y >= middle) {
+                let x_at = |y: f32| a.x + (b.x - a.x) * (y - a.y) / (b.y - a.y);
+                crossing.push((x_at(middle), x_at(bottom), x_at(top), winding));
+            }
+            crossing.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+            let mut winding = 0;
+            for i in 0..crossing.len().saturating_sub(1) {
+                winding += crossing[i].3;
+                let inside = match rule {
+                    FillRule::NonZero => winding != 0,
+                    FillRule::EvenOdd => winding % 2 != 0,
+                };
+                if !inside {
+                    continue;
+                }
+
+                let (left, right) = (crossing[i], crossing[i + 1]);
+                let corners = [Vector2f::new(left.1, bottom), Vector2f::new(right.1, bottom), Vector2f::new(right.2, top), Vector2f::new(left.2, top)];
+                for &index in [0, 1, 2, 0, 2, 3].iter() {
+                    vertices.push(Vertex { pos: [corners[index].x, corners[index].y], color });
+                }
+            }
+
+        }
+
+        return vertices;
+
+    }
+
+    /**
+    Tessellates the outline of the path into triangles.
+    */
+    pub fn stroke(&self, style: &StrokeStyle, tolerance: f32, color: Color) -> Vec<Vertex> {
+
+        let mut shapes = ShapeRenderer::new();
+        shapes.tolerance = tolerance;
+        shapes.stroke_path(self, style, color);
+        return shapes.vertices;
+
+    }
+
+}
+
+/**
+The height at which two edges cross, if they do somewhere other than their ends.
+*/
+fn crossing_y(a: &(Vector2f, Vector2f, i32), b: &(Vector2f, Vector2f, i32)) -> Option<f32> {
+
+    let (p, r) = (a.0, a.1 - a.0);
+    let (q, s) = (b.0, b.1 - b.0);
+    let denominator = r.x * s.y - r.y * s.x;
+    if denominator.abs() < 1e-12 {
+        return None;
+    }
+
+    let t = ((q.x - p.x) * s.y - (q.y - p.y) * s.x) / denominator;
+    let u = ((q.x - p.x) * r.y - (q.y - p.y) * r.x) / denominator;
+    if t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0 {
+        return Some(p.y + r.y * t);
+    }
+    return None;
+
+}
+
+/**
+Flattens an SVG arc by converting it to the center of its ellipse, as described in the implementation notes of the SVG specification.
+*/
+fn flatten_svg_arc(from: Vector2f, to: Vector2f, radii: Vector2f, rotation: f32, large_arc: bool, sweep: bool, tolerance: f32, points: &mut Vec<Vector2f>) {
+
+    let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+    if (to - from).magnitude2() < 1e-12 {
+        return;
+    }
+    if rx < 1e-6 || ry < 1e-6 {
+        points.push(to);
+        return;
+    }
+
+    let (sin, cos) = rotation.sin_cos();
+    let half = (from - to) / 2.0;
+    let x1 = cos * half.x + sin * half.y;
+    let y1 = -sin * half.x + cos * half.y;
+
+    // Radii too small to reach the end point are scaled up.
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+    let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+    let cx1 = coefficient * rx * y1 / ry;
+    let cy1 = -coefficient * ry * x1 / rx;
+    let center = Vector2f::new(cos * cx1 - sin * cy1 + (from.x + to.x) / 2.0, sin * cx1 + cos * cy1 + (from.y + to.y) / 2.0);
+
+    let angle = |u: Vector2f, v: Vector2f| (u.x * v.y - u.y * v.x).atan2(u.dot(v));
+    let start_vector = Vector2f::new((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let end_vector = Vector2f::new((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+    let start = angle(Vector2f::new(1.0, 0.0), start_vector);
+    let mut delta = angle(start_vector, end_vector);
+    if !sweep && delta > 0.0 {
+        delta -= 2.0 * PI;
+    } else if sweep && delta < 0.0 {
+        delta += 2.0 * PI;
+    }
+
+    let count = ((circle_segments(rx.max(ry), tolerance) as f32 * delta.abs() / (2.0 * PI)).ceil() as usize).max(1);
+    for i in 1..count {
+        let t = start + delta * i as f32 / count as f32;
+        let (x, y) = (rx * t.cos(), ry * t.sin());
+        points.push(Vector2f::new(center.x + cos * x - sin * y, center.y + sin * x + cos * y));
+    }
+    points.push(to);
+
+}
+
+struct SvgParser {
+
+    chars: Vec<char>,
+    pos: usize,
+
+}
+
+impl SvgParser {
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.pos).cloned();
+    }
+
+    fn skip_separators(&mut self) {
+        while self.peek().map(|c| c.is_whitespace() || c == ',').unwrap_or(false) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, expected: &str) -> Error {
+        let rest: String = self.chars[self.pos..].iter().take(16).collect();
+        return Error::Parse(format!("Expected {} in the SVG path at {}: \"{}\"", expected, self.pos, rest));
+    }
+
+    /**
+    Reads a number, which may follow the previous one without a separator, as in "1-2" or "0.5.5".
+    */
+    fn number(&mut self) -> error::Result<f32> {
+
+        self.skip_separators();
+        let start = self.pos;
+
+        if let Some('+') | Some('-') = self.peek() {
+            self.pos += 1;
+        }
+        let mut seen_dot = false;
+        let mut seen_digit = false;
+        while let Some(c) = self.peek() {
+            if c.is_ascii_digit() {
+                seen_digit = true;
+            } else if c == '.' && !seen_dot {
+                seen_dot = true;
+            } else {
+                break;
+            }
+            self.pos += 1;
+        }
+        if seen_digit {
+            if let Some('e') | Some('E') = self.peek() {
+                let mark = self.pos;
+                self.pos += 1;
+                if let Some('+') | Some('-') = self.peek() {
+                    self.pos += 1;
+                }
+                if self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) {
+                    while self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) {
+                        self.pos += 1;
+                    }
+                } else {
+                    self.pos = mark;
+                }
+            }
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+        return match text.parse::<f32>() {
+            Ok(value) if seen_digit => Ok(value),
+            _ => {
+                self.pos = start;
+                Err(self.error("a number"))
+            },
+        };
+
+    }
+
+    fn point(&mut self) -> error::Result<Vector2f> {
+        return Ok(Vector2f::new(self.number()?, self.number()?));
+    }
+
+    /**
+    Reads an arc flag, which is a single 0 or 1 that does not need a separator after it.
+    */
+    fn flag(&mut self) -> error::Result<bool> {
+
+        self.skip_separators();
+        let flag = match self.peek() {
+            Some('0') => false,
+            Some('1') => true,
+            _ => return Err(self.error("an arc flag")),
+        };
+        self.pos += 1;
+        return Ok(flag);
+
+    }
+
+}
+
+/**
+A node drawing tessellated paths, for vector art that does not change every frame.
+The vertices are in the local space of the node, and are uploaded when it is loaded.
+*/
+pub struct PathShape {
+
+    node: NodeObject2D,
+    pub vertices: Vec<Vertex>,
+    /**
+    How far curves may be from the polygons they are drawn as, in local units.
+    */
+    pub tolerance: f32,
+    geometry_renderer: Option<GeometryRenderer>,
+
+}
+
+impl PathShape {
+
+    pub fn new() -> PathShape {
+
+        return PathShape { node: NodeObject2D::new(), vertices: Vec::new(), tolerance: 0.25, geometry_renderer: None };
+
+    }
+
+    /**
+    Adds the inside of a path, on top of what was added before. Has to be called before the shape is loaded.
+    */
+    pub fn add_fill(&mut self, path: &Path, rule: FillRule, color: Color) {
+
+        let vertices = path.fill(rule, self.tolerance, color);
+        self.vertices.extend(vertices);
+
+    }
+
+    pub fn add_stroke(&mut self, path: &Path, style: &StrokeStyle, color: Color) {
+
+        let vertices = path.stroke(style, self.tolerance, color);
+        self.vertices.extend(vertices);
+
+    }
+
+}
+
+impl core::Drawable for PathShape {
+
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        self.geometry_renderer = Some(GeometryRenderer::from_vertices(&self.vertices, include_bytes!("../../shaders/std_geom_v.glsl"), include_bytes!("../../shaders/std_geom_f.glsl"), engine)?);
+        return Ok(());
+
+    }
+
+    fn render(&mut self, engine: &mut core::FlatEngine) {
+
+        let model = self.node.get_world_trans();
+        match self.geometry_renderer.as_mut() {
+            Some(renderer) => renderer.render(model, engine.camera.view, engine.camera.projection, engine),
+            None => panic!("The path shape is being drawn before it has been initialized!"),
+        }
+
+    }
+
+    fn destroy(&mut self, engine: &mut core::FlatEngine) {
+
+        if let Some(mut geometry_renderer) = self.geometry_renderer.take() {
+            geometry_renderer.destroy(engine.renderer.as_mut());
+        }
+
+    }
+
+}
+
+impl Node2D for PathShape {
+
+    fn get_node_obj_mut(&mut self) -> &mut NodeObject2D {
+        return &mut self.node;
+    }
+
+    fn get_node_obj(&self) -> &NodeObject2D {
+        return &self.node;
+    }
+
+}
@@ -0,0 +1,155 @@
+use super::*;
+
+use self::render::Texture;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/**
+Set this environment variable to write the rendered frame as the new reference image instead of comparing against it.
+*/
+pub const BLESS_VAR: &'static str = "FLAT_ENGINE_BLESS";
+
+/**
+Directory the actual and diff images of failed comparisons are written to.
+*/
+pub const OUTPUT_DIR: &'static str = "target/golden";
+
+/**
+The result of comparing a rendered frame against a reference image.
+*/
+pub struct Comparison {
+
+    pub mismatched_pixels: usize,
+    pub max_difference: u8,
+    /**
+    Matching pixels are shown faded, mismatched pixels in red. Only present if the images have the same size.
+    */
+    pub diff: Option<Texture>,
+
+}
+
+impl Comparison {
+
+    pub fn matches(&self) -> bool {
+        return self.mismatched_pixels == 0;
+    }
+
+}
+
+/**
+Compares two images pixel by pixel. A pixel mismatches if any of its channels differ by more than `tolerance`.
+*/
+pub fn compare(actual: &Texture, expected: &Texture, tolerance: u8) -> Comparison {
+
+    if actual.dimensions != expected.dimensions {
+        let pixels = expected.dimensions.x as usize * expected.dimensions.y as usize;
+        return Comparison { mismatched_pixels: pixels.max(1), max_difference: 255, diff: None };
+    }
+
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+    let mut diff: Vec<u8> = Vec::with_capacity(expected.data.len());
+
+    for (a, e) in actual.data.chunks(4).zip(expected.data.chunks(4)) {
+
+        let difference = a.iter().zip(e.iter()).map(|(a, e)| (*a as i16 - *e as i16).abs() as u8).max().unwrap_or(0);
+        max_difference = max_difference.max(difference);
+
+        if difference > tolerance {
+            mismatched_pixels += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luma = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 3) as u8;
+            let faded = 64 + luma / 4;
+            diff.extend_from_slice(&[faded, faded, faded, 255]);
+        }
+
+    }
+
+    return Comparison { mismatched_pixels, max_difference, diff: Some(Texture::from_data(&diff, expected.dimensions.x, expected.dimensions.y)) };
+
+}
+
+pub fn load_png(path: &Path) -> Option<Texture> {
+
+    let img = match image::open(path) {
+        Ok(img) => img.to_rgba(),
+        Err(_) => return None,
+    };
+    return Some(Texture::from_image(img));
+
+}
+
+pub fn save_png(texture: &Texture, path: &Path) -> std::io::Result<()> {
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    return image::save_buffer(path, &texture.data, texture.dimensions.x as u32, texture.dimensions.y as u32, image::ColorType::RGBA(8));
+
+}
+
+fn output_path(reference: &Path, suffix: &str) -> PathBuf {
+
+    let stem = reference.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or(String::from("golden"));
+    return Path::new(OUTPUT_DIR).join(format!("{}.{}.png", stem, suffix));
+
+}
+
+/**
+Compares a frame against a checked-in reference PNG and panics with a description if they differ.
+On failure the frame and a diff image are written to `OUTPUT_DIR`. If `BLESS_VAR` is set the reference is (re)written instead.
+*/
+pub fn assert_matches(actual: &Texture, reference: &str, tolerance: u8) {
+
+    let reference = Path::new(reference);
+
+    if env::var(BLESS_VAR).is_ok() {
+        save_png(actual, reference).expect("Could not write the reference image.");
+        return;
+    }
+
+    let expected = match load_png(reference) {
+        Some(expected) => expected,
+        None => {
+            let actual_path = output_path(reference, "actual");
+            let _ = save_png(actual, &actual_path);
+            panic!("Missing reference image {}. The rendered frame was written to {} (set {} to accept it).", reference.display(), actual_path.display(), BLESS_VAR);
+        },
+    };
+
+    let comparison = compare(actual, &expected, tolerance);
+
+    if !comparison.matches() {
+
+        let actual_path = output_path(reference, "actual");
+        let _ = save_png(actual, &actual_path);
+
+        let diff_path = output_path(reference, "diff");
+        if let Some(ref diff) = comparison.diff {
+            let _ = save_png(diff, &diff_path);
+        }
+
+        if comparison.diff.is_none() {
+            panic!("{}: the rendered frame is {}x{} but the reference is {}x{}. The frame was written to {}.", reference.display(), actual.dimensions.x, actual.dimensions.y, expected.dimensions.x, expected.dimensions.y, actual_path.display());
+        }
+
+        panic!("{}: {} pixels differ by more than {} (max difference {}). See {} and {}.", reference.display(), comparison.mismatched_pixels, tolerance, comparison.max_difference, actual_path.display(), diff_path.display());
+
+    }
+
+}
+
+/**
+Reads back the current frame before it is presented and compares it against a reference PNG, see `assert_matches`.
+*/
+pub fn assert_frame(engine: &mut core::FlatEngine, reference: &str, tolerance: u8) {
+
+    let frame = match engine.read_pixels() {
+        Ok(frame) => frame,
+        Err(e) => panic!("{}: the frame could not be read back: {}", reference, e),
+    };
+    assert_matches(&frame, reference, tolerance);
+
+}
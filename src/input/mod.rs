@@ -0,0 +1,433 @@
+use super::*;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use glutin::{ElementState, Event, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+
+/**
+Something an action or axis can be bound to.
+*/
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+
+}
+
+impl Binding {
+
+    /**
+    Parses a binding from its config name: the name of a `VirtualKeyCode` ("Space", "A", "Left") or "Mouse" followed by the button ("MouseLeft", "MouseRight", "MouseMiddle", "Mouse4").
+    */
+    pub fn parse(name: &str) -> Option<Binding> {
+
+        if name.starts_with("Mouse") {
+            return match &name[5..] {
+                "Left" => Some(Binding::Mouse(MouseButton::Left)),
+                "Right" => Some(Binding::Mouse(MouseButton::Right)),
+                "Middle" => Some(Binding::Mouse(MouseButton::Middle)),
+                other => other.parse::<u8>().ok().map(|b| Binding::Mouse(MouseButton::Other(b))),
+            };
+        }
+
+        return parse_key(name).map(|key| Binding::Key(key));
+
+    }
+
+}
+
+macro_rules! key_names {
+    ($($key:ident,)*) => {
+        fn parse_key(name: &str) -> Option<VirtualKeyCode> {
+            match name {
+                $(stringify!($key) => Some(VirtualKeyCode::$key),)*
+                _ => None,
+            }
+        }
+    }
+}
+
+key_names! {
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8,
+    Key9, Key0, A, B, C, D, E, F,
+    G, H, I, J, K, L, M, N,
+    O, P, Q, R, S, T, U, V,
+    W, X, Y, Z, Escape, F1, F2, F3,
+    F4, F5, F6, F7, F8, F9, F10, F11,
+    F12, F13, F14, F15, Snapshot, Scroll, Pause, Insert,
+    Home, Delete, End, PageDown, PageUp, Left, Up, Right,
+    Down, Back, Return, Space, Compose, Caret, Numlock, Numpad0,
+    Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8,
+    Numpad9, AbntC1, AbntC2, Add, Apostrophe, Apps, At, Ax,
+    Backslash, Calculator, Capital, Colon, Comma, Convert, Decimal, Divide,
+    Equals, Grave, Kana, Kanji, LAlt, LBracket, LControl, LShift,
+    LWin, Mail, MediaSelect, MediaStop, Minus, Multiply, Mute, MyComputer,
+    NavigateForward, NavigateBackward, NextTrack, NoConvert, NumpadComma, NumpadEnter, NumpadEquals, OEM102,
+    Period, PlayPause, Power, PrevTrack, RAlt, RBracket, RControl, RShift,
+    RWin, Semicolon, Slash, Sleep, Stop, Subtract, Sysrq, Tab,
+    Underline, Unlabeled, VolumeDown, VolumeUp, Wake, WebBack, WebFavorites, WebForward,
+    WebHome, WebRefresh, WebSearch, WebStop, Yen, Copy, Paste, Cut,
+}
+
+/**
+An axis is 1 while a positive binding is held, -1 while a negative binding is held, and 0 otherwise or when both are held. It changes immediately, without smoothing.
+*/
+#[derive(Clone, Debug)]
+pub struct AxisBinding {
+
+    pub positive: Vec<Binding>,
+    pub negative: Vec<Binding>,
+
+}
+
+/**
+Maps named actions ("jump") and axes ("move_x") to the keys and buttons that trigger them.
+*/
+#[derive(Clone, Debug)]
+pub struct Bindings {
+
+    pub actions: HashMap<String, Vec<Binding>>,
+    pub axes: HashMap<String, AxisBinding>,
+
+}
+
+impl Bindings {
+
+    pub fn new() -> Bindings {
+
+        return Bindings { actions: HashMap::new(), axes: HashMap::new() };
+
+    }
+
+    /**
+    Loads bindings from a JSON config file of the form:
+    `{ "actions": { "jump": ["Space", "MouseLeft"] }, "axes": { "move_x": { "positive": ["Right", "D"], "negative": ["Left", "A"] } } }`
+    */
+    pub fn load_from_path(path: &str) -> error::Result<Bindings> {
+
+        let source = fs::read_to_string(path)?;
+        return Bindings::load_from_str(&source);
+
+    }
+
+    pub fn load_from_str(source: &str) -> error::Result<Bindings> {
+
+        let config: serde_json::Value = serde_json::from_str(source)?;
+        let mut bindings = Bindings::new();
+
+        if let Some(actions) = config.get("actions") {
+            let actions = actions.as_object().ok_or(Error::Parse(String::from("\"actions\" must be an object.")))?;
+            for (name, list) in actions.iter() {
+                bindings.actions.insert(name.clone(), parse_binding_list(list, name)?);
+            }
+        }
+
+        if let Some(axes) = config.get("axes") {
+            let axes = axes.as_object().ok_or(Error::Parse(String::from("\"axes\" must be an object.")))?;
+            for (name, axis) in axes.iter() {
+                let positive = match axis.get("positive") {
+                    Some(list) => parse_binding_list(list, name)?,
+                    None => Vec::new(),
+                };
+                let negative = match axis.get("negative") {
+                    Some(list) => parse_binding_list(list, name)?,
+                    None => Vec::new(),
+                };
+                bindings.axes.insert(name.clone(), AxisBinding { positive, negative });
+            }
+        }
+
+        return Ok(bindings);
+
+    }
+
+    pub fn bind_action(&mut self, name: &str, binding: Binding) {
+
+        self.actions.entry(name.to_string()).or_insert(Vec::new()).push(binding);
+
+    }
+
+    pub fn bind_axis(&mut self, name: &str, positive: Vec<Binding>, negative: Vec<Binding>) {
+
+        self.axes.insert(name.to_string(), AxisBinding { positive, negative });
+
+    }
+
+}
+
+fn parse_binding_list(list: &serde_json::Value, name: &str) -> error::Result<Vec<Binding>> {
+
+    let list = list.as_array().ok_or(Error::Parse(format!("The bindings of \"{}\" must be a list of key names.", name)))?;
+    let mut bindings: Vec<Binding> = Vec::new();
+    for entry in list.iter() {
+        let key = entry.as_str().ok_or(Error::Parse(format!("The bindings of \"{}\" must be a list of key names.", name)))?;
+        bindings.push(Binding::parse(key).ok_or(Error::Parse(format!("Unknown key or button \"{}\" bound to \"{}\".", key, name)))?);
+    }
+    return Ok(bindings);
+
+}
+
+/**
+The keyboard and mouse state for the current frame. `FlatEngine::poll_events` feeds it the window events, so "just pressed" and "just released" refer to the last poll.
+*/
+pub struct Input {
+
+    keys_down: HashSet<VirtualKeyCode>,
+    keys_pressed: HashSet<VirtualKeyCode>,
+    keys_released: HashSet<VirtualKeyCode>,
+    buttons_down: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    buttons_released: HashSet<MouseButton>,
+    /**
+    What was held at the end of the last frame, to tell when an action starts being held.
+    */
+    keys_down_last: HashSet<VirtualKeyCode>,
+    buttons_down_last: HashSet<MouseButton>,
+    /**
+    In window coordinates, from the top left corner.
+    */
+    cursor_pos: Vector2f,
+    cursor_world_pos: Vector2f,
+    /**
+    Scrolled lines this frame. Pixel deltas from touchpads are converted at `PIXELS_PER_LINE`.
+    */
+    scroll: Vector2f,
+    pub bindings: Bindings,
+
+}
+
+const PIXELS_PER_LINE: f32 = 20.0;
+
+impl Input {
+
+    pub fn new() -> Input {
+
+        return Input {
+            keys_down: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            keys_released: HashSet::new(),
+            buttons_down: HashSet::new(),
+            buttons_pressed: HashSet::new(),
+            buttons_released: HashSet::new(),
+            keys_down_last: HashSet::new(),
+            buttons_down_last: HashSet::new(),
+            cursor_pos: Vector2f::new(0.0, 0.0),
+            cursor_world_pos: Vector2f::new(0.0, 0.0),
+            scroll: Vector2f::new(0.0, 0.0),
+            bindings: Bindings::new(),
+        };
+
+    }
+
+    /**
+    Clears the per-frame state. Called before the events of a new frame are handled.
+    */
+    pub fn begin_frame(&mut self) {
+
+        self.keys_down_last = self.keys_down.clone();
+        self.buttons_down_last = self.buttons_down.clone();
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+        self.scroll = Vector2f::new(0.0, 0.0);
+
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+
+        if let Event::WindowEvent { ref event, .. } = *event {
+            match *event {
+                WindowEvent::KeyboardInput { input, .. } => {
+                    if let Some(key) = input.virtual_keycode {
+                        match input.state {
+                            ElementState::Pressed => self.press_key(key),
+                            ElementState::Released => self.release_key(key),
+                        }
+                    }
+                },
+                WindowEvent::MouseInput { state, button, .. } => {
+                    match state {
+                        ElementState::Pressed => self.press_button(button),
+                        ElementState::Released => self.release_button(button),
+                    }
+                },
+                WindowEvent::CursorMoved { position, .. } => {
+                    self.cursor_pos = Vector2f::new(position.x as f32, position.y as f32);
+                },
+                WindowEvent::MouseWheel { delta, .. } => {
+                    match delta {
+                        MouseScrollDelta::LineDelta(x, y) => self.scroll += Vector2f::new(x, y),
+                        MouseScrollDelta::PixelDelta(p) => self.scroll += Vector2f::new(p.x as f32 / PIXELS_PER_LINE, p.y as f32 / PIXELS_PER_LINE),
+                    }
+                },
+                // Keys held while the window loses focus never get a release event.
+                WindowEvent::Focused(false) => self.release_all(),
+                _ => (),
+            }
+        }
+
+    }
+
+    pub fn press_key(&mut self, key: VirtualKeyCode) {
+
+        // Key repeat sends more press events while the key is held, which must not count as new presses.
+        if self.keys_down.insert(key) {
+            self.keys_pressed.insert(key);
+        }
+
+    }
+
+    pub fn release_key(&mut self, key: VirtualKeyCode) {
+
+        if self.keys_down.remove(&key) {
+            self.keys_released.insert(key);
+        }
+
+    }
+
+    pub fn press_button(&mut self, button: MouseButton) {
+
+        if self.buttons_down.insert(button) {
+            self.buttons_pressed.insert(button);
+        }
+
+    }
+
+    pub fn release_button(&mut self, button: MouseButton) {
+
+        if self.buttons_down.remove(&button) {
+            self.buttons_released.insert(button);
+        }
+
+    }
+
+    pub fn release_all(&mut self) {
+
+        let keys: Vec<VirtualKeyCode> = self.keys_down.iter().cloned().collect();
+        for key in keys {
+            self.release_key(key);
+        }
+        let buttons: Vec<MouseButton> = self.buttons_down.iter().cloned().collect();
+        for button in buttons {
+            self.release_button(button);
+        }
+
+    }
+
+    pub fn is_key_down(&self, key: VirtualKeyCode) -> bool {
+        return self.keys_down.contains(&key);
+    }
+
+    pub fn is_key_pressed(&self, key: VirtualKeyCode) -> bool {
+        return self.keys_pressed.contains(&key);
+    }
+
+    pub fn is_key_released(&self, key: VirtualKeyCode) -> bool {
+        return self.keys_released.contains(&key);
+    }
+
+    pub fn is_button_down(&self, button: MouseButton) -> bool {
+        return self.buttons_down.contains(&button);
+    }
+
+    pub fn is_button_pressed(&self, button: MouseButton) -> bool {
+        return self.buttons_pressed.contains(&button);
+    }
+
+    pub fn is_button_released(&self, button: MouseButton) -> bool {
+        return self.buttons_released.contains(&button);
+    }
+
+    pub fn get_cursor_pos(&self) -> Vector2f {
+        return self.cursor_pos;
+    }
+
+    /**
+    The cursor position in world coordinates, as seen through the camera when the events were last polled.
+    */
+    pub fn get_cursor_world_pos(&self) -> Vector2f {
+        return self.cursor_world_pos;
+    }
+
+    pub fn update_cursor_world_pos(&mut self, camera: &core::Camera, dimensions: Vector2f) {
+        self.cursor_world_pos = camera.screen_to_world(self.cursor_pos, dimensions);
+    }
+
+    pub fn get_scroll(&self) -> Vector2f {
+        return self.scroll;
+    }
+
+    pub fn is_binding_down(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.is_key_down(key),
+            Binding::Mouse(button) => self.is_button_down(button),
+        }
+    }
+
+    pub fn is_binding_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.is_key_pressed(key),
+            Binding::Mouse(button) => self.is_button_pressed(button),
+        }
+    }
+
+    pub fn is_binding_released(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.is_key_released(key),
+            Binding::Mouse(button) => self.is_button_released(button),
+        }
+    }
+
+    /**
+    True while any binding of the action is held. Unknown actions are never down.
+    */
+    pub fn is_action_down(&self, action: &str) -> bool {
+        return self.bindings.actions.get(action).map_or(false, |b| b.iter().any(|b| self.is_binding_down(*b)));
+    }
+
+    fn was_binding_down(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keys_down_last.contains(&key),
+            Binding::Mouse(button) => self.buttons_down_last.contains(&button),
+        }
+    }
+
+    /**
+    True on the frame the action started being held: none of its bindings was held last frame, and one was pressed this frame.
+    Pressing a second binding while the first is held does not press the action again.
+    */
+    pub fn is_action_pressed(&self, action: &str) -> bool {
+        return self.bindings.actions.get(action).map_or(false, |b| !b.iter().any(|b| self.was_binding_down(*b)) && b.iter().any(|b| self.is_binding_pressed(*b)));
+    }
+
+    /**
+    True on the frame the action stopped being held.
+    */
+    pub fn is_action_released(&self, action: &str) -> bool {
+        return self.bindings.actions.get(action).map_or(false, |b| b.iter().any(|b| self.is_binding_released(*b)) && !self.is_action_down(action));
+    }
+
+    /**
+    Returns -1, 0 or 1 depending on which side of the axis is held. Holding both sides cancels out.
+    */
+    pub fn get_axis(&self, axis: &str) -> f32 {
+
+        let axis = match self.bindings.axes.get(axis) {
+            Some(axis) => axis,
+            None => return 0.0,
+        };
+
+        let mut value = 0.0;
+        if axis.positive.iter().any(|b| self.is_binding_down(*b)) {
+            value += 1.0;
+        }
+        if axis.negative.iter().any(|b| self.is_binding_down(*b)) {
+            value -= 1.0;
+        }
+        return value;
+
+    }
+
+}
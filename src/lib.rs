@@ -11,6 +11,10 @@ extern crate cgmath;
 extern crate gfx_device_gl;
 pub extern crate rusttype;
 extern crate stopwatch;
+extern crate serde_json;
+extern crate xml;
+extern crate base64;
+extern crate flate2;
 
 pub mod core;
 pub mod geometry;
@@ -19,9 +23,22 @@ pub mod node;
 pub mod render;
 pub mod text;
 pub mod spatial;
+pub mod software;
+pub mod golden;
+pub mod error;
+pub mod input;
+pub mod app;
+pub mod camera;
+pub mod post;
+pub mod atlas;
+pub mod animation;
+pub mod sheet;
+pub mod tilemap;
+pub mod tiled;
 
 pub type ColorFormat = gfx::format::Rgba8;
 pub type DepthFormat = gfx::format::DepthStencil;
 
 pub type ResourceType = gfx_device_gl::Resources;
 pub use self::types::*;
+pub use self::error::Error;
@@ -11,6 +11,10 @@ extern crate cgmath;
 extern crate gfx_device_gl;
 extern crate rusttype;
 extern crate stopwatch;
+extern crate serde_json;
+extern crate xml;
+extern crate base64;
+extern crate flate2;
 
 mod core;
 mod geometry;
@@ -18,6 +22,18 @@ mod types;
 mod node;
 mod render;
 mod text;
+mod spatial;
+mod software;
+mod error;
+mod input;
+mod app;
+mod camera;
+mod post;
+mod atlas;
+mod animation;
+mod sheet;
+mod tilemap;
+mod tiled;
 
 pub type ColorFormat = gfx::format::Rgba8;
 pub type DepthFormat = gfx::format::DepthStencil;
@@ -27,96 +43,91 @@ pub type ResourceType = gfx_device_gl::Resources;
 // THE FOLLOWING CODE IS TESTING CODE -- NOT PART OF LIBRARY DISTRIBUTION
 
 pub use self::types::*;
+pub use self::error::Error;
 use self::geometry::Triangle;
 use self::render::*;
 use self::node::*;
 use self::core::Drawable;
 use self::text::*;
-use self::std::time::Instant;
 use self::std::str::*;
 
 const CLEAR_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
 
 
 
-fn main() {
+struct Demo<'a> {
 
-    let window_builder = glutin::WindowBuilder::new().with_title("Flat Engine v 0.1".to_string()).with_fullscreen(None).with_resizable(false);
+    logo: Sprite,
+    text: Text<'a>,
+    // The logo position before and after the last fixed update, interpolated when rendering.
+    previous_x: f32,
+    x: f32,
+    running: bool,
 
-    let mut engine: core::FlatEngine = core::FlatEngine::init(window_builder);
+}
 
-    let mut texture: Texture = Texture::load_from_path("resources/logo.png");
-    let mut logo: Sprite = Sprite::new(Some(texture));
+impl<'a> app::App for Demo<'a> {
 
-    let mut text: Text = Text::new("Flat Engine v 0.1", Font::from_bytes(include_bytes!("../resources/trebuc.ttf") as &[u8]).unwrap(), 40.0, Color::white());
+    fn fixed_update(&mut self, dt: f32, engine: &mut core::FlatEngine) {
 
-    text.set_pos(Vector2f::new(0.0, 0.0));
+        self.previous_x = self.x;
+        self.x += engine.input.get_axis("move_x") * 100.0 * dt;
 
-    logo.set_pos(Vector2f::new(0.0, 0.0));
-    logo.set_size(Vector2f::new(engine.window.get_inner_size().unwrap().width, engine.window.get_inner_size().unwrap().height));
+    }
 
-    engine.load(&mut text);
-    engine.load(&mut logo);
+    fn update(&mut self, _dt: f32, engine: &mut core::FlatEngine) {
 
-    let mut stopwatch: stopwatch::Stopwatch = stopwatch::Stopwatch::start_new();
+        if engine.input.is_key_pressed(glutin::VirtualKeyCode::Escape) {
+            self.running = false;
+        }
 
-    let mut running = true;
-    while running {
+    }
 
-        let p = logo.get_pos();
+    fn render(&mut self, alpha: f32, engine: &mut core::FlatEngine) {
 
-        let delta: f64 = stopwatch.elapsed_ms() as f64 / 1000.0;
-        stopwatch.reset();
-        stopwatch.start();
+        let p = self.logo.get_pos();
+        self.logo.set_pos(Vector2f::new(self.previous_x + (self.x - self.previous_x) * alpha, p.y));
 
-        let mut fps_text = ((1.0 / delta) as i64).to_string().clone();
+        engine.clear(Color::black());
+        // Render code goes here.
+        engine.render(&mut self.logo);
+        engine.render(&mut self.text);
 
-        if (fps_text != text.text) {
-            text.set_text(fps_text);
-        }
+    }
 
-        let mut offset: f64 = 0.0;
-
-        engine.events_loop.poll_events(|event| {
-            if let glutin::Event::WindowEvent { event, .. } = event {
-                match event {
-                    glutin::WindowEvent::CloseRequested => running = false,
-                    glutin::WindowEvent::KeyboardInput {
-                        input:
-                        glutin::KeyboardInput {
-                            virtual_keycode: Some(glutin::VirtualKeyCode::Escape),
-                            ..
-                        },
-                        ..
-                    } => return,
-                    glutin::WindowEvent::KeyboardInput {
-                        input:
-                        glutin::KeyboardInput {
-                            virtual_keycode: Some(glutin::VirtualKeyCode::Right),
-                            ..
-                        },
-                        ..
-                    } => offset = delta * 100.0,
-                    glutin::WindowEvent::KeyboardInput {
-                        input:
-                        glutin::KeyboardInput {
-                            virtual_keycode: Some(glutin::VirtualKeyCode::Left),
-                            ..
-                        },
-                        ..
-                    } => offset = delta * -100.0,
-                    _ => (),
-                }
-            }
-        });
-
-        logo.set_pos(Vector2::new(p.x + offset, p.y));
+    fn stats_updated(&mut self, stats: &app::FrameStats) {
 
-        engine.clear(Color::black());
-        // Render code goes here.
-        engine.render(&mut logo);
-        engine.render(&mut text);
-        engine.swap_buffers();
+        self.text.set_text((stats.fps as i64).to_string()).unwrap();
+
+    }
 
+    fn is_running(&self) -> bool {
+        return self.running;
     }
-}
\ No newline at end of file
+
+}
+
+fn main() {
+
+    let window_builder = glutin::WindowBuilder::new().with_title("Flat Engine v 0.1".to_string()).with_fullscreen(None).with_resizable(false);
+
+    let mut engine: core::FlatEngine = core::FlatEngine::init(window_builder).unwrap();
+    engine.input.bindings.bind_axis("move_x", vec![input::Binding::Key(glutin::VirtualKeyCode::Right)], vec![input::Binding::Key(glutin::VirtualKeyCode::Left)]);
+
+    let texture: Texture = Texture::load_from_path("resources/logo.png").unwrap();
+    let mut logo: Sprite = Sprite::from_texture(Box::new(texture));
+
+    let mut text: Text = Text::new("Flat Engine v 0.1", load_font_from_path("resources/trebuc.ttf").unwrap(), 40.0, Color::white()).unwrap();
+
+    text.set_pos(Vector2f::new(0.0, 0.0));
+
+    logo.set_pos(Vector2f::new(0.0, 0.0));
+    logo.set_size(engine.get_dimensions());
+
+    engine.load(&mut text).unwrap();
+    engine.load(&mut logo).unwrap();
+
+    let mut demo = Demo { logo, text, previous_x: 0.0, x: 0.0, running: true };
+    app::run(&mut engine, &mut demo);
+
+}
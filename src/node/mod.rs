@@ -7,10 +7,32 @@ use cgmath::Matrix;
 #[derive(Copy, Clone)]
 /**
 The default node object contains the data necessary to handle a basic node (position and transform).
+The transform is built from the position, rotation, skew and scale, which are applied around the origin.
 */
 pub struct NodeObject2D {
 
-    pub trans: Matrix4f,
+    pub pos: Vector2f,
+    /**
+    Rotation in radians, counter-clockwise.
+    */
+    pub rotation: f32,
+    pub scale: Vector2f,
+    /**
+    Shear angles in radians along the x and y axes.
+    */
+    pub skew: Vector2f,
+    /**
+    The point in local (unscaled) space that is placed at `pos` and that the node rotates and scales around.
+    */
+    pub origin: Vector2f,
+    /**
+    The world transform of the parent node, kept up to date by the scene graph. Identity for nodes without a parent.
+    */
+    pub parent_trans: Matrix4f,
+    /**
+    Draw order within a sprite batch. Nodes with a greater depth are drawn on top.
+    */
+    pub depth: f32,
 
 }
 
@@ -18,23 +40,32 @@ impl NodeObject2D {
 
     pub fn new() -> NodeObject2D {
 
-        return NodeObject2D { trans: Matrix4f::identity() };
+        return NodeObject2D {
+            pos: Vector2f::new(0.0, 0.0),
+            rotation: 0.0,
+            scale: Vector2f::new(1.0, 1.0),
+            skew: Vector2f::new(0.0, 0.0),
+            origin: Vector2f::new(0.0, 0.0),
+            parent_trans: Matrix4f::identity(),
+            depth: 0.0,
+        };
 
     }
 
     pub fn from(trans: Matrix4f) -> NodeObject2D {
 
-        return NodeObject2D { trans: Matrix4f::identity()  };
+        let mut node = NodeObject2D::new();
+        node.set_trans(trans);
+        return node;
 
     }
 
     pub fn pos_and_scale(pos: Vector2f, scale: Vector2f) -> NodeObject2D {
 
-        let mut trans: Matrix4f = Matrix4f::identity();
-        trans.set_translation(pos.to_vec3());
-        trans.set_scale(scale.to_vec3());
-
-        return NodeObject2D { trans: Matrix4f::identity()  };
+        let mut node = NodeObject2D::new();
+        node.pos = pos;
+        node.scale = scale;
+        return node;
 
     }
 
@@ -43,22 +74,110 @@ impl NodeObject2D {
 impl NodeObject2D {
 
     pub fn set_pos(&mut self, pos: Vector2f) {
-        self.trans.set_translation(pos.to_vec3());
+        self.pos = pos;
     }
     pub fn get_pos(&self) -> Vector2f {
-        return self.trans.get_translation().to_vec2();
+        return self.pos;
     }
     pub fn set_scale(&mut self, scale: Vector2f) {
-        self.trans.set_scale(scale.to_vec3());
+        self.scale = scale;
     }
     pub fn get_scale(&self) -> Vector2f {
-        return self.trans.get_scale().to_vec2();
+        return self.scale;
+    }
+    pub fn set_rotation(&mut self, rotation: f32) {
+        self.rotation = rotation;
+    }
+    pub fn get_rotation(&self) -> f32 {
+        return self.rotation;
+    }
+    pub fn rotate(&mut self, angle: f32) {
+        self.rotation += angle;
+    }
+    pub fn set_skew(&mut self, skew: Vector2f) {
+        self.skew = skew;
     }
+    pub fn get_skew(&self) -> Vector2f {
+        return self.skew;
+    }
+    pub fn set_origin(&mut self, origin: Vector2f) {
+        self.origin = origin;
+    }
+    pub fn get_origin(&self) -> Vector2f {
+        return self.origin;
+    }
+
+    /**
+    Decomposes a 2D affine transform into position, rotation, scale and x skew, keeping the current origin.
+    Anything the components cannot express (z translation, perspective, y skew) is lost.
+    */
     pub fn set_trans(&mut self, trans: Matrix4f) {
-        self.trans = trans;
+
+        let (a, b, c, d) = (trans.x.x, trans.x.y, trans.y.x, trans.y.y);
+
+        let rotation = b.atan2(a);
+        let (sin, cos) = rotation.sin_cos();
+        let scale_x = (a * a + b * b).sqrt();
+        let scale_y = d * cos - c * sin;
+        let shear = if scale_y != 0.0 { (c * cos + d * sin) / scale_y } else { 0.0 };
+
+        self.rotation = rotation;
+        self.scale = Vector2f::new(scale_x, scale_y);
+        self.skew = Vector2f::new(shear.atan(), 0.0);
+
+        // The origin is subtracted before the linear part, so add it back to find the position.
+        let linear = self.get_linear();
+        let offset = linear * Vector4f::new(self.origin.x, self.origin.y, 0.0, 0.0);
+        self.pos = Vector2f::new(trans.w.x + offset.x, trans.w.y + offset.y);
+
     }
+
+    /**
+    The rotation, skew and scale part of the transform.
+    */
+    fn get_linear(&self) -> Matrix4f {
+
+        let (sin, cos) = self.rotation.sin_cos();
+        let rotation = Matrix4f::new(
+            cos, sin, 0.0, 0.0,
+            -sin, cos, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        );
+        let skew = Matrix4f::new(
+            1.0, self.skew.y.tan(), 0.0, 0.0,
+            self.skew.x.tan(), 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        );
+        let scale = Matrix4f::from_nonuniform_scale(self.scale.x, self.scale.y, 1.0);
+
+        return rotation * skew * scale;
+
+    }
+
+    /**
+    Builds the local transform: translate(pos) * rotate * skew * scale * translate(-origin).
+    */
     pub fn get_trans(&self) -> Matrix4f {
-        return self.trans;
+
+        let mut trans = self.get_linear() * Matrix4f::from_translation(Vector3f::new(-self.origin.x, -self.origin.y, 0.0));
+        trans.w.x += self.pos.x;
+        trans.w.y += self.pos.y;
+        return trans;
+
+    }
+    pub fn get_world_trans(&self) -> Matrix4f {
+        return self.parent_trans * self.get_trans();
+    }
+    pub fn get_world_pos(&self) -> Vector2f {
+        return self.get_world_trans().get_translation().to_vec2();
+    }
+    pub fn set_depth(&mut self, depth: f32) {
+        self.depth = depth;
+    }
+    pub fn get_depth(&self) -> f32 {
+        return self.depth;
     }
 
 }
@@ -82,12 +201,45 @@ pub trait Node2D {
     fn get_scale(&self) -> Vector2f {
         return self.get_node_obj().get_scale();
     }
+    fn set_rotation(&mut self, rotation: f32) {
+        self.get_node_obj_mut().set_rotation(rotation);
+    }
+    fn get_rotation(&self) -> f32 {
+        return self.get_node_obj().get_rotation();
+    }
+    fn rotate(&mut self, angle: f32) {
+        self.get_node_obj_mut().rotate(angle);
+    }
+    fn set_skew(&mut self, skew: Vector2f) {
+        self.get_node_obj_mut().set_skew(skew);
+    }
+    fn get_skew(&self) -> Vector2f {
+        return self.get_node_obj().get_skew();
+    }
+    fn set_origin(&mut self, origin: Vector2f) {
+        self.get_node_obj_mut().set_origin(origin);
+    }
+    fn get_origin(&self) -> Vector2f {
+        return self.get_node_obj().get_origin();
+    }
     fn set_trans(&mut self, trans: Matrix4f) {
         self.get_node_obj_mut().set_trans(trans);
     }
     fn get_trans(&self) -> Matrix4f {
         return self.get_node_obj().get_trans();
     }
+    fn get_world_trans(&self) -> Matrix4f {
+        return self.get_node_obj().get_world_trans();
+    }
+    fn get_world_pos(&self) -> Vector2f {
+        return self.get_node_obj().get_world_pos();
+    }
+    fn set_depth(&mut self, depth: f32) {
+        self.get_node_obj_mut().set_depth(depth);
+    }
+    fn get_depth(&self) -> f32 {
+        return self.get_node_obj().get_depth();
+    }
 
 }
 
@@ -110,10 +262,33 @@ pub trait SizedNode2D : Node2D {
         return Vector2f { x: self.get_fixed_size().x * self.get_scale().x, y: self.get_fixed_size().y * self.get_scale().y };
     }
 
-    fn get_rect(&self) -> Rect {
+    /**
+    Puts the origin at the center of the node, so that it rotates and scales around its center and `pos` refers to the center.
+    */
+    fn center_origin(&mut self) {
+        let fs: Vector2f = self.get_fixed_size();
+        self.set_origin(Vector2f::new(fs.x / 2.0, fs.y / 2.0));
+    }
 
-        return Rect { x: self.get_pos().x, y: self.get_pos().y, width: self.get_size().x, height: self.get_size().y };
+    /**
+    The unscaled rect of the node in its own local space.
+    */
+    fn get_local_rect(&self) -> Rect {
+        return Rect { x: 0.0, y: 0.0, width: self.get_fixed_size().x, height: self.get_fixed_size().y };
+    }
 
+    /**
+    The corners of the node after rotation, skew and scale are applied, in the space of its parent.
+    */
+    fn get_oriented_rect(&self) -> OrientedRect {
+        return OrientedRect::from_rect(&self.get_local_rect(), &self.get_trans());
+    }
+
+    /**
+    The axis aligned bounding box of the node in the space of its parent.
+    */
+    fn get_rect(&self) -> Rect {
+        return self.get_oriented_rect().get_bounds();
     }
 
 }
@@ -190,4 +365,265 @@ impl NodeObject3D {
         return self.trans;
     }
 
-}
\ No newline at end of file
+}
+
+pub type NodeId = usize;
+
+/**
+Anything that can be placed in a scene graph: a 2D node that can be drawn.
+*/
+pub trait SceneObject : Node2D + core::Drawable {}
+
+impl<T: Node2D + core::Drawable> SceneObject for T {}
+
+struct SceneNode<'a> {
+
+    object: Box<SceneObject + 'a>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    world: Matrix4f,
+    dirty: bool,
+
+}
+
+/**
+Owns a hierarchy of 2D nodes. Each node is transformed by the world transform of its parent, so moving a node moves all of its children with it.
+World transforms are cached and only recomputed for nodes that were accessed mutably (and their children) since the last update.
+*/
+pub struct SceneGraph<'a> {
+
+    nodes: core::ResourceStore<SceneNode<'a>>,
+    roots: Vec<NodeId>,
+
+}
+
+impl<'a> SceneGraph<'a> {
+
+    pub fn new() -> SceneGraph<'a> {
+
+        return SceneGraph { nodes: core::ResourceStore::new(), roots: Vec::new() };
+
+    }
+
+    /**
+    Adds a node without a parent.
+    */
+    pub fn add(&mut self, object: Box<SceneObject + 'a>) -> NodeId {
+
+        let id = self.nodes.insert(SceneNode { object, parent: None, children: Vec::new(), world: Matrix4f::identity(), dirty: true });
+        self.roots.push(id);
+        return id;
+
+    }
+
+    pub fn add_child(&mut self, parent: NodeId, object: Box<SceneObject + 'a>) -> NodeId {
+
+        let id = self.add(object);
+        self.set_parent(id, Some(parent));
+        return id;
+
+    }
+
+    /**
+    Moves a node (and its children) under a new parent, or to the top level if the parent is None.
+    The local transform of the node is kept, so its world transform changes with the new parent.
+    */
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+
+        if self.nodes.get(id).is_none() {
+            panic!("Cannot reparent a node that is not in the scene graph!");
+        }
+
+        if let Some(parent) = parent {
+            if self.nodes.get(parent).is_none() {
+                panic!("Cannot reparent a node to a parent that is not in the scene graph!");
+            }
+            if self.is_ancestor(id, parent) {
+                panic!("Cannot make a node a child of itself or of one of its descendants!");
+            }
+        }
+
+        // Detach from the old parent.
+        match self.nodes.get(id).unwrap().parent {
+            Some(old) => self.nodes.get_mut(old).unwrap().children.retain(|c| *c != id),
+            None => self.roots.retain(|r| *r != id),
+        }
+
+        // Attach to the new one.
+        match parent {
+            Some(parent) => self.nodes.get_mut(parent).unwrap().children.push(id),
+            None => self.roots.push(id),
+        }
+
+        let node = self.nodes.get_mut(id).unwrap();
+        node.parent = parent;
+        node.dirty = true;
+
+    }
+
+    /**
+    Returns true if `ancestor` is `id` or one of its parents.
+    */
+    pub fn is_ancestor(&self, ancestor: NodeId, id: NodeId) -> bool {
+
+        let mut current = Some(id);
+        while let Some(c) = current {
+            if c == ancestor {
+                return true;
+            }
+            current = self.nodes.get(c).and_then(|n| n.parent);
+        }
+        return false;
+
+    }
+
+    pub fn get_parent(&self, id: NodeId) -> Option<NodeId> {
+        return self.nodes.get(id).and_then(|n| n.parent);
+    }
+
+    pub fn get_children(&self, id: NodeId) -> &[NodeId] {
+        match self.nodes.get(id) {
+            Some(node) => &node.children,
+            None => &[],
+        }
+    }
+
+    pub fn get_roots(&self) -> &[NodeId] {
+        return &self.roots;
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&SceneObject> {
+        return self.nodes.get(id).map(|n| n.object.as_ref() as &SceneObject);
+    }
+
+    /**
+    Gives mutable access to a node. The node is marked dirty, so its world transform (and those of its children) are recomputed on the next update.
+    */
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut SceneObject> {
+        match self.nodes.get_mut(id) {
+            Some(node) => {
+                node.dirty = true;
+                Some(node.object.as_mut() as &mut SceneObject)
+            },
+            None => None,
+        }
+    }
+
+    /**
+    Removes a node and all of its descendants, returning them so that they can be destroyed or reused.
+    The returned list starts with the removed node and is in depth-first order.
+    */
+    pub fn remove(&mut self, id: NodeId) -> Vec<Box<SceneObject + 'a>> {
+
+        let mut removed: Vec<Box<SceneObject + 'a>> = Vec::new();
+
+        if self.nodes.get(id).is_none() {
+            return removed;
+        }
+
+        match self.nodes.get(id).unwrap().parent {
+            Some(parent) => self.nodes.get_mut(parent).unwrap().children.retain(|c| *c != id),
+            None => self.roots.retain(|r| *r != id),
+        }
+
+        let mut stack: Vec<NodeId> = vec![id];
+        while let Some(current) = stack.pop() {
+            if let Some(node) = self.nodes.remove(current) {
+                stack.extend(node.children.iter().rev());
+                removed.push(node.object);
+            }
+        }
+
+        return removed;
+
+    }
+
+    /**
+    Removes a node and all of its descendants and releases their renderer resources.
+    */
+    pub fn destroy_node(&mut self, id: NodeId, engine: &mut core::FlatEngine) {
+
+        for mut object in self.remove(id) {
+            object.destroy(engine);
+        }
+
+    }
+
+    pub fn get_world_trans(&mut self, id: NodeId) -> Matrix4f {
+
+        self.update();
+        return self.nodes.get(id).map(|n| n.world).unwrap_or(Matrix4f::identity());
+
+    }
+
+    /**
+    Recomputes the world transforms of all dirty nodes and their children.
+    */
+    pub fn update(&mut self) {
+
+        let mut stack: Vec<(NodeId, Matrix4f, bool)> = self.roots.iter().rev().map(|r| (*r, Matrix4f::identity(), false)).collect();
+
+        while let Some((id, parent_world, parent_changed)) = stack.pop() {
+
+            let node = self.nodes.get_mut(id).unwrap();
+            let changed = parent_changed || node.dirty;
+
+            if changed {
+                node.object.get_node_obj_mut().parent_trans = parent_world;
+                node.world = parent_world * node.object.get_trans();
+                node.dirty = false;
+            }
+
+            for child in node.children.iter().rev() {
+                stack.push((*child, node.world, changed));
+            }
+
+        }
+
+    }
+
+    /**
+    Visits every node, parents before their children.
+    */
+    fn for_each_mut<F>(&mut self, mut f: F) where F: FnMut(&mut SceneObject) {
+
+        let mut stack: Vec<NodeId> = self.roots.iter().rev().cloned().collect();
+
+        while let Some(id) = stack.pop() {
+            let node = self.nodes.get_mut(id).unwrap();
+            f(node.object.as_mut());
+            stack.extend(node.children.iter().rev());
+        }
+
+    }
+
+}
+
+impl<'a> core::Drawable for SceneGraph<'a> {
+
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        let mut result = Ok(());
+        self.for_each_mut(|object| {
+            if result.is_ok() {
+                result = object.load(engine);
+            }
+        });
+        return result;
+
+    }
+
+    fn render(&mut self, engine: &mut core::FlatEngine) {
+
+        self.update();
+        self.for_each_mut(|object| object.render(engine));
+
+    }
+
+    fn destroy(&mut self, engine: &mut core::FlatEngine) {
+
+        self.for_each_mut(|object| object.destroy(engine));
+
+    }
+
+}
@@ -0,0 +1,514 @@
+use super::*;
+
+use self::render::{RenderTarget, Texture, UvVertex2f};
+
+gfx_defines!{
+
+    constant PostParams {
+
+        param0: [f32; 4] = "u_Param0",
+        param1: [f32; 4] = "u_Param1",
+        param2: [f32; 4] = "u_Param2",
+        param3: [f32; 4] = "u_Param3",
+        resolution: [f32; 2] = "u_Resolution",
+        time: f32 = "u_Time",
+        padding: f32 = "u_Padding",
+
+    }
+
+    pipeline pipe {
+        vbuf: gfx::VertexBuffer<UvVertex2f> = (),
+        input: gfx::TextureSampler<[f32; 4]> = "t_Input",
+        secondary: gfx::TextureSampler<[f32; 4]> = "t_Secondary",
+        params: gfx::ConstantBuffer<PostParams> = "Post",
+        out: gfx::RenderTarget<ColorFormat> = "Target0",
+        scissor: gfx::Scissor = (),
+    }
+}
+
+pub const VERTEX_SHADER: &'static [u8] = include_bytes!("../../shaders/std_post_v.glsl");
+
+/**
+The two triangles covering the whole output, in normalized device coordinates.
+Flipping is needed when drawing into a render target on the GPU, as targets are stored upside down.
+*/
+pub fn fullscreen_quad(flipped: bool) -> [UvVertex2f; 6] {
+
+    let y = if flipped { -1.0 } else { 1.0 };
+    return [
+        UvVertex2f { pos: [-1.0, y], uv: [0.0, 0.0] },
+        UvVertex2f { pos: [-1.0, -y], uv: [0.0, 1.0] },
+        UvVertex2f { pos: [1.0, -y], uv: [1.0, 1.0] },
+        UvVertex2f { pos: [-1.0, y], uv: [0.0, 0.0] },
+        UvVertex2f { pos: [1.0, -y], uv: [1.0, 1.0] },
+        UvVertex2f { pos: [1.0, y], uv: [1.0, 0.0] },
+    ];
+
+}
+
+/**
+The shader of a pass. GLSL cannot be run by the software renderer, so it shades the built-in shaders on the CPU and draws custom shaders as a plain copy.
+*/
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PostShader {
+
+    Custom,
+    /**
+    Copies the input.
+    */
+    Copy,
+    /**
+    Param 0: strength, radius and softness.
+    */
+    Vignette,
+    /**
+    Param 0: amount.
+    */
+    Grayscale,
+    /**
+    Looks the colours up in the secondary texture (see `identity_lut`). Param 0: amount and LUT size.
+    */
+    ColorGrade,
+    /**
+    Keeps the part of each colour above a brightness. Param 0: threshold.
+    */
+    Threshold,
+    /**
+    A 9 tap gaussian blur. Param 0: the direction in pixels between taps.
+    */
+    Blur,
+    /**
+    Adds the input on top of the secondary texture. Param 0: intensity.
+    */
+    Combine,
+    /**
+    Param 0: scanline strength, curvature and vignette.
+    */
+    Crt,
+
+}
+
+impl PostShader {
+
+    /**
+    The fragment shader source of a built-in shader.
+    */
+    pub fn get_source(&self) -> Option<&'static [u8]> {
+
+        return match *self {
+            PostShader::Custom => None,
+            PostShader::Copy => Some(include_bytes!("../../shaders/std_post_copy_f.glsl")),
+            PostShader::Vignette => Some(include_bytes!("../../shaders/std_post_vignette_f.glsl")),
+            PostShader::Grayscale => Some(include_bytes!("../../shaders/std_post_grayscale_f.glsl")),
+            PostShader::ColorGrade => Some(include_bytes!("../../shaders/std_post_lut_f.glsl")),
+            PostShader::Threshold => Some(include_bytes!("../../shaders/std_post_threshold_f.glsl")),
+            PostShader::Blur => Some(include_bytes!("../../shaders/std_post_blur_f.glsl")),
+            PostShader::Combine => Some(include_bytes!("../../shaders/std_post_combine_f.glsl")),
+            PostShader::Crt => Some(include_bytes!("../../shaders/std_post_crt_f.glsl")),
+        };
+
+    }
+
+}
+
+/**
+Where the `t_Secondary` texture of a pass comes from.
+*/
+pub enum Secondary {
+
+    None,
+    /**
+    A texture created through the renderer.
+    */
+    Texture(core::TextureId),
+    /**
+    A texture owned by the pass, uploaded when the pass is first drawn.
+    */
+    Image(Texture),
+    /**
+    The input of the effect the pass belongs to, i.e. the image before its first pass.
+    */
+    EffectInput,
+
+}
+
+/**
+A full-screen pass applied to the scene after it has been rendered.
+
+Each pass draws a quad over the whole output with a fragment shader, which receives:
+
+- `t_Input`: the output of the previous pass (the scene for the first pass).
+- `t_Secondary`: an optional second texture, such as a LUT or the input of the effect.
+- A `Post` uniform block with `vec4 u_Param0` to `u_Param3` (the pass parameters), `vec2 u_Resolution` (the output size in pixels), `float u_Time` and `float u_Padding`.
+- `in vec2 v_Uv`, with (0, 0) at the top left of the image.
+
+Custom passes can use any subset of these. See the `std_post_*_f.glsl` shaders for examples.
+*/
+pub struct PostPass {
+
+    pub kind: PostShader,
+    pub f_shader: Vec<u8>,
+    /**
+    Uploaded as `u_Param0` to `u_Param3`.
+    */
+    pub params: [[f32; 4]; 4],
+    pub secondary: Secondary,
+    shader: Option<core::ShaderId>,
+    image: Option<core::TextureId>,
+
+}
+
+impl PostPass {
+
+    pub fn new(kind: PostShader) -> PostPass {
+
+        return PostPass {
+            kind,
+            f_shader: kind.get_source().unwrap_or(include_bytes!("../../shaders/std_post_copy_f.glsl")).to_vec(),
+            params: [[0.0; 4]; 4],
+            secondary: Secondary::None,
+            shader: None,
+            image: None,
+        };
+
+    }
+
+    /**
+    A pass with a custom fragment shader (see the module documentation for its inputs).
+    */
+    pub fn custom(f_shader: &[u8]) -> PostPass {
+
+        let mut pass = PostPass::new(PostShader::Custom);
+        pass.f_shader = f_shader.to_vec();
+        return pass;
+
+    }
+
+    pub fn with_param(mut self, index: usize, value: [f32; 4]) -> PostPass {
+
+        self.set_param(index, value);
+        return self;
+
+    }
+
+    pub fn with_secondary(mut self, secondary: Secondary) -> PostPass {
+
+        self.secondary = secondary;
+        return self;
+
+    }
+
+    pub fn set_param(&mut self, index: usize, value: [f32; 4]) {
+
+        self.params[index] = value;
+
+    }
+
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        if self.shader.is_none() {
+            self.shader = Some(engine.create_shader(core::PipelineKind::PostProcess(self.kind), VERTEX_SHADER, &self.f_shader)?);
+        }
+        if let Secondary::Image(ref image) = self.secondary {
+            if self.image.is_none() {
+                self.image = Some(engine.renderer.create_texture(image)?);
+            }
+        }
+        return Ok(());
+
+    }
+
+    fn destroy(&mut self, engine: &mut core::FlatEngine) {
+
+        if let Some(image) = self.image.take() {
+            engine.renderer.destroy_texture(image);
+        }
+        self.shader = None;
+
+    }
+
+}
+
+/**
+A named group of passes that is turned on and off as a whole.
+*/
+pub struct PostEffect {
+
+    pub name: String,
+    pub enabled: bool,
+    pub passes: Vec<PostPass>,
+
+}
+
+impl PostEffect {
+
+    pub fn new(name: &str, passes: Vec<PostPass>) -> PostEffect {
+
+        return PostEffect { name: name.to_string(), enabled: true, passes };
+
+    }
+
+    pub fn custom(name: &str, f_shader: &[u8]) -> PostEffect {
+
+        return PostEffect::new(name, vec![PostPass::custom(f_shader)]);
+
+    }
+
+    /**
+    Darkens the image from `radius` (the distance from the center, in uv units) outwards, reaching `strength` at `radius + softness`.
+    */
+    pub fn vignette(strength: f32, radius: f32, softness: f32) -> PostEffect {
+
+        return PostEffect::new("vignette", vec![PostPass::new(PostShader::Vignette).with_param(0, [strength, radius, softness, 0.0])]);
+
+    }
+
+    pub fn grayscale(amount: f32) -> PostEffect {
+
+        return PostEffect::new("grayscale", vec![PostPass::new(PostShader::Grayscale).with_param(0, [amount, 0.0, 0.0, 0.0])]);
+
+    }
+
+    /**
+    Maps the colours through a LUT strip laid out like `identity_lut`.
+    */
+    pub fn color_grade(lut: Texture, amount: f32) -> PostEffect {
+
+        let size = lut.dimensions.y as f32;
+        return PostEffect::new("color_grade", vec![
+            PostPass::new(PostShader::ColorGrade).with_param(0, [amount, size, 0.0, 0.0]).with_secondary(Secondary::Image(lut))
+        ]);
+
+    }
+
+    /**
+    Blurs the parts brighter than `threshold` and adds them back on top of the image.
+    */
+    pub fn bloom(threshold: f32, spread: f32, intensity: f32) -> PostEffect {
+
+        return PostEffect::new("bloom", vec![
+            PostPass::new(PostShader::Threshold).with_param(0, [threshold, 0.0, 0.0, 0.0]),
+            PostPass::new(PostShader::Blur).with_param(0, [spread, 0.0, 0.0, 0.0]),
+            PostPass::new(PostShader::Blur).with_param(0, [0.0, spread, 0.0, 0.0]),
+            PostPass::new(PostShader::Combine).with_param(0, [intensity, 0.0, 0.0, 0.0]).with_secondary(Secondary::EffectInput),
+        ]);
+
+    }
+
+    pub fn crt(scanlines: f32, curvature: f32, vignette: f32) -> PostEffect {
+
+        return PostEffect::new("crt", vec![PostPass::new(PostShader::Crt).with_param(0, [scanlines, curvature, vignette, 0.0])]);
+
+    }
+
+    /**
+    A horizontal and a vertical gaussian blur, with `spread` pixels between the samples.
+    */
+    pub fn blur(spread: f32) -> PostEffect {
+
+        return PostEffect::new("blur", vec![
+            PostPass::new(PostShader::Blur).with_param(0, [spread, 0.0, 0.0, 0.0]),
+            PostPass::new(PostShader::Blur).with_param(0, [0.0, spread, 0.0, 0.0]),
+        ]);
+
+    }
+
+}
+
+/**
+A LUT that leaves the colours unchanged, to be edited in an image editor and passed to `PostEffect::color_grade`.
+It is a strip of `size` slices of `size` x `size` pixels, with red increasing to the right and green downwards in each slice, and blue increasing from slice to slice.
+*/
+pub fn identity_lut(size: u16) -> Texture {
+
+    let max = (size.max(2) - 1) as f32;
+    let mut data: Vec<u8> = Vec::with_capacity(size as usize * size as usize * size as usize * 4);
+    for g in 0..size {
+        for b in 0..size {
+            for r in 0..size {
+                data.extend_from_slice(&[(r as f32 / max * 255.0).round() as u8, (g as f32 / max * 255.0).round() as u8, (b as f32 / max * 255.0).round() as u8, 255]);
+            }
+        }
+    }
+    return Texture::from_data(&data, size * size, size);
+
+}
+
+/**
+Renders the scene into an off-screen target and runs it through the enabled effects, in order, on the way to the window.
+The chain and each effect can be toggled at any time; when nothing is enabled the scene is drawn straight to the window.
+*/
+pub struct PostProcessChain {
+
+    pub effects: Vec<PostEffect>,
+    pub enabled: bool,
+    /**
+    Passed to the shaders as `u_Time`, advanced by `update`.
+    */
+    pub time: f32,
+    /**
+    Three targets are needed so that the input of an effect survives its passes.
+    */
+    targets: Vec<RenderTarget>,
+    size: Vector2u,
+
+}
+
+impl PostProcessChain {
+
+    pub fn new() -> PostProcessChain {
+
+        return PostProcessChain { effects: Vec::new(), enabled: true, time: 0.0, targets: Vec::new(), size: Vector2u::new(0, 0) };
+
+    }
+
+    pub fn add(&mut self, effect: PostEffect) {
+
+        self.effects.push(effect);
+
+    }
+
+    pub fn get(&self, name: &str) -> Option<&PostEffect> {
+
+        return self.effects.iter().find(|e| e.name == name);
+
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut PostEffect> {
+
+        return self.effects.iter_mut().find(|e| e.name == name);
+
+    }
+
+    /**
+    Turns the effect with the given name on or off, returning false if there is none.
+    */
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+
+        match self.get_mut(name) {
+            Some(effect) => {
+                effect.enabled = enabled;
+                true
+            },
+            None => false,
+        }
+
+    }
+
+    /**
+    Whether rendering goes through any passes.
+    */
+    pub fn is_active(&self) -> bool {
+
+        return self.enabled && self.effects.iter().any(|e| e.enabled && !e.passes.is_empty());
+
+    }
+
+    pub fn update(&mut self, dt: f32) {
+
+        self.time += dt;
+
+    }
+
+    /**
+    (Re)creates the targets if the framebuffer size changed.
+    */
+    fn prepare_targets(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        let dimensions = engine.get_dimensions() * engine.renderer.get_hidpi_factor();
+        let size = Vector2u::new(dimensions.x.round().max(1.0) as u32, dimensions.y.round().max(1.0) as u32);
+
+        if size != self.size || self.targets.is_empty() {
+            for target in self.targets.iter_mut() {
+                target.destroy(engine);
+            }
+            self.targets.clear();
+            for _ in 0..3 {
+                self.targets.push(RenderTarget::new(size, engine)?);
+            }
+            self.size = size;
+        }
+        return Ok(());
+
+    }
+
+    /**
+    Renders the scene drawn by the closure through the chain. The camera of the engine is used as it is.
+    The output replaces what is in the viewport of the window, so the scene should clear its background.
+    */
+    pub fn render<F>(&mut self, engine: &mut core::FlatEngine, draw: F) -> error::Result<()> where F: FnOnce(&mut core::FlatEngine) {
+
+        if !self.is_active() {
+            draw(engine);
+            return Ok(());
+        }
+
+        self.prepare_targets(engine)?;
+        for effect in self.effects.iter_mut().filter(|e| e.enabled) {
+            for pass in effect.passes.iter_mut() {
+                pass.load(engine)?;
+            }
+        }
+
+        let output = engine.get_render_target();
+        engine.set_render_target(Some(self.targets[0].get_id()));
+        draw(engine);
+
+        let passes: Vec<(usize, usize)> = self.effects.iter().enumerate()
+            .filter(|&(_, e)| e.enabled)
+            .flat_map(|(i, e)| (0..e.passes.len()).map(move |p| (i, p)))
+            .collect();
+
+        let mut current = 0;
+        let mut effect_input = 0;
+        for (n, &(e, p)) in passes.iter().enumerate() {
+
+            if p == 0 {
+                effect_input = current;
+            }
+            let next = (0..self.targets.len()).find(|&i| i != current && i != effect_input).unwrap();
+            let last = n + 1 == passes.len();
+
+            engine.set_render_target(if last { output } else { Some(self.targets[next].get_id()) });
+
+            let pass = &self.effects[e].passes[p];
+            let secondary = match pass.secondary {
+                Secondary::None => None,
+                Secondary::Texture(id) => Some(id),
+                Secondary::Image(_) => pass.image,
+                Secondary::EffectInput => Some(self.targets[effect_input].get_texture()),
+            };
+
+            engine.renderer.draw_post_process(&core::PostProcessCall {
+                shader: pass.shader.unwrap(),
+                input: self.targets[current].get_texture(),
+                secondary,
+                params: pass.params,
+                time: self.time,
+            });
+
+            current = next;
+
+        }
+
+        engine.flush();
+        return Ok(());
+
+    }
+
+    pub fn destroy(&mut self, engine: &mut core::FlatEngine) {
+
+        for target in self.targets.iter_mut() {
+            target.destroy(engine);
+        }
+        self.targets.clear();
+        self.size = Vector2u::new(0, 0);
+        for effect in self.effects.iter_mut() {
+            for pass in effect.passes.iter_mut() {
+                pass.destroy(engine);
+            }
+        }
+
+    }
+
+}
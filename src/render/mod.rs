@@ -2,11 +2,10 @@
 use super::*;
 
 use node::*;
+use std::cmp::Ordering;
 use gfx::handle::ShaderResourceView;
 use image;
 use geometry::GeometryRenderer;
-use gfx::Factory;
-use gfx::traits::FactoryExt;
 
 gfx_defines!{
 
@@ -28,6 +27,7 @@ gfx_defines!{
         tex: gfx::TextureSampler<[f32; 4]> = "t_Texture",
         trans: gfx::ConstantBuffer<GeometryTransform> = "Transform",
         out: gfx::BlendTarget<ColorFormat> = ("Target0", gfx::state::ColorMask::all(), gfx::preset::blend::ALPHA),
+        scissor: gfx::Scissor = (),
     }
 }
 
@@ -63,14 +63,26 @@ impl UvVertexArray {
 
     pub fn from_rect(rect: &Rect) -> UvVertexArray {
 
+        return UvVertexArray::from_rect_uv(rect, &Rect::new(0.0, 0.0, 1.0, 1.0));
+
+    }
+
+    /**
+    Maps a part of the texture onto the rect. The uv rect has its origin at the top left of the texture, like the texture data.
+    */
+    pub fn from_rect_uv(rect: &Rect, uv: &Rect) -> UvVertexArray {
+
+        let (left, right) = (uv.x, uv.x + uv.width);
+        let (top, bottom) = (uv.y, uv.y + uv.height);
+
         return UvVertexArray {
             data: [
-                UvVertex2f { pos: [rect.x, rect.y], uv: [0.0, 1.0] },
-                UvVertex2f { pos: [rect.x + rect.width, rect.y], uv: [1.0, 1.0] },
-                UvVertex2f { pos: [rect.x, rect.y + rect.height], uv: [0.0, 0.0] },
-                UvVertex2f { pos: [rect.x + rect.width, rect.y + rect.height], uv: [1.0, 0.0] },
-                UvVertex2f { pos: [rect.x + rect.width, rect.y], uv: [1.0, 1.0] },
-                UvVertex2f { pos: [rect.x, rect.y + rect.height], uv: [0.0, 0.0] }
+                UvVertex2f { pos: [rect.x, rect.y], uv: [left, bottom] },
+                UvVertex2f { pos: [rect.x + rect.width, rect.y], uv: [right, bottom] },
+                UvVertex2f { pos: [rect.x, rect.y + rect.height], uv: [left, top] },
+                UvVertex2f { pos: [rect.x + rect.width, rect.y + rect.height], uv: [right, top] },
+                UvVertex2f { pos: [rect.x + rect.width, rect.y], uv: [right, bottom] },
+                UvVertex2f { pos: [rect.x, rect.y + rect.height], uv: [left, top] }
             ]
         }
 
@@ -105,85 +117,139 @@ impl Texture {
 
     }
 
-    pub fn load_from_path(path: &str) -> Texture {
-        use gfx::format::Rgba8;
-        let img = image::open(path).unwrap().to_rgba();
-        let (width, height) = img.dimensions();
-        return Texture { data: Vec::from(img.as_ref()), dimensions: Vector2::new(width as u16, height as u16) };
+    /**
+    Copies an area of the texture, in pixels from its top left. The area is cut off at the edges of the texture.
+    */
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Texture {
+
+        let (texture_width, texture_height) = (self.dimensions.x as u32, self.dimensions.y as u32);
+        let width = width.min(texture_width.saturating_sub(x));
+        let height = height.min(texture_height.saturating_sub(y));
+
+        let mut data: Vec<u8> = Vec::with_capacity(width as usize * height as usize * 4);
+        for row in y..y + height {
+            let start = (row * texture_width + x) as usize * 4;
+            data.extend_from_slice(&self.data[start..start + width as usize * 4]);
+        }
+        return Texture { data, dimensions: Vector2::new(width as u16, height as u16) };
 
     }
 
-    pub fn load_from_image(bytes: &[u8]) -> Texture {
-        use gfx::format::Rgba8;
-        let img = image::load_from_memory(bytes).unwrap().to_rgba();
-        let (width, height) = img.dimensions();
-        return Texture { data: Vec::from(img.as_ref()), dimensions: Vector2::new(width as u16, height as u16) };
+    pub fn load_from_path(path: &str) -> error::Result<Texture> {
+        let img = image::open(path)?.to_rgba();
+        return Texture::from_loaded_image(img);
+
     }
 
-    pub fn get_shader_texture(&self, renderer: &mut core::Renderer) -> gfx::handle::ShaderResourceView<ResourceType, [f32; 4]> {
-        let kind = gfx::texture::Kind::D2(self.dimensions.x, self.dimensions.y, gfx::texture::AaMode::Single);
-        let (_, view) = renderer.factory.create_texture_immutable_u8::<gfx::format::Rgba8>(kind, gfx::texture::Mipmap::Provided, &[self.data.as_ref()]).unwrap();
-        return view;
+    pub fn load_from_image(bytes: &[u8]) -> error::Result<Texture> {
+        let img = image::load_from_memory(bytes)?.to_rgba();
+        return Texture::from_loaded_image(img);
+    }
+
+    fn from_loaded_image(img: image::ImageBuffer<image::Rgba<u8>, Vec<u8>>) -> error::Result<Texture> {
+        let (width, height) = img.dimensions();
+        if width > u16::max_value() as u32 || height > u16::max_value() as u32 {
+            return Err(Error::Texture(format!("The image is {}x{}, textures can be at most {} pixels wide and high.", width, height, u16::max_value())));
+        }
+        return Ok(Texture { data: Vec::from(img.as_ref()), dimensions: Vector2::new(width as u16, height as u16) });
     }
 
 }
 
 pub struct TextureRenderer {
 
-    data: render::pipe::Data<ResourceType>,
-    slice: gfx::Slice<ResourceType>,
-    pipeline_state: gfx::PipelineState<ResourceType, render::pipe::Meta>,
+    shader: core::ShaderId,
+    vertices: core::BufferId,
+    texture: core::TextureId,
+    /**
+    False if the texture belongs to something else, like a render target, and must not be destroyed with the renderer.
+    */
+    owns_texture: bool,
 
 }
 
 impl TextureRenderer {
 
-    pub fn new(data: render::pipe::Data<ResourceType>, slice: gfx::Slice<ResourceType>, pipeline_state: gfx::PipelineState<ResourceType, render::pipe::Meta>) -> TextureRenderer {
-        return TextureRenderer { data, slice, pipeline_state };
+    pub fn new(shader: core::ShaderId, vertices: core::BufferId, texture: core::TextureId) -> TextureRenderer {
+        return TextureRenderer { shader, vertices, texture, owns_texture: true };
+    }
+
+    pub fn get_shader(&self) -> core::ShaderId {
+        return self.shader;
     }
 
-    pub fn create(texture: &Texture, vertices: &[UvVertex2f], v_shader: &[u8], f_shader: &[u8], renderer: &mut core::Renderer) -> TextureRenderer {
+    /**
+    Creates a renderer that draws an existing texture without taking ownership of it.
+    */
+    pub fn create_shared(texture: core::TextureId, vertices: &[UvVertex2f], v_shader: &[u8], f_shader: &[u8], engine: &mut core::FlatEngine) -> error::Result<TextureRenderer> {
+
+        let shader = engine.create_shader(core::PipelineKind::Texture, v_shader, f_shader)?;
+        let vertices = engine.renderer.create_vertex_buffer(core::Vertices::Uv2f(vertices));
+
+        return Ok(TextureRenderer { shader, vertices, texture, owns_texture: false });
+
+    }
+
+    /**
+    Creates a renderer for a texture, sharing the upload with other renderers of an identical texture (see `FlatEngine::create_texture`).
+    */
+    pub fn create(texture: &Texture, vertices: &[UvVertex2f], v_shader: &[u8], f_shader: &[u8], engine: &mut core::FlatEngine) -> error::Result<TextureRenderer> {
         // Load shaders.
-        let pipeline_state = renderer.factory
-            .create_pipeline_simple(
-                v_shader,
-                f_shader,
-                pipe::new(),
-            )
-            .unwrap();
-        let (vertex_buffer, slice) = renderer.factory.create_vertex_buffer_with_slice(vertices, ());
-        let trans_buffer = renderer.factory.create_constant_buffer(1);
-
-        let sampler = renderer.factory.create_sampler_linear();
-
-        let data = pipe::Data {
-            vbuf: vertex_buffer,
-            tex: (texture.get_shader_texture(renderer), sampler),
-            trans: trans_buffer,
-            out: renderer.render_view.clone(),
-        };
+        let shader = engine.create_shader(core::PipelineKind::Texture, v_shader, f_shader)?;
+        let texture = engine.create_texture(texture)?;
+        let vertices = engine.renderer.create_vertex_buffer(core::Vertices::Uv2f(vertices));
 
-        return TextureRenderer::new(data, slice, pipeline_state);
+        return Ok(TextureRenderer::new(shader, vertices, texture));
 
     }
 
     // Automatically applies global Matrix4f to the render.
     pub fn render(&mut self, model_trans: Matrix4f, view_trans: Matrix4f, projection_trans: Matrix4f, engine: &mut core::FlatEngine) {
-        //println!("{}", model_trans.x.x);
-        engine.renderer.encoder.update_buffer(&self.data.trans, &[GeometryTransform { model: model_trans.get_data(), view: view_trans.get_data(), projection: projection_trans.get_data() }], 0); //update buffers
-        engine.renderer.encoder.draw(&self.slice, &mut self.pipeline_state, &self.data); // draw commands with buffer data and attached pso
-        engine.renderer.encoder.flush(engine.renderer.device.as_mut()); // execute draw commands
+        engine.renderer.draw(&core::DrawCall { shader: self.shader, vertices: self.vertices, texture: Some(self.texture), model: model_trans, view: view_trans, projection: projection_trans, range: None }); // draw commands with buffer data and attached pso
+        engine.renderer.flush(); // execute draw commands
+    }
+
+    pub fn update_vertices(&mut self, vertices: &[UvVertex2f], renderer: &mut core::Renderer) -> error::Result<()> {
+        return renderer.update_vertex_buffer(self.vertices, core::Vertices::Uv2f(vertices));
+    }
+
+    /**
+    Switches to another texture. Uploads may be shared with other renderers, so the old one is released rather than changed.
+    */
+    pub fn update_texture(&mut self, texture: &Texture, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        let id = engine.create_texture(texture)?;
+        if self.owns_texture {
+            engine.destroy_texture(self.texture);
+        }
+        self.texture = id;
+        self.owns_texture = true;
+        return Ok(());
+
+    }
+
+    /**
+    Switches to drawing an existing texture, releasing the texture of the renderer if it had its own.
+    */
+    pub fn set_shared_texture(&mut self, texture: core::TextureId, engine: &mut core::FlatEngine) {
+
+        if self.owns_texture {
+            engine.destroy_texture(self.texture);
+        }
+        self.texture = texture;
+        self.owns_texture = false;
+
     }
 
-    pub fn update_vertices(&mut self, vertices: &[UvVertex2f], renderer: &mut core::Renderer) {
-        let (vertex_buffer, slice) = renderer.factory.create_vertex_buffer_with_slice(vertices, ());
-        self.data.vbuf = vertex_buffer;
-        self.slice = slice;
+    pub fn destroy(&mut self, engine: &mut core::FlatEngine) {
+        engine.renderer.destroy_vertex_buffer(self.vertices);
+        if self.owns_texture {
+            engine.destroy_texture(self.texture);
+        }
     }
 
-    pub fn update_texture(&mut self, texture: &Texture, renderer: &mut core::Renderer) {
-        let sampler = renderer.factory.create_sampler_linear();
-        self.data.tex = (texture.get_shader_texture(renderer), sampler);
+    pub fn get_texture(&self) -> core::TextureId {
+        return self.texture;
     }
 
 }
@@ -194,8 +260,20 @@ pub struct Sprite {
     pub texture: Box<Texture>,
     pub vertices: UvVertexArray,
     pub texture_renderer: Option<TextureRenderer>,
+    /**
+    A texture owned by the renderer (like that of a render target) drawn instead of `texture`, which then only provides the size.
+    */
+    pub shared_texture: Option<core::TextureId>,
+    /**
+    The part of the texture that is shown, from (0, 0) at the top left to (1, 1) at the bottom right.
+    */
+    pub uv_rect: Rect,
     pub update_texture: bool,
     pub has_loaded: bool,
+    /**
+    The last error of an update while drawing, see `take_error`.
+    */
+    error: Option<Error>,
 
 }
 
@@ -208,8 +286,11 @@ impl Sprite {
             texture: Box::new(Texture::new()),
             vertices: UvVertexArray::zero(),
             texture_renderer: None,
+            shared_texture: None,
+            uv_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
             update_texture: false,
             has_loaded: false,
+            error: None,
         };
 
     }
@@ -220,14 +301,17 @@ impl Sprite {
             texture: texture,
             vertices: UvVertexArray::zero(),
             texture_renderer: None,
+            shared_texture: None,
+            uv_rect: Rect::new(0.0, 0.0, 1.0, 1.0),
             update_texture: false,
             has_loaded: false,
+            error: None,
         };
     }
 
-    pub fn from_image_path(path: &'static str) -> Sprite {
+    pub fn from_image_path(path: &str) -> error::Result<Sprite> {
 
-        let texture = Texture::load_from_path(path);
+        let texture = Texture::load_from_path(path)?;
 
         let size = Vector2f { x: texture.dimensions.x as f32, y: texture.dimensions.y as f32 };
 
@@ -235,13 +319,55 @@ impl Sprite {
 
         sprite.set_size(size);
 
+        return Ok(sprite);
+
+    }
+
+    /**
+    Creates a sprite that shows the contents of a render target.
+    */
+    pub fn from_render_target(target: &RenderTarget) -> Sprite {
+
+        let size = target.get_size();
+        let mut sprite = Sprite::from_texture(Box::new(Texture { data: Vec::new(), dimensions: Vector2::new(size.x as u16, size.y as u16) }));
+        sprite.shared_texture = Some(target.get_texture());
         return sprite;
 
     }
 
+    /**
+    Creates a sprite that shows a region of an atlas page. Sprites of the same page share one texture, so they are batched together.
+    */
+    pub fn from_region(texture: core::TextureId, region: &atlas::AtlasRegion) -> Sprite {
+
+        let mut sprite = Sprite::from_texture(Box::new(Texture { data: Vec::new(), dimensions: Vector2::new(region.rect.width as u16, region.rect.height as u16) }));
+        sprite.shared_texture = Some(texture);
+        sprite.uv_rect = region.uv;
+        return sprite;
+
+    }
+
+    /**
+    Shows a different atlas region. Nothing is uploaded, so this is cheap enough to do every frame.
+    The size of the sprite follows the size of the region, scaled like before.
+    */
+    pub fn set_region(&mut self, texture: core::TextureId, region: &atlas::AtlasRegion) {
+
+        self.texture = Box::new(Texture { data: Vec::new(), dimensions: Vector2::new(region.rect.width as u16, region.rect.height as u16) });
+        self.shared_texture = Some(texture);
+        self.uv_rect = region.uv;
+
+        if self.has_loaded {
+            self.update_texture = true;
+        }
+
+    }
+
     pub fn set_texture(&mut self, texture: Box<Texture>) {
 
         self.texture = texture;
+        self.shared_texture = None;
+        self.uv_rect = Rect::new(0.0, 0.0, 1.0, 1.0);
 
         if self.has_loaded {
             self.update_texture = true;
@@ -249,15 +375,54 @@ impl Sprite {
 
     }
 
+    /**
+    Uploads a texture changed by `set_texture` or `set_region`, which otherwise happens when the sprite is next drawn.
+    Drawing skips the sprite if the upload fails and keeps the error for `take_error`, so call this first to handle errors right away.
+    */
+    pub fn update(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        if self.update_texture {
+
+            if !self.has_loaded {
+                core::Drawable::load(self, engine)?;
+            } else {
+                match self.shared_texture {
+                    Some(texture) => self.texture_renderer.as_mut().unwrap().set_shared_texture(texture, engine),
+                    None => self.texture_renderer.as_mut().unwrap().update_texture(self.texture.as_ref(), engine)?,
+                }
+
+                // The size or the uv rect may have changed along with the texture.
+                self.vertices = UvVertexArray::from_rect_uv(&self.get_local_rect(), &self.uv_rect);
+                self.texture_renderer.as_mut().unwrap().update_vertices(&self.vertices.data, engine.renderer.as_mut())?;
+            }
+            self.update_texture = false;
+        }
+
+        return Ok(());
+
+    }
+
+    /**
+    The error of the last update that failed while drawing, which skipped the sprite. Taking it clears it.
+    */
+    pub fn take_error(&mut self) -> Option<Error> {
+        return self.error.take();
+    }
+
 }
 
 impl core::Drawable for Sprite {
 
-    fn load(&mut self, engine: &mut core::FlatEngine) {
-        self.vertices = UvVertexArray::from_rect(&Rect { x: 0.0, y: 0.0, width: self.get_fixed_size().x, height: self.get_fixed_size().y });
-        self.texture_renderer = Some(TextureRenderer::create(self.texture.as_ref(), &self.vertices.data, include_bytes!("../../shaders/std_texture_v.glsl"), include_bytes!("../../shaders/std_texture_f.glsl"), &mut engine.renderer));
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+        self.vertices = UvVertexArray::from_rect_uv(&self.get_local_rect(), &self.uv_rect);
+        self.texture_renderer = Some(match self.shared_texture {
+            Some(texture) => TextureRenderer::create_shared(texture, &self.vertices.data, include_bytes!("../../shaders/std_texture_v.glsl"), include_bytes!("../../shaders/std_texture_f.glsl"), engine)?,
+            None => TextureRenderer::create(self.texture.as_ref(), &self.vertices.data, include_bytes!("../../shaders/std_texture_v.glsl"), include_bytes!("../../shaders/std_texture_f.glsl"), engine)?,
+        });
         self.has_loaded = true;
 
+        return Ok(());
+
     }
 
     fn render(&mut self, engine: &mut core::FlatEngine) {
@@ -265,19 +430,12 @@ impl core::Drawable for Sprite {
         // Check if all neccessary parts have been initialized.
         if self.texture_renderer.is_some() {
 
-            // If the texture has changed, update the texture.
-            if self.update_texture {
-
-                if !self.has_loaded {
-                    self.load(engine);
-                } else {
-                    self.texture_renderer.as_mut().unwrap().update_texture(self.texture.as_ref(), &mut engine.renderer);
-
-                }
-                self.update_texture = false;
+            if let Err(e) = self.update(engine) {
+                self.error = Some(e);
+                return;
             }
 
-            self.texture_renderer.as_mut().unwrap().render(self.node.get_trans(), engine.renderer.camera.view, engine.renderer.camera.projection, engine);
+            self.texture_renderer.as_mut().unwrap().render(self.node.get_world_trans(), engine.camera.view, engine.camera.projection, engine);
 
         } else {
             // We never want to see this.
@@ -288,6 +446,11 @@ impl core::Drawable for Sprite {
 
     fn destroy(&mut self, engine: &mut core::FlatEngine) {
 
+        if let Some(mut texture_renderer) = self.texture_renderer.take() {
+            texture_renderer.destroy(engine);
+        }
+        self.has_loaded = false;
+
     }
 
 }
@@ -310,4 +473,205 @@ impl SizedNode2D for Sprite {
         return Vector2f { x: self.texture.as_ref().dimensions.x as f32, y: self.texture.as_ref().dimensions.y as f32 };
     }
 
-}
\ No newline at end of file
+}
+
+/**
+An off-screen image with its own color and depth buffers. Draw into it with `FlatEngine::render_to` and show it with `Sprite::from_render_target`.
+*/
+pub struct RenderTarget {
+
+    id: core::RenderTargetId,
+    texture: core::TextureId,
+    size: Vector2u,
+
+}
+
+impl RenderTarget {
+
+    pub fn new(size: Vector2u, engine: &mut core::FlatEngine) -> error::Result<RenderTarget> {
+
+        let (id, texture) = engine.renderer.create_render_target(size.x, size.y)?;
+        return Ok(RenderTarget { id, texture, size });
+
+    }
+
+    pub fn get_id(&self) -> core::RenderTargetId {
+        return self.id;
+    }
+
+    pub fn get_texture(&self) -> core::TextureId {
+        return self.texture;
+    }
+
+    pub fn get_size(&self) -> Vector2u {
+        return self.size;
+    }
+
+    /**
+    Copies the contents of the target to CPU memory, top row first.
+    */
+    pub fn read_pixels(&self, engine: &mut core::FlatEngine) -> error::Result<Texture> {
+        return engine.renderer.read_render_target(self.id);
+    }
+
+    /**
+    Frees the target. Sprites showing it must not be rendered afterwards.
+    */
+    pub fn destroy(&mut self, engine: &mut core::FlatEngine) {
+        engine.renderer.destroy_render_target(self.id);
+    }
+
+}
+
+struct BatchQuad {
+
+    texture: core::TextureId,
+    depth: f32,
+    vertices: [UvVertex2f; 6],
+
+}
+
+/**
+Collects the quads of many sprites into one vertex buffer and draws them with one draw call per texture.
+Sprites are added every frame, and rendering the batch submits and clears them.
+Quads are sorted by depth first (greater depth on top), then grouped by texture within each depth.
+Sprites loaded from identical images share their upload, as do sprites of the same atlas, so they merge into one draw.
+*/
+pub struct SpriteBatch {
+
+    quads: Vec<BatchQuad>,
+    vertices: Vec<UvVertex2f>,
+    shader: Option<core::ShaderId>,
+    buffer: Option<core::BufferId>,
+    draw_count: usize,
+
+}
+
+impl SpriteBatch {
+
+    pub fn new() -> SpriteBatch {
+
+        return SpriteBatch { quads: Vec::new(), vertices: Vec::new(), shader: None, buffer: None, draw_count: 0 };
+
+    }
+
+    /**
+    Queues a loaded sprite to be drawn with the rest of the batch.
+    */
+    pub fn add(&mut self, sprite: &mut Sprite, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        if sprite.texture_renderer.is_none() {
+            // We never want to see this.
+            panic!("The sprite object is being batched before it has been initialized!");
+        }
+
+        sprite.update(engine)?;
+
+        let trans = sprite.get_world_trans();
+        let mut vertices = [UvVertex2f::zero(); 6];
+        for (i, v) in sprite.vertices.data.iter().enumerate() {
+            let p = trans * Vector4f::new(v.pos[0], v.pos[1], 0.0, 1.0);
+            vertices[i] = UvVertex2f { pos: [p.x, p.y], uv: v.uv };
+        }
+
+        self.quads.push(BatchQuad { texture: sprite.texture_renderer.as_ref().unwrap().get_texture(), depth: sprite.get_depth(), vertices });
+
+        return Ok(());
+
+    }
+
+    pub fn len(&self) -> usize {
+        return self.quads.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.quads.clear();
+    }
+
+    /**
+    Returns the number of draw calls issued by the last render.
+    */
+    pub fn get_draw_count(&self) -> usize {
+        return self.draw_count;
+    }
+
+}
+
+impl core::Drawable for SpriteBatch {
+
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        self.shader = Some(engine.create_shader(core::PipelineKind::Texture, include_bytes!("../../shaders/std_texture_v.glsl"), include_bytes!("../../shaders/std_texture_f.glsl"))?);
+        self.buffer = Some(engine.renderer.create_dynamic_vertex_buffer(core::Vertices::Uv2f(&[]))?);
+
+        return Ok(());
+
+    }
+
+    fn render(&mut self, engine: &mut core::FlatEngine) {
+
+        let (shader, buffer) = match (self.shader, self.buffer) {
+            (Some(shader), Some(buffer)) => (shader, buffer),
+            _ => panic!("The sprite batch is being drawn before it has been initialized!"),
+        };
+
+        self.draw_count = 0;
+        if self.quads.is_empty() {
+            return;
+        }
+
+        // The sort is stable, so quads with the same depth and texture keep the order they were added in.
+        self.quads.sort_by(|a, b| a.depth.partial_cmp(&b.depth).unwrap_or(Ordering::Equal).then(a.texture.cmp(&b.texture)));
+
+        // Group consecutive quads sharing a texture into one vertex range.
+        let mut groups: Vec<(core::TextureId, u32, u32)> = Vec::new();
+        self.vertices.clear();
+
+        for quad in self.quads.iter() {
+
+            let start = self.vertices.len() as u32;
+            self.vertices.extend_from_slice(&quad.vertices);
+            let end = self.vertices.len() as u32;
+
+            let extend = match groups.last() {
+                Some(&(texture, _, _)) => texture == quad.texture,
+                None => false,
+            };
+
+            if extend {
+                groups.last_mut().unwrap().2 = end;
+            } else {
+                groups.push((quad.texture, start, end));
+            }
+
+        }
+
+        // The sprites of a frame whose vertices cannot be written are skipped.
+        if engine.renderer.update_vertex_buffer(buffer, core::Vertices::Uv2f(&self.vertices)).is_err() {
+            self.quads.clear();
+            return;
+        }
+
+        for &(texture, start, end) in groups.iter() {
+            // Vertices are already in world space, so no model transform is needed.
+            engine.renderer.draw(&core::DrawCall { shader, vertices: buffer, texture: Some(texture), model: Matrix4f::identity(), view: engine.camera.view, projection: engine.camera.projection, range: Some((start, end)) });
+        }
+
+        engine.renderer.flush();
+
+        self.draw_count = groups.len();
+        self.quads.clear();
+
+    }
+
+    fn destroy(&mut self, engine: &mut core::FlatEngine) {
+
+        if let Some(buffer) = self.buffer.take() {
+            engine.renderer.destroy_vertex_buffer(buffer);
+        }
+        self.shader = None;
+        self.quads.clear();
+
+    }
+
+}
@@ -0,0 +1,354 @@
+use super::*;
+
+use self::animation::{AnimatedSprite, AnimationClip, PlayMode};
+use self::atlas::{AtlasRegion, TextureAtlas};
+use self::node::*;
+use self::render::{Sprite, Texture};
+use serde_json::Value;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/**
+A frame of a sprite sheet.
+*/
+#[derive(Clone, Debug)]
+pub struct SheetFrame {
+
+    pub name: String,
+    /**
+    The trimmed image in the sheet.
+    */
+    pub region: AtlasRegion,
+    /**
+    The size of the frame before transparent borders were trimmed.
+    */
+    pub source_size: Vector2f,
+    /**
+    Where the trimmed image starts within the untrimmed frame, from its top left.
+    */
+    pub trim_offset: Vector2f,
+    /**
+    The point the frame is positioned by, relative to the untrimmed frame from (0, 0) at the top left to (1, 1) at the bottom right.
+    Defaults to the bottom left, which is where a sprite is positioned by without an origin.
+    */
+    pub pivot: Vector2f,
+    /**
+    In seconds. Aseprite exports a duration for every frame, TexturePacker sheets use 0.1.
+    */
+    pub duration: f32,
+
+}
+
+impl SheetFrame {
+
+    /**
+    The sprite origin that places the pivot of the untrimmed frame at the position of the sprite.
+    */
+    pub fn get_origin(&self) -> Vector2f {
+
+        // Sprites are y up with the origin at the bottom left of the trimmed image.
+        return Vector2f::new(self.pivot.x * self.source_size.x - self.trim_offset.x, self.region.rect.height + self.trim_offset.y - self.pivot.y * self.source_size.y);
+
+    }
+
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TagDirection {
+
+    Forward,
+    Reverse,
+    PingPong,
+    PingPongReverse,
+
+}
+
+/**
+A named range of frames, exported by Aseprite for each animation.
+*/
+#[derive(Clone, Debug)]
+pub struct SheetTag {
+
+    pub name: String,
+    /**
+    The first and last frame, inclusive.
+    */
+    pub from: usize,
+    pub to: usize,
+    pub direction: TagDirection,
+    /**
+    How many times the animation plays, 0 for forever.
+    */
+    pub repeat: u32,
+
+}
+
+/**
+A sprite sheet exported as JSON by Aseprite or TexturePacker, with the frames either in a hash (keyed by name) or an array.
+The sheet image becomes a page of `atlas`, so sprites of the sheet are batched together.
+*/
+pub struct SpriteSheet {
+
+    pub atlas: TextureAtlas,
+    /**
+    In the order of the file.
+    */
+    pub frames: Vec<SheetFrame>,
+    pub tags: Vec<SheetTag>,
+
+}
+
+fn parse_error(message: String) -> Error {
+    return Error::Parse(message);
+}
+
+fn get_number(value: &Value, key: &str) -> error::Result<f32> {
+
+    return value.get(key).and_then(|v| v.as_f64()).map(|v| v as f32).ok_or_else(|| parse_error(format!("Missing number {} in {}.", key, value)));
+
+}
+
+fn get_rect(value: &Value, key: &str) -> error::Result<Rect> {
+
+    let rect = value.get(key).ok_or_else(|| parse_error(format!("Missing {} in {}.", key, value)))?;
+    return Ok(Rect::new(get_number(rect, "x")?, get_number(rect, "y")?, get_number(rect, "w")?, get_number(rect, "h")?));
+
+}
+
+fn get_point(value: &Value, key: &str) -> error::Result<Option<Vector2f>> {
+
+    return match value.get(key) {
+        Some(point) => Ok(Some(Vector2f::new(get_number(point, "x")?, get_number(point, "y")?))),
+        None => Ok(None),
+    };
+
+}
+
+impl SpriteSheet {
+
+    /**
+    Loads the JSON file and the image it names, which is looked up next to it.
+    */
+    pub fn load_from_path(path: &str) -> error::Result<SpriteSheet> {
+
+        let mut json = String::new();
+        File::open(path)?.read_to_string(&mut json)?;
+
+        let value: Value = serde_json::from_str(&json)?;
+        let image = match value.get("meta").and_then(|m| m.get("image")).and_then(|i| i.as_str()) {
+            Some(image) => image.to_string(),
+            None => return Err(parse_error(format!("The sprite sheet {} does not name its image.", path))),
+        };
+        let image_path = Path::new(path).parent().unwrap_or(Path::new("")).join(image);
+        let texture = Texture::load_from_path(&image_path.to_string_lossy())?;
+
+        return SpriteSheet::from_value(&value, texture);
+
+    }
+
+    /**
+    Reads the JSON of a sheet whose image has already been loaded.
+    */
+    pub fn load_from_str(json: &str, image: Texture) -> error::Result<SpriteSheet> {
+
+        let value: Value = serde_json::from_str(json)?;
+        return SpriteSheet::from_value(&value, image);
+
+    }
+
+    fn from_value(value: &Value, image: Texture) -> error::Result<SpriteSheet> {
+
+        // Hash sheets are keyed by name, array sheets name each frame with "filename".
+        let entries: Vec<(String, &Value)> = match value.get("frames") {
+            Some(&Value::Object(ref frames)) => frames.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            Some(&Value::Array(ref frames)) => frames.iter().enumerate().map(|(i, v)| (v.get("filename").and_then(|f| f.as_str()).map(|f| f.to_string()).unwrap_or(i.to_string()), v)).collect(),
+            _ => return Err(parse_error(String::from("The sprite sheet has no frames."))),
+        };
+
+        let mut atlas = TextureAtlas::new(Vector2u::new(image.dimensions.x as u32, image.dimensions.y as u32), 0);
+        let page = atlas.add_page(image);
+
+        let mut frames: Vec<SheetFrame> = Vec::new();
+        for (name, entry) in entries {
+
+            if entry.get("rotated").and_then(|r| r.as_bool()).unwrap_or(false) {
+                return Err(parse_error(format!("The frame {} is rotated, which is not supported. Disable rotation when exporting.", name)));
+            }
+
+            let rect = get_rect(entry, "frame")?;
+            let region = atlas.add_region(&name, page, rect)?;
+            let trimmed = match entry.get("spriteSourceSize") {
+                Some(_) => get_rect(entry, "spriteSourceSize")?,
+                None => Rect::new(0.0, 0.0, rect.width, rect.height),
+            };
+            let source_size = match entry.get("sourceSize") {
+                Some(size) => Vector2f::new(get_number(size, "w")?, get_number(size, "h")?),
+                None => Vector2f::new(rect.width, rect.height),
+            };
+
+            frames.push(SheetFrame {
+                name,
+                region,
+                source_size,
+                trim_offset: Vector2f::new(trimmed.x, trimmed.y),
+                pivot: get_point(entry, "pivot")?.unwrap_or(Vector2f::new(0.0, 1.0)),
+                duration: entry.get("duration").and_then(|d| d.as_f64()).map(|d| d as f32 / 1000.0).unwrap_or(0.1),
+            });
+
+        }
+
+        let meta = value.get("meta");
+        let mut tags: Vec<SheetTag> = Vec::new();
+        for tag in meta.and_then(|m| m.get("frameTags")).and_then(|t| t.as_array()).map(|t| t.as_slice()).unwrap_or(&[]) {
+
+            let name = tag.get("name").and_then(|n| n.as_str()).unwrap_or("").to_string();
+            let from = get_number(tag, "from")? as usize;
+            let to = get_number(tag, "to")? as usize;
+            if from > to || to >= frames.len() {
+                return Err(parse_error(format!("The tag {} refers to frames {} to {}, but there are {} frames.", name, from, to, frames.len())));
+            }
+
+            let direction = match tag.get("direction").and_then(|d| d.as_str()).unwrap_or("forward") {
+                "reverse" => TagDirection::Reverse,
+                "pingpong" => TagDirection::PingPong,
+                "pingpong_reverse" => TagDirection::PingPongReverse,
+                _ => TagDirection::Forward,
+            };
+            // Aseprite writes the repeat count as a string.
+            let repeat = match tag.get("repeat") {
+                Some(&Value::String(ref repeat)) => repeat.parse().unwrap_or(0),
+                Some(repeat) => repeat.as_u64().unwrap_or(0) as u32,
+                None => 0,
+            };
+
+            tags.push(SheetTag { name, from, to, direction, repeat });
+
+        }
+
+        // Aseprite stores pivots on slices, with keys that apply from a frame up to the next key. Only the first slice with a pivot is used.
+        let slices = meta.and_then(|m| m.get("slices")).and_then(|s| s.as_array()).map(|s| s.as_slice()).unwrap_or(&[]);
+        let keys = |slice: &Value| slice.get("keys").and_then(|k| k.as_array()).cloned().unwrap_or(Vec::new());
+        if let Some(slice) = slices.iter().find(|s| keys(s).iter().any(|k| k.get("pivot").is_some())) {
+
+            let mut keys = keys(slice);
+            keys.sort_by_key(|k| k.get("frame").and_then(|f| f.as_u64()).unwrap_or(0));
+
+            for (i, key) in keys.iter().enumerate() {
+                let start = key.get("frame").and_then(|f| f.as_u64()).unwrap_or(0) as usize;
+                let end = keys.get(i + 1).and_then(|k| k.get("frame")).and_then(|f| f.as_u64()).map(|f| f as usize).unwrap_or(frames.len());
+                // A key without a pivot leaves its frames with their own pivot.
+                let pivot = match get_point(key, "pivot")? {
+                    Some(pivot) => pivot,
+                    None => continue,
+                };
+                let bounds = get_rect(key, "bounds")?;
+                for frame in frames.iter_mut().take(end).skip(start) {
+                    frame.pivot = Vector2f::new((bounds.x + pivot.x) / frame.source_size.x, (bounds.y + pivot.y) / frame.source_size.y);
+                }
+            }
+
+        }
+
+        return Ok(SpriteSheet { atlas, frames, tags });
+
+    }
+
+    pub fn get_frame(&self, name: &str) -> Option<&SheetFrame> {
+        return self.frames.iter().find(|f| f.name == name);
+    }
+
+    pub fn get_tag(&self, name: &str) -> Option<&SheetTag> {
+        return self.tags.iter().find(|t| t.name == name);
+    }
+
+    /**
+    Sends the sheet image to the renderer. Needed before sprites are created.
+    */
+    pub fn upload(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        return self.atlas.upload(engine);
+
+    }
+
+    /**
+    Creates a sprite showing a frame, with its origin at the pivot.
+    */
+    pub fn create_sprite(&self, name: &str) -> error::Result<Sprite> {
+
+        let frame = match self.get_frame(name) {
+            Some(frame) => frame,
+            None => return Err(Error::Texture(format!("The sprite sheet has no frame named {}.", name))),
+        };
+        let mut sprite = self.atlas.create_sprite(name)?;
+        sprite.set_origin(frame.get_origin());
+        return Ok(sprite);
+
+    }
+
+    /**
+    Creates a clip from a tag, with the durations and pivots of its frames.
+    Tags that repeat a limited number of times are played once, with their frames repeated in the clip. Like in Aseprite, each direction of a ping-pong counts as a repeat.
+    */
+    pub fn create_clip(&self, tag: &str) -> Option<AnimationClip> {
+
+        let tag = self.get_tag(tag)?;
+        let ping_pong = tag.direction == TagDirection::PingPong || tag.direction == TagDirection::PingPongReverse;
+
+        let mode = if tag.repeat > 0 {
+            PlayMode::Once
+        } else if ping_pong {
+            PlayMode::PingPong
+        } else {
+            PlayMode::Loop
+        };
+
+        let mut pass: Vec<usize> = (tag.from..tag.to + 1).collect();
+        if tag.direction == TagDirection::Reverse || tag.direction == TagDirection::PingPongReverse {
+            pass.reverse();
+        }
+
+        let mut indices = pass.clone();
+        for _ in 1..tag.repeat {
+            // Ping-pongs turn around on the last frame of a pass instead of showing it twice.
+            if ping_pong {
+                pass.reverse();
+                indices.extend_from_slice(&pass[1..]);
+            } else {
+                indices.extend_from_slice(&pass);
+            }
+        }
+
+        let mut clip = AnimationClip::new(&tag.name, mode);
+        for i in indices {
+            let frame = &self.frames[i];
+            clip.add_frame(frame.region, frame.duration);
+            clip.frames.last_mut().unwrap().origin = Some(frame.get_origin());
+        }
+        return Some(clip);
+
+    }
+
+    /**
+    Creates a clip for every tag.
+    */
+    pub fn create_clips(&self) -> Vec<AnimationClip> {
+
+        return self.tags.iter().filter_map(|t| self.create_clip(&t.name)).collect();
+
+    }
+
+    /**
+    Creates an animated sprite with a clip for every tag. The sheet has to be uploaded first.
+    */
+    pub fn create_animated_sprite(&self) -> error::Result<AnimatedSprite> {
+
+        let mut sprite = AnimatedSprite::new(&self.atlas)?;
+        for clip in self.create_clips() {
+            sprite.add_clip(clip)?;
+        }
+        return Ok(sprite);
+
+    }
+
+}
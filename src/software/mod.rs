@@ -0,0 +1,771 @@
+use super::*;
+
+use self::core::*;
+use self::render::{Texture, UvVertex2f};
+use self::geometry::Vertex;
+use self::spatial::UvVertex3f;
+use self::text::TextVertex;
+
+/**
+An RGBA8 image stored top row first, which is what the software renderer draws into.
+*/
+pub struct Framebuffer {
+
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Blend {
+
+    Replace,
+    Alpha,
+
+}
+
+impl Framebuffer {
+
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+
+        return Framebuffer { width, height, data: vec![0; (width * height * 4) as usize] };
+
+    }
+
+    pub fn fill(&mut self, color: Color) {
+
+        let raw = [to_u8(color.r), to_u8(color.g), to_u8(color.b), to_u8(color.a)];
+        for pixel in self.data.chunks_mut(4) {
+            pixel.copy_from_slice(&raw);
+        }
+
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+
+        let i = ((y * self.width + x) * 4) as usize;
+        return [self.data[i], self.data[i + 1], self.data[i + 2], self.data[i + 3]];
+
+    }
+
+    pub fn to_texture(&self) -> Texture {
+
+        return Texture::from_data(&self.data, self.width as u16, self.height as u16);
+
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: [f32; 4], blend: Blend) {
+
+        let i = ((y * self.width + x) * 4) as usize;
+        let out = match blend {
+            Blend::Replace => color,
+            Blend::Alpha => {
+                // Matches gfx::preset::blend::ALPHA: colour is mixed by source alpha, alpha is added.
+                let dst = [self.data[i] as f32 / 255.0, self.data[i + 1] as f32 / 255.0, self.data[i + 2] as f32 / 255.0, self.data[i + 3] as f32 / 255.0];
+                let a = color[3];
+                [
+                    color[0] * a + dst[0] * (1.0 - a),
+                    color[1] * a + dst[1] * (1.0 - a),
+                    color[2] * a + dst[2] * (1.0 - a),
+                    a + dst[3],
+                ]
+            },
+        };
+
+        self.data[i] = to_u8(out[0]);
+        self.data[i + 1] = to_u8(out[1]);
+        self.data[i + 2] = to_u8(out[2]);
+        self.data[i + 3] = to_u8(out[3]);
+
+    }
+
+}
+
+fn to_u8(value: f32) -> u8 {
+    return (value.max(0.0).min(1.0) * 255.0 + 0.5) as u8;
+}
+
+/**
+Samples a texture with bilinear filtering, clamping to the edges like the linear sampler on the GPU.
+*/
+pub fn sample_texture(texture: &Texture, u: f32, v: f32) -> [f32; 4] {
+
+    let w = texture.dimensions.x as i32;
+    let h = texture.dimensions.y as i32;
+
+    if w == 0 || h == 0 {
+        return [0.0; 4];
+    }
+
+    let x = u * w as f32 - 0.5;
+    let y = v * h as f32 - 0.5;
+    let x0 = x.floor();
+    let y0 = y.floor();
+    let fx = x - x0;
+    let fy = y - y0;
+
+    let fetch = |px: i32, py: i32| -> [f32; 4] {
+        let px = px.max(0).min(w - 1);
+        let py = py.max(0).min(h - 1);
+        let i = ((py * w + px) * 4) as usize;
+        if i + 3 >= texture.data.len() {
+            return [0.0; 4];
+        }
+        return [texture.data[i] as f32 / 255.0, texture.data[i + 1] as f32 / 255.0, texture.data[i + 2] as f32 / 255.0, texture.data[i + 3] as f32 / 255.0];
+    };
+
+    let (x0, y0) = (x0 as i32, y0 as i32);
+    let c00 = fetch(x0, y0);
+    let c10 = fetch(x0 + 1, y0);
+    let c01 = fetch(x0, y0 + 1);
+    let c11 = fetch(x0 + 1, y0 + 1);
+
+    let mut out = [0.0; 4];
+    for i in 0..4 {
+        let top = c00[i] + (c10[i] - c00[i]) * fx;
+        let bottom = c01[i] + (c11[i] - c01[i]) * fx;
+        out[i] = top + (bottom - top) * fy;
+    }
+    return out;
+
+}
+
+/**
+Rasterizes a single triangle given in clip space. The shade closure receives the barycentric weights of the pixel centre.
+Pixels outside of `scissor` (min x, min y, max x, max y, with the first row at the top) are not drawn.
+Pixel centres exactly on an edge belong to the triangle only if it is a top or left edge, so that triangles sharing an edge do not both draw it.
+*/
+fn rasterize_triangle<F>(target: &mut Framebuffer, clip: [Vector4f; 3], scissor: (u32, u32, u32, u32), blend: Blend, mut shade: F) where F: FnMut([f32; 3]) -> [f32; 4] {
+
+    if clip.iter().any(|c| c.w <= 0.0) {
+        return;
+    }
+
+    let width = target.width as f32;
+    let height = target.height as f32;
+
+    // Convert to window coordinates with the first row at the top of the framebuffer.
+    let mut screen = [Vector3f::new(0.0, 0.0, 0.0); 3];
+    for i in 0..3 {
+        let ndc = Vector3f::new(clip[i].x / clip[i].w, clip[i].y / clip[i].w, clip[i].z / clip[i].w);
+        screen[i] = Vector3f::new((ndc.x + 1.0) * 0.5 * width, (1.0 - ndc.y) * 0.5 * height, ndc.z);
+    }
+
+    // The edge functions are evaluated on positions snapped to 1/256 of a pixel in double precision, which makes them exact.
+    // Two triangles sharing an edge then agree on which pixel centres lie on it.
+    let snap = |v: f32| (v as f64 * 256.0).round() / 256.0;
+    let points: Vec<(f64, f64)> = screen.iter().map(|p| (snap(p.x), snap(p.y))).collect();
+    let edge = |a: (f64, f64), b: (f64, f64), x: f64, y: f64| -> f64 {
+        return (b.0 - a.0) * (y - a.1) - (b.1 - a.1) * (x - a.0);
+    };
+
+    let area = edge(points[0], points[1], points[2].0, points[2].1);
+    if area == 0.0 {
+        return;
+    }
+
+    // Edge i is opposite vertex i. Its weight grows towards the inside of the triangle, so it is a left edge if the weight grows to the right,
+    // and a top edge if it is horizontal and the weight grows downwards.
+    let mut owns_edge = [false; 3];
+    for i in 0..3 {
+        let (a, b) = (points[(i + 1) % 3], points[(i + 2) % 3]);
+        let (dx, dy) = (-(b.1 - a.1) / area, (b.0 - a.0) / area);
+        owns_edge[i] = dx > 0.0 || (dx == 0.0 && dy > 0.0);
+    }
+
+    let min_x = (screen.iter().fold(width, |m, p| m.min(p.x)).max(0.0).floor() as u32).max(scissor.0);
+    let max_x = (screen.iter().fold(0.0f32, |m, p| m.max(p.x)).min(width).ceil() as u32).min(scissor.2);
+    let min_y = (screen.iter().fold(height, |m, p| m.min(p.y)).max(0.0).floor() as u32).max(scissor.1);
+    let max_y = (screen.iter().fold(0.0f32, |m, p| m.max(p.y)).min(height).ceil() as u32).min(scissor.3);
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+
+            let px = x as f64 + 0.5;
+            let py = y as f64 + 0.5;
+
+            let w = [
+                edge(points[1], points[2], px, py) / area,
+                edge(points[2], points[0], px, py) / area,
+                edge(points[0], points[1], px, py) / area,
+            ];
+
+            if (0..3).any(|i| w[i] < 0.0 || (w[i] == 0.0 && !owns_edge[i])) {
+                continue;
+            }
+            let (w0, w1, w2) = (w[0] as f32, w[1] as f32, w[2] as f32);
+
+            let z = screen[0].z * w0 + screen[1].z * w1 + screen[2].z * w2;
+            if z < -1.0 || z > 1.0 {
+                continue;
+            }
+
+            let color = shade([w0, w1, w2]);
+            target.blend(x, y, color, blend);
+
+        }
+    }
+
+}
+
+// Rejects textures the sampler would read past the end of, like the GPU does.
+fn check_texture(texture: &Texture) -> error::Result<()> {
+
+    if texture.dimensions.x == 0 || texture.dimensions.y == 0 || texture.data.len() != texture.dimensions.x as usize * texture.dimensions.y as usize * 4 {
+        return Err(Error::Texture(format!("The {}x{} texture has no pixels or the wrong number of them.", texture.dimensions.x, texture.dimensions.y)));
+    }
+    return Ok(());
+
+}
+
+fn in_range<T>(vertices: &[T], range: Option<(u32, u32)>) -> &[T] {
+    match range {
+        Some((start, end)) => &vertices[(start as usize).min(vertices.len())..(end as usize).min(vertices.len())],
+        None => vertices,
+    }
+}
+
+fn interpolate2(values: [[f32; 2]; 3], w: [f32; 3]) -> [f32; 2] {
+    return [
+        values[0][0] * w[0] + values[1][0] * w[1] + values[2][0] * w[2],
+        values[0][1] * w[0] + values[1][1] * w[1] + values[2][1] * w[2],
+    ];
+}
+
+enum SoftwareBuffer {
+
+    Uv2f(Vec<UvVertex2f>),
+    Color(Vec<Vertex>),
+    Uv3f(Vec<UvVertex3f>),
+    Text(Vec<TextVertex>),
+
+}
+
+impl SoftwareBuffer {
+
+    fn from_vertices(vertices: Vertices) -> SoftwareBuffer {
+        match vertices {
+            Vertices::Uv2f(v) => SoftwareBuffer::Uv2f(v.to_vec()),
+            Vertices::Color(v) => SoftwareBuffer::Color(v.to_vec()),
+            Vertices::Uv3f(v) => SoftwareBuffer::Uv3f(v.to_vec()),
+            Vertices::Text(v) => SoftwareBuffer::Text(v.to_vec()),
+        }
+    }
+
+}
+
+/**
+A pure CPU renderer backend.
+GLSL cannot be run on the CPU, so each pipeline kind is shaded with a built-in equivalent of the matching standard shader, whatever source was passed to `create_shader`.
+*/
+pub struct SoftwareRenderer {
+
+    back: Framebuffer,
+    front: Framebuffer,
+    blank_texture: Texture,
+    shaders: ResourceStore<PipelineKind>,
+    textures: ResourceStore<Texture>,
+    buffers: ResourceStore<SoftwareBuffer>,
+    viewport: Option<Rect>,
+    /**
+    Render targets and the id of the texture their contents are copied to.
+    */
+    targets: ResourceStore<(Framebuffer, TextureId)>,
+    bound_target: Option<RenderTargetId>,
+
+}
+
+impl SoftwareRenderer {
+
+    pub fn new(width: u32, height: u32) -> SoftwareRenderer {
+
+        return SoftwareRenderer {
+            back: Framebuffer::new(width, height),
+            front: Framebuffer::new(width, height),
+            blank_texture: Texture::new(),
+            shaders: ResourceStore::new(),
+            textures: ResourceStore::new(),
+            buffers: ResourceStore::new(),
+            viewport: None,
+            targets: ResourceStore::new(),
+            bound_target: None,
+        };
+
+    }
+
+    /**
+    The last frame presented with `swap_buffers`.
+    */
+    pub fn get_framebuffer(&self) -> &Framebuffer {
+        return &self.front;
+    }
+
+    /**
+    The frame currently being drawn.
+    */
+    pub fn get_back_buffer(&self) -> &Framebuffer {
+        return &self.back;
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.back = Framebuffer::new(width, height);
+        self.front = Framebuffer::new(width, height);
+    }
+
+    /**
+    The area that can be drawn to, see `rasterize_triangle`.
+    */
+    fn get_scissor(&self) -> (u32, u32, u32, u32) {
+
+        // The viewport has its origin at the bottom left, the framebuffer at the top left. It does not apply to render targets.
+        match if self.bound_target.is_some() { None } else { self.viewport } {
+            Some(v) => {
+                let height = self.back.height as f32;
+                (v.x.max(0.0).round() as u32, (height - v.y - v.height).max(0.0).round() as u32, (v.x + v.width).max(0.0).round() as u32, (height - v.y).max(0.0).round() as u32)
+            },
+            None => (0, 0, u32::max_value(), u32::max_value()),
+        }
+
+    }
+
+    /**
+    Copies the contents of the bound render target to its texture.
+    */
+    fn resolve_target(&mut self) {
+
+        if let Some((framebuffer, texture)) = self.bound_target.and_then(|id| self.targets.get(id)).map(|t| (t.0.to_texture(), t.1)) {
+            self.textures.set(texture, framebuffer);
+        }
+
+    }
+
+}
+
+impl Renderer for SoftwareRenderer {
+
+    fn get_dimensions(&self) -> Vector2f {
+        return Vector2f { x: self.back.width as f32, y: self.back.height as f32 };
+    }
+
+    fn update_size(&mut self) {
+        // The framebuffer only changes size through resize.
+    }
+
+    fn get_hidpi_factor(&self) -> f32 {
+        return 1.0;
+    }
+
+    fn set_viewport(&mut self, viewport: Option<Rect>) {
+        self.viewport = viewport;
+    }
+
+    fn clear(&mut self, color: Color) {
+        match self.bound_target.and_then(|id| self.targets.get_mut(id)) {
+            Some(target) => target.0.fill(color),
+            None => self.back.fill(color),
+        }
+    }
+
+    fn flush(&mut self) {
+        // Draws are executed immediately.
+    }
+
+    fn swap_buffers(&mut self) {
+        self.front.data.copy_from_slice(&self.back.data);
+    }
+
+    fn read_pixels(&mut self) -> error::Result<Texture> {
+        return Ok(self.back.to_texture());
+    }
+
+    fn create_shader(&mut self, kind: PipelineKind, _v_shader: &[u8], _f_shader: &[u8]) -> error::Result<ShaderId> {
+        return Ok(self.shaders.insert(kind));
+    }
+
+    fn create_texture(&mut self, texture: &Texture) -> error::Result<TextureId> {
+        check_texture(texture)?;
+        return Ok(self.textures.insert(Texture::from_data(&texture.data, texture.dimensions.x, texture.dimensions.y)));
+    }
+
+    fn update_texture(&mut self, id: TextureId, texture: &Texture) -> error::Result<()> {
+        check_texture(texture)?;
+        self.textures.set(id, Texture::from_data(&texture.data, texture.dimensions.x, texture.dimensions.y));
+        return Ok(());
+    }
+
+    fn destroy_texture(&mut self, id: TextureId) {
+        self.textures.remove(id);
+    }
+
+    fn create_render_target(&mut self, width: u32, height: u32) -> error::Result<(RenderTargetId, TextureId)> {
+
+        if width == 0 || height == 0 || width > u16::max_value() as u32 || height > u16::max_value() as u32 {
+            return Err(Error::Texture(format!("Invalid render target size {}x{}.", width, height)));
+        }
+
+        let framebuffer = Framebuffer::new(width, height);
+        let texture = self.textures.insert(framebuffer.to_texture());
+        return Ok((self.targets.insert((framebuffer, texture)), texture));
+
+    }
+
+    fn set_render_target(&mut self, target: Option<RenderTargetId>) {
+        self.resolve_target();
+        self.bound_target = target;
+    }
+
+    fn read_render_target(&mut self, target: RenderTargetId) -> error::Result<Texture> {
+        match self.targets.get(target) {
+            Some(target) => Ok(target.0.to_texture()),
+            None => Err(Error::Texture(String::from("The render target does not exist."))),
+        }
+    }
+
+    fn destroy_render_target(&mut self, target: RenderTargetId) {
+
+        if self.bound_target == Some(target) {
+            self.bound_target = None;
+        }
+        if let Some((_, texture)) = self.targets.remove(target) {
+            self.textures.remove(texture);
+        }
+
+    }
+
+    fn create_vertex_buffer(&mut self, vertices: Vertices) -> BufferId {
+        return self.buffers.insert(SoftwareBuffer::from_vertices(vertices));
+    }
+
+    fn create_dynamic_vertex_buffer(&mut self, vertices: Vertices) -> error::Result<BufferId> {
+        return Ok(self.create_vertex_buffer(vertices));
+    }
+
+    fn update_vertex_buffer(&mut self, id: BufferId, vertices: Vertices) -> error::Result<()> {
+        self.buffers.set(id, SoftwareBuffer::from_vertices(vertices));
+        return Ok(());
+    }
+
+    fn destroy_vertex_buffer(&mut self, id: BufferId) {
+        self.buffers.remove(id);
+    }
+
+    fn draw(&mut self, call: &DrawCall) {
+
+        let mvp = call.projection * call.view * call.model;
+
+        let textures = &self.textures;
+        let texture = match call.texture.and_then(|id| textures.get(id)) {
+            Some(texture) => texture,
+            None => &self.blank_texture,
+        };
+
+        let scissor = self.get_scissor();
+
+        let targets = &mut self.targets;
+        let target = match self.bound_target.and_then(|id| targets.get_mut(id)) {
+            Some(target) => &mut target.0,
+            None => &mut self.back,
+        };
+
+        match (self.shaders.get(call.shader), self.buffers.get(call.vertices)) {
+            (Some(&PipelineKind::Texture), Some(&SoftwareBuffer::Uv2f(ref vertices))) => {
+                for tri in in_range(vertices, call.range).chunks(3).filter(|t| t.len() == 3) {
+                    let clip = [
+                        mvp * Vector4f::new(tri[0].pos[0], tri[0].pos[1], 0.0, 1.0),
+                        mvp * Vector4f::new(tri[1].pos[0], tri[1].pos[1], 0.0, 1.0),
+                        mvp * Vector4f::new(tri[2].pos[0], tri[2].pos[1], 0.0, 1.0),
+                    ];
+                    let uvs = [tri[0].uv, tri[1].uv, tri[2].uv];
+                    rasterize_triangle(target, clip, scissor, Blend::Alpha, |w| {
+                        let uv = interpolate2(uvs, w);
+                        return sample_texture(texture, uv[0], uv[1]);
+                    });
+                }
+            },
+            (Some(&PipelineKind::Geometry), Some(&SoftwareBuffer::Color(ref vertices))) => {
+                for tri in in_range(vertices, call.range).chunks(3).filter(|t| t.len() == 3) {
+                    let clip = [
+                        mvp * Vector4f::new(tri[0].pos[0], tri[0].pos[1], 0.0, 1.0),
+                        mvp * Vector4f::new(tri[1].pos[0], tri[1].pos[1], 0.0, 1.0),
+                        mvp * Vector4f::new(tri[2].pos[0], tri[2].pos[1], 0.0, 1.0),
+                    ];
+                    let colors = [tri[0].color, tri[1].color, tri[2].color];
+                    rasterize_triangle(target, clip, scissor, Blend::Alpha, |w| {
+                        let mut color = [0.0; 4];
+                        for i in 0..4 {
+                            color[i] = colors[0][i] * w[0] + colors[1][i] * w[1] + colors[2][i] * w[2];
+                        }
+                        return color;
+                    });
+                }
+            },
+            (Some(&PipelineKind::Mesh), Some(&SoftwareBuffer::Uv3f(ref vertices))) => {
+                for tri in in_range(vertices, call.range).chunks(3).filter(|t| t.len() == 3) {
+                    let clip = [
+                        mvp * Vector4f::new(tri[0].pos[0], tri[0].pos[1], tri[0].pos[2], 1.0),
+                        mvp * Vector4f::new(tri[1].pos[0], tri[1].pos[1], tri[1].pos[2], 1.0),
+                        mvp * Vector4f::new(tri[2].pos[0], tri[2].pos[1], tri[2].pos[2], 1.0),
+                    ];
+                    let uvs = [tri[0].uv, tri[1].uv, tri[2].uv];
+                    rasterize_triangle(target, clip, scissor, Blend::Alpha, |w| {
+                        let uv = interpolate2(uvs, w);
+                        return sample_texture(texture, uv[0], uv[1]);
+                    });
+                }
+            },
+            (Some(&PipelineKind::Text), Some(&SoftwareBuffer::Text(ref vertices))) => {
+                for tri in in_range(vertices, call.range).chunks(3).filter(|t| t.len() == 3) {
+                    let clip = [
+                        mvp * Vector4f::new(tri[0].pos[0], tri[0].pos[1], 0.0, 1.0),
+                        mvp * Vector4f::new(tri[1].pos[0], tri[1].pos[1], 0.0, 1.0),
+                        mvp * Vector4f::new(tri[2].pos[0], tri[2].pos[1], 0.0, 1.0),
+                    ];
+                    let uvs = [tri[0].uv, tri[1].uv, tri[2].uv];
+                    let colors = [tri[0].color, tri[1].color, tri[2].color];
+                    rasterize_triangle(target, clip, scissor, Blend::Alpha, |w| {
+                        let uv = interpolate2(uvs, w);
+                        let texel = sample_texture(texture, uv[0], uv[1]);
+                        let mut color = [0.0; 4];
+                        for i in 0..4 {
+                            color[i] = texel[i] * (colors[0][i] * w[0] + colors[1][i] * w[1] + colors[2][i] * w[2]);
+                        }
+                        return color;
+                    });
+                }
+            },
+            _ => panic!("The draw call refers to a missing shader or vertex buffer, or their pipeline kinds do not match!"),
+        }
+
+    }
+
+    fn draw_distance_text(&mut self, call: &DistanceTextCall) {
+
+        let draw = &call.draw;
+        let mvp = draw.projection * draw.view * draw.model;
+
+        let textures = &self.textures;
+        let texture = match draw.texture.and_then(|id| textures.get(id)) {
+            Some(texture) => texture,
+            None => &self.blank_texture,
+        };
+
+        let scissor = self.get_scissor();
+
+        let targets = &mut self.targets;
+        let target = match self.bound_target.and_then(|id| targets.get_mut(id)) {
+            Some(target) => &mut target.0,
+            None => &mut self.back,
+        };
+
+        let vertices = match (self.shaders.get(draw.shader), self.buffers.get(draw.vertices)) {
+            (Some(&PipelineKind::DistanceText), Some(&SoftwareBuffer::Text(ref vertices))) => vertices,
+            _ => panic!("The draw call refers to a missing shader or vertex buffer, or one that is not a distance field text shader!"),
+        };
+
+        let size = [target.width as f32 * 0.5, target.height as f32 * 0.5];
+        let texture_size = [texture.dimensions.x as f32, texture.dimensions.y as f32];
+
+        for tri in in_range(vertices, draw.range).chunks(3).filter(|t| t.len() == 3) {
+            let clip = [
+                mvp * Vector4f::new(tri[0].pos[0], tri[0].pos[1], 0.0, 1.0),
+                mvp * Vector4f::new(tri[1].pos[0], tri[1].pos[1], 0.0, 1.0),
+                mvp * Vector4f::new(tri[2].pos[0], tri[2].pos[1], 0.0, 1.0),
+            ];
+            let uvs = [tri[0].uv, tri[1].uv, tri[2].uv];
+            let colors = [tri[0].color, tri[1].color, tri[2].color];
+
+            // Quads are affine, so the texels covered by a pixel (what the shader gets from the derivatives) are constant across the triangle.
+            let screen: Vec<[f32; 2]> = clip.iter().map(|c| [c.x / c.w * size[0], c.y / c.w * size[1]]).collect();
+            let texels: Vec<[f32; 2]> = uvs.iter().map(|uv| [uv[0] * texture_size[0], uv[1] * texture_size[1]]).collect();
+            let area = |p: &[[f32; 2]]| ((p[1][0] - p[0][0]) * (p[2][1] - p[0][1]) - (p[2][0] - p[0][0]) * (p[1][1] - p[0][1])).abs();
+            let rate = match area(&screen) {
+                a if a > 0.0 => (area(&texels) / a).sqrt(),
+                _ => continue,
+            };
+
+            rasterize_triangle(target, clip, scissor, Blend::Alpha, |w| {
+                let uv = interpolate2(uvs, w);
+                let texel = sample_texture(texture, uv[0], uv[1]);
+                let mut color = [0.0; 4];
+                for i in 0..4 {
+                    color[i] = colors[0][i] * w[0] + colors[1][i] * w[1] + colors[2][i] * w[2];
+                }
+                return shade_distance(&call.params, texel, color, rate);
+            });
+        }
+
+    }
+
+    fn draw_post_process(&mut self, call: &PostProcessCall) {
+
+        let shader = match self.shaders.get(call.shader) {
+            Some(&PipelineKind::PostProcess(shader)) => shader,
+            _ => panic!("The post process call refers to a missing shader, or one that is not a post process shader!"),
+        };
+
+        let scissor = self.get_scissor();
+        let textures = &self.textures;
+        let input = textures.get(call.input).unwrap_or(&self.blank_texture);
+        let secondary = call.secondary.and_then(|id| textures.get(id)).unwrap_or(&self.blank_texture);
+
+        let targets = &mut self.targets;
+        let target = match self.bound_target.and_then(|id| targets.get_mut(id)) {
+            Some(target) => &mut target.0,
+            None => &mut self.back,
+        };
+
+        let resolution = [target.width as f32, target.height as f32];
+        for y in scissor.1..scissor.3.min(target.height) {
+            for x in scissor.0..scissor.2.min(target.width) {
+                let uv = [(x as f32 + 0.5) / resolution[0], (y as f32 + 0.5) / resolution[1]];
+                let color = shade_post(shader, call, input, secondary, uv, resolution);
+                target.blend(x, y, color, Blend::Replace);
+            }
+        }
+
+    }
+
+}
+
+/**
+Puts a colour with straight alpha over another.
+*/
+fn over(top: [f32; 4], bottom: [f32; 4]) -> [f32; 4] {
+
+    let a = top[3] + bottom[3] * (1.0 - top[3]);
+    if a <= 0.0 {
+        return [0.0; 4];
+    }
+
+    let mut color = [0.0, 0.0, 0.0, a];
+    for i in 0..3 {
+        color[i] = (top[i] * top[3] + bottom[i] * bottom[3] * (1.0 - top[3])) / a;
+    }
+    return color;
+
+}
+
+/**
+The CPU equivalent of `std_text_sdf_f.glsl`, where `rate` stands in for the screen space derivatives of the distance.
+*/
+fn shade_distance(params: &[[f32; 4]; 4], texel: [f32; 4], color: [f32; 4], rate: f32) -> [f32; 4] {
+
+    let clamp = |x: f32| x.max(0.0).min(1.0);
+
+    let d = (texel[3] - 0.5) * params[2][2];
+    // The distance is in texels, so it changes by `rate` from one pixel to the next.
+    let width = rate.max(0.0001) + params[2][3];
+
+    let mut fill = color;
+    for i in 0..3 {
+        fill[i] *= texel[i] + (1.0 - texel[i]) * params[3][0];
+    }
+    fill[3] *= clamp(d / width + 0.5);
+
+    let mut outline = params[0];
+    outline[3] *= clamp((d + params[2][0]) / width + 0.5);
+
+    let mut glow = params[1];
+    glow[3] *= match params[2][1] {
+        g if g > 0.0 => clamp(1.0 + (d + params[2][0]) / g).powi(2),
+        _ => 0.0,
+    };
+
+    return over(over(fill, outline), glow);
+
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+
+    let t = ((x - edge0) / (edge1 - edge0)).max(0.0).min(1.0);
+    return t * t * (3.0 - 2.0 * t);
+
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    return [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t];
+}
+
+/**
+The CPU equivalents of the `std_post_*_f.glsl` shaders. Custom shaders are drawn as a copy.
+*/
+fn shade_post(shader: post::PostShader, call: &PostProcessCall, input: &Texture, secondary: &Texture, uv: [f32; 2], resolution: [f32; 2]) -> [f32; 4] {
+
+    let p = call.params[0];
+
+    match shader {
+        post::PostShader::Custom | post::PostShader::Copy => sample_texture(input, uv[0], uv[1]),
+        post::PostShader::Vignette => {
+            let c = sample_texture(input, uv[0], uv[1]);
+            let d = ((uv[0] - 0.5) * (uv[0] - 0.5) + (uv[1] - 0.5) * (uv[1] - 0.5)).sqrt();
+            let shade = 1.0 - smoothstep(p[1], p[1] + p[2], d) * p[0];
+            [c[0] * shade, c[1] * shade, c[2] * shade, c[3]]
+        },
+        post::PostShader::Grayscale => {
+            let c = sample_texture(input, uv[0], uv[1]);
+            let luma = c[0] * 0.299 + c[1] * 0.587 + c[2] * 0.114;
+            let rgb = mix([c[0], c[1], c[2]], [luma; 3], p[0]);
+            [rgb[0], rgb[1], rgb[2], c[3]]
+        },
+        post::PostShader::ColorGrade => {
+            let c = sample_texture(input, uv[0], uv[1]);
+            let (r, g, b) = (c[0].max(0.0).min(1.0), c[1].max(0.0).min(1.0), c[2].max(0.0).min(1.0));
+            let n = p[1];
+            let slice = b * (n - 1.0);
+            let s0 = slice.floor();
+            let s1 = (s0 + 1.0).min(n - 1.0);
+            let u = r * (n - 1.0) + 0.5;
+            let v = (g * (n - 1.0) + 0.5) / n;
+            let a = sample_texture(secondary, (s0 * n + u) / (n * n), v);
+            let b = sample_texture(secondary, (s1 * n + u) / (n * n), v);
+            let graded = mix([a[0], a[1], a[2]], [b[0], b[1], b[2]], slice - s0);
+            let rgb = mix([c[0], c[1], c[2]], graded, p[0]);
+            [rgb[0], rgb[1], rgb[2], c[3]]
+        },
+        post::PostShader::Threshold => {
+            let c = sample_texture(input, uv[0], uv[1]);
+            let brightness = c[0].max(c[1]).max(c[2]);
+            let keep = (brightness - p[0]).max(0.0) / brightness.max(0.0001);
+            [c[0] * keep, c[1] * keep, c[2] * keep, 1.0]
+        },
+        post::PostShader::Blur => {
+            let weights = [0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216];
+            let offset = [p[0] / resolution[0], p[1] / resolution[1]];
+            let mut color = sample_texture(input, uv[0], uv[1]);
+            for k in 0..4 {
+                color[k] *= weights[0];
+            }
+            for i in 1..5 {
+                for &side in [1.0, -1.0].iter() {
+                    let t = i as f32 * side;
+                    let c = sample_texture(input, uv[0] + offset[0] * t, uv[1] + offset[1] * t);
+                    for k in 0..4 {
+                        color[k] += c[k] * weights[i];
+                    }
+                }
+            }
+            color
+        },
+        post::PostShader::Combine => {
+            let base = sample_texture(secondary, uv[0], uv[1]);
+            let c = sample_texture(input, uv[0], uv[1]);
+            [base[0] + c[0] * p[0], base[1] + c[1] * p[0], base[2] + c[2] * p[0], base[3]]
+        },
+        post::PostShader::Crt => {
+            let mut centered = [uv[0] * 2.0 - 1.0, uv[1] * 2.0 - 1.0];
+            let d = centered[0] * centered[0] + centered[1] * centered[1];
+            centered = [centered[0] + centered[0] * d * p[1], centered[1] + centered[1] * d * p[1]];
+            if centered[0].abs() > 1.0 || centered[1].abs() > 1.0 {
+                return [0.0, 0.0, 0.0, 1.0];
+            }
+            let uv = [centered[0] * 0.5 + 0.5, centered[1] * 0.5 + 0.5];
+            let c = sample_texture(input, uv[0], uv[1]);
+            let scanline = 0.5 + 0.5 * (uv[1] * resolution[1] * ::std::f32::consts::PI).sin();
+            let d = centered[0] * centered[0] + centered[1] * centered[1];
+            let shade = (1.0 + (scanline - 1.0) * p[0]) * (1.0 - p[2] * d * 0.5);
+            [c[0] * shade, c[1] * shade, c[2] * shade, c[3]]
+        },
+    }
+
+}
@@ -1,7 +1,6 @@
 use super::*;
 
 use node::*;
-use gfx::traits::FactoryExt;
 
 gfx_defines!{
 
@@ -22,67 +21,53 @@ gfx_defines!{
         tex: gfx::TextureSampler<[f32; 4]> = "t_Texture",
         trans: gfx::ConstantBuffer<MeshTransform> = "Transform",
         out: gfx::BlendTarget<ColorFormat> = ("Target0", gfx::state::ColorMask::all(), gfx::preset::blend::ALPHA),
+        scissor: gfx::Scissor = (),
     }
 }
 
 pub struct MeshRenderer {
 
-    data: spatial::pipe::Data<ResourceType>,
-    slice: gfx::Slice<ResourceType>,
-    pipeline_state: gfx::PipelineState<ResourceType, spatial::pipe::Meta>,
+    shader: core::ShaderId,
+    vertices: core::BufferId,
+    texture: core::TextureId,
 
 }
 
 impl MeshRenderer {
 
-    pub fn new(data: spatial::pipe::Data<ResourceType>, slice: gfx::Slice<ResourceType>, pipeline_state: gfx::PipelineState<ResourceType, spatial::pipe::Meta>) -> MeshRenderer{
+    pub fn new(shader: core::ShaderId, vertices: core::BufferId, texture: core::TextureId) -> MeshRenderer{
 
-        return MeshRenderer { data, slice, pipeline_state };
+        return MeshRenderer { shader, vertices, texture };
 
     }
 
-    pub fn create(vertices: &[UvVertex3f], texture: &render::Texture, v_shader: &[u8], f_shader: &[u8], renderer: &mut core::Renderer) -> MeshRenderer {
+    pub fn create(vertices: &[UvVertex3f], texture: &render::Texture, v_shader: &[u8], f_shader: &[u8], engine: &mut core::FlatEngine) -> error::Result<MeshRenderer> {
         // Load shaders.
-        let pipeline_state = renderer.factory
-            .create_pipeline_simple(
-                v_shader,
-                f_shader,
-                pipe::new(),
-            )
-            .unwrap();
-        let (vertex_buffer, slice) = renderer.factory.create_vertex_buffer_with_slice(vertices, ());
-        let trans_buffer = renderer.factory.create_constant_buffer(1);
+        let shader = engine.create_shader(core::PipelineKind::Mesh, v_shader, f_shader)?;
+        let texture = engine.renderer.create_texture(texture)?;
+        let vertices = engine.renderer.create_vertex_buffer(core::Vertices::Uv3f(vertices));
 
-        let sampler = renderer.factory.create_sampler_linear();
-
-        let data = pipe::Data {
-            vbuf: vertex_buffer,
-            tex: (texture.get_shader_texture(renderer), sampler),
-            trans: trans_buffer,
-            out: renderer.render_view.clone(),
-        };
-
-        return MeshRenderer::new(data, slice, pipeline_state);
+        return Ok(MeshRenderer::new(shader, vertices, texture));
 
     }
 
     pub fn render(&mut self, model_trans: Matrix4f, view_trans: Matrix4f, projection_trans: Matrix4f, engine: &mut core::FlatEngine) {
-        engine.renderer.encoder.update_buffer(&self.data.trans, &[MeshTransform { model: model_trans.get_data(), view: view_trans.get_data(), projection: projection_trans.get_data() }], 0); //update buffers
-        engine.renderer.encoder.draw(&self.slice, &mut self.pipeline_state, &self.data); // draw commands with buffer data and attached pso
-        engine.renderer.encoder.flush(engine.renderer.device.as_mut()); // execute draw commands
+        engine.renderer.draw(&core::DrawCall { shader: self.shader, vertices: self.vertices, texture: Some(self.texture), model: model_trans, view: view_trans, projection: projection_trans, range: None }); // draw commands with buffer data and attached pso
+        engine.renderer.flush(); // execute draw commands
     }
 
-    pub fn update_vertices(&mut self, vertices: &[UvVertex3f], renderer: &mut core::Renderer) {
-        let (vertex_buffer, slice) = renderer.factory.create_vertex_buffer_with_slice(vertices, ());
-        self.data.vbuf = vertex_buffer;
-        self.slice = slice;
+    pub fn update_vertices(&mut self, vertices: &[UvVertex3f], renderer: &mut core::Renderer) -> error::Result<()> {
+        return renderer.update_vertex_buffer(self.vertices, core::Vertices::Uv3f(vertices));
     }
 
-    pub fn update_texture(&mut self, texture: &render::Texture, renderer: &mut core::Renderer) {
-        let sampler = renderer.factory.create_sampler_linear();
-        self.data.tex = (texture.get_shader_texture(renderer), sampler);
+    pub fn update_texture(&mut self, texture: &render::Texture, renderer: &mut core::Renderer) -> error::Result<()> {
+        return renderer.update_texture(self.texture, texture);
     }
 
+    pub fn destroy(&mut self, renderer: &mut core::Renderer) {
+        renderer.destroy_vertex_buffer(self.vertices);
+        renderer.destroy_texture(self.texture);
+    }
 
 }
 
@@ -141,22 +126,27 @@ impl Entity {
 
 impl core::Drawable for Entity {
 
-    fn load(&mut self, engine: &mut core::FlatEngine) {
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
         if self.texture.is_some() {
-            self.mesh_renderer = Some(MeshRenderer::create(&self.mesh.vertices, self.texture.as_ref().unwrap().as_ref(), include_bytes!("../../shaders/std_mesh_v.glsl"), include_bytes!("../../shaders/std_mesh_f.glsl"), &mut engine.renderer));
+            self.mesh_renderer = Some(MeshRenderer::create(&self.mesh.vertices, self.texture.as_ref().unwrap().as_ref(), include_bytes!("../../shaders/std_mesh_v.glsl"), include_bytes!("../../shaders/std_mesh_f.glsl"), engine)?);
         } else {
-            self.mesh_renderer = Some(MeshRenderer::create(&self.mesh.vertices, &render::Texture::new(), include_bytes!("../../shaders/std_mesh_v.glsl"), include_bytes!("../../shaders/std_mesh_f.glsl"), &mut engine.renderer));
+            self.mesh_renderer = Some(MeshRenderer::create(&self.mesh.vertices, &render::Texture::new(), include_bytes!("../../shaders/std_mesh_v.glsl"), include_bytes!("../../shaders/std_mesh_f.glsl"), engine)?);
         }
+        return Ok(());
     }
 
     fn render(&mut self, engine: &mut core::FlatEngine) {
         if self.mesh_renderer.is_some() {
-            self.mesh_renderer.as_mut().unwrap().render(self.node.get_trans(), engine.renderer.camera.view, engine.renderer.camera.projection, engine);
+            self.mesh_renderer.as_mut().unwrap().render(self.node.get_trans(), engine.camera.view, engine.camera.projection, engine);
         }
     }
 
     fn destroy(&mut self, engine: &mut core::FlatEngine) {
 
+        if let Some(mut mesh_renderer) = self.mesh_renderer.take() {
+            mesh_renderer.destroy(engine.renderer.as_mut());
+        }
+
     }
 
 }
@@ -2,143 +2,2418 @@ use super::*;
 use self::node::*;
 use self::render::*;
 
+use std::collections::HashMap;
 use std::convert::AsRef;
 use std::fs::File;
 use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::SystemTime;
 pub use rusttype::Font;
 
+gfx_defines!{
+
+    vertex TextVertex {
+        pos: [f32; 2] = "a_Pos",
+        uv: [f32; 2] = "a_Uv",
+        color: [f32; 4] = "a_Color",
+    }
+
+    constant TextTransform {
+
+        model: [[f32; 4]; 4] = "model_Transform",
+        view: [[f32; 4]; 4] = "view_Transform",
+        projection: [[f32; 4]; 4] = "projection_Transform",
+
+    }
+
+    pipeline pipe {
+        vbuf: gfx::VertexBuffer<TextVertex> = (),
+        tex: gfx::TextureSampler<[f32; 4]> = "t_Texture",
+        trans: gfx::ConstantBuffer<TextTransform> = "Transform",
+        out: gfx::BlendTarget<ColorFormat> = ("Target0", gfx::state::ColorMask::all(), gfx::preset::blend::ALPHA),
+        scissor: gfx::Scissor = (),
+    }
+
+    constant DistanceParams {
+
+        param0: [f32; 4] = "u_Param0",
+        param1: [f32; 4] = "u_Param1",
+        param2: [f32; 4] = "u_Param2",
+        param3: [f32; 4] = "u_Param3",
+
+    }
+
+    pipeline distance_pipe {
+        vbuf: gfx::VertexBuffer<TextVertex> = (),
+        tex: gfx::TextureSampler<[f32; 4]> = "t_Texture",
+        trans: gfx::ConstantBuffer<TextTransform> = "Transform",
+        params: gfx::ConstantBuffer<DistanceParams> = "Effects",
+        out: gfx::BlendTarget<ColorFormat> = ("Target0", gfx::state::ColorMask::all(), gfx::preset::blend::ALPHA),
+        scissor: gfx::Scissor = (),
+    }
+}
+
+/**
+Identifies a font in the glyph cache.
+*/
+pub type FontId = usize;
+
+static NEXT_FONT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/**
+Hands out a font id that has not been used before.
+*/
+pub fn new_font_id() -> FontId {
+    return NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+/**
+Loads a TrueType font from memory.
+*/
+pub fn load_font(bytes: Vec<u8>) -> error::Result<Font<'static>> {
+
+    return Ok(Font::from_bytes(bytes)?);
+
+}
+
+pub fn load_font_from_path(path: &str) -> error::Result<Font<'static>> {
+
+    let mut bytes: Vec<u8> = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    return load_font(bytes);
+
+}
+
+/**
+Looks up the fonts that the glyphs of a layout refer to by id.
+*/
+pub trait FontSource {
+
+    fn get_font(&self, id: FontId) -> Option<&Font>;
+
+}
+
+/**
+A font together with the id that identifies it in layouts and the glyph cache.
+*/
+#[derive(Clone)]
+pub struct FontFace<'a> {
+
+    pub font: Font<'a>,
+    /**
+    Faces cloned from each other share their id, and so their cached glyphs.
+    */
+    pub id: FontId,
+
+}
+
+impl<'a> FontFace<'a> {
+
+    pub fn new(font: Font<'a>) -> FontFace<'a> {
+        return FontFace { font, id: new_font_id() };
+    }
+
+}
+
+impl<'a> FontSource for FontFace<'a> {
+
+    fn get_font(&self, id: FontId) -> Option<&Font> {
+        return if id == self.id { Some(&self.font) } else { None };
+    }
+
+}
+
+/**
+The faces of a font used for regular, bold and italic text.
+*/
+#[derive(Clone)]
+pub struct FontFamily<'a> {
+
+    pub regular: FontFace<'a>,
+    pub bold: Option<FontFace<'a>>,
+    pub italic: Option<FontFace<'a>>,
+    pub bold_italic: Option<FontFace<'a>>,
+
+}
+
+impl<'a> FontFamily<'a> {
+
+    pub fn new(regular: Font<'a>) -> FontFamily<'a> {
+        return FontFamily { regular: FontFace::new(regular), bold: None, italic: None, bold_italic: None };
+    }
+
+    pub fn with_bold(mut self, font: Font<'a>) -> FontFamily<'a> {
+        self.bold = Some(FontFace::new(font));
+        return self;
+    }
+
+    pub fn with_italic(mut self, font: Font<'a>) -> FontFamily<'a> {
+        self.italic = Some(FontFace::new(font));
+        return self;
+    }
+
+    pub fn with_bold_italic(mut self, font: Font<'a>) -> FontFamily<'a> {
+        self.bold_italic = Some(FontFace::new(font));
+        return self;
+    }
+
+    /**
+    The face for a style. Missing faces fall back to a face with less of the style, down to the regular face.
+    */
+    pub fn get_face(&self, bold: bool, italic: bool) -> &FontFace<'a> {
+
+        let candidates = [
+            if bold && italic { self.bold_italic.as_ref() } else { None },
+            if bold { self.bold.as_ref() } else { None },
+            if italic { self.italic.as_ref() } else { None },
+        ];
+        return candidates.iter().filter_map(|&face| face).next().unwrap_or(&self.regular);
+
+    }
+
+}
+
+impl<'a> FontSource for FontFamily<'a> {
+
+    fn get_font(&self, id: FontId) -> Option<&Font> {
+        let faces = [Some(&self.regular), self.bold.as_ref(), self.italic.as_ref(), self.bold_italic.as_ref()];
+        return faces.iter().filter_map(|&face| face).find(|face| face.id == id).map(|face| &face.font);
+    }
+
+}
+
+/**
+The fonts a text can use. Markup picks families by name and the default family is used for the rest.
+Characters that the family of their run has no glyph for are taken from the fallback faces, in the order they were added, so that fonts for CJK, emoji or symbols can fill in.
+*/
+#[derive(Clone)]
+pub struct FontCollection<'a> {
+
+    pub default: FontFamily<'a>,
+    pub families: HashMap<String, FontFamily<'a>>,
+    pub fallbacks: Vec<FontFace<'a>>,
+
+}
+
+impl<'a> FontCollection<'a> {
+
+    pub fn new(default: FontFamily<'a>) -> FontCollection<'a> {
+        return FontCollection { default, families: HashMap::new(), fallbacks: Vec::new() };
+    }
+
+    pub fn with_family(mut self, name: &str, family: FontFamily<'a>) -> FontCollection<'a> {
+        self.families.insert(name.to_string(), family);
+        return self;
+    }
+
+    pub fn with_fallback(mut self, font: Font<'a>) -> FontCollection<'a> {
+        self.fallbacks.push(FontFace::new(font));
+        return self;
+    }
+
+    /**
+    The family of a name, or the default family for None and unknown names.
+    */
+    pub fn get_family(&self, name: Option<&str>) -> &FontFamily<'a> {
+        return name.and_then(|name| self.families.get(name)).unwrap_or(&self.default);
+    }
+
+}
+
+impl<'a> FontSource for FontCollection<'a> {
+
+    fn get_font(&self, id: FontId) -> Option<&Font> {
+
+        if let Some(font) = self.default.get_font(id) {
+            return Some(font);
+        }
+        if let Some(font) = self.families.values().filter_map(|family| family.get_font(id)).next() {
+            return Some(font);
+        }
+        return self.fallbacks.iter().find(|face| face.id == id).map(|face| &face.font);
+
+    }
+
+}
+
+/**
+How the lines of a text are placed horizontally in the layout box.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HorizontalAlign {
+    Left,
+    Center,
+    Right,
+    /**
+    Widens the spaces so that every line fills the box, except the last line of each paragraph which is left aligned.
+    */
+    Justify,
+}
+
+/**
+How the block of lines is placed vertically when the layout box is taller than the text.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerticalAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+/**
+The base direction of the paragraphs of a text, which decides the order of runs of text in different directions on a line.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextDirection {
+    /**
+    Takes the direction of the first letter of each paragraph with a direction, or left to right if there is none.
+    */
+    Auto,
+    LeftToRight,
+    RightToLeft,
+}
+
+/**
+Controls how a text is broken into lines and where the lines are placed.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutOptions {
+
+    /**
+    Lines wider than this are wrapped at word boundaries. Words that do not fit on a line by themselves are broken between characters.
+    Widths that are negative or not finite do not wrap.
+    */
+    pub max_width: Option<f32>,
+    /**
+    The height of the layout box. Without one the box is as tall as the lines.
+    */
+    pub height: Option<f32>,
+    pub align: HorizontalAlign,
+    pub vertical_align: VerticalAlign,
+    /**
+    Multiplies the distance between two baselines.
+    */
+    pub line_height: f32,
+    /**
+    Extra space between two characters in pixels.
+    */
+    pub letter_spacing: f32,
+    pub direction: TextDirection,
+
+}
+
+impl LayoutOptions {
+
+    pub fn new() -> LayoutOptions {
+        return LayoutOptions { max_width: None, height: None, align: HorizontalAlign::Left, vertical_align: VerticalAlign::Top, line_height: 1.0, letter_spacing: 0.0, direction: TextDirection::Auto };
+    }
+
+    pub fn with_max_width(mut self, max_width: f32) -> LayoutOptions {
+        self.max_width = Some(max_width);
+        return self;
+    }
+
+    /**
+    The width lines are wrapped at, if it is one that can be wrapped at.
+    */
+    pub fn get_wrap_width(&self) -> Option<f32> {
+        return self.max_width.filter(|w| w.is_finite() && *w >= 0.0);
+    }
+
+    pub fn with_height(mut self, height: f32) -> LayoutOptions {
+        self.height = Some(height);
+        return self;
+    }
+
+    pub fn with_align(mut self, align: HorizontalAlign) -> LayoutOptions {
+        self.align = align;
+        return self;
+    }
+
+    pub fn with_vertical_align(mut self, vertical_align: VerticalAlign) -> LayoutOptions {
+        self.vertical_align = vertical_align;
+        return self;
+    }
+
+    pub fn with_line_height(mut self, line_height: f32) -> LayoutOptions {
+        self.line_height = line_height;
+        return self;
+    }
+
+    pub fn with_letter_spacing(mut self, letter_spacing: f32) -> LayoutOptions {
+        self.letter_spacing = letter_spacing;
+        return self;
+    }
+
+    pub fn with_direction(mut self, direction: TextDirection) -> LayoutOptions {
+        self.direction = direction;
+        return self;
+    }
+
+}
+
+/**
+A character placed by a `TextLayout`. Whitespace at the point where a line is wrapped is dropped, all other characters have a glyph.
+Glyphs are in the order of the text, and their positions follow the order they are shown in, so that right to left text runs from right to left.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayoutGlyph {
+
+    /**
+    The character that is shown, which differs from the text for shaped Arabic letters and mirrored brackets. A ligature shows two characters of the text.
+    */
+    pub character: char,
+    /**
+    The byte offset of the character in the text of all runs put together.
+    */
+    pub index: usize,
+    pub line: usize,
+    /**
+    The index of the run the character belongs to.
+    */
+    pub run: usize,
+    pub font: FontId,
+    pub id: rusttype::GlyphId,
+    pub size: f32,
+    /**
+    True if the character shows the image of its run instead of a glyph.
+    */
+    pub image: bool,
+    /**
+    The pen position on the baseline, in pixels from the top left of the layout box.
+    */
+    pub position: Vector2f,
+    pub advance: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    /**
+    The bidirectional embedding level, which is odd for characters in right to left text.
+    */
+    pub level: u8,
+
+}
+
+impl LayoutGlyph {
+
+    /**
+    The box the character takes up on its line, in pixels from the top left of the layout box.
+    */
+    pub fn get_rect(&self) -> Rect {
+        return Rect::new(self.position.x, self.position.y - self.ascent, self.advance, self.ascent - self.descent);
+    }
+
+}
+
+/**
+The placement of one line of a `TextLayout`. Distances are in pixels from the top left of the layout box.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineMetrics {
+
+    /**
+    The range of the line's glyphs in `TextLayout::glyphs`.
+    */
+    pub start: usize,
+    pub end: usize,
+    pub x: f32,
+    pub top: f32,
+    pub baseline: f32,
+    /**
+    The width of the line's content, without trailing whitespace.
+    */
+    pub width: f32,
+    /**
+    The distance from this line's top to the next line's top.
+    */
+    pub height: f32,
+    pub ascent: f32,
+    pub descent: f32,
+
+}
+
+/**
+A piece of text in one font and size, laid out together with the other runs by `TextLayout::from_runs`.
+*/
+pub struct LayoutRun<'r, 'f: 'r> {
+
+    pub text: &'r str,
+    pub face: &'r FontFace<'f>,
+    /**
+    Faces tried in order for characters that `face` has no glyph for.
+    */
+    pub fallbacks: &'r [FontFace<'f>],
+    pub size: f32,
+    /**
+    Shows an image of this size for every character of the run instead of a glyph. The image stands on the descent of the font.
+    */
+    pub image: Option<Vector2f>,
+
+}
+
+impl<'r, 'f> LayoutRun<'r, 'f> {
+
+    /**
+    The first face of the run with a glyph for a character.
+    */
+    fn find_face(&self, c: char) -> Option<&'r FontFace<'f>> {
+
+        if self.face.font.glyph(c).id().0 != 0 {
+            return Some(self.face);
+        }
+        return self.fallbacks.iter().find(|face| face.font.glyph(c).id().0 != 0);
+
+    }
+
+}
+
+/**
+Breaks a text into lines and places its glyphs.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+
+    pub glyphs: Vec<LayoutGlyph>,
+    pub lines: Vec<LineMetrics>,
+    /**
+    The size of the layout box.
+    */
+    pub size: Vector2f,
+
+}
+
+/**
+A character of a paragraph, measured but not yet placed.
+*/
+struct MeasuredChar {
+    character: char,
+    index: usize,
+    run: usize,
+    font: FontId,
+    id: rusttype::GlyphId,
+    size: f32,
+    image: bool,
+    advance: f32,
+    // Kerning against the previous character of the paragraph, in the order the two are shown.
+    kerning: f32,
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+    level: u8,
+}
+
+/**
+The characters between two newlines, and the vertical metrics used if there are none.
+*/
+struct Paragraph {
+    chars: Vec<MeasuredChar>,
+    metrics: (f32, f32, f32),
+    level: u8,
+}
+
+impl TextLayout {
+
+    pub fn new(text: &str, face: &FontFace, size: f32, options: &LayoutOptions) -> TextLayout {
+
+        return TextLayout::from_runs(&[LayoutRun { text, face, fallbacks: &[], size, image: None }], options);
+
+    }
+
+    /**
+    Lays out runs of different fonts and sizes as one text. Each line is as tall as the tallest run on it.
+    Arabic letters are joined using the presentation forms of the fonts, and lines with both left to right and right to left text are put in order with a simplified version of the Unicode bidirectional algorithm.
+    */
+    pub fn from_runs(runs: &[LayoutRun], options: &LayoutOptions) -> TextLayout {
+
+        // Split the text of all runs into paragraphs of characters, their byte offsets and their runs.
+        let mut sources: Vec<(Vec<(char, usize, usize)>, usize)> = vec![(Vec::new(), 0)];
+        let mut offset = 0;
+        for (r, run) in runs.iter().enumerate() {
+            for (i, c) in run.text.char_indices() {
+                if c == '\n' {
+                    sources.push((Vec::new(), r));
+                    continue;
+                }
+                if c != '\r' {
+                    sources.last_mut().unwrap().0.push((c, offset + i, r));
+                }
+            }
+            offset += run.text.len();
+        }
+
+        let paragraphs: Vec<Paragraph> = sources.iter().map(|&(ref chars, r)| TextLayout::measure_paragraph(chars, runs, r, options.direction)).collect();
+
+        // Break every paragraph into ranges of characters that fit on a line.
+        let mut breaks: Vec<(usize, usize, usize, bool)> = Vec::new();
+        for (p, paragraph) in paragraphs.iter().enumerate() {
+            let lines = TextLayout::break_lines(&paragraph.chars, options);
+            let count = lines.len();
+            for (i, (start, end)) in lines.into_iter().enumerate() {
+                breaks.push((p, start, end, i + 1 == count));
+            }
+        }
+
+        // The vertical metrics of each line are those of its tallest character, empty lines take those of their paragraph.
+        let line_metrics: Vec<(f32, f32, f32)> = breaks.iter().map(|&(p, start, end, _)| {
+            let chars = &paragraphs[p].chars[start..end];
+            let (ascent, descent, line_gap) = if chars.is_empty() {
+                paragraphs[p].metrics
+            } else {
+                (
+                    chars.iter().map(|c| c.ascent).fold(std::f32::NEG_INFINITY, f32::max),
+                    chars.iter().map(|c| c.descent).fold(std::f32::INFINITY, f32::min),
+                    chars.iter().map(|c| c.line_gap).fold(0.0, f32::max),
+                )
+            };
+            (ascent, descent, (ascent - descent + line_gap) * options.line_height)
+        }).collect();
+
+        let widest = breaks.iter().map(|&(p, start, end, _)| TextLayout::measure(&paragraphs[p].chars[start..end], options.letter_spacing)).fold(0.0, f32::max);
+        let box_width = options.get_wrap_width().unwrap_or(widest);
+        let content_height: f32 = line_metrics.iter().map(|m| m.2).sum();
+        let box_height = options.height.unwrap_or(content_height);
+        let mut top = match options.vertical_align {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Middle => (box_height - content_height) / 2.0,
+            VerticalAlign::Bottom => box_height - content_height,
+        };
+
+        let mut glyphs: Vec<LayoutGlyph> = Vec::new();
+        let mut lines: Vec<LineMetrics> = Vec::new();
+
+        for (line, &(p, start, end, last)) in breaks.iter().enumerate() {
+
+            let chars = &paragraphs[p].chars[start..end];
+            let (ascent, descent, line_height) = line_metrics[line];
+            let width = TextLayout::measure(chars, options.letter_spacing);
+            let mut x = match options.align {
+                HorizontalAlign::Left | HorizontalAlign::Justify => 0.0,
+                HorizontalAlign::Center => (box_width - width) / 2.0,
+                HorizontalAlign::Right => box_width - width,
+            };
+            let mut stretch = 0.0;
+            if options.align == HorizontalAlign::Justify && !last {
+                let spaces = chars.iter().filter(|c| c.character.is_whitespace()).count();
+                if spaces > 0 && width < box_width {
+                    stretch = (box_width - width) / spaces as f32;
+                }
+            }
+
+            let baseline = top + (line_height - (ascent - descent)) / 2.0 + ascent;
+            let line_x = x;
+            let first = glyphs.len();
+
+            // Whitespace at the end of a line takes the direction of the paragraph, and hangs over the start of a right to left line.
+            let mut levels: Vec<u8> = chars.iter().map(|c| c.level).collect();
+            for i in (0..chars.len()).rev().take_while(|&i| chars[i].character.is_whitespace()) {
+                levels[i] = paragraphs[p].level;
+                if paragraphs[p].level % 2 == 1 {
+                    x -= chars[i].advance + options.letter_spacing;
+                }
+            }
+
+            let order = visual_order(&levels);
+            let mut positions: Vec<f32> = vec![0.0; chars.len()];
+            for (k, &i) in order.iter().enumerate() {
+                if k > 0 {
+                    let previous = order[k - 1];
+                    if i == previous + 1 {
+                        x += chars[i].kerning;
+                    } else if previous == i + 1 {
+                        x += chars[previous].kerning;
+                    }
+                }
+                positions[i] = x;
+                x += chars[i].advance + options.letter_spacing;
+                if chars[i].character.is_whitespace() {
+                    x += stretch;
+                }
+            }
+
+            for (i, c) in chars.iter().enumerate() {
+                let mut advance = c.advance;
+                if c.character.is_whitespace() {
+                    advance += stretch;
+                }
+                glyphs.push(LayoutGlyph { character: c.character, index: c.index, line, run: c.run, font: c.font, id: c.id, size: c.size, image: c.image, position: Vector2f::new(positions[i], baseline), advance, ascent: c.ascent, descent: c.descent, level: levels[i] });
+            }
+
+            lines.push(LineMetrics { start: first, end: glyphs.len(), x: line_x, top, baseline, width: if stretch > 0.0 { box_width } else { width }, height: line_height, ascent, descent });
+            top += line_height;
+
+        }
+
+        return TextLayout { glyphs, lines, size: Vector2f::new(box_width, box_height) };
+
+    }
+
+    /**
+    Shapes the characters of a paragraph, resolves their directions and measures them. `run` gives the metrics of the paragraph if it is empty.
+    */
+    fn measure_paragraph(source: &[(char, usize, usize)], runs: &[LayoutRun], run: usize, direction: TextDirection) -> Paragraph {
+
+        let metrics = |face: &FontFace, size: f32| {
+            let v_metrics = face.font.v_metrics(rusttype::Scale::uniform(size));
+            (v_metrics.ascent, v_metrics.descent, v_metrics.line_gap)
+        };
+
+        let characters: Vec<char> = source.iter().map(|s| s.0).collect();
+        let shaped = shape_arabic(&characters, |i, c| runs[source[i].2].image.is_none() && runs[source[i].2].find_face(c).is_some());
+        let classes: Vec<BidiClass> = shaped.iter().map(|&(c, _)| bidi_class(c)).collect();
+        let (level, levels) = resolve_levels(&classes, direction);
+
+        let mut chars: Vec<MeasuredChar> = Vec::new();
+        for (&(c, i), &char_level) in shaped.iter().zip(levels.iter()) {
+
+            let (_, index, r) = source[i];
+            let run = &runs[r];
+            let scale = rusttype::Scale::uniform(run.size);
+
+            let c = if char_level % 2 == 1 { mirror(c).unwrap_or(c) } else { c };
+            let face = match run.image {
+                Some(_) => run.face,
+                None => run.find_face(c).unwrap_or(run.face),
+            };
+            let (ascent, descent, line_gap) = metrics(face, run.size);
+            let (id, advance, char_ascent) = match run.image {
+                Some(size) => (rusttype::GlyphId(0), size.x, size.y + descent),
+                None => {
+                    let glyph = face.font.glyph(c).scaled(scale);
+                    (glyph.id(), glyph.h_metrics().advance_width, ascent)
+                },
+            };
+
+            // Only glyphs of the same font, size and direction are kerned against each other. In right to left text the previous character is on the right.
+            let kerning = match chars.last() {
+                Some(previous) if !previous.image && run.image.is_none() && previous.font == face.id && previous.size == run.size && previous.level % 2 == char_level % 2 => {
+                    if char_level % 2 == 1 {
+                        face.font.pair_kerning(scale, id, previous.id)
+                    } else {
+                        face.font.pair_kerning(scale, previous.id, id)
+                    }
+                },
+                _ => 0.0,
+            };
+            chars.push(MeasuredChar { character: c, index, run: r, font: face.id, id, size: run.size, image: run.image.is_some(), advance, kerning, ascent: char_ascent, descent, line_gap, level: char_level });
+
+        }
+
+        let metrics = match runs.get(run) {
+            Some(run) => metrics(run.face, run.size),
+            None => (0.0, 0.0, 0.0),
+        };
+        return Paragraph { chars, metrics, level };
+
+    }
+
+    /**
+    The width of a run of characters placed on one line, without trailing whitespace.
+    */
+    fn measure(chars: &[MeasuredChar], letter_spacing: f32) -> f32 {
+
+        let len = chars.iter().rposition(|c| !c.character.is_whitespace()).map(|i| i + 1).unwrap_or(0);
+        let mut width = 0.0;
+        for (i, c) in chars[..len].iter().enumerate() {
+            if i > 0 {
+                width += c.kerning + letter_spacing;
+            }
+            width += c.advance;
+        }
+        return width;
+
+    }
+
+    /**
+    Greedily fills lines word by word and returns the character range of each line. Whitespace at a break is left out of both lines.
+    */
+    fn break_lines(chars: &[MeasuredChar], options: &LayoutOptions) -> Vec<(usize, usize)> {
+
+        let max_width = match options.get_wrap_width() {
+            Some(max_width) => max_width,
+            None => return vec![(0, chars.len())],
+        };
+
+        let mut lines: Vec<(usize, usize)> = Vec::new();
+        let mut start = 0;
+        let mut end = 0;
+        let mut i = 0;
+
+        while i < chars.len() {
+
+            // The next word along with the whitespace in front of it.
+            let mut word_start = i;
+            while word_start < chars.len() && chars[word_start].character.is_whitespace() {
+                word_start += 1;
+            }
+            let mut word_end = word_start;
+            while word_end < chars.len() && !chars[word_end].character.is_whitespace() {
+                word_end += 1;
+            }
+
+            if TextLayout::measure(&chars[start..word_end], options.letter_spacing) <= max_width {
+                end = word_end;
+                i = word_end;
+                continue;
+            }
+
+            if end > start {
+                // Wrap before the word.
+                lines.push((start, end));
+                start = word_start;
+                end = word_start;
+                i = word_start;
+                continue;
+            }
+
+            // The word is too wide for a line of its own, so break it after the last character that fits.
+            let mut split = (word_start + 1).min(word_end);
+            while split < word_end && TextLayout::measure(&chars[start..split + 1], options.letter_spacing) <= max_width {
+                split += 1;
+            }
+            lines.push((start, split));
+            start = split;
+            end = split;
+            i = split;
+
+        }
+
+        if end > start || lines.is_empty() {
+            lines.push((start, end.max(start)));
+        }
+
+        return lines;
+
+    }
+
+    /**
+    The smallest rectangle around all lines, in pixels from the top left of the layout box.
+    */
+    pub fn get_content_rect(&self) -> Rect {
+
+        if self.lines.is_empty() {
+            return Rect::new(0.0, 0.0, 0.0, 0.0);
+        }
+
+        let left = self.lines.iter().map(|l| l.x).fold(std::f32::INFINITY, f32::min);
+        let right = self.lines.iter().map(|l| l.x + l.width).fold(std::f32::NEG_INFINITY, f32::max);
+        let top = self.lines[0].top;
+        let bottom = self.lines.last().map(|l| l.top + l.height).unwrap();
+        return Rect::new(left, top, right - left, bottom - top);
+
+    }
+
+}
+
+/**
+The bidirectional character types of the Unicode bidirectional algorithm, without those for explicit embeddings.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BidiClass {
+    LeftToRight,
+    RightToLeft,
+    ArabicLetter,
+    EuropeanNumber,
+    ArabicNumber,
+    EuropeanSeparator,
+    EuropeanTerminator,
+    CommonSeparator,
+    NonspacingMark,
+    Whitespace,
+    OtherNeutral,
+}
+
+/**
+The bidirectional type of a character, from the ranges of the scripts written right to left. Letters of all other scripts are left to right.
+*/
+fn bidi_class(c: char) -> BidiClass {
+
+    use self::BidiClass::*;
+
+    return match c as u32 {
+        0x30..=0x39 | 0x06F0..=0x06F9 => EuropeanNumber,
+        0x0660..=0x0669 | 0x066B | 0x066C => ArabicNumber,
+        0x2B | 0x2D => EuropeanSeparator,
+        0x23..=0x25 | 0xA2..=0xA5 | 0xB0 | 0xB1 | 0x20A0..=0x20CF => EuropeanTerminator,
+        0x2C | 0x2E | 0x2F | 0x3A | 0xA0 => CommonSeparator,
+        0x0300..=0x036F | 0x0591..=0x05BD | 0x05BF | 0x05C1 | 0x05C2 | 0x05C4 | 0x05C5 | 0x05C7 | 0x0610..=0x061A | 0x064B..=0x065F | 0x0670 | 0x06D6..=0x06DC | 0x06DF..=0x06E4 | 0x06E7 | 0x06E8 | 0x06EA..=0x06ED => NonspacingMark,
+        0x0590..=0x05FF | 0x07C0..=0x085F | 0xFB1D..=0xFB4F | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF => RightToLeft,
+        0x0600..=0x07BF | 0x0860..=0x08FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFE => ArabicLetter,
+        _ if c.is_whitespace() => Whitespace,
+        _ if c.is_alphanumeric() => LeftToRight,
+        _ => OtherNeutral,
+    };
+
+}
+
+/**
+Resolves the embedding level of each character of a paragraph, following the weak, neutral and implicit rules of the Unicode bidirectional algorithm.
+Returns the level of the paragraph along with them.
+*/
+fn resolve_levels(classes: &[BidiClass], direction: TextDirection) -> (u8, Vec<u8>) {
+
+    use self::BidiClass::*;
+
+    let level: u8 = match direction {
+        TextDirection::LeftToRight => 0,
+        TextDirection::RightToLeft => 1,
+        TextDirection::Auto => match classes.iter().find(|&&c| c == LeftToRight || c == RightToLeft || c == ArabicLetter) {
+            Some(&LeftToRight) | None => 0,
+            Some(_) => 1,
+        },
+    };
+    let embedding = if level == 0 { LeftToRight } else { RightToLeft };
+    let mut types = classes.to_vec();
+    let n = types.len();
+
+    // Marks take the type of the character they are on, numbers after Arabic letters are Arabic numbers, and Arabic letters are right to left.
+    for i in 0..n {
+        if types[i] == NonspacingMark {
+            types[i] = if i == 0 { embedding } else { types[i - 1] };
+        }
+    }
+    let mut strong = embedding;
+    for t in types.iter_mut() {
+        match *t {
+            LeftToRight | RightToLeft | ArabicLetter => strong = *t,
+            EuropeanNumber if strong == ArabicLetter => *t = ArabicNumber,
+            _ => {},
+        }
+    }
+    for t in types.iter_mut().filter(|t| **t == ArabicLetter) {
+        *t = RightToLeft;
+    }
+
+    // Separators within numbers and currency signs next to them belong to the numbers.
+    for i in 1..n.max(1) - 1 {
+        let (before, after) = (types[i - 1], types[i + 1]);
+        if before == EuropeanNumber && after == EuropeanNumber && (types[i] == EuropeanSeparator || types[i] == CommonSeparator) {
+            types[i] = EuropeanNumber;
+        } else if before == ArabicNumber && after == ArabicNumber && types[i] == CommonSeparator {
+            types[i] = ArabicNumber;
+        }
+    }
+    let mut i = 0;
+    while i < n {
+        if types[i] != EuropeanTerminator {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < n && types[i] == EuropeanTerminator {
+            i += 1;
+        }
+        if (start > 0 && types[start - 1] == EuropeanNumber) || (i < n && types[i] == EuropeanNumber) {
+            for t in types[start..i].iter_mut() {
+                *t = EuropeanNumber;
+            }
+        }
+    }
+    for t in types.iter_mut() {
+        if *t == EuropeanSeparator || *t == EuropeanTerminator || *t == CommonSeparator {
+            *t = OtherNeutral;
+        }
+    }
+    let mut strong = embedding;
+    for t in types.iter_mut() {
+        match *t {
+            LeftToRight | RightToLeft => strong = *t,
+            EuropeanNumber if strong == LeftToRight => *t = LeftToRight,
+            _ => {},
+        }
+    }
+
+    // Neutrals between text of the same direction take that direction, other neutrals the direction of the paragraph. Numbers count as right to left here.
+    let direction_of = |t: BidiClass| if t == LeftToRight { LeftToRight } else { RightToLeft };
+    let mut i = 0;
+    while i < n {
+        if types[i] != Whitespace && types[i] != OtherNeutral {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < n && (types[i] == Whitespace || types[i] == OtherNeutral) {
+            i += 1;
+        }
+        let before = if start == 0 { embedding } else { direction_of(types[start - 1]) };
+        let after = if i == n { embedding } else { direction_of(types[i]) };
+        let resolved = if before == after { before } else { embedding };
+        for t in types[start..i].iter_mut() {
+            *t = resolved;
+        }
+    }
+
+    let levels = types.iter().map(|&t| match (level % 2, t) {
+        (0, RightToLeft) => level + 1,
+        (0, ArabicNumber) | (0, EuropeanNumber) => level + 2,
+        (1, LeftToRight) | (1, ArabicNumber) | (1, EuropeanNumber) => level + 1,
+        _ => level,
+    }).collect();
+
+    return (level, levels);
+
+}
+
+/**
+The order the characters of a line are shown in from left to right, given their embedding levels.
+*/
+fn visual_order(levels: &[u8]) -> Vec<usize> {
+
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let highest = levels.iter().cloned().max().unwrap_or(0);
+    let lowest_odd = levels.iter().cloned().min().unwrap_or(0) | 1;
+
+    // Reverse every sequence at or above each level, from the highest level down to the lowest odd one.
+    let mut level = highest;
+    while level >= lowest_odd {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < order.len() && levels[order[i]] >= level {
+                i += 1;
+            }
+            order[start..i].reverse();
+        }
+        level -= 1;
+    }
+
+    return order;
+
+}
+
+/**
+The mirror image of a bracket, which replaces it in right to left text.
+*/
+fn mirror(c: char) -> Option<char> {
+
+    let pairs = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>'), ('\u{AB}', '\u{BB}'), ('\u{2039}', '\u{203A}')];
+    for &(open, close) in pairs.iter() {
+        if c == open {
+            return Some(close);
+        }
+        if c == close {
+            return Some(open);
+        }
+    }
+    return None;
+
+}
+
+/**
+The isolated presentation form of an Arabic letter and whether the letter joins the letter after it.
+The final, initial and medial forms follow the isolated one in that order, letters that do not join the next letter only have a final form.
+*/
+fn arabic_forms(c: char) -> Option<(u32, bool)> {
+
+    let code = c as u32;
+    return match code {
+        0x0622 => Some((0xFE81, false)),
+        0x0623 => Some((0xFE83, false)),
+        0x0624 => Some((0xFE85, false)),
+        0x0625 => Some((0xFE87, false)),
+        0x0626 => Some((0xFE89, true)),
+        0x0627 => Some((0xFE8D, false)),
+        0x0628 => Some((0xFE8F, true)),
+        0x0629 => Some((0xFE93, false)),
+        0x062A..=0x062E => Some((0xFE95 + (code - 0x062A) * 4, true)),
+        0x062F..=0x0632 => Some((0xFEA9 + (code - 0x062F) * 2, false)),
+        0x0633..=0x063A => Some((0xFEB1 + (code - 0x0633) * 4, true)),
+        0x0641..=0x0647 => Some((0xFED1 + (code - 0x0641) * 4, true)),
+        0x0648 => Some((0xFEED, false)),
+        0x0649 => Some((0xFEEF, false)),
+        0x064A => Some((0xFEF1, true)),
+        _ => None,
+    };
+
+}
+
+/**
+Replaces Arabic letters by the presentation form for their position in a word, and lam followed by alef by their ligature.
+Forms are only used if `has_glyph` finds a glyph for them, which is given the position of the letter. Returns the characters along with the position they came from.
+*/
+fn shape_arabic<F: Fn(usize, char) -> bool>(chars: &[char], has_glyph: F) -> Vec<(char, usize)> {
+
+    // Tatweel joins on both sides but has no forms of its own. Marks are skipped when looking for the letters next to another.
+    let joins_next = |c: char| c == '\u{640}' || arabic_forms(c).map(|forms| forms.1).unwrap_or(false);
+    let joins_previous = |c: char| c == '\u{640}' || arabic_forms(c).is_some();
+    let letter_after = |i: usize| (i + 1..chars.len()).find(|&j| bidi_class(chars[j]) != BidiClass::NonspacingMark);
+
+    let mut shaped: Vec<(char, usize)> = Vec::new();
+    // Whether the last letter joins the next letter.
+    let mut joining = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+
+        let c = chars[i];
+        if bidi_class(c) == BidiClass::NonspacingMark {
+            shaped.push((c, i));
+            i += 1;
+            continue;
+        }
+
+        let after = letter_after(i);
+        let joins_before = joining && joins_previous(c);
+        joining = joins_next(c);
+
+        let (isolated, dual) = match arabic_forms(c) {
+            Some(forms) => forms,
+            None => {
+                shaped.push((c, i));
+                i += 1;
+                continue;
+            },
+        };
+
+        if c == '\u{644}' {
+            let ligature = after.and_then(|j| match chars[j] {
+                '\u{622}' => Some((j, 0xFEF5)),
+                '\u{623}' => Some((j, 0xFEF7)),
+                '\u{625}' => Some((j, 0xFEF9)),
+                '\u{627}' => Some((j, 0xFEFB)),
+                _ => None,
+            });
+            if let Some((j, ligature)) = ligature {
+                let form = ::std::char::from_u32(ligature + if joins_before { 1 } else { 0 }).unwrap();
+                if has_glyph(i, form) {
+                    shaped.push((form, i));
+                    shaped.extend((i + 1..j).map(|k| (chars[k], k)));
+                    joining = false;
+                    i = j + 1;
+                    continue;
+                }
+            }
+        }
+
+        let joins_after = dual && after.map(|j| joins_previous(chars[j])).unwrap_or(false);
+        let offset = match (joins_before, joins_after) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        let form = ::std::char::from_u32(isolated + offset).unwrap();
+        shaped.push((if has_glyph(i, form) { form } else { c }, i));
+        i += 1;
+
+    }
+
+    return shaped;
+
+}
+
+/**
+How a run of rich text is drawn.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextStyle {
+
+    pub color: Color,
+    pub size: f32,
+    /**
+    The name of a family of the text's `FontCollection`, or None for the default family.
+    */
+    pub family: Option<String>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /**
+    The target of a link, as given in the markup.
+    */
+    pub link: Option<String>,
+
+}
+
+impl TextStyle {
+
+    pub fn new(size: f32, color: Color) -> TextStyle {
+        return TextStyle { color, size, family: None, bold: false, italic: false, underline: false, strikethrough: false, link: None };
+    }
+
+}
+
+/**
+A piece of rich text in a single style.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextRun {
+
+    pub text: String,
+    pub style: TextStyle,
+    /**
+    The name of an icon shown instead of text. The text of an icon run is a single object replacement character.
+    */
+    pub icon: Option<String>,
+
+}
+
+/**
+Parses BBCode style markup into runs of styled text. The supported tags are
+`[b]`, `[i]`, `[u]`, `[s]`, `[color=red]`, `[color=#rrggbb]`, `[color=#rrggbbaa]`, `[size=20]`, `[font=family]` and `[url=target]`, which are closed by `[/b]`, `[/color]` and so on,
+and `[img=name]` for an icon, which is not closed. Tags have to be closed in the reverse order they were opened, tags left open at the end are closed implicitly.
+Anything else in brackets is kept as text, and `[[` is a literal bracket.
+*/
+pub fn parse_markup(markup: &str, base: &TextStyle) -> error::Result<Vec<TextRun>> {
+
+    let mut runs: Vec<TextRun> = Vec::new();
+    let mut text = String::new();
+    let mut style = base.clone();
+    // The open tags, with the style in use before each of them.
+    let mut open: Vec<(String, TextStyle)> = Vec::new();
+
+    let mut rest = markup;
+    while let Some(start) = rest.find('[') {
+
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with("[[") {
+            text.push('[');
+            rest = &rest[2..];
+            continue;
+        }
+
+        let end = match rest.find(']') {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = &rest[1..end];
+        let (name, value) = match tag.find('=') {
+            Some(i) => (&tag[..i], Some(&tag[i + 1..])),
+            None => (tag, None),
+        };
+
+        // Work out the style after the tag, keeping unknown tags as text.
+        let mut next = style.clone();
+        let mut icon: Option<String> = None;
+        let mut closes = false;
+        match (name, value) {
+            ("b", None) => next.bold = true,
+            ("i", None) => next.italic = true,
+            ("u", None) => next.underline = true,
+            ("s", None) => next.strikethrough = true,
+            ("color", Some(value)) => next.color = parse_color(value)?,
+            ("size", Some(value)) => {
+                next.size = match value.parse::<f32>() {
+                    Ok(size) if size.is_finite() && size > 0.0 => size,
+                    _ => return Err(Error::Parse(format!("Invalid text size '{}'.", value))),
+                };
+            },
+            ("font", Some(value)) => next.family = Some(value.to_string()),
+            ("url", Some(value)) => next.link = Some(value.to_string()),
+            ("img", Some(value)) => icon = Some(value.to_string()),
+            (_, None) if name.starts_with('/') && ["b", "i", "u", "s", "color", "size", "font", "url"].contains(&&name[1..]) => closes = true,
+            _ => {
+                text.push('[');
+                rest = &rest[1..];
+                continue;
+            },
+        }
+        rest = &rest[end + 1..];
+
+        if !text.is_empty() {
+            runs.push(TextRun { text: text.clone(), style: style.clone(), icon: None });
+            text.clear();
+        }
+
+        if closes {
+            style = match open.pop() {
+                Some((ref tag, ref previous)) if tag == &name[1..] => previous.clone(),
+                Some((tag, _)) => return Err(Error::Parse(format!("Found [{}] while [{}] is still open.", name, tag))),
+                None => return Err(Error::Parse(format!("Found [{}] without an opening tag.", name))),
+            };
+        } else if icon.is_some() {
+            runs.push(TextRun { text: String::from("\u{FFFC}"), style: style.clone(), icon });
+        } else {
+            open.push((name.to_string(), style));
+            style = next;
+        }
+
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        runs.push(TextRun { text, style, icon: None });
+    }
+
+    return Ok(runs);
+
+}
+
+/**
+Parses a color name or a hex color with or without alpha.
+*/
+fn parse_color(value: &str) -> error::Result<Color> {
+
+    let rgb = |r: f32, g: f32, b: f32| Color { r, g, b, a: 1.0 };
+    let color = match value.to_lowercase().as_str() {
+        "black" => Color::black(),
+        "white" => Color::white(),
+        "red" => Color::red(),
+        "green" => Color::green(),
+        "blue" => Color::blue(),
+        "yellow" => Color::yellow(),
+        "cyan" => rgb(0.0, 1.0, 1.0),
+        "magenta" => rgb(1.0, 0.0, 1.0),
+        "gray" | "grey" => rgb(0.5, 0.5, 0.5),
+        "orange" => rgb(1.0, 0.5, 0.0),
+        hex if hex.starts_with('#') && (hex.len() == 7 || hex.len() == 9) && hex[1..].chars().all(|c| c.is_ascii_hexdigit()) => {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap() as f32 / 255.0;
+            Color { r: channel(1), g: channel(3), b: channel(5), a: if hex.len() == 9 { channel(7) } else { 1.0 } }
+        },
+        _ => return Err(Error::Parse(format!("Invalid color '{}'.", value))),
+    };
+    return Ok(color);
+
+}
+
 impl Texture {
 
-    pub fn from_text(text: &str, font: &rusttype::Font, size: f32, color: Color) -> Texture {
-        // The font size to use
-        let scale = rusttype::Scale::uniform(size);
+    /**
+    Renders a single line of text at its natural size.
+    */
+    pub fn from_text(text: &str, font: &rusttype::Font, size: f32, color: Color) -> error::Result<Texture> {
+
+        if !size.is_finite() || size <= 0.0 {
+            return Err(Error::Font(format!("Invalid font size {}.", size)));
+        }
+
+        let face = FontFace::new(font.clone());
+        return Texture::from_layout(&TextLayout::new(text, &face, size, &LayoutOptions::new()), &face, color);
+
+    }
+
+    /**
+    Renders the glyphs of a layout into a texture the size of the layout box. Parts of glyphs outside the box are cut off, and images are left out.
+    */
+    pub fn from_layout(layout: &TextLayout, fonts: &FontSource, color: Color) -> error::Result<Texture> {
+
+        let width = (layout.size.x.ceil() as u32).max(1);
+        let height = (layout.size.y.ceil() as u32).max(1);
+
+        if !layout.size.x.is_finite() || !layout.size.y.is_finite() || width > u16::max_value() as u32 || height > u16::max_value() as u32 {
+            return Err(Error::Texture(format!("The text is too large to fit into a texture ({}x{} pixels).", layout.size.x, layout.size.y)));
+        }
+
+        let color = [(color.r * 255.0) as u8, (color.g * 255.0) as u8, (color.b * 255.0) as u8, (color.a * 255.0) as u8];
+
+        let mut image = image::DynamicImage::new_rgba8(width, height).to_rgba();
+
+        for glyph in layout.glyphs.iter().filter(|g| !g.image) {
+
+            let font = match fonts.get_font(glyph.font) {
+                Some(font) => font,
+                None => return Err(Error::Font(format!("The layout refers to the unknown font {}.", glyph.font))),
+            };
+            let positioned = font.glyph(glyph.id).scaled(rusttype::Scale::uniform(glyph.size)).positioned(rusttype::point(glyph.position.x, glyph.position.y));
+
+            if let Some(bounding_box) = positioned.pixel_bounding_box() {
+                // Draw the glyph into the image per-pixel by using the draw closure
+                positioned.draw(|x, y, v| {
+                    // Offset the position by the glyph bounding box
+                    let px = x as i32 + bounding_box.min.x;
+                    let py = y as i32 + bounding_box.min.y;
+                    if px < 0 || py < 0 || px as u32 >= width || py as u32 >= height {
+                        return;
+                    }
+                    // Turn the coverage into an alpha value
+                    let alpha = (v * color[3] as f32) as u8;
+                    let pixel = image.get_pixel_mut(px as u32, py as u32);
+                    if alpha > pixel.data[3] {
+                        *pixel = image::Rgba { data: [color[0], color[1], color[2], alpha] };
+                    }
+                });
+            }
+
+        }
+
+        return Ok(Texture { data: Vec::from(image.as_ref()), dimensions: Vector2 { x: width as u16, y: height as u16 } });
+
+    }
+
+}
+
+impl Sprite {
+
+    pub fn from_text(text: &str, font: &rusttype::Font, size: f32, color: Color) -> error::Result<Sprite> {
+
+        return Ok(Sprite::from_texture(Box::new(Texture::from_text(text, font, size, color)?)));
+
+    }
+
+}
+
+/**
+The font size distance fields are generated at. Text drawn with `TextRendering::DistanceField` scales them to its own size.
+*/
+pub const DISTANCE_FIELD_SIZE: f32 = 32.0;
+/**
+How far from the outline of a glyph its distance field reaches, in pixels at `DISTANCE_FIELD_SIZE`. This limits the width of outlines and glows.
+*/
+pub const DISTANCE_FIELD_SPREAD: f32 = 8.0;
+
+// Glyphs are rasterized this many times larger than the distance field, so that the distances follow the outline rather than the pixels of one rasterization.
+const DISTANCE_FIELD_SAMPLES: u32 = 4;
+
+/**
+Generates the signed distance field of a glyph at `DISTANCE_FIELD_SIZE`, padded by `DISTANCE_FIELD_SPREAD` on each side.
+The distance is stored in the alpha channel, with 0.5 on the outline, 1 at the spread inside the glyph and 0 at the spread outside of it. The colour is white.
+Also returns the top left of the field relative to the pen position, in pixels with y pointing down. Glyphs without an outline have no field.
+*/
+pub fn render_distance_field(font: &Font, id: rusttype::GlyphId) -> Option<(Texture, Vector2f)> {
+
+    let samples = DISTANCE_FIELD_SAMPLES as i32;
+    let scale = DISTANCE_FIELD_SIZE * samples as f32;
+    let positioned = font.glyph(id).scaled(rusttype::Scale::uniform(scale)).positioned(rusttype::point(0.0, 0.0));
+    let bounding_box = match positioned.pixel_bounding_box() {
+        Some(bounding_box) if bounding_box.width() > 0 && bounding_box.height() > 0 => bounding_box,
+        _ => return None,
+    };
+
+    // The padded field in samples, rounded up to whole pixels of the result.
+    let padding = DISTANCE_FIELD_SPREAD as i32 * samples;
+    let width = ((bounding_box.width() + 2 * padding + samples - 1) / samples) as usize;
+    let height = ((bounding_box.height() + 2 * padding + samples - 1) / samples) as usize;
+    let (sample_width, sample_height) = (width * samples as usize, height * samples as usize);
+
+    let mut inside = vec![false; sample_width * sample_height];
+    positioned.draw(|x, y, v| {
+        inside[(y as usize + padding as usize) * sample_width + x as usize + padding as usize] = v >= 0.5;
+    });
+
+    // The squared distances of each sample to the nearest sample on the other side of the outline.
+    let far = 1e20;
+    let to_inside = squared_distances(&inside.iter().map(|&i| if i { 0.0 } else { far }).collect::<Vec<_>>(), sample_width, sample_height);
+    let to_outside = squared_distances(&inside.iter().map(|&i| if i { far } else { 0.0 }).collect::<Vec<_>>(), sample_width, sample_height);
+
+    let mut data = vec![255; width * height * 4];
+    for y in 0..height {
+        for x in 0..width {
+
+            // Average the signed distances of the samples in the pixel, measured from the boundary between samples.
+            let mut distance = 0.0;
+            for sy in y * samples as usize..(y + 1) * samples as usize {
+                for sx in x * samples as usize..(x + 1) * samples as usize {
+                    let i = sy * sample_width + sx;
+                    distance += match inside[i] {
+                        true => to_outside[i].sqrt() - 0.5,
+                        false => 0.5 - to_inside[i].sqrt(),
+                    };
+                }
+            }
+            // Average, then convert from samples to pixels.
+            distance /= (samples * samples * samples) as f32;
+
+            let alpha = (0.5 + distance / (2.0 * DISTANCE_FIELD_SPREAD)).max(0.0).min(1.0);
+            data[(y * width + x) * 4 + 3] = (alpha * 255.0).round() as u8;
+
+        }
+    }
+
+    let offset = Vector2f::new((bounding_box.min.x - padding) as f32, (bounding_box.min.y - padding) as f32) / samples as f32;
+    return Some((Texture { data, dimensions: Vector2::new(width as u16, height as u16) }, offset));
+
+}
+
+/**
+The squared euclidean distance transform of a grid, where `f` is 0 at the samples distances are measured to and very large elsewhere.
+This is the separable algorithm by Felzenszwalb and Huttenlocher, run over the columns and then the rows.
+*/
+fn squared_distances(f: &[f32], width: usize, height: usize) -> Vec<f32> {
+
+    let mut grid = Vec::from(f);
+    let length = width.max(height);
+    let mut line = vec![0.0; length];
+    let mut result = vec![0.0; length];
+    let mut parabolas = vec![0; length];
+    let mut bounds = vec![0.0; length + 1];
+
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = grid[y * width + x];
+        }
+        lower_envelope(&line[..height], &mut result, &mut parabolas, &mut bounds);
+        for y in 0..height {
+            grid[y * width + x] = result[y];
+        }
+    }
+
+    for y in 0..height {
+        line[..width].copy_from_slice(&grid[y * width..(y + 1) * width]);
+        lower_envelope(&line[..width], &mut result, &mut parabolas, &mut bounds);
+        grid[y * width..(y + 1) * width].copy_from_slice(&result[..width]);
+    }
+
+    return grid;
+
+}
+
+/**
+The one dimensional distance transform, as the lower envelope of the parabolas rooted at each sample.
+*/
+fn lower_envelope(f: &[f32], result: &mut [f32], parabolas: &mut [usize], bounds: &mut [f32]) {
+
+    let far = f32::INFINITY;
+    let intersection = |q: usize, p: usize| ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2.0 * q as f32 - 2.0 * p as f32);
+
+    let mut k = 0;
+    parabolas[0] = 0;
+    bounds[0] = -far;
+    bounds[1] = far;
+
+    for q in 1..f.len() {
+        let mut s = intersection(q, parabolas[k]);
+        while s <= bounds[k] {
+            k -= 1;
+            s = intersection(q, parabolas[k]);
+        }
+        k += 1;
+        parabolas[k] = q;
+        bounds[k] = s;
+        bounds[k + 1] = far;
+    }
+
+    k = 0;
+    for q in 0..f.len() {
+        while bounds[k + 1] < q as f32 {
+            k += 1;
+        }
+        let p = parabolas[k];
+        result[q] = (q as f32 - p as f32) * (q as f32 - p as f32) + f[p];
+    }
+
+}
+
+/**
+Where a rasterized glyph is stored in the glyph cache.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CachedGlyph {
+
+    /**
+    The part of the cache texture holding the glyph, from (0, 0) at the top left to (1, 1) at the bottom right.
+    */
+    pub uv: Rect,
+    /**
+    The top left of the glyph's bitmap relative to the pen position, in pixels with y pointing down.
+    */
+    pub offset: Vector2f,
+    pub size: Vector2f,
+
+}
+
+// The font id the solid block of the cache is stored under, which `new_font_id` does not reach.
+const SOLID_FONT_ID: FontId = ::std::usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    font: FontId,
+    id: rusttype::GlyphId,
+    // The bits of the font size, so that the key can be hashed.
+    size: u32,
+    distance: bool,
+}
+
+impl GlyphKey {
+
+    fn new(font: FontId, id: rusttype::GlyphId, size: f32) -> GlyphKey {
+        return GlyphKey { font, id, size: size.to_bits(), distance: false };
+    }
+
+    fn distance(font: FontId, id: rusttype::GlyphId) -> GlyphKey {
+        return GlyphKey { font, id, size: DISTANCE_FIELD_SIZE.to_bits(), distance: true };
+    }
+
+}
+
+struct CacheRow {
+    y: u32,
+    height: u32,
+    // Where the next glyph of the row goes.
+    x: u32,
+    last_used: u64,
+    glyphs: Vec<GlyphKey>,
+}
+
+/**
+Rasterizes glyphs once and packs them into rows of a shared texture, so that text is drawn as quads of that texture.
+When the texture is full the least recently used row is emptied and reused. Each eviction increases the generation, after which uvs handed out before may show other glyphs.
+*/
+pub struct GlyphCache {
 
-        // Use a dark red colour
-        let color = ((color.r * (255 as f32)) as u8, (color.g * (255 as f32)) as u8, (color.b * (255 as f32)) as u8);
+    page: Texture,
+    rows: Vec<CacheRow>,
+    glyphs: HashMap<GlyphKey, (usize, CachedGlyph)>,
+    // Increased by every call to `prepare`. Rows used by the current call have this as their `last_used`.
+    clock: u64,
+    generation: u64,
+    texture: Option<core::TextureId>,
+    dirty: bool,
 
-        let v_metrics = font.v_metrics(scale);
+}
+
+impl GlyphCache {
 
-        // layout the glyphs in a line with 20 pixels padding
-        let glyphs: Vec<_> = font
-            .layout(text, scale, rusttype::point(20.0, 20.0 + v_metrics.ascent))
-            .collect();
+    pub fn new(width: u16, height: u16) -> GlyphCache {
 
-        // work out the layout size
-        let glyphs_height = (v_metrics.ascent - v_metrics.descent).ceil() as u32;
-        let glyphs_width = {
-            let min_x = glyphs
-                .first()
-                .map(|g| g.pixel_bounding_box().unwrap().min.x)
-                .unwrap();
-            let max_x = glyphs
-                .last()
-                .map(|g| g.pixel_bounding_box().unwrap().max.x)
-                .unwrap();
-            (max_x - min_x) as u32
+        return GlyphCache {
+            page: Texture { data: vec![0; width as usize * height as usize * 4], dimensions: Vector2::new(width, height) },
+            rows: Vec::new(),
+            glyphs: HashMap::new(),
+            clock: 0,
+            generation: 0,
+            texture: None,
+            dirty: true,
         };
 
-        // Create a new rgba image with some padding
-        let mut image = image::DynamicImage::new_rgba8(glyphs_width + 40, glyphs_height + 40).to_rgba();
+    }
 
-        // Loop through the glyphs in the text, positing each one on a line
-        for glyph in glyphs {
-            if let Some(bounding_box) = glyph.pixel_bounding_box() {
-                // Draw the glyph into the image per-pixel by using the draw closure
-                glyph.draw(|x, y, v| {
-                    image.put_pixel(
-                        // Offset the position by the glyph bounding box
-                        x + bounding_box.min.x as u32,
-                        y + bounding_box.min.y as u32,
-                        // Turn the coverage into an alpha value
-                        image::Rgba {
-                            data: [color.0, color.1, color.2, (v * 255.0) as u8],
-                        },
-                    )
-                });
+    pub fn get_size(&self) -> Vector2u {
+        return Vector2u::new(self.page.dimensions.x as u32, self.page.dimensions.y as u32);
+    }
+
+    /**
+    The number of glyphs in the cache.
+    */
+    pub fn len(&self) -> usize {
+        return self.glyphs.len();
+    }
+
+    pub fn get_generation(&self) -> u64 {
+        return self.generation;
+    }
+
+    /**
+    The texture the glyphs are rasterized into, white with the coverage in the alpha channel.
+    */
+    pub fn get_page(&self) -> &Texture {
+        return &self.page;
+    }
+
+    /**
+    Looks up a glyph added by `prepare`. Glyphs without an outline, like spaces, are never cached.
+    */
+    pub fn get(&self, font: FontId, id: rusttype::GlyphId, size: f32) -> Option<CachedGlyph> {
+        return self.glyphs.get(&GlyphKey::new(font, id, size)).map(|&(_, glyph)| glyph);
+    }
+
+    /**
+    Makes sure all the glyphs of a layout are in the cache, rasterizing the missing ones. Images in the layout are left to `prepare_image`.
+    Rows holding the glyphs are marked as used, so that they are not evicted to make room for the rest of the layout.
+    */
+    pub fn prepare(&mut self, fonts: &FontSource, glyphs: &[LayoutGlyph]) -> error::Result<()> {
+
+        self.clock += 1;
+
+        for glyph in glyphs.iter().filter(|g| !g.image) {
+
+            let key = GlyphKey::new(glyph.font, glyph.id, glyph.size);
+            if let Some(&(row, _)) = self.glyphs.get(&key) {
+                self.rows[row].last_used = self.clock;
+                continue;
             }
+
+            let font = match fonts.get_font(glyph.font) {
+                Some(font) => font,
+                None => return Err(Error::Font(format!("The layout refers to the unknown font {}.", glyph.font))),
+            };
+            let positioned = font.glyph(glyph.id).scaled(rusttype::Scale::uniform(glyph.size)).positioned(rusttype::point(0.0, 0.0));
+            let bounding_box = match positioned.pixel_bounding_box() {
+                Some(bounding_box) if bounding_box.width() > 0 && bounding_box.height() > 0 => bounding_box,
+                _ => continue,
+            };
+
+            let (width, height) = (bounding_box.width() as u32, bounding_box.height() as u32);
+            let (row, x, y) = self.allocate(width + 2, height + 2)?;
+
+            // Leave a border of one transparent pixel, so that filtering does not pick up the neighbouring glyphs.
+            let (x, y) = (x + 1, y + 1);
+            let page_width = self.page.dimensions.x as u32;
+            let data = &mut self.page.data;
+            positioned.draw(|px, py, v| {
+                let i = (((y + py) * page_width + x + px) * 4) as usize;
+                data[i..i + 4].copy_from_slice(&[255, 255, 255, (v * 255.0).round() as u8]);
+            });
+
+            let size = Vector2f::new(self.page.dimensions.x as f32, self.page.dimensions.y as f32);
+            let cached = CachedGlyph {
+                uv: Rect::new(x as f32 / size.x, y as f32 / size.y, width as f32 / size.x, height as f32 / size.y),
+                offset: Vector2f::new(bounding_box.min.x as f32, bounding_box.min.y as f32),
+                size: Vector2f::new(width as f32, height as f32),
+            };
+            self.glyphs.insert(key, (row, cached));
+            self.rows[row].glyphs.push(key);
+            self.dirty = true;
+
         }
 
-        let (width, height) = image.dimensions();
+        return Ok(());
 
-        return Texture { data: Vec::from(image.as_ref()), dimensions: Vector2 { x: width as u16, y: height as u16 } };
     }
 
-}
+    /**
+    Looks up the distance field of a glyph added by `prepare_distance`. The offset and size are in pixels at `DISTANCE_FIELD_SIZE`.
+    */
+    pub fn get_distance(&self, font: FontId, id: rusttype::GlyphId) -> Option<CachedGlyph> {
+        return self.glyphs.get(&GlyphKey::distance(font, id)).map(|&(_, glyph)| glyph);
+    }
 
-impl Sprite {
+    /**
+    Like `prepare`, but adds the distance fields of the glyphs (see `render_distance_field`), which are shared by all sizes of a glyph.
+    */
+    pub fn prepare_distance(&mut self, fonts: &FontSource, glyphs: &[LayoutGlyph]) -> error::Result<()> {
 
-    pub fn from_text(text: &str, font: &rusttype::Font, size: f32, color: Color) -> Sprite {
+        self.clock += 1;
 
-        return Sprite {
-            node: NodeObject2D::new(),
-            texture: Box::new(Texture::from_text(text, font, size, color)),
-            vertices: UvVertexArray::zero(),
-            texture_renderer: None,
-            update_texture: false,
-            has_loaded: false
+        for glyph in glyphs.iter().filter(|g| !g.image) {
+
+            let key = GlyphKey::distance(glyph.font, glyph.id);
+            if let Some(&(row, _)) = self.glyphs.get(&key) {
+                self.rows[row].last_used = self.clock;
+                continue;
+            }
+
+            let font = match fonts.get_font(glyph.font) {
+                Some(font) => font,
+                None => return Err(Error::Font(format!("The layout refers to the unknown font {}.", glyph.font))),
+            };
+            let (field, offset) = match render_distance_field(font, glyph.id) {
+                Some(field) => field,
+                None => continue,
+            };
+
+            let (width, height) = (field.dimensions.x as u32, field.dimensions.y as u32);
+            let (row, x, y) = self.allocate(width + 2, height + 2)?;
+
+            // The field fades out towards its edges, so the border is left transparent like for glyphs.
+            let (x, y) = (x + 1, y + 1);
+            let stride = self.page.dimensions.x as usize * 4;
+            for py in 0..height as usize {
+                let target = (y as usize + py) * stride + x as usize * 4;
+                let source = py * width as usize * 4;
+                self.page.data[target..target + width as usize * 4].copy_from_slice(&field.data[source..source + width as usize * 4]);
+            }
+
+            let size = Vector2f::new(self.page.dimensions.x as f32, self.page.dimensions.y as f32);
+            let cached = CachedGlyph {
+                uv: Rect::new(x as f32 / size.x, y as f32 / size.y, width as f32 / size.x, height as f32 / size.y),
+                offset,
+                size: Vector2f::new(width as f32, height as f32),
+            };
+            self.glyphs.insert(key, (row, cached));
+            self.rows[row].glyphs.push(key);
+            self.dirty = true;
+
+        }
+
+        return Ok(());
+
+    }
+
+    /**
+    Copies an image into the cache, so that it can be drawn together with text. Images are looked up with `get` like glyphs, with a size of zero.
+    The image has to hold `dimensions.x * dimensions.y` pixels.
+    The image's row is marked as used by the last call to `prepare`, so images should be added after the glyphs they are drawn with.
+    */
+    pub fn prepare_image(&mut self, font: FontId, id: u32, image: &Texture) -> error::Result<CachedGlyph> {
+
+        let key = GlyphKey::new(font, rusttype::GlyphId(id), 0.0);
+        if let Some(&(row, cached)) = self.glyphs.get(&key) {
+            self.rows[row].last_used = self.clock;
+            return Ok(cached);
+        }
+
+        let (width, height) = (image.dimensions.x as u32, image.dimensions.y as u32);
+        let (row, x, y) = self.allocate(width + 2, height + 2)?;
+
+        // Unlike glyphs, images are cut off at their edges, so the border repeats the edges to keep filtering from fading them out.
+        let stride = self.page.dimensions.x as usize * 4;
+        for py in 0..height as usize + 2 {
+            let source_y = py.max(1).min(height as usize) - 1;
+            for px in 0..width as usize + 2 {
+                let source = (source_y * width as usize + px.max(1).min(width as usize) - 1) * 4;
+                let target = (y as usize + py) * stride + (x as usize + px) * 4;
+                self.page.data[target..target + 4].copy_from_slice(&image.data[source..source + 4]);
+            }
+        }
+
+        let (x, y) = (x + 1, y + 1);
+
+        let size = Vector2f::new(self.page.dimensions.x as f32, self.page.dimensions.y as f32);
+        let cached = CachedGlyph {
+            uv: Rect::new(x as f32 / size.x, y as f32 / size.y, width as f32 / size.x, height as f32 / size.y),
+            offset: Vector2f::new(0.0, 0.0),
+            size: Vector2f::new(width as f32, height as f32),
+        };
+        self.glyphs.insert(key, (row, cached));
+        self.rows[row].glyphs.push(key);
+        self.dirty = true;
+
+        return Ok(cached);
+
+    }
+
+    /**
+    A solid white part of the cache, for drawing lines with the glyphs.
+    */
+    pub fn prepare_solid(&mut self) -> error::Result<Rect> {
+
+        let image = Texture { data: vec![255; 4], dimensions: Vector2::new(1, 1) };
+        return Ok(self.prepare_image(SOLID_FONT_ID, 0, &image)?.uv);
+
+    }
+
+    /**
+    Finds room for a glyph, returning its row and the position of its top left corner.
+    */
+    fn allocate(&mut self, width: u32, height: u32) -> error::Result<(usize, u32, u32)> {
+
+        let page = self.get_size();
+        if width > page.x || height > page.y {
+            return Err(Error::Texture(format!("A {}x{} glyph does not fit into the {}x{} glyph cache.", width, height, page.x, page.y)));
+        }
+
+        // The row with the least height to spare that the glyph fits in, if it does not waste too much.
+        let mut best: Option<usize> = None;
+        for (i, row) in self.rows.iter().enumerate() {
+            let fits = row.height >= height && row.height <= height + height / 4 + 4 && row.x + width <= page.x;
+            if fits && best.map(|b| row.height < self.rows[b].height).unwrap_or(true) {
+                best = Some(i);
+            }
+        }
+
+        // Open a new row below the others. The height is rounded up so that glyphs of similar sizes share rows.
+        if best.is_none() {
+            let top = self.rows.iter().map(|r| r.y + r.height).max().unwrap_or(0);
+            let row_height = ((height + 3) / 4 * 4).min(page.y - top.min(page.y));
+            if row_height >= height {
+                self.rows.push(CacheRow { y: top, height: row_height, x: 0, last_used: self.clock, glyphs: Vec::new() });
+                best = Some(self.rows.len() - 1);
+            }
+        }
+
+        // Empty the least recently used row that is tall enough.
+        if best.is_none() {
+            let clock = self.clock;
+            best = self.rows.iter().enumerate().filter(|&(_, r)| r.height >= height && r.last_used < clock).min_by_key(|&(_, r)| r.last_used).map(|(i, _)| i);
+            match best {
+                Some(i) => self.evict(i),
+                None => return Err(Error::Texture(format!("The {}x{} glyph cache is too small for the glyphs in use.", page.x, page.y))),
+            }
+        }
+
+        let index = best.unwrap();
+        let row = &mut self.rows[index];
+        let x = row.x;
+        row.x += width;
+        row.last_used = self.clock;
+        return Ok((index, x, row.y));
+
+    }
+
+    fn evict(&mut self, index: usize) {
+
+        let (y, height) = (self.rows[index].y, self.rows[index].height);
+        for key in self.rows[index].glyphs.drain(..) {
+            self.glyphs.remove(&key);
+        }
+        self.rows[index].x = 0;
+
+        let stride = self.page.dimensions.x as usize * 4;
+        for b in self.page.data[y as usize * stride..(y + height) as usize * stride].iter_mut() {
+            *b = 0;
+        }
+
+        self.generation += 1;
+        self.dirty = true;
+
+    }
+
+    /**
+    Empties the cache.
+    */
+    pub fn clear(&mut self) {
+
+        self.rows.clear();
+        self.glyphs.clear();
+        for b in self.page.data.iter_mut() {
+            *b = 0;
+        }
+        self.generation += 1;
+        self.dirty = true;
+
+    }
+
+    /**
+    Uploads the texture if glyphs were added since the last upload, and returns it.
+    */
+    pub fn upload(&mut self, renderer: &mut core::Renderer) -> error::Result<core::TextureId> {
+
+        let texture = match self.texture {
+            Some(texture) => {
+                if self.dirty {
+                    renderer.update_texture(texture, &self.page)?;
+                }
+                texture
+            },
+            None => renderer.create_texture(&self.page)?,
+        };
+
+        self.texture = Some(texture);
+        self.dirty = false;
+        return Ok(texture);
+
+    }
+
+    pub fn destroy(&mut self, renderer: &mut core::Renderer) {
+
+        if let Some(texture) = self.texture.take() {
+            renderer.destroy_texture(texture);
         }
+        self.dirty = true;
 
     }
 
 }
 
+/**
+How the glyphs of a `Text` are drawn.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextRendering {
+
+    /**
+    Glyphs rasterized at the size of the text, which are the sharpest at that size but blur when the text is scaled.
+    */
+    Bitmap,
+    /**
+    Glyphs drawn from distance fields, which stay crisp at any scale and can have outlines, glows and shadows.
+    */
+    DistanceField,
+
+}
+
+/**
+Effects drawn around the glyphs of a `Text` with `TextRendering::DistanceField`. Widths and offsets are in pixels at the size of the text.
+Outlines and glows together reach at most `DISTANCE_FIELD_SPREAD` pixels of a glyph at `DISTANCE_FIELD_SIZE` from its outline.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextEffects {
+
+    pub outline_width: f32,
+    pub outline_color: Color,
+    pub glow_width: f32,
+    pub glow_color: Color,
+    /**
+    The offset of the shadow with y pointing down.
+    */
+    pub shadow_offset: Vector2f,
+    pub shadow_softness: f32,
+    /**
+    The colour of the shadow, which is not drawn if it is transparent.
+    */
+    pub shadow_color: Color,
+
+}
+
+impl TextEffects {
+
+    pub fn new() -> TextEffects {
+
+        let clear = Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 };
+        return TextEffects {
+            outline_width: 0.0,
+            outline_color: clear,
+            glow_width: 0.0,
+            glow_color: clear,
+            shadow_offset: Vector2f::new(0.0, 0.0),
+            shadow_softness: 0.0,
+            shadow_color: clear,
+        };
+
+    }
+
+    pub fn with_outline(mut self, width: f32, color: Color) -> TextEffects {
+        self.outline_width = width;
+        self.outline_color = color;
+        return self;
+    }
+
+    pub fn with_glow(mut self, width: f32, color: Color) -> TextEffects {
+        self.glow_width = width;
+        self.glow_color = color;
+        return self;
+    }
+
+    pub fn with_shadow(mut self, offset: Vector2f, softness: f32, color: Color) -> TextEffects {
+        self.shadow_offset = offset;
+        self.shadow_softness = softness;
+        self.shadow_color = color;
+        return self;
+    }
+
+}
+
+/**
+A node that draws a text as quads of the engine's glyph cache, so that changing the text only updates the vertices.
+The text can be plain or markup as parsed by `parse_markup`, and both are laid out with the size and color of the node as the base style.
+*/
 pub struct Text<'a> {
 
     pub node: NodeObject2D,
-    pub vertices: UvVertexArray,
+    pub vertices: Vec<TextVertex>,
+    /**
+    The text as it was set, which is markup if the text was set by `from_markup` or `set_markup`.
+    */
     pub text: String,
-    pub texture: Texture,
-    pub texture_renderer: Option<TextureRenderer>,
-    pub font: Font<'a>,
+    pub fonts: FontCollection<'a>,
     pub size: f32,
     pub color: Color,
+    pub options: LayoutOptions,
+    pub runs: Vec<TextRun>,
+    pub layout: TextLayout,
+    pub rendering: TextRendering,
+    /**
+    Only drawn with `TextRendering::DistanceField`.
+    */
+    pub effects: TextEffects,
     pub update_text: bool,
     pub has_loaded: bool,
+    markup: bool,
+    icons: Vec<(String, Texture)>,
+    /**
+    Identifies the icons in the glyph cache. Replaced whenever an icon changes.
+    */
+    icon_id: FontId,
+    visible_glyphs: Option<usize>,
+    /**
+    The number of vertices up to and including each glyph of the layout.
+    */
+    glyph_vertex_ends: Vec<u32>,
+    /**
+    Where the copy of the vertices drawn as the shadow starts.
+    */
+    shadow_start: Option<u32>,
+    shader: Option<core::ShaderId>,
+    distance_shader: Option<core::ShaderId>,
+    buffer: Option<core::BufferId>,
+    /**
+    The generation of the glyph cache the vertices were built with.
+    */
+    cache_generation: u64,
 
 }
 
 impl<'a> Text<'a> {
 
-    pub fn new(text: &'a str, font: Font<'a>, size: f32, color: Color) -> Text<'a> {
+    pub fn new(text: &'a str, font: Font<'a>, size: f32, color: Color) -> error::Result<Text<'a>> {
+
+        return Text::with_options(text, font, size, color, LayoutOptions::new());
+
+    }
+
+    pub fn with_options(text: &'a str, font: Font<'a>, size: f32, color: Color, options: LayoutOptions) -> error::Result<Text<'a>> {
+
+        return Text::create(text, false, FontCollection::new(FontFamily::new(font)), size, color, options);
+
+    }
+
+    /**
+    Creates a text from markup, see `parse_markup` for the tags. Icons have to be added with `add_icon` before they show up.
+    */
+    pub fn from_markup(markup: &str, fonts: FontCollection<'a>, size: f32, color: Color, options: LayoutOptions) -> error::Result<Text<'a>> {
+
+        return Text::create(markup, true, fonts, size, color, options);
 
-        let tex = Texture::from_text(text, &font, size, color);
+    }
+
+    fn create(text: &str, markup: bool, fonts: FontCollection<'a>, size: f32, color: Color, options: LayoutOptions) -> error::Result<Text<'a>> {
+
+        if !size.is_finite() || size <= 0.0 {
+            return Err(Error::Font(format!("Invalid font size {}.", size)));
+        }
 
-        return Text {
+        let mut result = Text {
             node: NodeObject2D::new(),
-            vertices: UvVertexArray::zero(),
+            vertices: Vec::new(),
             text: text.to_string(),
-            texture: tex,
-            texture_renderer: None,
-            font: font,
+            fonts: fonts,
             size: size,
             color: color,
+            options: options,
+            runs: Vec::new(),
+            layout: TextLayout { glyphs: Vec::new(), lines: Vec::new(), size: Vector2f::new(0.0, 0.0) },
+            rendering: TextRendering::Bitmap,
+            effects: TextEffects::new(),
             update_text: false,
-            has_loaded: false
+            has_loaded: false,
+            markup: markup,
+            icons: Vec::new(),
+            icon_id: new_font_id(),
+            visible_glyphs: None,
+            glyph_vertex_ends: Vec::new(),
+            shadow_start: None,
+            shader: None,
+            distance_shader: None,
+            buffer: None,
+            cache_generation: 0,
         };
+        result.refresh()?;
+
+        return Ok(result);
 
     }
 
-    pub fn set_color(&mut self, color: Color) {
+    /**
+    Parses the text into runs and lays them out again. The vertices are rebuilt the next time the text is drawn.
+    */
+    fn refresh(&mut self) -> error::Result<()> {
 
-        self.color = color;
+        let base = TextStyle::new(self.size, self.color);
+        self.runs = if self.markup {
+            parse_markup(&self.text, &base)?
+        } else {
+            vec![TextRun { text: self.text.clone(), style: base, icon: None }]
+        };
 
-        self.texture = Texture::from_text(&self.text, &self.font, self.size, self.color);
+        let mut runs: Vec<LayoutRun> = Vec::new();
+        for run in self.runs.iter() {
+            // Icons are as tall as the text around them, unknown icons take up no space.
+            let image = run.icon.as_ref().map(|name| match self.icons.iter().find(|icon| &icon.0 == name) {
+                Some(&(_, ref texture)) => Vector2f::new(run.style.size * texture.dimensions.x as f32 / texture.dimensions.y.max(1) as f32, run.style.size),
+                None => Vector2f::new(0.0, 0.0),
+            });
+            let family = self.fonts.get_family(run.style.family.as_deref());
+            runs.push(LayoutRun { text: &run.text, face: family.get_face(run.style.bold, run.style.italic), fallbacks: &self.fonts.fallbacks, size: run.style.size, image });
+        }
 
+        self.layout = TextLayout::from_runs(&runs, &self.options);
         self.update_text = true;
 
+        return Ok(());
+
+    }
+
+    /**
+    Changes the base color. Colors set by the markup are kept.
+    */
+    pub fn set_color(&mut self, color: Color) -> error::Result<()> {
+
+        self.color = color;
+        return self.refresh();
+
     }
 
-    pub fn set_text(&mut self, text: String) {
+    /**
+    Sets plain text, in which brackets have no meaning.
+    */
+    pub fn set_text(&mut self, text: String) -> error::Result<()> {
 
         self.text = text;
+        self.markup = false;
+        return self.refresh();
+
+    }
+
+    /**
+    Sets markup, see `parse_markup`. The text is left unchanged if the markup is invalid.
+    */
+    pub fn set_markup(&mut self, markup: String) -> error::Result<()> {
+
+        parse_markup(&markup, &TextStyle::new(self.size, self.color))?;
+
+        self.text = markup;
+        self.markup = true;
+        return self.refresh();
+
+    }
+
+    pub fn set_fonts(&mut self, fonts: FontCollection<'a>) -> error::Result<()> {
+
+        self.fonts = fonts;
+        return self.refresh();
+
+    }
+
+    /**
+    Changes how the text is wrapped and aligned. The size of the node follows the new layout box.
+    */
+    pub fn set_options(&mut self, options: LayoutOptions) -> error::Result<()> {
+
+        self.options = options;
+        return self.refresh();
+
+    }
+
+    /**
+    Switches between bitmap and distance field glyphs. The layout stays the same, the glyphs are cached again when the text is next drawn.
+    */
+    pub fn set_rendering(&mut self, rendering: TextRendering) {
+
+        self.rendering = rendering;
+        self.update_text = true;
+
+    }
+
+    /**
+    Changes the outline, glow and shadow. They are only drawn with `TextRendering::DistanceField`.
+    */
+    pub fn set_effects(&mut self, effects: TextEffects) {
+
+        self.effects = effects;
+        self.update_text = true;
+
+    }
+
+    /**
+    Adds an icon for `[img=name]` tags, replacing any icon of the same name.
+    */
+    pub fn add_icon(&mut self, name: &str, texture: Texture) -> error::Result<()> {
+
+        if texture.dimensions.x == 0 || texture.dimensions.y == 0 || texture.data.len() != texture.dimensions.x as usize * texture.dimensions.y as usize * 4 {
+            return Err(Error::Texture(format!("The icon '{}' has no pixels or the wrong number of them.", name)));
+        }
+
+        match self.icons.iter().position(|icon| icon.0 == name) {
+            Some(i) => self.icons[i].1 = texture,
+            None => self.icons.push((name.to_string(), texture)),
+        }
+        self.icon_id = new_font_id();
+
+        return self.refresh();
+
+    }
+
+    pub fn get_lines(&self) -> &[LineMetrics] {
+        return &self.layout.lines;
+    }
+
+    pub fn get_glyph_count(&self) -> usize {
+        return self.layout.glyphs.len();
+    }
+
+    /**
+    Only draws the first glyphs of the layout, together with their underlines, or all of them for None. Used to reveal a text one character at a time.
+    */
+    pub fn set_visible_glyphs(&mut self, count: Option<usize>) {
+        self.visible_glyphs = count;
+    }
+
+    pub fn get_visible_glyphs(&self) -> Option<usize> {
+        return self.visible_glyphs;
+    }
+
+    /**
+    The box a glyph of the layout takes up, in the local space of the node.
+    */
+    pub fn get_glyph_rect(&self, index: usize) -> Option<Rect> {
+
+        let rect = self.layout.glyphs.get(index)?.get_rect();
+        return Some(Rect::new(rect.x, self.layout.size.y - rect.y - rect.height, rect.width, rect.height));
+
+    }
+
+    /**
+    The glyph of the layout at a world position, if any.
+    */
+    pub fn get_glyph_at(&self, pos: Vector2f) -> Option<usize> {
+
+        let local = self.get_world_trans().invert()? * Vector4f::new(pos.x, pos.y, 0.0, 1.0);
+        return (0..self.layout.glyphs.len()).find(|&i| {
+            let rect = self.get_glyph_rect(i).unwrap();
+            local.x >= rect.x && local.x < rect.x + rect.width && local.y >= rect.y && local.y < rect.y + rect.height
+        });
+
+    }
 
-        self.texture = Texture::from_text(&self.text, &self.font, self.size, self.color);
+    /**
+    The target of the link at a world position, if any.
+    */
+    pub fn get_link_at(&self, pos: Vector2f) -> Option<&str> {
 
-        if self.has_loaded {
-            self.update_text = true;
+        let glyph = &self.layout.glyphs[self.get_glyph_at(pos)?];
+        return self.runs[glyph.run].style.link.as_deref();
+
+    }
+
+    /**
+    Rebuilds the quads of a changed text and caches its new glyphs, which otherwise happens when the text is next drawn.
+    Drawing skips the text without reporting why when this fails, so call this first to handle errors. Loads the text if it has not been loaded.
+    */
+    pub fn update(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        match self.buffer {
+            Some(buffer) => self.prepare(engine, buffer)?,
+            None => {
+                core::Drawable::load(self, engine)?;
+                engine.glyphs.upload(engine.renderer.as_mut())?
+            },
+        };
+
+        return Ok(());
+
+    }
+
+    /**
+    Brings the vertex buffer up to date with the text and the glyph cache, returning the texture of the cache.
+    */
+    fn prepare(&mut self, engine: &mut core::FlatEngine, buffer: core::BufferId) -> error::Result<core::TextureId> {
+
+        // Evicted glyphs may have been replaced by others, so the quads are rebuilt after an eviction as well.
+        if self.update_text || self.cache_generation != engine.glyphs.get_generation() {
+
+            self.build_vertices(&mut engine.glyphs)?;
+
+            // Submit the new vertices to the buffer.
+            engine.renderer.update_vertex_buffer(buffer, core::Vertices::Text(&self.vertices))?;
+
+            self.update_text = false;
+        }
+
+        return engine.glyphs.upload(engine.renderer.as_mut());
+
+    }
+
+    /**
+    The parameters of the distance field shader, see `core::DistanceTextCall`. The widths are converted to pixels of the distance fields.
+    */
+    fn get_distance_params(&self, shadow: bool) -> [[f32; 4]; 4] {
+
+        let texels = DISTANCE_FIELD_SIZE / self.size;
+        let effects = &self.effects;
+        let color = |c: Color| [c.r, c.g, c.b, c.a];
+
+        let outline = (effects.outline_width * texels).max(0.0).min(DISTANCE_FIELD_SPREAD);
+        let glow = (effects.glow_width * texels).max(0.0).min(DISTANCE_FIELD_SPREAD - outline);
+        let range = 2.0 * DISTANCE_FIELD_SPREAD;
+
+        // The shadow is the silhouette of the glyphs with their outline, in the colour of its vertices.
+        if shadow {
+            let softness = (effects.shadow_softness * texels).max(0.0);
+            return [color(effects.shadow_color), [0.0; 4], [outline, 0.0, range, softness], [1.0, 0.0, 0.0, 0.0]];
+        }
+
+        return [color(effects.outline_color), color(effects.glow_color), [outline, glow, range, 0.0], [0.0; 4]];
+
+    }
+
+    /**
+    Makes sure the glyphs and icons are cached and builds a quad for each of them, preceded by their underline and strikethrough.
+    Quads are placed on whole pixels, and the layout's y axis, which points down, is flipped to point up like the node's.
+    */
+    fn build_vertices(&mut self, cache: &mut GlyphCache) -> error::Result<()> {
+
+        let distance = self.rendering == TextRendering::DistanceField;
+        if distance {
+            cache.prepare_distance(&self.fonts, &self.layout.glyphs)?;
+        } else {
+            cache.prepare(&self.fonts, &self.layout.glyphs)?;
+        }
+
+        let mut images: Vec<Option<CachedGlyph>> = Vec::new();
+        for run in self.runs.iter() {
+            let icon = run.icon.as_ref().and_then(|name| self.icons.iter().position(|icon| &icon.0 == name));
+            images.push(match icon {
+                Some(i) => Some(cache.prepare_image(self.icon_id, i as u32, &self.icons[i].1)?),
+                None => None,
+            });
+        }
+        let solid = if self.runs.iter().any(|run| run.style.underline || run.style.strikethrough) {
+            Some(cache.prepare_solid()?)
+        } else {
+            None
+        };
+
+        let height = self.layout.size.y;
+        let glyphs = &self.layout.glyphs;
+        let vertices = &mut self.vertices;
+
+        // The glyph to the right of each glyph on its line, which is not the next one in right to left text.
+        let mut right: Vec<Option<usize>> = vec![None; glyphs.len()];
+        for line in self.layout.lines.iter() {
+            let mut order: Vec<usize> = (line.start..line.end).collect();
+            order.sort_by(|&a, &b| glyphs[a].position.x.partial_cmp(&glyphs[b].position.x).unwrap_or(::std::cmp::Ordering::Equal));
+            for pair in order.windows(2) {
+                right[pair[0]] = Some(pair[1]);
+            }
+        }
+
+        vertices.clear();
+        self.glyph_vertex_ends.clear();
+        for (i, glyph) in glyphs.iter().enumerate() {
+
+            let style = &self.runs[glyph.run].style;
+            let color = [style.color.r, style.color.g, style.color.b, style.color.a];
+
+            // Lines run on to the glyph to the right if it is of the same run, so that they are not broken by letter spacing.
+            if let Some(ref uv) = solid {
+                let end = match right[i].map(|j| &glyphs[j]) {
+                    Some(next) if next.run == glyph.run => next.position.x,
+                    _ => glyph.position.x + glyph.advance,
+                };
+                let thickness = (style.size / 14.0).round().max(1.0);
+                let x = glyph.position.x.round();
+                let width = end.round() - x;
+                let baseline = glyph.position.y.round();
+                if style.underline {
+                    push_quad(vertices, Rect::new(x, height.round() - baseline - (style.size * 0.1).round() - thickness, width, thickness), uv, color);
+                }
+                if style.strikethrough {
+                    push_quad(vertices, Rect::new(x, height.round() - baseline + (style.size * 0.3).round() - thickness, width, thickness), uv, color);
+                }
+            }
+
+            if glyph.image {
+                if let Some(cached) = images[glyph.run] {
+                    // Icons keep their own colors, only the alpha of the text applies.
+                    let y = (height - glyph.position.y + glyph.descent).round();
+                    push_quad(vertices, Rect::new(glyph.position.x.round(), y, glyph.advance.round(), (glyph.ascent - glyph.descent).round()), &cached.uv, [1.0, 1.0, 1.0, style.color.a]);
+                }
+            } else if distance {
+                // Distance fields are scaled to the size of the glyph, so they do not line up with pixels like bitmaps do.
+                if let Some(cached) = cache.get_distance(glyph.font, glyph.id) {
+                    let scale = glyph.size / DISTANCE_FIELD_SIZE;
+                    let x = glyph.position.x.round() + cached.offset.x * scale;
+                    let y = (height - glyph.position.y).round() - (cached.offset.y + cached.size.y) * scale;
+                    push_quad(vertices, Rect::new(x, y, cached.size.x * scale, cached.size.y * scale), &cached.uv, color);
+                }
+            } else if let Some(cached) = cache.get(glyph.font, glyph.id, glyph.size) {
+                let x = glyph.position.x.round() + cached.offset.x;
+                let y = (height - glyph.position.y).round() - cached.offset.y - cached.size.y;
+                push_quad(vertices, Rect::new(x, y, cached.size.x, cached.size.y), &cached.uv, color);
+            }
+
+            self.glyph_vertex_ends.push(vertices.len() as u32);
+
+        }
+
+        // The shadow is a copy of the quads behind them, so that it follows the glyphs that are visible.
+        self.shadow_start = None;
+        let shadow = self.effects.shadow_color;
+        if distance && shadow.a > 0.0 {
+            let start = vertices.len();
+            let offset = self.effects.shadow_offset;
+            for i in 0..start {
+                let v = vertices[i];
+                vertices.push(TextVertex { pos: [v.pos[0] + offset.x, v.pos[1] - offset.y], uv: v.uv, color: [shadow.r, shadow.g, shadow.b, shadow.a * v.color[3]] });
+            }
+            self.shadow_start = Some(start as u32);
         }
 
+        self.cache_generation = cache.get_generation();
+        return Ok(());
+
+    }
+
+}
+
+fn push_quad(vertices: &mut Vec<TextVertex>, rect: Rect, uv: &Rect, color: [f32; 4]) {
+
+    for v in UvVertexArray::from_rect_uv(&rect, uv).data.iter() {
+        vertices.push(TextVertex { pos: v.pos, uv: v.uv, color });
     }
 
 }
@@ -158,51 +2433,77 @@ impl<'a> Node2D for Text<'a> {
 impl<'a> SizedNode2D for Text<'a> {
 
     fn get_fixed_size(&self) -> Vector2f {
-        return Vector2f { x: self.texture.dimensions.x as f32, y: self.texture.dimensions.y as f32 };
+        return self.layout.size;
     }
 
 }
 
 impl<'a> core::Drawable for Text<'a> {
 
-    fn load(&mut self, engine: &mut core::FlatEngine) {
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
 
-        self.vertices = UvVertexArray::from_rect(&Rect { x: 0.0, y: 0.0, width: self.get_fixed_size().x, height: self.get_fixed_size().y });
+        self.build_vertices(&mut engine.glyphs)?;
 
-        self.texture_renderer = Some(TextureRenderer::create(&self.texture, &self.vertices.data, include_bytes!("../../shaders/std_texture_v.glsl"), include_bytes!("../../shaders/std_texture_f.glsl"), &mut engine.renderer));
+        self.shader = Some(engine.create_shader(core::PipelineKind::Text, include_bytes!("../../shaders/std_text_v.glsl"), include_bytes!("../../shaders/std_text_f.glsl"))?);
+        self.distance_shader = Some(engine.create_shader(core::PipelineKind::DistanceText, include_bytes!("../../shaders/std_text_v.glsl"), include_bytes!("../../shaders/std_text_sdf_f.glsl"))?);
+        self.buffer = Some(engine.renderer.create_dynamic_vertex_buffer(core::Vertices::Text(&self.vertices))?);
 
+        self.update_text = false;
         self.has_loaded = true;
 
+        return Ok(());
+
     }
 
     fn render(&mut self, engine: &mut core::FlatEngine) {
 
         // Check if all neccessary parts have been initialized.
-        if self.texture_renderer.is_some() {
-
-            if self.update_text {
+        let (shader, distance_shader, buffer) = match (self.shader, self.distance_shader, self.buffer) {
+            (Some(shader), Some(distance_shader), Some(buffer)) => (shader, distance_shader, buffer),
+            _ => panic!("The text object is being drawn before it has been initialized!"),
+        };
 
-                self.texture_renderer.as_mut().unwrap().update_texture(&self.texture, &mut engine.renderer);
+        // Errors are reported by `update`, drawing skips the text.
+        let texture = match self.prepare(engine, buffer) {
+            Ok(texture) => texture,
+            Err(_) => return,
+        };
 
-                // Recreate the vertices.
-                self.vertices = UvVertexArray::from_rect(&self.get_rect());
-                // Submit the new vertices to the buffer.
-                self.texture_renderer.as_mut().unwrap().update_vertices(&self.vertices.data, &mut engine.renderer);
+        let glyphs_end = self.shadow_start.unwrap_or(self.vertices.len() as u32);
+        let end = match self.visible_glyphs {
+            Some(count) if count < self.glyph_vertex_ends.len() => if count == 0 { 0 } else { self.glyph_vertex_ends[count - 1] },
+            _ => glyphs_end,
+        };
 
-                self.update_text = false;
-            }
+        if end == 0 {
+            return;
+        }
 
-            self.texture_renderer.as_mut().unwrap().render(self.node.get_trans(), engine.renderer.camera.view, engine.renderer.camera.projection, engine);
+        let draw = core::DrawCall { shader, vertices: buffer, texture: Some(texture), model: self.node.get_world_trans(), view: engine.camera.view, projection: engine.camera.projection, range: Some((0, end)) };
 
+        if self.rendering == TextRendering::DistanceField {
+            let draw = core::DrawCall { shader: distance_shader, ..draw };
+            if let Some(start) = self.shadow_start {
+                let shadow = core::DrawCall { range: Some((start, start + end)), ..draw };
+                engine.renderer.draw_distance_text(&core::DistanceTextCall { draw: shadow, params: self.get_distance_params(true) });
+            }
+            engine.renderer.draw_distance_text(&core::DistanceTextCall { draw, params: self.get_distance_params(false) });
         } else {
-            // We never want to see this.
-            panic!("The sprite object is being drawn before it has been initialized!");
+            engine.renderer.draw(&draw);
         }
+        engine.renderer.flush();
 
     }
 
     fn destroy(&mut self, engine: &mut core::FlatEngine) {
 
+        if let Some(buffer) = self.buffer.take() {
+            engine.renderer.destroy_vertex_buffer(buffer);
+        }
+        self.shader = None;
+        self.distance_shader = None;
+        self.has_loaded = false;
+
     }
 
 }
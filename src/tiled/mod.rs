@@ -0,0 +1,1198 @@
+use super::*;
+
+use self::node::*;
+use self::render::{Sprite, Texture};
+use self::tilemap::{Tile, TileAnimation, TileFlags, Tilemap, Tileset, MAX_TILESET_PAGE_SIZE};
+use flate2::read::{GzDecoder, ZlibDecoder};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use xml::reader::{EventReader, XmlEvent};
+
+/**
+The bits of a global tile id that hold the flip flags, the rest is the id.
+*/
+const FLIPPED_HORIZONTALLY: u32 = 0x80000000;
+const FLIPPED_VERTICALLY: u32 = 0x40000000;
+const FLIPPED_DIAGONALLY: u32 = 0x20000000;
+const ROTATED_HEXAGONAL: u32 = 0x10000000;
+const FLAGS: u32 = FLIPPED_HORIZONTALLY | FLIPPED_VERTICALLY | FLIPPED_DIAGONALLY | ROTATED_HEXAGONAL;
+
+/**
+Splits a global tile id as stored in a layer into the id and the flip flags.
+*/
+pub fn split_gid(gid: u32) -> (u32, TileFlags) {
+
+    let flags = TileFlags { flip_x: gid & FLIPPED_HORIZONTALLY != 0, flip_y: gid & FLIPPED_VERTICALLY != 0, flip_diagonal: gid & FLIPPED_DIAGONALLY != 0 };
+    return (gid & !FLAGS, flags);
+
+}
+
+/**
+A custom property set in the editor. Colors and files are kept as the strings Tiled writes, objects as their ids.
+*/
+#[derive(Clone, PartialEq, Debug)]
+pub enum PropertyValue {
+
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    /**
+    A property with a custom class, holding its members.
+    */
+    Class(Properties),
+
+}
+
+impl PropertyValue {
+
+    pub fn as_bool(&self) -> Option<bool> {
+        return match *self {
+            PropertyValue::Bool(value) => Some(value),
+            _ => None,
+        };
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        return match *self {
+            PropertyValue::Int(value) => Some(value),
+            _ => None,
+        };
+    }
+
+    /**
+    The value of an int or float property.
+    */
+    pub fn as_float(&self) -> Option<f64> {
+        return match *self {
+            PropertyValue::Int(value) => Some(value as f64),
+            PropertyValue::Float(value) => Some(value),
+            _ => None,
+        };
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        return match *self {
+            PropertyValue::String(ref value) => Some(value),
+            _ => None,
+        };
+    }
+
+}
+
+pub type Properties = HashMap<String, PropertyValue>;
+
+#[derive(Clone, Debug)]
+pub struct TiledImage {
+
+    /**
+    Resolved relative to the file the image was referenced from.
+    */
+    pub source: PathBuf,
+    pub width: u32,
+    pub height: u32,
+
+}
+
+impl TiledImage {
+
+    fn load(&self) -> error::Result<Texture> {
+        return Texture::load_from_path(&self.source.to_string_lossy());
+    }
+
+}
+
+/**
+A tile of a tileset that has more than just its image, such as properties, an animation or collision shapes.
+*/
+#[derive(Clone, Debug)]
+pub struct TiledTile {
+
+    /**
+    The id within the tileset.
+    */
+    pub id: u32,
+    pub kind: String,
+    pub properties: Properties,
+    /**
+    The tile ids within the tileset and how many seconds each is shown.
+    */
+    pub animation: Vec<(u32, f32)>,
+    /**
+    The image of the tile, for tilesets that are a collection of images.
+    */
+    pub image: Option<TiledImage>,
+    /**
+    Collision shapes, relative to the top left of the tile.
+    */
+    pub objects: Vec<TiledObject>,
+
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledTileset {
+
+    /**
+    The global id of the first tile. The tiles that follow have consecutive ids.
+    */
+    pub first_gid: u32,
+    pub name: String,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    pub tile_count: u32,
+    pub columns: u32,
+    /**
+    The image the tiles are cut from, None for a collection of images.
+    */
+    pub image: Option<TiledImage>,
+    pub tiles: Vec<TiledTile>,
+    pub properties: Properties,
+
+}
+
+impl TiledTileset {
+
+    pub fn get_tile(&self, id: u32) -> Option<&TiledTile> {
+        return self.tiles.iter().find(|t| t.id == id);
+    }
+
+    /**
+    Whether a global tile id (without flags) belongs to the tileset.
+    */
+    pub fn contains(&self, gid: u32) -> bool {
+
+        if gid < self.first_gid {
+            return false;
+        }
+        return match self.image {
+            Some(_) => gid - self.first_gid < self.tile_count,
+            None => self.get_tile(gid - self.first_gid).is_some(),
+        };
+
+    }
+
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ObjectShape {
+
+    Rectangle,
+    Ellipse,
+    Point,
+    /**
+    Points relative to the position of the object.
+    */
+    Polygon(Vec<Vector2f>),
+    Polyline(Vec<Vector2f>),
+    /**
+    A tile image placed as an object, by its global id with flags.
+    */
+    Tile(u32),
+    Text(String),
+
+}
+
+/**
+An object of an object layer, in the coordinates of Tiled: pixels from the top left of the map, y down.
+Use `TiledMap::get_object_rect` and `TiledMap::get_object_points` for positions in the space of the map node.
+*/
+#[derive(Clone, Debug)]
+pub struct TiledObject {
+
+    pub id: u32,
+    pub name: String,
+    /**
+    The type (or class) given to the object in the editor.
+    */
+    pub kind: String,
+    /**
+    The top left corner, or the bottom left corner for tile objects.
+    */
+    pub pos: Vector2f,
+    pub size: Vector2f,
+    /**
+    Clockwise, in degrees, around `pos`.
+    */
+    pub rotation: f32,
+    pub visible: bool,
+    pub shape: ObjectShape,
+    pub properties: Properties,
+
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledTileLayer {
+
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    /**
+    Global tile ids with flags, row by row from the top. 0 is an empty cell.
+    */
+    pub data: Vec<u32>,
+    pub visible: bool,
+    pub opacity: f32,
+    /**
+    In pixels, y down.
+    */
+    pub offset: Vector2f,
+    pub properties: Properties,
+
+}
+
+impl TiledTileLayer {
+
+    pub fn get_gid(&self, x: u32, y: u32) -> Option<u32> {
+
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        return Some(self.data[(y * self.width + x) as usize]);
+
+    }
+
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledObjectLayer {
+
+    pub name: String,
+    pub visible: bool,
+    pub opacity: f32,
+    pub offset: Vector2f,
+    pub objects: Vec<TiledObject>,
+    pub properties: Properties,
+
+}
+
+#[derive(Clone, Debug)]
+pub struct TiledImageLayer {
+
+    pub name: String,
+    pub visible: bool,
+    pub opacity: f32,
+    pub offset: Vector2f,
+    pub image: Option<TiledImage>,
+    pub properties: Properties,
+
+}
+
+#[derive(Clone, Debug)]
+pub enum TiledLayer {
+
+    Tiles(TiledTileLayer),
+    Objects(TiledObjectLayer),
+    Image(TiledImageLayer),
+
+}
+
+impl TiledLayer {
+
+    pub fn get_name(&self) -> &str {
+        return match *self {
+            TiledLayer::Tiles(ref layer) => &layer.name,
+            TiledLayer::Objects(ref layer) => &layer.name,
+            TiledLayer::Image(ref layer) => &layer.name,
+        };
+    }
+
+}
+
+/**
+A map made with the Tiled editor, loaded from a TMX (XML) or JSON file along with any external tilesets (TSX or JSON).
+Tile layer data can be CSV, base64, or base64 compressed with zlib or gzip. Infinite maps are not supported.
+Group layers are flattened into the layers they contain, with their offsets, opacity and visibility combined.
+*/
+#[derive(Clone, Debug)]
+pub struct TiledMap {
+
+    pub orientation: String,
+    /**
+    In tiles.
+    */
+    pub width: u32,
+    pub height: u32,
+    /**
+    In pixels.
+    */
+    pub tile_width: u32,
+    pub tile_height: u32,
+    pub tilesets: Vec<TiledTileset>,
+    /**
+    In drawing order, bottom first.
+    */
+    pub layers: Vec<TiledLayer>,
+    pub properties: Properties,
+
+}
+
+fn parse_error(message: String) -> Error {
+    return Error::Parse(message);
+}
+
+/**
+Checks the tile size of a tileset, as tiles without a size cannot be cut out of its image.
+*/
+fn tile_size(name: &str, width: u32, height: u32) -> error::Result<(u32, u32)> {
+
+    if width == 0 || height == 0 {
+        return Err(parse_error(format!("The tiles of the tileset {} are {}x{}.", name, width, height)));
+    }
+    return Ok((width, height));
+
+}
+
+fn read_file(path: &Path) -> error::Result<String> {
+
+    let mut source = String::new();
+    File::open(path)?.read_to_string(&mut source)?;
+    return Ok(source);
+
+}
+
+fn is_xml(source: &str) -> bool {
+    return source.trim_start().starts_with('<');
+}
+
+/**
+Reads the cells of an encoded layer: base64 of little endian 32 bit ids, optionally compressed.
+*/
+fn decode_base64(data: &str, compression: &str) -> error::Result<Vec<u32>> {
+
+    let stripped: String = data.chars().filter(|c| !c.is_whitespace()).collect();
+    let bytes = base64::decode(&stripped)?;
+
+    let bytes = match compression {
+        "" => bytes,
+        "zlib" => {
+            let mut decompressed: Vec<u8> = Vec::new();
+            ZlibDecoder::new(&bytes[..]).read_to_end(&mut decompressed).map_err(|e| parse_error(format!("Invalid zlib layer data: {}", e)))?;
+            decompressed
+        },
+        "gzip" => {
+            let mut decompressed: Vec<u8> = Vec::new();
+            GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed).map_err(|e| parse_error(format!("Invalid gzip layer data: {}", e)))?;
+            decompressed
+        },
+        other => return Err(parse_error(format!("Unsupported layer compression {}.", other))),
+    };
+
+    if bytes.len() % 4 != 0 {
+        return Err(parse_error(String::from("The layer data is not a whole number of tiles.")));
+    }
+    return Ok(bytes.chunks(4).map(|b| b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24).collect());
+
+}
+
+fn decode_csv(data: &str) -> error::Result<Vec<u32>> {
+
+    return data.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).map(|s| s.parse::<u32>().map_err(|_| parse_error(format!("Invalid tile id {} in the layer data.", s)))).collect();
+
+}
+
+fn parse_points(points: &str) -> error::Result<Vec<Vector2f>> {
+
+    let mut result: Vec<Vector2f> = Vec::new();
+    for point in points.split_whitespace() {
+        let mut coords = point.split(',').map(|c| c.parse::<f32>());
+        match (coords.next(), coords.next()) {
+            (Some(Ok(x)), Some(Ok(y))) => result.push(Vector2f::new(x, y)),
+            _ => return Err(parse_error(format!("Invalid point {}.", point))),
+        }
+    }
+    return Ok(result);
+
+}
+
+fn parse_property(kind: &str, value: &str) -> error::Result<PropertyValue> {
+
+    return match kind {
+        "bool" => Ok(PropertyValue::Bool(value == "true")),
+        "int" | "object" => value.parse().map(PropertyValue::Int).map_err(|_| parse_error(format!("Invalid int property {}.", value))),
+        "float" => value.parse().map(PropertyValue::Float).map_err(|_| parse_error(format!("Invalid float property {}.", value))),
+        _ => Ok(PropertyValue::String(value.to_string())),
+    };
+
+}
+
+/**
+An XML element read into memory, as TMX files are small enough to not need streaming.
+*/
+struct Element {
+
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    text: String,
+
+}
+
+impl Element {
+
+    fn parse(source: &str) -> error::Result<Element> {
+
+        let mut stack: Vec<Element> = Vec::new();
+        for event in EventReader::from_str(source) {
+            match event? {
+                XmlEvent::StartElement { name, attributes, .. } => {
+                    let attributes = attributes.into_iter().map(|a| (a.name.local_name, a.value)).collect();
+                    stack.push(Element { name: name.local_name, attributes, children: Vec::new(), text: String::new() });
+                },
+                XmlEvent::EndElement { .. } => {
+                    let element = stack.pop().unwrap();
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => return Ok(element),
+                    }
+                },
+                XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text);
+                    }
+                },
+                _ => (),
+            }
+        }
+        return Err(parse_error(String::from("The XML document has no root element.")));
+
+    }
+
+    fn attr(&self, key: &str) -> Option<&str> {
+        return self.attributes.iter().find(|a| a.0 == key).map(|a| a.1.as_str());
+    }
+
+    fn parse_attr<T: FromStr>(&self, key: &str) -> error::Result<Option<T>> {
+
+        return match self.attr(key) {
+            Some(value) => value.parse().map(Some).map_err(|_| parse_error(format!("Invalid value {} for {} of <{}>.", value, key, self.name))),
+            None => Ok(None),
+        };
+
+    }
+
+    fn get_attr<T: FromStr>(&self, key: &str) -> error::Result<T> {
+
+        return match self.parse_attr(key)? {
+            Some(value) => Ok(value),
+            None => Err(parse_error(format!("Missing {} of <{}>.", key, self.name))),
+        };
+
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        return self.children.iter().find(|c| c.name == name);
+    }
+
+    fn properties(&self) -> error::Result<Properties> {
+
+        let mut properties = Properties::new();
+        for property in self.child("properties").map(|p| p.children.as_slice()).unwrap_or(&[]) {
+            let name = property.attr("name").unwrap_or("").to_string();
+            let kind = property.attr("type").unwrap_or("string");
+            let value = match kind {
+                "class" => PropertyValue::Class(property.properties()?),
+                // Multi-line strings are stored as text instead of an attribute.
+                _ => parse_property(kind, property.attr("value").unwrap_or(&property.text))?,
+            };
+            properties.insert(name, value);
+        }
+        return Ok(properties);
+
+    }
+
+    fn image(&self, dir: &Path) -> error::Result<Option<TiledImage>> {
+
+        return match self.child("image") {
+            Some(image) => Ok(Some(TiledImage { source: dir.join(image.attr("source").unwrap_or("")), width: image.parse_attr("width")?.unwrap_or(0), height: image.parse_attr("height")?.unwrap_or(0) })),
+            None => Ok(None),
+        };
+
+    }
+
+}
+
+fn json_f32(value: &Value, key: &str) -> Option<f32> {
+    return value.get(key).and_then(|v| v.as_f64()).map(|v| v as f32);
+}
+
+fn json_u32(value: &Value, key: &str) -> Option<u32> {
+    return value.get(key).and_then(|v| v.as_u64()).map(|v| v as u32);
+}
+
+fn json_str<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    return value.get(key).and_then(|v| v.as_str());
+}
+
+fn json_array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    return value.get(key).and_then(|v| v.as_array()).map(|v| v.as_slice()).unwrap_or(&[]);
+}
+
+fn json_properties(value: &Value) -> error::Result<Properties> {
+
+    let mut properties = Properties::new();
+    for property in json_array(value, "properties") {
+        let name = json_str(property, "name").unwrap_or("").to_string();
+        let kind = json_str(property, "type").unwrap_or("string");
+        let value = match (kind, property.get("value")) {
+            (_, Some(&Value::Bool(value))) => PropertyValue::Bool(value),
+            (_, Some(&Value::String(ref value))) => parse_property(kind, value)?,
+            ("int", Some(value)) | ("object", Some(value)) => PropertyValue::Int(value.as_i64().unwrap_or(0)),
+            (_, Some(&Value::Number(ref value))) => PropertyValue::Float(value.as_f64().unwrap_or(0.0)),
+            ("class", Some(&Value::Object(ref members))) => {
+                let members = members.iter().map(|(k, v)| match *v {
+                    Value::Bool(b) => (k.clone(), PropertyValue::Bool(b)),
+                    Value::Number(ref n) if n.is_i64() => (k.clone(), PropertyValue::Int(n.as_i64().unwrap())),
+                    Value::Number(ref n) => (k.clone(), PropertyValue::Float(n.as_f64().unwrap_or(0.0))),
+                    ref other => (k.clone(), PropertyValue::String(other.as_str().map(|s| s.to_string()).unwrap_or(other.to_string()))),
+                }).collect();
+                PropertyValue::Class(members)
+            },
+            _ => PropertyValue::String(String::new()),
+        };
+        properties.insert(name, value);
+    }
+    return Ok(properties);
+
+}
+
+fn json_image(value: &Value, dir: &Path) -> Option<TiledImage> {
+
+    return json_str(value, "image").map(|image| TiledImage { source: dir.join(image), width: json_u32(value, "imagewidth").unwrap_or(0), height: json_u32(value, "imageheight").unwrap_or(0) });
+
+}
+
+/**
+Offsets, opacity and visibility inherited from group layers.
+*/
+#[derive(Copy, Clone)]
+struct Group {
+
+    offset: Vector2f,
+    opacity: f32,
+    visible: bool,
+
+}
+
+impl Group {
+
+    fn root() -> Group {
+        return Group { offset: Vector2f::new(0.0, 0.0), opacity: 1.0, visible: true };
+    }
+
+    fn child(&self, offset: Vector2f, opacity: f32, visible: bool) -> Group {
+        return Group { offset: self.offset + offset, opacity: self.opacity * opacity, visible: self.visible && visible };
+    }
+
+}
+
+impl TiledMap {
+
+    /**
+    Loads a map, reading it as XML or JSON depending on its contents. Tilesets and images are looked up relative to it.
+    */
+    pub fn load_from_path(path: &str) -> error::Result<TiledMap> {
+
+        let source = read_file(Path::new(path))?;
+        return TiledMap::load_from_str(&source, &Path::new(path).parent().unwrap_or(Path::new("")).to_string_lossy());
+
+    }
+
+    /**
+    Reads a map from XML or JSON. External tilesets and images are looked up relative to `dir`.
+    */
+    pub fn load_from_str(source: &str, dir: &str) -> error::Result<TiledMap> {
+
+        let dir = Path::new(dir);
+        let mut map = if is_xml(source) {
+            TiledMap::from_xml(&Element::parse(source)?, dir)?
+        } else {
+            let value: Value = serde_json::from_str(source)?;
+            TiledMap::from_json(&value, dir)?
+        };
+        map.tilesets.sort_by_key(|t| t.first_gid);
+        return Ok(map);
+
+    }
+
+    fn from_xml(root: &Element, dir: &Path) -> error::Result<TiledMap> {
+
+        if root.name != "map" {
+            return Err(parse_error(format!("Expected a <map> element, found <{}>.", root.name)));
+        }
+        if root.attr("infinite") == Some("1") {
+            return Err(parse_error(String::from("Infinite maps are not supported.")));
+        }
+
+        let mut map = TiledMap {
+            orientation: root.attr("orientation").unwrap_or("orthogonal").to_string(),
+            width: root.get_attr("width")?,
+            height: root.get_attr("height")?,
+            tile_width: root.get_attr("tilewidth")?,
+            tile_height: root.get_attr("tileheight")?,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            properties: root.properties()?,
+        };
+
+        for tileset in root.children.iter().filter(|c| c.name == "tileset") {
+            let first_gid = tileset.get_attr("firstgid")?;
+            let tileset = match tileset.attr("source") {
+                Some(source) => {
+                    let path = dir.join(source);
+                    let external = read_file(&path)?;
+                    TiledMap::tileset_from_str(&external, first_gid, path.parent().unwrap_or(Path::new("")))?
+                },
+                None => TiledMap::tileset_from_xml(tileset, first_gid, dir)?,
+            };
+            map.tilesets.push(tileset);
+        }
+
+        map.layers_from_xml(root, dir, Group::root())?;
+        return Ok(map);
+
+    }
+
+    fn layers_from_xml(&mut self, parent: &Element, dir: &Path, group: Group) -> error::Result<()> {
+
+        for element in parent.children.iter() {
+
+            let name = element.attr("name").unwrap_or("").to_string();
+            let offset = Vector2f::new(element.parse_attr("offsetx")?.unwrap_or(0.0), element.parse_attr("offsety")?.unwrap_or(0.0));
+            let layer_group = group.child(offset, element.parse_attr("opacity")?.unwrap_or(1.0), element.attr("visible") != Some("0"));
+
+            match element.name.as_str() {
+                "layer" => {
+                    let width = element.parse_attr("width")?.unwrap_or(self.width);
+                    let height = element.parse_attr("height")?.unwrap_or(self.height);
+                    let data = match element.child("data") {
+                        Some(data) => match data.attr("encoding") {
+                            Some("csv") => decode_csv(&data.text)?,
+                            Some("base64") => decode_base64(&data.text, data.attr("compression").unwrap_or(""))?,
+                            Some(other) => return Err(parse_error(format!("Unsupported layer encoding {}.", other))),
+                            None => {
+                                let mut gids: Vec<u32> = Vec::new();
+                                for tile in data.children.iter().filter(|c| c.name == "tile") {
+                                    gids.push(tile.parse_attr("gid")?.unwrap_or(0));
+                                }
+                                gids
+                            },
+                        },
+                        None => Vec::new(),
+                    };
+                    self.push_tile_layer(TiledTileLayer { name, width, height, data, visible: layer_group.visible, opacity: layer_group.opacity, offset: layer_group.offset, properties: element.properties()? })?;
+                },
+                "objectgroup" => {
+                    let mut objects: Vec<TiledObject> = Vec::new();
+                    for object in element.children.iter().filter(|c| c.name == "object") {
+                        objects.push(TiledMap::object_from_xml(object)?);
+                    }
+                    self.layers.push(TiledLayer::Objects(TiledObjectLayer { name, visible: layer_group.visible, opacity: layer_group.opacity, offset: layer_group.offset, objects, properties: element.properties()? }));
+                },
+                "imagelayer" => {
+                    self.layers.push(TiledLayer::Image(TiledImageLayer { name, visible: layer_group.visible, opacity: layer_group.opacity, offset: layer_group.offset, image: element.image(dir)?, properties: element.properties()? }));
+                },
+                "group" => self.layers_from_xml(element, dir, layer_group)?,
+                _ => (),
+            }
+
+        }
+        return Ok(());
+
+    }
+
+    fn object_from_xml(element: &Element) -> error::Result<TiledObject> {
+
+        let shape = if element.child("ellipse").is_some() {
+            ObjectShape::Ellipse
+        } else if element.child("point").is_some() {
+            ObjectShape::Point
+        } else if let Some(polygon) = element.child("polygon") {
+            ObjectShape::Polygon(parse_points(polygon.attr("points").unwrap_or(""))?)
+        } else if let Some(polyline) = element.child("polyline") {
+            ObjectShape::Polyline(parse_points(polyline.attr("points").unwrap_or(""))?)
+        } else if let Some(text) = element.child("text") {
+            ObjectShape::Text(text.text.clone())
+        } else if let Some(gid) = element.parse_attr("gid")? {
+            ObjectShape::Tile(gid)
+        } else {
+            ObjectShape::Rectangle
+        };
+
+        return Ok(TiledObject {
+            id: element.parse_attr("id")?.unwrap_or(0),
+            name: element.attr("name").unwrap_or("").to_string(),
+            // Tiled 1.9 renamed the type of objects to class.
+            kind: element.attr("type").or(element.attr("class")).unwrap_or("").to_string(),
+            pos: Vector2f::new(element.parse_attr("x")?.unwrap_or(0.0), element.parse_attr("y")?.unwrap_or(0.0)),
+            size: Vector2f::new(element.parse_attr("width")?.unwrap_or(0.0), element.parse_attr("height")?.unwrap_or(0.0)),
+            rotation: element.parse_attr("rotation")?.unwrap_or(0.0),
+            visible: element.attr("visible") != Some("0"),
+            shape,
+            properties: element.properties()?,
+        });
+
+    }
+
+    fn tileset_from_str(source: &str, first_gid: u32, dir: &Path) -> error::Result<TiledTileset> {
+
+        if is_xml(source) {
+            return TiledMap::tileset_from_xml(&Element::parse(source)?, first_gid, dir);
+        }
+        let value: Value = serde_json::from_str(source)?;
+        return TiledMap::tileset_from_json(&value, first_gid, dir);
+
+    }
+
+    fn tileset_from_xml(element: &Element, first_gid: u32, dir: &Path) -> error::Result<TiledTileset> {
+
+        let mut tiles: Vec<TiledTile> = Vec::new();
+        for tile in element.children.iter().filter(|c| c.name == "tile") {
+
+            let mut animation: Vec<(u32, f32)> = Vec::new();
+            for frame in tile.child("animation").map(|a| a.children.as_slice()).unwrap_or(&[]) {
+                animation.push((frame.get_attr("tileid")?, frame.get_attr::<f32>("duration")? / 1000.0));
+            }
+
+            let mut objects: Vec<TiledObject> = Vec::new();
+            for object in tile.child("objectgroup").map(|g| g.children.as_slice()).unwrap_or(&[]).iter().filter(|c| c.name == "object") {
+                objects.push(TiledMap::object_from_xml(object)?);
+            }
+
+            tiles.push(TiledTile {
+                id: tile.get_attr("id")?,
+                kind: tile.attr("type").or(tile.attr("class")).unwrap_or("").to_string(),
+                properties: tile.properties()?,
+                animation,
+                image: tile.image(dir)?,
+                objects,
+            });
+
+        }
+
+        let name = element.attr("name").unwrap_or("").to_string();
+        let (tile_width, tile_height) = tile_size(&name, element.get_attr("tilewidth")?, element.get_attr("tileheight")?)?;
+        return Ok(TiledTileset {
+            first_gid,
+            name,
+            tile_width,
+            tile_height,
+            spacing: element.parse_attr("spacing")?.unwrap_or(0),
+            margin: element.parse_attr("margin")?.unwrap_or(0),
+            tile_count: element.parse_attr("tilecount")?.unwrap_or(tiles.len() as u32),
+            columns: element.parse_attr("columns")?.unwrap_or(0),
+            image: element.image(dir)?,
+            tiles,
+            properties: element.properties()?,
+        });
+
+    }
+
+    fn from_json(value: &Value, dir: &Path) -> error::Result<TiledMap> {
+
+        if value.get("infinite").and_then(|i| i.as_bool()).unwrap_or(false) {
+            return Err(parse_error(String::from("Infinite maps are not supported.")));
+        }
+
+        let required = |key: &str| json_u32(value, key).ok_or_else(|| parse_error(format!("Missing {} of the map.", key)));
+        let mut map = TiledMap {
+            orientation: json_str(value, "orientation").unwrap_or("orthogonal").to_string(),
+            width: required("width")?,
+            height: required("height")?,
+            tile_width: required("tilewidth")?,
+            tile_height: required("tileheight")?,
+            tilesets: Vec::new(),
+            layers: Vec::new(),
+            properties: json_properties(value)?,
+        };
+
+        for tileset in json_array(value, "tilesets") {
+            let first_gid = json_u32(tileset, "firstgid").ok_or_else(|| parse_error(String::from("Missing firstgid of a tileset.")))?;
+            let tileset = match json_str(tileset, "source") {
+                Some(source) => {
+                    let path = dir.join(source);
+                    let external = read_file(&path)?;
+                    TiledMap::tileset_from_str(&external, first_gid, path.parent().unwrap_or(Path::new("")))?
+                },
+                None => TiledMap::tileset_from_json(tileset, first_gid, dir)?,
+            };
+            map.tilesets.push(tileset);
+        }
+
+        map.layers_from_json(json_array(value, "layers"), dir, Group::root())?;
+        return Ok(map);
+
+    }
+
+    fn layers_from_json(&mut self, layers: &[Value], dir: &Path, group: Group) -> error::Result<()> {
+
+        for layer in layers {
+
+            let name = json_str(layer, "name").unwrap_or("").to_string();
+            let offset = Vector2f::new(json_f32(layer, "offsetx").unwrap_or(0.0), json_f32(layer, "offsety").unwrap_or(0.0));
+            let layer_group = group.child(offset, json_f32(layer, "opacity").unwrap_or(1.0), layer.get("visible").and_then(|v| v.as_bool()).unwrap_or(true));
+
+            match json_str(layer, "type").unwrap_or("") {
+                "tilelayer" => {
+                    if layer.get("chunks").is_some() {
+                        return Err(parse_error(String::from("Infinite maps are not supported.")));
+                    }
+                    let data = match layer.get("data") {
+                        Some(&Value::Array(ref gids)) => gids.iter().map(|g| g.as_u64().unwrap_or(0) as u32).collect(),
+                        Some(&Value::String(ref data)) => decode_base64(data, json_str(layer, "compression").unwrap_or(""))?,
+                        _ => Vec::new(),
+                    };
+                    self.push_tile_layer(TiledTileLayer {
+                        name,
+                        width: json_u32(layer, "width").unwrap_or(self.width),
+                        height: json_u32(layer, "height").unwrap_or(self.height),
+                        data,
+                        visible: layer_group.visible,
+                        opacity: layer_group.opacity,
+                        offset: layer_group.offset,
+                        properties: json_properties(layer)?,
+                    })?;
+                },
+                "objectgroup" => {
+                    let mut objects: Vec<TiledObject> = Vec::new();
+                    for object in json_array(layer, "objects") {
+                        objects.push(TiledMap::object_from_json(object)?);
+                    }
+                    self.layers.push(TiledLayer::Objects(TiledObjectLayer { name, visible: layer_group.visible, opacity: layer_group.opacity, offset: layer_group.offset, objects, properties: json_properties(layer)? }));
+                },
+                "imagelayer" => {
+                    self.layers.push(TiledLayer::Image(TiledImageLayer { name, visible: layer_group.visible, opacity: layer_group.opacity, offset: layer_group.offset, image: json_image(layer, dir), properties: json_properties(layer)? }));
+                },
+                "group" => self.layers_from_json(json_array(layer, "layers"), dir, layer_group)?,
+                _ => (),
+            }
+
+        }
+        return Ok(());
+
+    }
+
+    fn object_from_json(value: &Value) -> error::Result<TiledObject> {
+
+        let points = |key: &str| json_array(value, key).iter().map(|p| Vector2f::new(json_f32(p, "x").unwrap_or(0.0), json_f32(p, "y").unwrap_or(0.0))).collect();
+        let flag = |key: &str| value.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+
+        let shape = if flag("ellipse") {
+            ObjectShape::Ellipse
+        } else if flag("point") {
+            ObjectShape::Point
+        } else if value.get("polygon").is_some() {
+            ObjectShape::Polygon(points("polygon"))
+        } else if value.get("polyline").is_some() {
+            ObjectShape::Polyline(points("polyline"))
+        } else if let Some(text) = value.get("text") {
+            ObjectShape::Text(json_str(text, "text").unwrap_or("").to_string())
+        } else if let Some(gid) = json_u32(value, "gid") {
+            ObjectShape::Tile(gid)
+        } else {
+            ObjectShape::Rectangle
+        };
+
+        return Ok(TiledObject {
+            id: json_u32(value, "id").unwrap_or(0),
+            name: json_str(value, "name").unwrap_or("").to_string(),
+            kind: json_str(value, "type").or(json_str(value, "class")).unwrap_or("").to_string(),
+            pos: Vector2f::new(json_f32(value, "x").unwrap_or(0.0), json_f32(value, "y").unwrap_or(0.0)),
+            size: Vector2f::new(json_f32(value, "width").unwrap_or(0.0), json_f32(value, "height").unwrap_or(0.0)),
+            rotation: json_f32(value, "rotation").unwrap_or(0.0),
+            visible: value.get("visible").and_then(|v| v.as_bool()).unwrap_or(true),
+            shape,
+            properties: json_properties(value)?,
+        });
+
+    }
+
+    fn tileset_from_json(value: &Value, first_gid: u32, dir: &Path) -> error::Result<TiledTileset> {
+
+        let mut tiles: Vec<TiledTile> = Vec::new();
+        for tile in json_array(value, "tiles") {
+
+            let animation = json_array(tile, "animation").iter().map(|f| (json_u32(f, "tileid").unwrap_or(0), json_f32(f, "duration").unwrap_or(0.0) / 1000.0)).collect();
+
+            let mut objects: Vec<TiledObject> = Vec::new();
+            if let Some(group) = tile.get("objectgroup") {
+                for object in json_array(group, "objects") {
+                    objects.push(TiledMap::object_from_json(object)?);
+                }
+            }
+
+            tiles.push(TiledTile {
+                id: json_u32(tile, "id").ok_or_else(|| parse_error(String::from("Missing id of a tile.")))?,
+                kind: json_str(tile, "type").or(json_str(tile, "class")).unwrap_or("").to_string(),
+                properties: json_properties(tile)?,
+                animation,
+                image: json_image(tile, dir),
+                objects,
+            });
+
+        }
+
+        let required = |key: &str| json_u32(value, key).ok_or_else(|| parse_error(format!("Missing {} of a tileset.", key)));
+        let name = json_str(value, "name").unwrap_or("").to_string();
+        let (tile_width, tile_height) = tile_size(&name, required("tilewidth")?, required("tileheight")?)?;
+        return Ok(TiledTileset {
+            first_gid,
+            name,
+            tile_width,
+            tile_height,
+            spacing: json_u32(value, "spacing").unwrap_or(0),
+            margin: json_u32(value, "margin").unwrap_or(0),
+            tile_count: json_u32(value, "tilecount").unwrap_or(tiles.len() as u32),
+            columns: json_u32(value, "columns").unwrap_or(0),
+            image: json_image(value, dir),
+            tiles,
+            properties: json_properties(value)?,
+        });
+
+    }
+
+    fn push_tile_layer(&mut self, layer: TiledTileLayer) -> error::Result<()> {
+
+        if layer.data.len() != (layer.width * layer.height) as usize {
+            return Err(parse_error(format!("The layer {} has {} tiles, but is {}x{}.", layer.name, layer.data.len(), layer.width, layer.height)));
+        }
+        self.layers.push(TiledLayer::Tiles(layer));
+        return Ok(());
+
+    }
+
+    pub fn get_layer(&self, name: &str) -> Option<&TiledLayer> {
+        return self.layers.iter().find(|l| l.get_name() == name);
+    }
+
+    pub fn get_object_layer(&self, name: &str) -> Option<&TiledObjectLayer> {
+        return self.layers.iter().filter_map(|l| match *l { TiledLayer::Objects(ref layer) => Some(layer), _ => None }).find(|l| l.name == name);
+    }
+
+    /**
+    Every object of every object layer, with the layer it is in.
+    */
+    pub fn get_objects(&self) -> Vec<(&TiledObjectLayer, &TiledObject)> {
+
+        let mut objects: Vec<(&TiledObjectLayer, &TiledObject)> = Vec::new();
+        for layer in self.layers.iter() {
+            if let TiledLayer::Objects(ref layer) = *layer {
+                objects.extend(layer.objects.iter().map(|o| (layer, o)));
+            }
+        }
+        return objects;
+
+    }
+
+    /**
+    The tileset a global tile id (with or without flags) belongs to.
+    */
+    pub fn get_tileset(&self, gid: u32) -> Option<&TiledTileset> {
+
+        let (gid, _) = split_gid(gid);
+        return self.tilesets.iter().rev().find(|t| t.contains(gid));
+
+    }
+
+    /**
+    The size of the map in pixels.
+    */
+    pub fn get_pixel_size(&self) -> Vector2f {
+        return Vector2f::new((self.width * self.tile_width) as f32, (self.height * self.tile_height) as f32);
+    }
+
+    /**
+    Converts a point in Tiled's pixel coordinates (y down from the top left) to the local space of the tilemap node, which is y up from the bottom left.
+    */
+    pub fn to_local(&self, point: Vector2f) -> Vector2f {
+        return Vector2f::new(point.x, self.get_pixel_size().y - point.y);
+    }
+
+    /**
+    The bounds of an object in the local space of the tilemap node, ignoring its rotation.
+    */
+    pub fn get_object_rect(&self, object: &TiledObject) -> Rect {
+
+        let top = match object.shape {
+            // Tile objects are positioned by their bottom left corner.
+            ObjectShape::Tile(_) => object.pos.y - object.size.y,
+            _ => object.pos.y,
+        };
+        let bottom_left = self.to_local(Vector2f::new(object.pos.x, top + object.size.y));
+        return Rect::new(bottom_left.x, bottom_left.y, object.size.x, object.size.y);
+
+    }
+
+    /**
+    The outline of an object in the local space of the tilemap node, with its rotation applied, for building collision shapes.
+    Rectangles, ellipses and tiles give the corners of their bounds, points a single point.
+    */
+    pub fn get_object_points(&self, object: &TiledObject) -> Vec<Vector2f> {
+
+        let (w, h) = (object.size.x, object.size.y);
+        let points = match object.shape {
+            ObjectShape::Polygon(ref points) | ObjectShape::Polyline(ref points) => points.clone(),
+            ObjectShape::Point => vec![Vector2f::new(0.0, 0.0)],
+            ObjectShape::Tile(_) => vec![Vector2f::new(0.0, 0.0), Vector2f::new(w, 0.0), Vector2f::new(w, -h), Vector2f::new(0.0, -h)],
+            _ => vec![Vector2f::new(0.0, 0.0), Vector2f::new(w, 0.0), Vector2f::new(w, h), Vector2f::new(0.0, h)],
+        };
+
+        let (sin, cos) = object.rotation.to_radians().sin_cos();
+        return points.iter().map(|p| self.to_local(Vector2f::new(object.pos.x + p.x * cos - p.y * sin, object.pos.y + p.x * sin + p.y * cos))).collect();
+
+    }
+
+    /**
+    Loads the images of all tilesets into one tileset for a `Tilemap`, packed onto atlas pages of at most `MAX_TILESET_PAGE_SIZE` pixels with a pixel of padding around each tile.
+    Returns it with the tile id each global tile id is given.
+    */
+    pub fn create_tileset(&self) -> error::Result<(Tileset, HashMap<u32, u32>)> {
+
+        return self.create_tileset_with_page_size(MAX_TILESET_PAGE_SIZE, 1);
+
+    }
+
+    /**
+    Like `create_tileset`, with a different limit for the size of the pages and padding around the tiles, see `Tileset::from_images`.
+    */
+    pub fn create_tileset_with_page_size(&self, max_page_size: u32, padding: u32) -> error::Result<(Tileset, HashMap<u32, u32>)> {
+
+        // Every tile image and its global id. Tiles are cut out of the tileset images so that each can be padded on its own.
+        let mut gids: Vec<u32> = Vec::new();
+        let mut images: Vec<Texture> = Vec::new();
+        for tileset in self.tilesets.iter() {
+            match tileset.image {
+                Some(ref image) => {
+                    let texture = image.load()?;
+                    let (image_width, image_height) = (texture.dimensions.x as u32, texture.dimensions.y as u32);
+                    let (tile_width, tile_height) = (tileset.tile_width, tileset.tile_height);
+                    let columns = if tileset.columns > 0 { tileset.columns } else { (image_width + tileset.spacing).saturating_sub(tileset.margin * 2) / (tile_width + tileset.spacing) };
+                    for i in 0..tileset.tile_count {
+                        let x = tileset.margin + (i % columns.max(1)) * (tile_width + tileset.spacing);
+                        let y = tileset.margin + (i / columns.max(1)) * (tile_height + tileset.spacing);
+                        if x + tile_width <= image_width && y + tile_height <= image_height {
+                            gids.push(tileset.first_gid + i);
+                            images.push(texture.crop(x, y, tile_width, tile_height));
+                        }
+                    }
+                },
+                None => {
+                    for tile in tileset.tiles.iter() {
+                        if let Some(ref image) = tile.image {
+                            gids.push(tileset.first_gid + tile.id);
+                            images.push(image.load()?);
+                        }
+                    }
+                },
+            }
+        }
+
+        let mut tileset = Tileset::from_images(&images, Vector2u::new(self.tile_width, self.tile_height), padding, max_page_size)?;
+        let ids: HashMap<u32, u32> = gids.iter().enumerate().map(|(id, &gid)| (gid, id as u32)).collect();
+
+        for tiled in self.tilesets.iter() {
+            for tile in tiled.tiles.iter().filter(|t| !t.animation.is_empty()) {
+                let frames = tile.animation.iter().filter_map(|&(id, duration)| ids.get(&(tiled.first_gid + id)).map(|&id| (id, duration))).collect();
+                if let Some(&id) = ids.get(&(tiled.first_gid + tile.id)) {
+                    tileset.set_animation(id, TileAnimation { frames });
+                }
+            }
+        }
+
+        return Ok((tileset, ids));
+
+    }
+
+    /**
+    Describes what `create_tilemap` cannot show like Tiled does: the offsets and opacity of tile layers, and tiles of another size than the map's, which are stretched to it.
+    */
+    pub fn get_tilemap_warnings(&self) -> Vec<String> {
+
+        let mut warnings: Vec<String> = Vec::new();
+
+        for layer in self.layers.iter() {
+            if let TiledLayer::Tiles(ref layer) = *layer {
+                if layer.offset.x != 0.0 || layer.offset.y != 0.0 {
+                    warnings.push(format!("The tile layer {} is offset by ({}, {}), which is ignored.", layer.name, layer.offset.x, layer.offset.y));
+                }
+                if layer.opacity < 1.0 {
+                    warnings.push(format!("The tile layer {} has an opacity of {}, but is drawn opaque.", layer.name, layer.opacity));
+                }
+            }
+        }
+
+        let map_size = (self.tile_width, self.tile_height);
+        for tileset in self.tilesets.iter() {
+            match tileset.image {
+                Some(_) => if (tileset.tile_width, tileset.tile_height) != map_size {
+                    warnings.push(format!("The tiles of {} are {}x{}, but are drawn at the {}x{} of the map.", tileset.name, tileset.tile_width, tileset.tile_height, map_size.0, map_size.1));
+                },
+                None => for tile in tileset.tiles.iter() {
+                    if let Some(ref image) = tile.image {
+                        if (image.width, image.height) != map_size {
+                            warnings.push(format!("The tile {} of {} is {}x{}, but is drawn at the {}x{} of the map.", tile.id, tileset.name, image.width, image.height, map_size.0, map_size.1));
+                        }
+                    }
+                },
+            }
+        }
+
+        return warnings;
+
+    }
+
+    /**
+    Creates a tilemap node with a layer for each tile layer of the map, loading the tileset images.
+    Tiles are drawn at the tile size of the map, and the offsets and opacity of layers are not applied, see `get_tilemap_warnings`.
+    */
+    pub fn create_tilemap(&self) -> error::Result<Tilemap> {
+
+        if self.orientation != "orthogonal" {
+            return Err(parse_error(format!("Only orthogonal maps can be drawn, this map is {}.", self.orientation)));
+        }
+
+        let (tileset, ids) = self.create_tileset()?;
+        let mut tilemap = Tilemap::new(tileset, self.width, self.height, Vector2f::new(self.tile_width as f32, self.tile_height as f32));
+
+        for layer in self.layers.iter() {
+            if let TiledLayer::Tiles(ref layer) = *layer {
+                let index = tilemap.add_layer(&layer.name);
+                tilemap.layers[index].visible = layer.visible;
+                for y in 0..layer.height.min(self.height) {
+                    for x in 0..layer.width.min(self.width) {
+                        let (gid, flags) = split_gid(layer.data[(y * layer.width + x) as usize]);
+                        if let Some(&id) = ids.get(&gid) {
+                            tilemap.set_tile(index, x, y, Some(Tile::with_flags(id, flags)));
+                        }
+                    }
+                }
+            }
+        }
+
+        return Ok(tilemap);
+
+    }
+
+    /**
+    Creates a sprite showing an image layer, positioned in the local space of the tilemap node.
+    */
+    pub fn create_image_sprite(&self, layer: &TiledImageLayer) -> error::Result<Sprite> {
+
+        let image = match layer.image {
+            Some(ref image) => image.load()?,
+            None => return Err(Error::Texture(format!("The image layer {} has no image.", layer.name))),
+        };
+        let height = image.dimensions.y as f32;
+
+        let mut sprite = Sprite::from_texture(Box::new(image));
+        sprite.set_pos(self.to_local(Vector2f::new(layer.offset.x, layer.offset.y + height)));
+        return Ok(sprite);
+
+    }
+
+}
@@ -0,0 +1,698 @@
+use super::*;
+
+use self::atlas::{AtlasRegion, TextureAtlas};
+use self::node::*;
+use self::render::{Texture, UvVertex2f};
+use std::collections::HashMap;
+
+/**
+The largest atlas pages a `Tileset` makes by default, a texture size that all but the oldest GPUs support.
+*/
+pub const MAX_TILESET_PAGE_SIZE: u32 = 4096;
+
+/**
+How a tile is mirrored, using the same flags as Tiled. They are applied diagonally first, then horizontally, then vertically.
+*/
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TileFlags {
+
+    pub flip_x: bool,
+    pub flip_y: bool,
+    /**
+    Swaps the x and y axes of the tile, which combined with a flip rotates it by 90 degrees.
+    */
+    pub flip_diagonal: bool,
+
+}
+
+impl TileFlags {
+
+    pub fn none() -> TileFlags {
+        return TileFlags { flip_x: false, flip_y: false, flip_diagonal: false };
+    }
+
+    /**
+    The flags that rotate a tile clockwise by the given number of quarter turns.
+    */
+    pub fn rotated(quarter_turns: i32) -> TileFlags {
+
+        return match ((quarter_turns % 4) + 4) % 4 {
+            1 => TileFlags { flip_x: true, flip_y: false, flip_diagonal: true },
+            2 => TileFlags { flip_x: true, flip_y: true, flip_diagonal: false },
+            3 => TileFlags { flip_x: false, flip_y: true, flip_diagonal: true },
+            _ => TileFlags::none(),
+        };
+
+    }
+
+    /**
+    The point of the tile image shown at a corner of the tile, both from (0, 0) at the top left to (1, 1) at the bottom right.
+    */
+    fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+
+        let (mut x, mut y) = (x, y);
+        // Undo the flips in reverse order to find where the corner comes from.
+        if self.flip_y {
+            y = 1.0 - y;
+        }
+        if self.flip_x {
+            x = 1.0 - x;
+        }
+        if self.flip_diagonal {
+            let t = x;
+            x = y;
+            y = t;
+        }
+        return (x, y);
+
+    }
+
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Tile {
+
+    /**
+    The index of the tile in the tileset.
+    */
+    pub id: u32,
+    pub flags: TileFlags,
+
+}
+
+impl Tile {
+
+    pub fn new(id: u32) -> Tile {
+        return Tile { id, flags: TileFlags::none() };
+    }
+
+    pub fn with_flags(id: u32, flags: TileFlags) -> Tile {
+        return Tile { id, flags };
+    }
+
+}
+
+/**
+The images of a tile, shown one after another in a loop.
+*/
+#[derive(Clone, Debug)]
+pub struct TileAnimation {
+
+    /**
+    The tile shown and for how many seconds.
+    */
+    pub frames: Vec<(u32, f32)>,
+
+}
+
+impl TileAnimation {
+
+    /**
+    The tile shown at a point in time.
+    */
+    pub fn get_tile_at(&self, time: f32) -> Option<u32> {
+
+        let duration: f32 = self.frames.iter().map(|f| f.1.max(0.0)).sum();
+        if duration <= 0.0 {
+            return self.frames.first().map(|f| f.0);
+        }
+
+        let mut t = time.max(0.0) % duration;
+        for &(tile, frame_duration) in self.frames.iter() {
+            if t < frame_duration {
+                return Some(tile);
+            }
+            t -= frame_duration.max(0.0);
+        }
+        return self.frames.last().map(|f| f.0);
+
+    }
+
+}
+
+/**
+The images of the tiles of a tilemap, on one or more atlas pages. Each chunk draws the tiles of a page with one draw call.
+*/
+pub struct Tileset {
+
+    pub atlas: TextureAtlas,
+    pub tile_size: Vector2u,
+    regions: Vec<AtlasRegion>,
+    /**
+    Animations by the id of the tile they replace.
+    */
+    pub animations: HashMap<u32, TileAnimation>,
+
+}
+
+impl Tileset {
+
+    /**
+    Cuts an image into tiles, numbered left to right and then top to bottom, and packs them with `padding` pixels around each, see `from_images`.
+    `margin` is the space around the tiles at the edge of the image and `spacing` the space between tiles, in pixels.
+    */
+    pub fn from_grid(image: Texture, tile_size: Vector2u, margin: u32, spacing: u32, padding: u32) -> error::Result<Tileset> {
+
+        if tile_size.x == 0 || tile_size.y == 0 {
+            return Err(Error::Texture(format!("Invalid tile size {}x{}.", tile_size.x, tile_size.y)));
+        }
+
+        let (width, height) = (image.dimensions.x as u32, image.dimensions.y as u32);
+        let columns = (width + spacing).saturating_sub(margin * 2) / (tile_size.x + spacing);
+        let rows = (height + spacing).saturating_sub(margin * 2) / (tile_size.y + spacing);
+
+        let mut tiles: Vec<Texture> = Vec::new();
+        for row in 0..rows {
+            for column in 0..columns {
+                let x = margin + column * (tile_size.x + spacing);
+                let y = margin + row * (tile_size.y + spacing);
+                tiles.push(image.crop(x, y, tile_size.x, tile_size.y));
+            }
+        }
+
+        return Tileset::from_images(&tiles, tile_size, padding, MAX_TILESET_PAGE_SIZE);
+
+    }
+
+    /**
+    Packs images as the tiles, in order, onto atlas pages of at most `max_page_size` pixels.
+    Each image is surrounded by `padding` pixels copied from its edges. Tiles drawn at fractions of a pixel or zoomed are filtered, so without padding they bleed into each other.
+    */
+    pub fn from_images(images: &[Texture], tile_size: Vector2u, padding: u32, max_page_size: u32) -> error::Result<Tileset> {
+
+        let padded: Vec<(u32, u32)> = images.iter().map(|i| (i.dimensions.x as u32 + padding * 2, i.dimensions.y as u32 + padding * 2)).collect();
+
+        let max_page_size = max_page_size.min(u16::max_value() as u32).max(1);
+        let largest = padded.iter().map(|p| p.0.max(p.1)).max().unwrap_or(1).max(1);
+        if largest > max_page_size {
+            return Err(Error::Texture(format!("A tile is {} pixels across with its padding, which does not fit into a {}x{} page.", largest, max_page_size, max_page_size)));
+        }
+
+        // Pages are as small as the tiles allow, so that small tilesets do not allocate the largest pages.
+        // The area of the tiles is where to start, and the pages grow until the tiles fit onto one of them or the pages cannot grow any further.
+        let area: u64 = padded.iter().map(|p| p.0 as u64 * p.1 as u64).sum();
+        let mut page_size = largest.next_power_of_two().min(max_page_size);
+        while page_size < max_page_size && (page_size as u64 * page_size as u64) < area {
+            page_size = (page_size * 2).min(max_page_size);
+        }
+
+        loop {
+
+            let mut atlas = TextureAtlas::new(Vector2u::new(page_size, page_size), padding);
+            let mut regions: Vec<AtlasRegion> = Vec::new();
+            for (i, image) in images.iter().enumerate() {
+                regions.push(atlas.add(&i.to_string(), image)?);
+            }
+
+            if atlas.get_page_count() <= 1 || page_size >= max_page_size {
+                return Ok(Tileset { atlas, tile_size, regions, animations: HashMap::new() });
+            }
+            page_size = (page_size * 2).min(max_page_size);
+
+        }
+
+    }
+
+    /**
+    Uses named textures of an atlas as the tiles, in order.
+    */
+    pub fn from_atlas(atlas: TextureAtlas, tiles: &[&str], tile_size: Vector2u) -> error::Result<Tileset> {
+
+        let mut regions: Vec<AtlasRegion> = Vec::new();
+        for name in tiles {
+            let region = match atlas.get(name) {
+                Some(region) => region,
+                None => return Err(Error::Texture(format!("The atlas has no texture named {}.", name))),
+            };
+            regions.push(region);
+        }
+
+        return Ok(Tileset { atlas, tile_size, regions, animations: HashMap::new() });
+
+    }
+
+    pub fn get_tile_count(&self) -> usize {
+        return self.regions.len();
+    }
+
+    pub fn get_region(&self, id: u32) -> Option<AtlasRegion> {
+        return self.regions.get(id as usize).cloned();
+    }
+
+    /**
+    Makes every tile with the id cycle through the frames of the animation.
+    */
+    pub fn set_animation(&mut self, id: u32, animation: TileAnimation) {
+
+        self.animations.insert(id, animation);
+
+    }
+
+}
+
+struct Chunk {
+
+    buffer: Option<core::BufferId>,
+    vertex_count: usize,
+    /**
+    The vertex range of the tiles on each atlas page.
+    */
+    ranges: Vec<(usize, u32, u32)>,
+    dirty: bool,
+    /**
+    Whether the chunk has to be rebuilt when tile animations change frame.
+    */
+    animated: bool,
+
+}
+
+impl Chunk {
+
+    fn new() -> Chunk {
+        return Chunk { buffer: None, vertex_count: 0, ranges: Vec::new(), dirty: true, animated: false };
+    }
+
+}
+
+pub struct TileLayer {
+
+    pub name: String,
+    pub visible: bool,
+    tiles: Vec<Option<Tile>>,
+    chunks: Vec<Chunk>,
+
+}
+
+/**
+A grid of tiles in one or more layers, drawn in order. Row 0 is the top row, as in image and map editors, and the origin of the node is the bottom left corner of the map.
+The tiles are drawn from static vertex buffers built for chunks of the map, and only chunks that overlap the view of the camera are drawn.
+*/
+pub struct Tilemap {
+
+    pub node: NodeObject2D,
+    pub tileset: Tileset,
+    pub layers: Vec<TileLayer>,
+    width: u32,
+    height: u32,
+    tile_size: Vector2f,
+    /**
+    The number of tiles along each side of a chunk.
+    */
+    chunk_size: u32,
+    time: f32,
+    /**
+    The tile shown by each animation, to know when chunks have to be rebuilt.
+    */
+    animation_frames: HashMap<u32, u32>,
+    shader: Option<core::ShaderId>,
+    drawn_chunks: usize,
+
+}
+
+impl Tilemap {
+
+    pub fn new(tileset: Tileset, width: u32, height: u32, tile_size: Vector2f) -> Tilemap {
+
+        return Tilemap {
+            node: NodeObject2D::new(),
+            tileset,
+            layers: Vec::new(),
+            width,
+            height,
+            tile_size,
+            chunk_size: 16,
+            time: 0.0,
+            animation_frames: HashMap::new(),
+            shader: None,
+            drawn_chunks: 0,
+        };
+
+    }
+
+    /**
+    Changes the number of tiles along each side of a chunk. Larger chunks mean fewer draw calls but more tiles drawn outside of the view.
+    */
+    pub fn set_chunk_size(&mut self, chunk_size: u32, engine: &mut core::FlatEngine) {
+
+        self.chunk_size = chunk_size.max(1);
+        for i in 0..self.layers.len() {
+            self.destroy_chunks(i, engine);
+            self.layers[i].chunks = self.create_chunks();
+        }
+
+    }
+
+    fn get_chunk_grid(&self) -> (u32, u32) {
+        return ((self.width + self.chunk_size - 1) / self.chunk_size, (self.height + self.chunk_size - 1) / self.chunk_size);
+    }
+
+    fn create_chunks(&self) -> Vec<Chunk> {
+
+        let (columns, rows) = self.get_chunk_grid();
+        return (0..columns * rows).map(|_| Chunk::new()).collect();
+
+    }
+
+    /**
+    Adds an empty layer on top of the others, returning its index.
+    */
+    pub fn add_layer(&mut self, name: &str) -> usize {
+
+        let chunks = self.create_chunks();
+        self.layers.push(TileLayer { name: name.to_string(), visible: true, tiles: vec![None; (self.width * self.height) as usize], chunks });
+        return self.layers.len() - 1;
+
+    }
+
+    pub fn get_layer_index(&self, name: &str) -> Option<usize> {
+        return self.layers.iter().position(|l| l.name == name);
+    }
+
+    pub fn get_width(&self) -> u32 {
+        return self.width;
+    }
+
+    pub fn get_height(&self) -> u32 {
+        return self.height;
+    }
+
+    pub fn get_tile_size(&self) -> Vector2f {
+        return self.tile_size;
+    }
+
+    /**
+    Sets or clears a tile. Returns false if the layer or position does not exist.
+    */
+    pub fn set_tile(&mut self, layer: usize, x: u32, y: u32, tile: Option<Tile>) -> bool {
+
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+
+        let chunk = self.get_chunk_index(x, y);
+        let width = self.width;
+        match self.layers.get_mut(layer) {
+            Some(layer) => {
+                layer.tiles[(y * width + x) as usize] = tile;
+                layer.chunks[chunk].dirty = true;
+                true
+            },
+            None => false,
+        }
+
+    }
+
+    pub fn get_tile(&self, layer: usize, x: u32, y: u32) -> Option<Tile> {
+
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        return self.layers.get(layer).and_then(|l| l.tiles[(y * self.width + x) as usize]);
+
+    }
+
+    fn get_chunk_index(&self, x: u32, y: u32) -> usize {
+        let (columns, _) = self.get_chunk_grid();
+        return ((y / self.chunk_size) * columns + x / self.chunk_size) as usize;
+    }
+
+    /**
+    The tile at a world position. The result can be outside of the map.
+    */
+    pub fn world_to_tile(&self, pos: Vector2f) -> Vector2i {
+
+        let local = match self.get_world_trans().invert() {
+            Some(inverse) => inverse * Vector4f::new(pos.x, pos.y, 0.0, 1.0),
+            None => return Vector2i::new(-1, -1),
+        };
+        let column = (local.x / self.tile_size.x).floor() as i32;
+        let row_from_bottom = (local.y / self.tile_size.y).floor() as i32;
+        return Vector2i::new(column, self.height as i32 - 1 - row_from_bottom);
+
+    }
+
+    /**
+    The world position of the center of a tile.
+    */
+    pub fn tile_to_world(&self, x: i32, y: i32) -> Vector2f {
+
+        let local = Vector4f::new((x as f32 + 0.5) * self.tile_size.x, (self.height as f32 - y as f32 - 0.5) * self.tile_size.y, 0.0, 1.0);
+        let world = self.get_world_trans() * local;
+        return Vector2f::new(world.x, world.y);
+
+    }
+
+    /**
+    Advances the tile animations.
+    */
+    pub fn update(&mut self, dt: f32) {
+
+        self.time += dt;
+
+        let mut changed = false;
+        for (&id, animation) in self.tileset.animations.iter() {
+            let tile = animation.get_tile_at(self.time).unwrap_or(id);
+            if self.animation_frames.insert(id, tile) != Some(tile) {
+                changed = true;
+            }
+        }
+
+        if changed {
+            for layer in self.layers.iter_mut() {
+                for chunk in layer.chunks.iter_mut().filter(|c| c.animated) {
+                    chunk.dirty = true;
+                }
+            }
+        }
+
+    }
+
+    /**
+    The number of chunks drawn by the last render, for checking the culling.
+    */
+    pub fn get_drawn_chunk_count(&self) -> usize {
+        return self.drawn_chunks;
+    }
+
+    /**
+    Builds the vertices of a chunk grouped by atlas page, returning them, the range of each page and whether the chunk contains animated tiles.
+    */
+    fn build_chunk(&self, layer: usize, chunk: usize) -> (Vec<UvVertex2f>, Vec<(usize, u32, u32)>, bool) {
+
+        let (columns, _) = self.get_chunk_grid();
+        let start_x = (chunk as u32 % columns) * self.chunk_size;
+        let start_y = (chunk as u32 / columns) * self.chunk_size;
+        let tiles = &self.layers[layer].tiles;
+
+        let mut pages: Vec<(usize, Vec<UvVertex2f>)> = Vec::new();
+        let mut animated = false;
+
+        for y in start_y..(start_y + self.chunk_size).min(self.height) {
+            for x in start_x..(start_x + self.chunk_size).min(self.width) {
+
+                let tile = match tiles[(y * self.width + x) as usize] {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+
+                let id = match self.tileset.animations.get(&tile.id) {
+                    Some(animation) => {
+                        animated = true;
+                        animation.get_tile_at(self.time).unwrap_or(tile.id)
+                    },
+                    None => tile.id,
+                };
+                let (page, uv) = match self.tileset.get_region(id) {
+                    Some(region) => (region.page, region.uv),
+                    None => continue,
+                };
+
+                let left = x as f32 * self.tile_size.x;
+                let bottom = (self.height - 1 - y) as f32 * self.tile_size.y;
+                let vertex = |cx: f32, cy: f32| -> UvVertex2f {
+                    let (u, v) = tile.flags.apply(cx, cy);
+                    return UvVertex2f { pos: [left + cx * self.tile_size.x, bottom + (1.0 - cy) * self.tile_size.y], uv: [uv.x + u * uv.width, uv.y + v * uv.height] };
+                };
+
+                let (bottom_left, bottom_right, top_left, top_right) = (vertex(0.0, 1.0), vertex(1.0, 1.0), vertex(0.0, 0.0), vertex(1.0, 0.0));
+                let index = match pages.iter().position(|p| p.0 == page) {
+                    Some(index) => index,
+                    None => {
+                        pages.push((page, Vec::new()));
+                        pages.len() - 1
+                    },
+                };
+                pages[index].1.extend_from_slice(&[bottom_left, bottom_right, top_left, top_right, bottom_right, top_left]);
+
+            }
+        }
+
+        // Tiles of a layer do not overlap, so drawing them page by page does not change the image.
+        let mut vertices: Vec<UvVertex2f> = Vec::new();
+        let mut ranges: Vec<(usize, u32, u32)> = Vec::new();
+        for (page, page_vertices) in pages {
+            let start = vertices.len() as u32;
+            vertices.extend(page_vertices);
+            ranges.push((page, start, vertices.len() as u32));
+        }
+
+        return (vertices, ranges, animated);
+
+    }
+
+    /**
+    The range of chunks (min column, min row, max column, max row, inclusive) overlapping the view of the camera.
+    */
+    fn get_visible_chunks(&self, engine: &core::FlatEngine) -> Option<(u32, u32, u32, u32)> {
+
+        let (columns, rows) = self.get_chunk_grid();
+        if columns == 0 || rows == 0 {
+            return None;
+        }
+
+        // The view in the local space of the map.
+        let view = engine.camera.get_visible_rect();
+        let local = OrientedRect::from_rect(&view, &self.get_world_trans().invert()?).get_bounds();
+
+        let chunk_width = self.chunk_size as f32 * self.tile_size.x;
+        let chunk_height = self.chunk_size as f32 * self.tile_size.y;
+        let map_height = self.height as f32 * self.tile_size.y;
+
+        // Chunk rows count from the top of the map. A chunk that only touches the edge of the view is not visible.
+        let min_x = (local.x / chunk_width).floor();
+        let max_x = ((local.x + local.width) / chunk_width).ceil() - 1.0;
+        let min_y = ((map_height - local.y - local.height) / chunk_height).floor();
+        let max_y = ((map_height - local.y) / chunk_height).ceil() - 1.0;
+
+        if max_x < 0.0 || max_y < 0.0 || min_x >= columns as f32 || min_y >= rows as f32 {
+            return None;
+        }
+        return Some((min_x.max(0.0) as u32, min_y.max(0.0) as u32, (max_x as u32).min(columns - 1), (max_y as u32).min(rows - 1)));
+
+    }
+
+    fn destroy_chunks(&mut self, layer: usize, engine: &mut core::FlatEngine) {
+
+        for chunk in self.layers[layer].chunks.iter_mut() {
+            if let Some(buffer) = chunk.buffer.take() {
+                engine.renderer.destroy_vertex_buffer(buffer);
+            }
+            chunk.dirty = true;
+        }
+
+    }
+
+}
+
+impl Node2D for Tilemap {
+
+    fn get_node_obj_mut(&mut self) -> &mut NodeObject2D {
+        return &mut self.node;
+    }
+
+    fn get_node_obj(&self) -> &NodeObject2D {
+        return &self.node;
+    }
+
+}
+
+impl SizedNode2D for Tilemap {
+
+    fn get_fixed_size(&self) -> Vector2f {
+        return Vector2f::new(self.width as f32 * self.tile_size.x, self.height as f32 * self.tile_size.y);
+    }
+
+}
+
+impl core::Drawable for Tilemap {
+
+    fn load(&mut self, engine: &mut core::FlatEngine) -> error::Result<()> {
+
+        self.tileset.atlas.upload(engine)?;
+        self.shader = Some(engine.create_shader(core::PipelineKind::Texture, include_bytes!("../../shaders/std_texture_v.glsl"), include_bytes!("../../shaders/std_texture_f.glsl"))?);
+        self.update(0.0);
+
+        return Ok(());
+
+    }
+
+    fn render(&mut self, engine: &mut core::FlatEngine) {
+
+        let shader = match self.shader {
+            Some(shader) => shader,
+            None => panic!("The tilemap is being drawn before it has been initialized!"),
+        };
+
+        self.drawn_chunks = 0;
+        let (min_x, min_y, max_x, max_y) = match self.get_visible_chunks(engine) {
+            Some(range) => range,
+            None => return,
+        };
+        let (columns, _) = self.get_chunk_grid();
+        let model = self.get_world_trans();
+
+        for layer in 0..self.layers.len() {
+
+            if !self.layers[layer].visible {
+                continue;
+            }
+
+            for row in min_y..max_y + 1 {
+                for column in min_x..max_x + 1 {
+
+                    let index = (row * columns + column) as usize;
+
+                    // Chunks are only rebuilt once they are seen.
+                    if self.layers[layer].chunks[index].dirty {
+                        let (vertices, ranges, animated) = self.build_chunk(layer, index);
+                        let chunk = &mut self.layers[layer].chunks[index];
+                        match (chunk.buffer, vertices.is_empty()) {
+                            (Some(buffer), true) => {
+                                engine.renderer.destroy_vertex_buffer(buffer);
+                                chunk.buffer = None;
+                            },
+                            // The chunk stays dirty and is not drawn if its vertices cannot be written.
+                            (Some(buffer), false) => if engine.renderer.update_vertex_buffer(buffer, core::Vertices::Uv2f(&vertices)).is_err() {
+                                continue;
+                            },
+                            (None, false) => chunk.buffer = Some(engine.renderer.create_vertex_buffer(core::Vertices::Uv2f(&vertices))),
+                            (None, true) => (),
+                        }
+                        chunk.vertex_count = vertices.len();
+                        chunk.ranges = ranges;
+                        chunk.animated = animated;
+                        chunk.dirty = false;
+                    }
+
+                    let chunk = &self.layers[layer].chunks[index];
+                    if let Some(buffer) = chunk.buffer {
+                        for &(page, start, end) in chunk.ranges.iter() {
+                            let texture = match self.tileset.atlas.get_texture(page) {
+                                Some(texture) => texture,
+                                None => panic!("The tilemap is being drawn before it has been initialized!"),
+                            };
+                            engine.renderer.draw(&core::DrawCall { shader, vertices: buffer, texture: Some(texture), model, view: engine.camera.view, projection: engine.camera.projection, range: Some((start, end)) });
+                        }
+                        self.drawn_chunks += 1;
+                    }
+
+                }
+            }
+
+        }
+
+        engine.renderer.flush();
+
+    }
+
+    fn destroy(&mut self, engine: &mut core::FlatEngine) {
+
+        for layer in 0..self.layers.len() {
+            self.destroy_chunks(layer, engine);
+        }
+        self.tileset.atlas.destroy(engine);
+        self.shader = None;
+
+    }
+
+}
@@ -48,7 +48,7 @@ impl ToVec2f for Vector3f {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Color {
 
     pub r: f32,
@@ -93,7 +93,7 @@ impl Color {
 
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Rect {
 
     pub x: f32,
@@ -131,6 +131,82 @@ impl Rect {
 
 }
 
+/**
+A rect that has been rotated, skewed or scaled, stored as its four corners in the order top left, top right, bottom right, bottom left (of the untransformed rect).
+*/
+#[derive(Copy, Clone)]
+pub struct OrientedRect {
+
+    pub corners: [Vector2f; 4],
+
+}
+
+impl OrientedRect {
+
+    pub fn from_rect(rect: &Rect, trans: &Matrix4f) -> OrientedRect {
+
+        let points = [
+            Vector2f::new(rect.x, rect.y),
+            Vector2f::new(rect.x + rect.width, rect.y),
+            Vector2f::new(rect.x + rect.width, rect.y + rect.height),
+            Vector2f::new(rect.x, rect.y + rect.height),
+        ];
+
+        let mut corners = [Vector2f::new(0.0, 0.0); 4];
+        for i in 0..4 {
+            let p = trans * Vector4f::new(points[i].x, points[i].y, 0.0, 1.0);
+            corners[i] = Vector2f::new(p.x, p.y);
+        }
+
+        return OrientedRect { corners };
+
+    }
+
+    pub fn get_center(&self) -> Vector2f {
+        return (self.corners[0] + self.corners[2]) / 2.0;
+    }
+
+    /**
+    The smallest axis aligned rect containing all of the corners.
+    */
+    pub fn get_bounds(&self) -> Rect {
+
+        let mut min = self.corners[0];
+        let mut max = self.corners[0];
+        for c in self.corners.iter() {
+            min.x = min.x.min(c.x);
+            min.y = min.y.min(c.y);
+            max.x = max.x.max(c.x);
+            max.y = max.y.max(c.y);
+        }
+        return Rect { x: min.x, y: min.y, width: max.x - min.x, height: max.y - min.y };
+
+    }
+
+    /**
+    Returns true if the point lies inside the (convex) quad, regardless of the winding of the corners.
+    */
+    pub fn contains(&self, point: Vector2f) -> bool {
+
+        let mut sign = 0.0;
+        for i in 0..4 {
+            let a = self.corners[i];
+            let b = self.corners[(i + 1) % 4];
+            let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+            if cross != 0.0 {
+                if sign == 0.0 {
+                    sign = cross.signum();
+                } else if cross.signum() != sign {
+                    return false;
+                }
+            }
+        }
+        return true;
+
+    }
+
+}
+
 pub trait Translatable {
 
     fn set_translation(&mut self, translation: Vector3f);
@@ -0,0 +1,132 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::animation::*;
+use flat_engine::atlas::{AtlasRegion, TextureAtlas};
+use flat_engine::core::FlatEngine;
+use flat_engine::node::*;
+use flat_engine::render::Texture;
+
+fn region(i: usize) -> AtlasRegion {
+    return AtlasRegion { page: 0, rect: Rect::new(i as f32 * 8.0, 0.0, 8.0, 8.0), uv: Rect::new(i as f32 * 0.25, 0.0, 0.25, 1.0) };
+}
+
+fn clip(name: &str, mode: PlayMode) -> AnimationClip {
+    return AnimationClip::from_regions(name, &[region(0), region(1), region(2)], 0.1, mode);
+}
+
+fn frames(animator: &mut Animator, steps: usize) -> Vec<usize> {
+    return (0..steps).map(|_| {
+        animator.update(0.1);
+        animator.get_frame_index()
+    }).collect();
+}
+
+#[test]
+fn play_modes() {
+
+    let mut animator = Animator::new();
+    animator.add_clip(clip("loop", PlayMode::Loop));
+    animator.add_clip(clip("ping_pong", PlayMode::PingPong));
+    animator.add_clip(clip("once", PlayMode::Once));
+
+    // Slightly more than a frame per step, so float rounding never holds a frame back.
+    assert!(animator.play("loop"));
+    animator.update(0.001);
+    assert_eq!(frames(&mut animator, 5), vec![1, 2, 0, 1, 2]);
+
+    assert!(animator.play("ping_pong"));
+    animator.update(0.001);
+    assert_eq!(frames(&mut animator, 6), vec![1, 2, 1, 0, 1, 2]);
+
+    assert!(animator.play("once"));
+    animator.update(0.001);
+    assert_eq!(frames(&mut animator, 2), vec![1, 2]);
+    assert_eq!(animator.update(0.1), vec![AnimationEvent::Finished { clip: "once".to_string() }]);
+    assert!(animator.is_finished() && !animator.is_playing());
+    assert_eq!(animator.get_frame_index(), 2);
+
+    // Stopping a finished clip rewinds it, so it can be resumed.
+    animator.stop();
+    assert!(!animator.is_finished() && animator.get_frame_index() == 0);
+    animator.resume();
+    assert!(animator.is_playing());
+    assert_eq!(frames(&mut animator, 2), vec![1, 2]);
+
+    assert!(!animator.play("missing"));
+
+}
+
+#[test]
+fn speed_and_events() {
+
+    let mut walk = clip("walk", PlayMode::Loop);
+    walk.set_event(0, "step");
+    walk.set_event(2, "step");
+
+    let mut animator = Animator::new();
+    animator.add_clip(walk);
+    animator.play("walk");
+
+    let step = |frame| AnimationEvent::Frame { clip: "walk".to_string(), frame, event: "step".to_string() };
+
+    // The first frame is entered when the clip starts.
+    assert_eq!(animator.update(0.0), vec![step(0)]);
+
+    // At double speed, 0.125 seconds passes frames 1 and 2.
+    animator.speed = 2.0;
+    assert_eq!(animator.update(0.125), vec![step(2)]);
+    assert_eq!(animator.get_frame_index(), 2);
+
+    // Paused animators do not advance.
+    animator.pause();
+    assert!(animator.update(1.0).is_empty());
+    assert_eq!(animator.get_frame_index(), 2);
+
+    // Playing the clip that is already playing does not restart it.
+    animator.resume();
+    animator.play("walk");
+    assert_eq!(animator.get_frame_index(), 2);
+    animator.restart("walk");
+    assert_eq!(animator.get_frame_index(), 0);
+
+}
+
+#[test]
+fn animated_sprite() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(32, 32));
+
+    let mut atlas = TextureAtlas::new(Vector2u::new(32, 32), 0);
+    atlas.add("small", &Texture::from_data(&[255; 4 * 4 * 4], 4, 4)).unwrap();
+    atlas.add("large", &Texture::from_data(&[255; 8 * 8 * 4], 8, 8)).unwrap();
+    atlas.upload(&mut engine).unwrap();
+
+    let mut sprite = AnimatedSprite::new(&atlas).unwrap();
+    sprite.add_clip(AnimationClip::from_atlas("grow", &atlas, &["small", "large"], 0.5, PlayMode::Once).unwrap()).unwrap();
+    sprite.play("grow");
+    engine.load(&mut sprite).unwrap();
+
+    let texture = sprite.sprite.texture_renderer.as_ref().unwrap().get_texture();
+    assert_eq!(Some(texture), atlas.get_texture(0));
+    assert_eq!(sprite.sprite.uv_rect, atlas.get("small").unwrap().uv);
+    assert_eq!(sprite.get_size(), Vector2f::new(4.0, 4.0));
+
+    // Changing frames keeps drawing the page texture, with the uv rect and size of the new region.
+    sprite.update(0.5);
+    engine.render(&mut sprite);
+    assert_eq!(sprite.sprite.texture_renderer.as_ref().unwrap().get_texture(), texture);
+    assert_eq!(sprite.sprite.uv_rect, atlas.get("large").unwrap().uv);
+    assert_eq!(sprite.get_size(), Vector2f::new(8.0, 8.0));
+
+    assert!(AnimationClip::from_atlas("missing", &atlas, &["none"], 0.5, PlayMode::Once).is_err());
+
+    // Clips with frames on pages the sprite does not have are rejected when they are added.
+    let mut other = TextureAtlas::new(Vector2u::new(8, 8), 0);
+    other.add("a", &Texture::from_data(&[255; 8 * 8 * 4], 8, 8)).unwrap();
+    other.add("b", &Texture::from_data(&[255; 8 * 8 * 4], 8, 8)).unwrap();
+    let clip = AnimationClip::from_atlas("other", &other, &["a", "b"], 0.5, PlayMode::Loop).unwrap();
+    assert!(sprite.add_clip(clip).is_err());
+    assert!(!sprite.play("other"));
+
+}
@@ -0,0 +1,72 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::core::FlatEngine;
+use flat_engine::app::*;
+
+struct Counter {
+
+    fixed_updates: u32,
+    updates: u32,
+    last_alpha: f32,
+
+}
+
+impl App for Counter {
+
+    fn fixed_update(&mut self, _dt: f32, _engine: &mut FlatEngine) {
+        self.fixed_updates += 1;
+    }
+
+    fn update(&mut self, _dt: f32, _engine: &mut FlatEngine) {
+        self.updates += 1;
+    }
+
+    fn render(&mut self, alpha: f32, engine: &mut FlatEngine) {
+        self.last_alpha = alpha;
+        engine.clear(Color::black());
+    }
+
+}
+
+#[test]
+fn fixed_steps_accumulate() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(8, 8));
+    let mut app = Counter { fixed_updates: 0, updates: 0, last_alpha: 0.0 };
+    let mut runner = Runner::new(RunnerSettings::with_fixed_rate(10.0));
+
+    // 0.25 of a step, not enough for an update yet.
+    runner.step(&mut engine, &mut app, 0.025);
+    assert_eq!(app.fixed_updates, 0);
+    assert!((app.last_alpha - 0.25).abs() < 0.001);
+
+    // Brings the total to 2.75 steps.
+    runner.step(&mut engine, &mut app, 0.25);
+    assert_eq!(app.fixed_updates, 2);
+    assert_eq!(app.updates, 2);
+    assert!((app.last_alpha - 0.75).abs() < 0.001);
+
+}
+
+#[test]
+fn catch_up_is_capped() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(8, 8));
+    let mut app = Counter { fixed_updates: 0, updates: 0, last_alpha: 0.0 };
+    let mut settings = RunnerSettings::with_fixed_rate(100.0);
+    settings.max_fixed_updates = 5;
+    settings.max_frame_time = 0.1;
+    let mut runner = Runner::new(settings);
+
+    // A one second stall: only 0.1 seconds are simulated, in 5 steps, the rest is dropped.
+    runner.step(&mut engine, &mut app, 1.0);
+    assert_eq!(app.fixed_updates, 5);
+    assert!(app.last_alpha < 1.0);
+
+    let stats = runner.get_stats();
+    assert!((stats.dropped_time - 0.95).abs() < 0.001);
+    assert_eq!(stats.frames, 1);
+    assert!((stats.fps - 1.0).abs() < 0.001);
+
+}
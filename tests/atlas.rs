@@ -0,0 +1,71 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::atlas::{RectPacker, TextureAtlas};
+use flat_engine::render::Texture;
+
+fn solid(color: [u8; 4], width: u16, height: u16) -> Texture {
+
+    let mut data: Vec<u8> = Vec::new();
+    for _ in 0..width as usize * height as usize {
+        data.extend_from_slice(&color);
+    }
+    return Texture::from_data(&data, width, height);
+
+}
+
+#[test]
+fn packed_rects_do_not_overlap() {
+
+    let mut packer = RectPacker::new(128, 128);
+    let mut rects: Vec<Rect> = Vec::new();
+
+    // Pseudo random sizes, packed until the packer is full.
+    let mut seed: u32 = 7;
+    for _ in 0..200 {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        let width = 4 + (seed >> 16) % 20;
+        let height = 4 + (seed >> 8) % 20;
+        if let Some((x, y)) = packer.pack(width, height) {
+            let rect = Rect::new(x as f32, y as f32, width as f32, height as f32);
+            assert!(rect.x + rect.width <= 128.0 && rect.y + rect.height <= 128.0);
+            assert!(rects.iter().all(|r| !r.intersects(rect)));
+            rects.push(rect);
+        }
+    }
+
+    assert!(packer.get_occupancy() > 0.6, "occupancy {}", packer.get_occupancy());
+
+    packer.clear();
+    assert_eq!(packer.pack(128, 128), Some((0, 0)));
+    assert_eq!(packer.pack(1, 1), None);
+
+}
+
+#[test]
+fn atlas_pages_and_regions() {
+
+    let mut atlas = TextureAtlas::new(Vector2u::new(32, 32), 1);
+
+    let red = atlas.add("red", &solid([255, 0, 0, 255], 14, 14)).unwrap();
+    assert_eq!(red.page, 0);
+    assert_eq!(red.rect, Rect::new(1.0, 1.0, 14.0, 14.0));
+    assert_eq!(red.uv, Rect::new(1.0 / 32.0, 1.0 / 32.0, 14.0 / 32.0, 14.0 / 32.0));
+
+    // The padding is filled with the edge of the texture.
+    let page = atlas.get_page(0).unwrap();
+    assert_eq!(&page.data[0..4], &[255, 0, 0, 255]);
+
+    // Four 14x14 textures (16x16 with padding) fill a page, the fifth starts a new one.
+    for name in ["a", "b", "c", "d"].iter() {
+        atlas.add(name, &solid([0, 0, 255, 255], 14, 14)).unwrap();
+    }
+    assert_eq!(atlas.get_page_count(), 2);
+    assert_eq!(atlas.get("d").unwrap().page, 1);
+    assert_eq!(atlas.get("red"), Some(red));
+
+    assert!(atlas.add("large", &solid([0, 0, 0, 255], 31, 8)).is_err());
+    assert!(atlas.get("missing").is_none());
+    assert!(atlas.create_sprite("red").is_err());
+
+}
@@ -0,0 +1,123 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::camera::Camera2D;
+use flat_engine::core::ScalingMode;
+
+fn close(a: Vector2f, b: Vector2f) -> bool {
+    return (a.x - b.x).abs() < 0.01 && (a.y - b.y).abs() < 0.01;
+}
+
+#[test]
+fn screen_world_round_trip() {
+
+    let mut camera = Camera2D::new(Vector2f::new(200.0, 100.0));
+    camera.set_pos(Vector2f::new(50.0, 20.0));
+    camera.set_zoom(2.0);
+
+    // The anchor (the center of the screen) shows the camera position.
+    assert!(close(camera.screen_to_world(Vector2f::new(100.0, 50.0)), Vector2f::new(50.0, 20.0)));
+    // Window y points down, world y points up, and the zoom halves the distances.
+    assert!(close(camera.screen_to_world(Vector2f::new(200.0, 0.0)), Vector2f::new(100.0, 45.0)));
+
+    camera.set_rotation(0.7);
+    let world = Vector2f::new(63.0, -4.0);
+    assert!(close(camera.screen_to_world(camera.world_to_screen(world)), world));
+
+}
+
+#[test]
+fn visible_rect() {
+
+    let mut camera = Camera2D::new(Vector2f::new(200.0, 100.0));
+    camera.set_pos(Vector2f::new(50.0, 20.0));
+    camera.set_zoom(2.0);
+
+    let rect = camera.get_visible_rect();
+    assert!(close(rect.get_pos(), Vector2f::new(0.0, -5.0)) && close(rect.get_size(), Vector2f::new(100.0, 50.0)));
+
+    // A quarter turn swaps the width and height.
+    camera.set_rotation(::std::f32::consts::PI / 2.0);
+    let rect = camera.get_visible_rect();
+    assert!(close(rect.get_size(), Vector2f::new(50.0, 100.0)));
+
+}
+
+#[test]
+fn follow_with_deadzone_and_bounds() {
+
+    let mut camera = Camera2D::new(Vector2f::new(100.0, 100.0));
+    camera.deadzone = Vector2f::new(10.0, 10.0);
+
+    camera.follow(Vector2f::new(5.0, -8.0), 0.016);
+    assert!(close(camera.get_pos(), Vector2f::new(0.0, 0.0)));
+
+    camera.follow(Vector2f::new(30.0, 0.0), 0.016);
+    assert!(close(camera.get_pos(), Vector2f::new(20.0, 0.0)));
+
+    camera.bounds = Some(Rect::new(0.0, 0.0, 500.0, 500.0));
+    camera.follow(Vector2f::new(30.0, 0.0), 0.016);
+    assert!(close(camera.get_pos(), Vector2f::new(50.0, 50.0)));
+
+}
+
+#[test]
+fn shake_fades_out() {
+
+    let mut camera = Camera2D::new(Vector2f::new(100.0, 100.0));
+    let still = camera.get_view();
+
+    camera.shake(5.0, 0.5);
+    camera.update(0.1);
+    assert!(camera.get_view() != still);
+
+    camera.update(0.5);
+    assert!(camera.get_view() == still);
+
+}
+
+#[test]
+fn shaking_at_a_bound_does_not_move_the_camera() {
+
+    let mut camera = Camera2D::new(Vector2f::new(100.0, 100.0));
+    camera.bounds = Some(Rect::new(0.0, 0.0, 500.0, 500.0));
+    camera.set_pos(Vector2f::new(50.0, 50.0));
+
+    // The shake moves the view past the bounds, but only while it lasts.
+    camera.shake(20.0, 0.5);
+    let mut outside = false;
+    for _ in 0..40 {
+        camera.update(0.016);
+        let visible = camera.get_visible_rect();
+        outside |= visible.x < 0.0 || visible.y < 0.0;
+    }
+    assert!(outside);
+
+    camera.update(0.5);
+    assert_eq!(camera.get_pos(), Vector2f::new(50.0, 50.0));
+    let visible = camera.get_visible_rect();
+    assert!(close(Vector2f::new(visible.x, visible.y), Vector2f::new(0.0, 0.0)));
+
+}
+
+#[test]
+fn scaling_modes() {
+
+    let window = Vector2f::new(500.0, 300.0);
+    let design = Vector2f::new(160.0, 90.0);
+
+    let letterbox = ScalingMode::Letterbox(design).compute(window);
+    assert_eq!(letterbox.viewport, Rect::new(0.0, 9.0, 500.0, 281.25));
+
+    let pixel_perfect = ScalingMode::PixelPerfect(design).compute(window);
+    assert_eq!(pixel_perfect.viewport, Rect::new(10.0, 15.0, 480.0, 270.0));
+    assert!(close(pixel_perfect.view_size, design));
+
+    let expand = ScalingMode::Expand(design).compute(window);
+    assert_eq!(expand.viewport, Rect::new(0.0, 0.0, 500.0, 300.0));
+    assert!(close(expand.view_size, Vector2f::new(160.0, 96.0)));
+
+    let stretch = ScalingMode::Stretch(design).compute(window);
+    assert!(close(stretch.view_size, design));
+
+}
@@ -0,0 +1,166 @@
+// Helpers shared by the integration tests. Each test crate uses some of them.
+#![allow(dead_code)]
+
+fn push_u16(data: &mut Vec<u8>, value: u16) {
+    data.push((value >> 8) as u8);
+    data.push(value as u8);
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    push_u16(data, (value >> 16) as u16);
+    push_u16(data, value as u16);
+}
+
+/**
+Builds a minimal TrueType font in which every character of `chars` is a solid box and whitespace is blank.
+The font has 1000 units per em with an ascent of 800 and a descent of 200, so at a size of 10 pixels each glyph advances `advance / 100` pixels and its box is 7 pixels tall.
+Text tests use it so they do not depend on the fonts installed on the machine.
+*/
+pub fn box_font(chars: &str, advance: u16) -> Vec<u8> {
+
+    return kerned_box_font(chars, advance, &[]);
+
+}
+
+/**
+Builds a font like `box_font` with a kerning table, in which each pair of characters moves the second character by the given number of font units.
+*/
+pub fn kerned_box_font(chars: &str, advance: u16, kerning: &[(char, char, i16)]) -> Vec<u8> {
+
+    let mut chars: Vec<char> = chars.chars().collect();
+    chars.sort();
+    chars.dedup();
+    let glyph_count = chars.len() + 1;
+    let inset: i16 = (advance / 6) as i16;
+
+    // Glyph 0 is the empty .notdef glyph.
+    let mut glyf: Vec<u8> = Vec::new();
+    let mut loca: Vec<u8> = Vec::new();
+    let mut hmtx: Vec<u8> = Vec::new();
+    push_u16(&mut hmtx, advance);
+    push_u16(&mut hmtx, 0);
+    push_u32(&mut loca, 0);
+    push_u32(&mut loca, 0);
+    for c in chars.iter() {
+        push_u16(&mut hmtx, advance);
+        if !c.is_whitespace() {
+            let (x0, x1, y0, y1) = (inset, advance as i16 - inset, 0, 700);
+            push_u16(&mut hmtx, x0 as u16);
+            push_u16(&mut glyf, 1);
+            for v in [x0, y0, x1, y1].iter() {
+                push_u16(&mut glyf, *v as u16);
+            }
+            // One contour of four on-curve points with 16 bit deltas and no instructions.
+            push_u16(&mut glyf, 3);
+            push_u16(&mut glyf, 0);
+            glyf.extend_from_slice(&[1, 1, 1, 1]);
+            for v in [x0, 0, x1 - x0, 0, y0, y1 - y0, 0, y0 - y1].iter() {
+                push_u16(&mut glyf, *v as u16);
+            }
+        } else {
+            push_u16(&mut hmtx, 0);
+        }
+        push_u32(&mut loca, glyf.len() as u32);
+    }
+
+    let mut head: Vec<u8> = Vec::new();
+    push_u32(&mut head, 0x00010000);
+    push_u32(&mut head, 0x00010000);
+    push_u32(&mut head, 0);
+    push_u32(&mut head, 0x5F0F3CF5);
+    push_u16(&mut head, 0);
+    push_u16(&mut head, 1000);
+    head.extend_from_slice(&[0; 16]);
+    for v in [0, -200, advance as i16, 800].iter() {
+        push_u16(&mut head, *v as u16);
+    }
+    push_u16(&mut head, 0);
+    push_u16(&mut head, 8);
+    push_u16(&mut head, 2);
+    // Long loca offsets.
+    push_u16(&mut head, 1);
+    push_u16(&mut head, 0);
+
+    let mut hhea: Vec<u8> = Vec::new();
+    push_u32(&mut hhea, 0x00010000);
+    for v in [800, -200, 0, advance as i16, 0, 0, advance as i16, 1, 0, 0, 0, 0, 0, 0, 0].iter() {
+        push_u16(&mut hhea, *v as u16);
+    }
+    push_u16(&mut hhea, glyph_count as u16);
+
+    let mut maxp: Vec<u8> = Vec::new();
+    push_u32(&mut maxp, 0x00005000);
+    push_u16(&mut maxp, glyph_count as u16);
+
+    // A single Windows full unicode encoding using a format 12 table with one group per character.
+    let mut cmap: Vec<u8> = Vec::new();
+    push_u16(&mut cmap, 0);
+    push_u16(&mut cmap, 1);
+    push_u16(&mut cmap, 3);
+    push_u16(&mut cmap, 10);
+    push_u32(&mut cmap, 12);
+    push_u16(&mut cmap, 12);
+    push_u16(&mut cmap, 0);
+    push_u32(&mut cmap, 16 + 12 * chars.len() as u32);
+    push_u32(&mut cmap, 0);
+    push_u32(&mut cmap, chars.len() as u32);
+    for (i, c) in chars.iter().enumerate() {
+        push_u32(&mut cmap, *c as u32);
+        push_u32(&mut cmap, *c as u32);
+        push_u32(&mut cmap, i as u32 + 1);
+    }
+
+    // One horizontal format 0 subtable, with the pairs sorted by their glyph ids.
+    let glyph_id = |c: char| chars.binary_search(&c).expect("Kerning pairs have to use characters of the font.") as u32 + 1;
+    let mut pairs: Vec<(u32, i16)> = kerning.iter().map(|&(left, right, value)| (glyph_id(left) << 16 | glyph_id(right), value)).collect();
+    pairs.sort_by_key(|pair| pair.0);
+    let mut kern: Vec<u8> = Vec::new();
+    push_u16(&mut kern, 0);
+    push_u16(&mut kern, 1);
+    push_u16(&mut kern, 0);
+    push_u16(&mut kern, 14 + 6 * pairs.len() as u16);
+    push_u16(&mut kern, 1);
+    push_u16(&mut kern, pairs.len() as u16);
+    let selector = if pairs.is_empty() { 0 } else { 31 - (pairs.len() as u32).leading_zeros() };
+    let search_range = if pairs.is_empty() { 0 } else { 6 << selector };
+    push_u16(&mut kern, search_range as u16);
+    push_u16(&mut kern, selector as u16);
+    push_u16(&mut kern, (6 * pairs.len() - search_range) as u16);
+    for &(key, value) in pairs.iter() {
+        push_u32(&mut kern, key);
+        push_u16(&mut kern, value as u16);
+    }
+
+    // The table directory has to be sorted by tag.
+    let tables: Vec<(&[u8; 4], Vec<u8>)> = vec![(b"cmap", cmap), (b"glyf", glyf), (b"head", head), (b"hhea", hhea), (b"hmtx", hmtx), (b"kern", kern), (b"loca", loca), (b"maxp", maxp)];
+
+    let mut font: Vec<u8> = Vec::new();
+    push_u32(&mut font, 0x00010000);
+    let entry_selector = 31 - (tables.len() as u32).leading_zeros();
+    push_u16(&mut font, tables.len() as u16);
+    push_u16(&mut font, 16 << entry_selector);
+    push_u16(&mut font, entry_selector as u16);
+    push_u16(&mut font, tables.len() as u16 * 16 - (16 << entry_selector));
+
+    let mut offset = 12 + 16 * tables.len();
+    for &(tag, ref data) in tables.iter() {
+        let mut checksum: u32 = 0;
+        for (i, b) in data.iter().enumerate() {
+            checksum = checksum.wrapping_add((*b as u32) << (24 - 8 * (i % 4)));
+        }
+        font.extend_from_slice(tag);
+        push_u32(&mut font, checksum);
+        push_u32(&mut font, offset as u32);
+        push_u32(&mut font, data.len() as u32);
+        offset += (data.len() + 3) / 4 * 4;
+    }
+    for &(_, ref data) in tables.iter() {
+        font.extend_from_slice(data);
+        while font.len() % 4 != 0 {
+            font.push(0);
+        }
+    }
+
+    return font;
+
+}
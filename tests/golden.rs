@@ -0,0 +1,485 @@
+extern crate flat_engine;
+
+mod common;
+
+use flat_engine::*;
+use flat_engine::core::{FlatEngine, ScalingMode};
+use flat_engine::render::{RenderTarget, Sprite, SpriteBatch, Texture};
+use flat_engine::geometry::{FillRule, LineCap, LineJoin, Path, PathShape, StrokeStyle, Triangle};
+use flat_engine::spatial::{Entity, Mesh};
+use flat_engine::node::*;
+use flat_engine::golden;
+use flat_engine::post::{PostEffect, PostProcessChain};
+use flat_engine::atlas::TextureAtlas;
+use flat_engine::tilemap::{Tile, TileFlags, Tilemap, Tileset};
+use flat_engine::text::{load_font, FontCollection, FontFamily, HorizontalAlign, LayoutOptions, Text, TextEffects, TextRendering, VerticalAlign};
+
+const TOLERANCE: u8 = 2;
+
+fn solid(color: [u8; 4]) -> Texture {
+
+    let mut data: Vec<u8> = Vec::new();
+    for _ in 0..4 {
+        data.extend_from_slice(&color);
+    }
+    return Texture::from_data(&data, 2, 2);
+
+}
+
+fn checkerboard() -> Texture {
+
+    let mut data: Vec<u8> = Vec::new();
+    for y in 0..4 {
+        for x in 0..4 {
+            if (x + y) % 2 == 0 {
+                data.extend_from_slice(&[255, 255, 255, 255]);
+            } else {
+                data.extend_from_slice(&[255, 0, 0, 255]);
+            }
+        }
+    }
+    return Texture::from_data(&data, 4, 4);
+
+}
+
+#[test]
+fn sprite() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    let mut sprite = Sprite::from_texture(Box::new(checkerboard()));
+    sprite.set_pos(Vector2f::new(16.0, 8.0));
+    sprite.set_size(Vector2f::new(32.0, 48.0));
+    engine.load(&mut sprite).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut sprite);
+    golden::assert_frame(&mut engine, "tests/golden/sprite.png", TOLERANCE);
+
+}
+
+#[test]
+fn triangle() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    let mut triangle = Triangle::new(Color::green());
+    triangle.set_pos(Vector2f::new(32.0, 32.0));
+    triangle.set_scale(Vector2f::new(48.0, 48.0));
+    engine.load(&mut triangle).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut triangle);
+    golden::assert_frame(&mut engine, "tests/golden/triangle.png", TOLERANCE);
+
+}
+
+#[test]
+fn entity() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    let mesh = Mesh::from_vertices(vec![
+        Vector3f::new(8.0, 8.0, 0.0),
+        Vector3f::new(56.0, 8.0, 0.0),
+        Vector3f::new(32.0, 56.0, 0.0),
+    ]);
+    let mut entity = Entity::from_mesh(mesh, Some(Box::new(checkerboard())));
+    engine.load(&mut entity).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut entity);
+    golden::assert_frame(&mut engine, "tests/golden/entity.png", TOLERANCE);
+
+}
+
+#[test]
+fn sprite_batch() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    let mut sprites: Vec<Sprite> = Vec::new();
+    for i in 0..4 {
+        let texture = if i % 2 == 0 { solid([0, 0, 255, 255]) } else { checkerboard() };
+        let mut sprite = Sprite::from_texture(Box::new(texture));
+        sprite.set_pos(Vector2f::new(8.0 + i as f32 * 10.0, 8.0 + i as f32 * 10.0));
+        sprite.set_size(Vector2f::new(24.0, 24.0));
+        // The first sprite is drawn on top of all the others.
+        sprite.set_depth(if i == 0 { 1.0 } else { 0.0 });
+        engine.load(&mut sprite).unwrap();
+        sprites.push(sprite);
+    }
+
+    let mut batch = SpriteBatch::new();
+    engine.load(&mut batch).unwrap();
+
+    engine.clear(Color::black());
+    for sprite in sprites.iter_mut() {
+        batch.add(sprite, &mut engine).unwrap();
+    }
+    engine.render(&mut batch);
+    golden::assert_frame(&mut engine, "tests/golden/sprite_batch.png", TOLERANCE);
+
+    // Sprites from identical images share a texture, so the three sprites below the first one take one draw per texture.
+    assert_eq!(batch.get_draw_count(), 3);
+
+}
+
+#[test]
+fn scene_graph() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    let mut parent = Sprite::from_texture(Box::new(checkerboard()));
+    parent.set_pos(Vector2f::new(4.0, 4.0));
+    parent.set_size(Vector2f::new(16.0, 16.0));
+
+    // Child transforms are in the local space of the parent, which is scaled by 4 to stretch its 4x4 texture to 16x16.
+    let mut child = Sprite::from_texture(Box::new(solid([0, 0, 255, 255])));
+    child.set_pos(Vector2f::new(1.0, 1.0));
+    child.set_scale(Vector2f::new(0.5, 0.5));
+
+    let mut graph = SceneGraph::new();
+    let parent_id = graph.add(Box::new(parent));
+    graph.add_child(parent_id, Box::new(child));
+    engine.load(&mut graph).unwrap();
+
+    // Moving the parent moves the child with it.
+    graph.get_mut(parent_id).unwrap().set_pos(Vector2f::new(16.0, 16.0));
+
+    engine.clear(Color::black());
+    engine.render(&mut graph);
+    golden::assert_frame(&mut engine, "tests/golden/scene_graph.png", TOLERANCE);
+
+}
+
+#[test]
+fn rotated_sprite() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    let mut sprite = Sprite::from_texture(Box::new(checkerboard()));
+    sprite.center_origin();
+    sprite.set_pos(Vector2f::new(32.0, 32.0));
+    sprite.set_size(Vector2f::new(32.0, 32.0));
+    sprite.set_rotation(::std::f32::consts::PI / 4.0);
+    engine.load(&mut sprite).unwrap();
+
+    // Rotating around the center keeps the sprite centered on its position.
+    let rect = sprite.get_rect();
+    assert!((rect.x + rect.width / 2.0 - 32.0).abs() < 0.001 && (rect.y + rect.height / 2.0 - 32.0).abs() < 0.001);
+    assert!((rect.width - 32.0 * 2.0f32.sqrt()).abs() < 0.001);
+    assert!(sprite.get_oriented_rect().contains(Vector2f::new(32.0, 10.0)));
+    assert!(!sprite.get_oriented_rect().contains(Vector2f::new(12.0, 12.0)));
+
+    engine.clear(Color::black());
+    engine.render(&mut sprite);
+    golden::assert_frame(&mut engine, "tests/golden/rotated_sprite.png", TOLERANCE);
+
+}
+
+#[test]
+fn translucent_quad() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    // The diagonal shared by the two triangles of the quad runs through pixel centres, which must be blended once.
+    let mut sprite = Sprite::from_texture(Box::new(solid([255, 255, 255, 128])));
+    sprite.set_pos(Vector2f::new(16.0, 16.0));
+    sprite.set_size(Vector2f::new(32.0, 32.0));
+    engine.load(&mut sprite).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut sprite);
+    let frame = engine.read_pixels().unwrap();
+    let inside: Vec<&[u8]> = frame.data.chunks(4).enumerate().filter(|&(i, _)| (16..48).contains(&(i % 64)) && (16..48).contains(&(i / 64))).map(|(_, p)| p).collect();
+    assert_eq!(inside.len(), 32 * 32);
+    assert!(inside.iter().all(|p| p == &inside[0]));
+
+    golden::assert_frame(&mut engine, "tests/golden/translucent_quad.png", TOLERANCE);
+
+}
+
+#[test]
+fn letterbox() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+    engine.set_scaling_mode(ScalingMode::Letterbox(Vector2f::new(32.0, 16.0)));
+    assert_eq!(engine.get_scaling().viewport, Rect::new(0.0, 16.0, 64.0, 32.0));
+
+    // Covers the whole design area and more, but only the design area is drawn.
+    let mut sprite = Sprite::from_texture(Box::new(checkerboard()));
+    sprite.set_pos(Vector2f::new(-8.0, -8.0));
+    sprite.set_size(Vector2f::new(48.0, 32.0));
+    engine.load(&mut sprite).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut sprite);
+    golden::assert_frame(&mut engine, "tests/golden/letterbox.png", TOLERANCE);
+
+}
+
+#[test]
+fn render_target() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    let mut target = RenderTarget::new(Vector2u::new(16, 16), &mut engine).unwrap();
+
+    let mut triangle = Triangle::new(Color::green());
+    triangle.set_pos(Vector2f::new(8.0, 8.0));
+    triangle.set_scale(Vector2f::new(12.0, 12.0));
+    engine.load(&mut triangle).unwrap();
+
+    engine.render_to(&target, |engine| {
+        engine.clear(Color::blue());
+        engine.render(&mut triangle);
+    });
+
+    // Read back the target: the corner is background, the middle is the triangle.
+    let pixels = target.read_pixels(&mut engine).unwrap();
+    assert_eq!(pixels.dimensions, Vector2::new(16, 16));
+    assert_eq!(&pixels.data[0..4], &[0, 0, 255, 255]);
+    let center = (8 * 16 + 8) * 4;
+    assert_eq!(&pixels.data[center..center + 4], &[0, 255, 0, 255]);
+
+    // Draw the target on screen, scaled up.
+    let mut sprite = Sprite::from_render_target(&target);
+    sprite.set_pos(Vector2f::new(8.0, 8.0));
+    sprite.set_size(Vector2f::new(48.0, 48.0));
+    engine.load(&mut sprite).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut sprite);
+    golden::assert_frame(&mut engine, "tests/golden/render_target.png", TOLERANCE);
+
+    // A destroyed target can no longer be read.
+    target.destroy(&mut engine);
+    assert!(target.read_pixels(&mut engine).is_err());
+
+}
+
+#[test]
+fn post_process() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    let mut sprite = Sprite::from_texture(Box::new(checkerboard()));
+    sprite.set_size(Vector2f::new(64.0, 64.0));
+    engine.load(&mut sprite).unwrap();
+
+    let mut chain = PostProcessChain::new();
+    chain.add(PostEffect::grayscale(1.0));
+    chain.add(PostEffect::vignette(1.0, 0.25, 0.3));
+
+    engine.clear(Color::black());
+    chain.render(&mut engine, |engine| {
+        engine.clear(Color::black());
+        engine.render(&mut sprite);
+    }).unwrap();
+    golden::assert_frame(&mut engine, "tests/golden/post_process.png", TOLERANCE);
+
+}
+
+#[test]
+fn atlas() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    let mut atlas = TextureAtlas::new(Vector2u::new(16, 16), 1);
+    atlas.add_all(&[("checkerboard", &checkerboard()), ("blue", &solid([0, 0, 255, 255]))]).unwrap();
+    atlas.upload(&mut engine).unwrap();
+
+    let mut checker = atlas.create_sprite("checkerboard").unwrap();
+    checker.set_pos(Vector2f::new(4.0, 4.0));
+    checker.set_size(Vector2f::new(32.0, 32.0));
+    let mut blue = atlas.create_sprite("blue").unwrap();
+    blue.set_pos(Vector2f::new(40.0, 40.0));
+    blue.set_size(Vector2f::new(16.0, 16.0));
+    engine.load(&mut checker).unwrap();
+    engine.load(&mut blue).unwrap();
+
+    // Both sprites use the page texture, so the batch draws them together.
+    assert_eq!(checker.texture_renderer.as_ref().unwrap().get_texture(), blue.texture_renderer.as_ref().unwrap().get_texture());
+
+    let mut batch = SpriteBatch::new();
+    engine.load(&mut batch).unwrap();
+
+    engine.clear(Color::black());
+    batch.add(&mut checker, &mut engine).unwrap();
+    batch.add(&mut blue, &mut engine).unwrap();
+    engine.render(&mut batch);
+    golden::assert_frame(&mut engine, "tests/golden/atlas.png", TOLERANCE);
+    assert_eq!(batch.get_draw_count(), 1);
+
+}
+
+#[test]
+fn tilemap() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    // A red tile with a white top left corner, so flips and rotations can be seen, and a blue tile.
+    let mut data: Vec<u8> = Vec::new();
+    for y in 0..4 {
+        for x in 0..8 {
+            if x >= 4 {
+                data.extend_from_slice(&[0, 0, 255, 255]);
+            } else if x < 2 && y < 2 {
+                data.extend_from_slice(&[255, 255, 255, 255]);
+            } else {
+                data.extend_from_slice(&[255, 0, 0, 255]);
+            }
+        }
+    }
+    let tileset = Tileset::from_grid(Texture::from_data(&data, 8, 4), Vector2u::new(4, 4), 0, 0, 1).unwrap();
+
+    let mut map = Tilemap::new(tileset, 4, 4, Vector2f::new(16.0, 16.0));
+    map.set_chunk_size(2, &mut engine);
+    let ground = map.add_layer("ground");
+    let top = map.add_layer("top");
+    for x in 0..4 {
+        map.set_tile(ground, x, 3, Some(Tile::new(1)));
+        map.set_tile(top, x, 0, Some(Tile::with_flags(0, TileFlags::rotated(x as i32))));
+    }
+    map.set_tile(top, 0, 1, Some(Tile::with_flags(0, TileFlags { flip_x: true, flip_y: false, flip_diagonal: false })));
+    map.set_tile(top, 1, 1, Some(Tile::with_flags(0, TileFlags { flip_x: false, flip_y: true, flip_diagonal: false })));
+    engine.load(&mut map).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut map);
+    golden::assert_frame(&mut engine, "tests/golden/tilemap.png", TOLERANCE);
+
+}
+
+#[test]
+fn shapes() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    {
+        let shapes = &mut engine.shapes;
+        shapes.fill_rect_colors(Rect::new(2.0, 2.0, 20.0, 12.0), [Color::red(), Color::green(), Color::blue(), Color::white()]);
+        shapes.stroke_rounded_rect(Rect::new(26.0, 2.0, 34.0, 14.0), 5.0, 2.0, Color::yellow());
+        shapes.fill_circle(Vector2f::new(12.0, 28.0), 8.0, Color::blue());
+        shapes.stroke_ellipse(Vector2f::new(42.0, 28.0), Vector2f::new(16.0, 7.0), 1.5, Color::green());
+        shapes.fill_arc(Vector2f::new(12.0, 50.0), 10.0, 0.0, ::std::f32::consts::PI * 1.5, Color::red());
+        shapes.polyline(&[Vector2f::new(26.0, 40.0), Vector2f::new(34.0, 58.0), Vector2f::new(42.0, 44.0), Vector2f::new(50.0, 58.0)], 3.0, LineJoin::Round, LineCap::Round, Color::white());
+        // A concave arrow, translucent so that it blends with the line below it.
+        let arrow = [Vector2f::new(40.0, 48.0), Vector2f::new(62.0, 48.0), Vector2f::new(62.0, 62.0), Vector2f::new(51.0, 54.0), Vector2f::new(40.0, 62.0)];
+        shapes.fill_polygon(&arrow, Color { r: 1.0, g: 0.0, b: 1.0, a: 0.5 });
+    }
+
+    engine.clear(Color::black());
+    engine.draw_shapes().unwrap();
+    assert_eq!(engine.shapes.get_vertex_count(), 0);
+    golden::assert_frame(&mut engine, "tests/golden/shapes.png", TOLERANCE);
+
+}
+
+#[test]
+fn path() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    // A ring, filled with the even-odd rule so that the inner circle is a hole.
+    let mut ring = Path::new();
+    ring.circle(Vector2f::new(16.0, 16.0), 12.0);
+    ring.circle(Vector2f::new(16.0, 16.0), 6.0);
+
+    // A heart from SVG, which is y down.
+    let heart = Path::from_svg("M10 6 C10 2 4 2 4 6 C4 10 10 12 10 16 C10 12 16 10 16 6 C16 2 10 2 10 6 Z").unwrap();
+
+    let mut wave = Path::new();
+    wave.move_to(Vector2f::new(4.0, 52.0)).quad_to(Vector2f::new(16.0, 64.0), Vector2f::new(30.0, 52.0)).quad_to(Vector2f::new(44.0, 40.0), Vector2f::new(60.0, 52.0));
+
+    let mut shape = PathShape::new();
+    shape.add_fill(&ring, FillRule::EvenOdd, Color::blue());
+    shape.add_stroke(&ring, &StrokeStyle::new(1.0).with_dashes(&[3.0, 2.0], 0.0), Color::white());
+    shape.add_stroke(&wave, &StrokeStyle::new(3.0).with_cap(LineCap::Round).with_join(LineJoin::Round), Color::green());
+    engine.load(&mut shape).unwrap();
+
+    let mut icon = PathShape::new();
+    icon.add_fill(&heart, FillRule::NonZero, Color::red());
+    icon.set_pos(Vector2f::new(32.0, 40.0));
+    icon.set_scale(Vector2f::new(1.5, -1.5));
+    engine.load(&mut icon).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut shape);
+    engine.render(&mut icon);
+    golden::assert_frame(&mut engine, "tests/golden/path.png", TOLERANCE);
+
+}
+
+#[test]
+fn text() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+    let font = load_font(common::box_font("abcdefghijklmnopqrstuvwxyz ", 600)).unwrap();
+
+    let options = LayoutOptions::new().with_max_width(60.0).with_height(40.0).with_align(HorizontalAlign::Center).with_vertical_align(VerticalAlign::Middle);
+    let mut wrapped = Text::with_options("abc de fghij klm", font.clone(), 10.0, Color::yellow(), options).unwrap();
+    wrapped.set_pos(Vector2f::new(2.0, 22.0));
+
+    let mut label = Text::new("ab\nc", font, 10.0, Color { r: 0.0, g: 1.0, b: 1.0, a: 0.5 }).unwrap();
+    label.set_pos(Vector2f::new(4.0, 2.0));
+
+    engine.load(&mut wrapped).unwrap();
+    engine.load(&mut label).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut wrapped);
+    engine.render(&mut label);
+    golden::assert_frame(&mut engine, "tests/golden/text.png", TOLERANCE);
+
+}
+
+#[test]
+fn rich_text() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+    let chars = "abcdefghijklmnopqrstuvwxyz ";
+    let family = FontFamily::new(load_font(common::box_font(chars, 600)).unwrap()).with_bold(load_font(common::box_font(chars, 800)).unwrap());
+
+    let markup = "[color=orange]ab[/color] [b]cd[/b] [u]ef[/u]\n[s]gh[/s] [img=gem] [size=16][color=#40a0ffc0]ij[/color][/size]";
+    let mut text = Text::from_markup(markup, FontCollection::new(family), 10.0, Color::white(), LayoutOptions::new().with_max_width(60.0)).unwrap();
+    let gem = vec![255, 0, 255, 255, 0, 255, 255, 255, 0, 255, 255, 255, 255, 0, 255, 255];
+    text.add_icon("gem", Texture { data: gem, dimensions: Vector2::new(2, 2) }).unwrap();
+    text.set_pos(Vector2f::new(2.0, 20.0));
+
+    engine.load(&mut text).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut text);
+    golden::assert_frame(&mut engine, "tests/golden/rich_text.png", TOLERANCE);
+
+}
+
+#[test]
+fn distance_text() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+    let font = load_font(common::box_font("abcdefghijklmnopqrstuvwxyz ", 600)).unwrap();
+
+    // Scaled up three times, with effects that keep their width relative to the glyphs.
+    let mut large = Text::new("ab", font.clone(), 8.0, Color::white()).unwrap();
+    large.set_rendering(TextRendering::DistanceField);
+    large.set_effects(TextEffects::new().with_outline(1.0, Color::red()).with_shadow(Vector2f::new(1.0, 1.0), 0.5, Color { r: 0.0, g: 0.0, b: 0.0, a: 0.75 }));
+    large.set_scale(Vector2f::new(3.0, 3.0));
+    large.set_pos(Vector2f::new(4.0, 30.0));
+
+    let mut glowing = Text::new("cde", font, 12.0, Color::yellow()).unwrap();
+    glowing.set_rendering(TextRendering::DistanceField);
+    glowing.set_effects(TextEffects::new().with_glow(3.0, Color { r: 0.0, g: 0.5, b: 1.0, a: 1.0 }));
+    glowing.set_pos(Vector2f::new(6.0, 6.0));
+
+    engine.load(&mut large).unwrap();
+    engine.load(&mut glowing).unwrap();
+
+    engine.clear(Color { r: 0.2, g: 0.2, b: 0.3, a: 1.0 });
+    engine.render(&mut large);
+    engine.render(&mut glowing);
+    golden::assert_frame(&mut engine, "tests/golden/distance_text.png", TOLERANCE);
+
+}
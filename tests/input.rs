@@ -0,0 +1,104 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::core::FlatEngine;
+use flat_engine::input::*;
+use flat_engine::glutin::{MouseButton, VirtualKeyCode};
+
+const BINDINGS: &'static str = r#"{
+    "actions": { "jump": ["Space", "MouseLeft"] },
+    "axes": { "move_x": { "positive": ["Right", "D"], "negative": ["Left", "A"] } }
+}"#;
+
+#[test]
+fn pressed_state_lasts_one_frame() {
+
+    let mut input = Input::new();
+
+    input.begin_frame();
+    input.press_key(VirtualKeyCode::A);
+    assert!(input.is_key_down(VirtualKeyCode::A) && input.is_key_pressed(VirtualKeyCode::A));
+
+    // A repeated press while held is not a new press.
+    input.begin_frame();
+    input.press_key(VirtualKeyCode::A);
+    assert!(input.is_key_down(VirtualKeyCode::A) && !input.is_key_pressed(VirtualKeyCode::A));
+
+    input.begin_frame();
+    input.release_key(VirtualKeyCode::A);
+    assert!(!input.is_key_down(VirtualKeyCode::A) && input.is_key_released(VirtualKeyCode::A));
+
+    input.begin_frame();
+    assert!(!input.is_key_released(VirtualKeyCode::A));
+
+}
+
+#[test]
+fn actions_and_axes_from_config() {
+
+    let mut input = Input::new();
+    input.bindings = Bindings::load_from_str(BINDINGS).unwrap();
+
+    input.begin_frame();
+    input.press_button(MouseButton::Left);
+    assert!(input.is_action_pressed("jump") && input.is_action_down("jump"));
+    assert!(!input.is_action_down("unbound"));
+
+    input.press_key(VirtualKeyCode::D);
+    assert_eq!(input.get_axis("move_x"), 1.0);
+    input.press_key(VirtualKeyCode::Left);
+    assert_eq!(input.get_axis("move_x"), 0.0);
+    input.release_key(VirtualKeyCode::D);
+    assert_eq!(input.get_axis("move_x"), -1.0);
+
+    assert!(Bindings::load_from_str(r#"{ "actions": { "jump": ["NotAKey"] } }"#).is_err());
+
+}
+
+#[test]
+fn action_pressed_once_across_bindings() {
+
+    let mut input = Input::new();
+    input.bindings = Bindings::load_from_str(BINDINGS).unwrap();
+
+    input.begin_frame();
+    input.press_key(VirtualKeyCode::Space);
+    assert!(input.is_action_pressed("jump"));
+
+    // The second binding goes down while the first is still held.
+    input.begin_frame();
+    input.press_button(MouseButton::Left);
+    assert!(!input.is_action_pressed("jump") && input.is_action_down("jump"));
+
+    // Letting go of one binding neither releases nor presses the action.
+    input.begin_frame();
+    input.release_key(VirtualKeyCode::Space);
+    assert!(!input.is_action_pressed("jump") && !input.is_action_released("jump"));
+
+    input.begin_frame();
+    input.release_button(MouseButton::Left);
+    assert!(input.is_action_released("jump"));
+
+    // Both bindings going down on the same frame press the action once.
+    input.begin_frame();
+    input.press_key(VirtualKeyCode::Space);
+    input.press_button(MouseButton::Left);
+    assert!(input.is_action_pressed("jump"));
+
+    input.begin_frame();
+    assert!(!input.is_action_pressed("jump"));
+
+}
+
+#[test]
+fn cursor_world_pos() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+    engine.camera.set_pos(Vector3f::new(100.0, 0.0, 0.0));
+
+    // The window origin is the top left, the world origin is the bottom left of the camera.
+    engine.input.update_cursor_world_pos(&engine.camera, engine.get_dimensions());
+    let pos = engine.input.get_cursor_world_pos();
+    assert!((pos.x - 100.0).abs() < 0.001 && (pos.y - 64.0).abs() < 0.001);
+
+}
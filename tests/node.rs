@@ -0,0 +1,119 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::node::{Node2D, SceneGraph};
+use flat_engine::render::Sprite;
+
+fn sprite_at(x: f32, y: f32) -> Box<Sprite> {
+
+    let mut sprite = Sprite::new();
+    sprite.set_pos(Vector2f::new(x, y));
+    return Box::new(sprite);
+
+}
+
+fn world_pos(graph: &mut SceneGraph, id: usize) -> Vector2f {
+
+    let p = graph.get_world_trans(id) * Vector4f::new(0.0, 0.0, 0.0, 1.0);
+    return Vector2f::new(p.x, p.y);
+
+}
+
+fn close(a: Vector2f, b: Vector2f) -> bool {
+    return (a.x - b.x).abs() < 0.001 && (a.y - b.y).abs() < 0.001;
+}
+
+#[test]
+fn world_transforms_follow_nested_parents() {
+
+    let mut graph = SceneGraph::new();
+    let root = graph.add(sprite_at(10.0, 20.0));
+    let child = graph.add_child(root, sprite_at(5.0, 0.0));
+    let grandchild = graph.add_child(child, sprite_at(1.0, 2.0));
+
+    assert!(close(world_pos(&mut graph, grandchild), Vector2f::new(16.0, 22.0)));
+
+    // Scaling the middle node scales the offset of its child, but not its own.
+    graph.get_mut(child).unwrap().set_scale(Vector2f::new(2.0, 3.0));
+    assert!(close(world_pos(&mut graph, child), Vector2f::new(15.0, 20.0)));
+    assert!(close(world_pos(&mut graph, grandchild), Vector2f::new(17.0, 26.0)));
+
+    // Moving the root moves everything below it.
+    graph.get_mut(root).unwrap().set_pos(Vector2f::new(0.0, 0.0));
+    assert!(close(world_pos(&mut graph, grandchild), Vector2f::new(7.0, 6.0)));
+
+}
+
+#[test]
+fn reparenting_keeps_the_local_transform() {
+
+    let mut graph = SceneGraph::new();
+    let a = graph.add(sprite_at(10.0, 0.0));
+    let b = graph.add(sprite_at(0.0, 100.0));
+    let child = graph.add_child(a, sprite_at(1.0, 1.0));
+
+    assert!(close(world_pos(&mut graph, child), Vector2f::new(11.0, 1.0)));
+
+    graph.set_parent(child, Some(b));
+    assert_eq!(graph.get_parent(child), Some(b));
+    assert!(graph.get_children(a).is_empty());
+    assert_eq!(graph.get_children(b), &[child]);
+    assert!(close(world_pos(&mut graph, child), Vector2f::new(1.0, 101.0)));
+
+    // Without a parent the node becomes a root.
+    graph.set_parent(child, None);
+    assert_eq!(graph.get_parent(child), None);
+    assert!(graph.get_children(b).is_empty());
+    assert_eq!(graph.get_roots(), &[a, b, child]);
+    assert!(close(world_pos(&mut graph, child), Vector2f::new(1.0, 1.0)));
+
+}
+
+#[test]
+fn removing_a_node_removes_its_subtree() {
+
+    let mut graph = SceneGraph::new();
+    let root = graph.add(sprite_at(0.0, 0.0));
+    let branch = graph.add_child(root, sprite_at(0.0, 0.0));
+    let leaf = graph.add_child(branch, sprite_at(0.0, 0.0));
+    let other_leaf = graph.add_child(branch, sprite_at(0.0, 0.0));
+    let sibling = graph.add_child(root, sprite_at(0.0, 0.0));
+
+    let removed = graph.remove(branch);
+    assert_eq!(removed.len(), 3);
+
+    assert!(graph.get(branch).is_none());
+    assert!(graph.get(leaf).is_none());
+    assert!(graph.get(other_leaf).is_none());
+    assert_eq!(graph.get_children(root), &[sibling]);
+
+    // Removing it again does nothing.
+    assert!(graph.remove(branch).is_empty());
+
+}
+
+#[test]
+#[should_panic(expected = "descendants")]
+fn reparenting_under_a_descendant_is_rejected() {
+
+    let mut graph = SceneGraph::new();
+    let root = graph.add(sprite_at(0.0, 0.0));
+    let child = graph.add_child(root, sprite_at(0.0, 0.0));
+    let grandchild = graph.add_child(child, sprite_at(0.0, 0.0));
+
+    assert!(graph.is_ancestor(root, grandchild));
+    assert!(!graph.is_ancestor(grandchild, root));
+
+    graph.set_parent(root, Some(grandchild));
+
+}
+
+#[test]
+#[should_panic(expected = "itself")]
+fn reparenting_under_itself_is_rejected() {
+
+    let mut graph = SceneGraph::new();
+    let node = graph.add(sprite_at(0.0, 0.0));
+    graph.set_parent(node, Some(node));
+
+}
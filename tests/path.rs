@@ -0,0 +1,135 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::geometry::*;
+
+fn area(vertices: &[Vertex]) -> f32 {
+
+    return vertices.chunks(3).map(|t| {
+        let (a, b, c) = (t[0].pos, t[1].pos, t[2].pos);
+        ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])).abs() / 2.0
+    }).sum();
+
+}
+
+fn square(path: &mut Path, min: f32, max: f32, clockwise: bool) {
+
+    let mut corners = vec![Vector2f::new(min, min), Vector2f::new(max, min), Vector2f::new(max, max), Vector2f::new(min, max)];
+    if clockwise {
+        corners.reverse();
+    }
+    path.move_to(corners[0]);
+    for &corner in corners[1..].iter() {
+        path.line_to(corner);
+    }
+    path.close();
+
+}
+
+#[test]
+fn parse_svg() {
+
+    let path = Path::from_svg("M10 10 h 20 v20 H10 z").unwrap();
+    assert_eq!(path.commands, vec![
+        PathCommand::MoveTo(Vector2f::new(10.0, 10.0)),
+        PathCommand::LineTo(Vector2f::new(30.0, 10.0)),
+        PathCommand::LineTo(Vector2f::new(30.0, 30.0)),
+        PathCommand::LineTo(Vector2f::new(10.0, 30.0)),
+        PathCommand::Close,
+    ]);
+
+    // Extra points after a move are lines, and numbers need no separators when the sign or a second dot starts the next one.
+    let path = Path::from_svg("m1,1 2,0L10-5.5.5e1 2").unwrap();
+    assert_eq!(path.commands[1], PathCommand::LineTo(Vector2f::new(3.0, 1.0)));
+    assert_eq!(path.commands[2], PathCommand::LineTo(Vector2f::new(10.0, -5.5)));
+    assert_eq!(path.commands.len(), 4);
+    assert_eq!(path.commands[3], PathCommand::LineTo(Vector2f::new(5.0, 2.0)));
+
+    // Smooth curves reflect the previous control point.
+    let path = Path::from_svg("M0 0 Q 5 10 10 0 T 20 0").unwrap();
+    assert_eq!(path.commands[2], PathCommand::QuadTo(Vector2f::new(15.0, -10.0), Vector2f::new(20.0, 0.0)));
+    let path = Path::from_svg("M0 0 C0 5 10 5 10 0 S20 -5 20 0").unwrap();
+    assert_eq!(path.commands[2], PathCommand::CubicTo(Vector2f::new(10.0, -5.0), Vector2f::new(20.0, -5.0), Vector2f::new(20.0, 0.0)));
+
+    // Arc flags need no separators either.
+    let path = Path::from_svg("M0 0a5 5 0 1010 0").unwrap();
+    assert_eq!(path.commands[1], PathCommand::ArcTo { radii: Vector2f::new(5.0, 5.0), rotation: 0.0, large_arc: true, sweep: false, to: Vector2f::new(10.0, 0.0) });
+
+    assert!(Path::from_svg("10 10").is_err());
+    assert!(Path::from_svg("M10").is_err());
+    assert!(Path::from_svg("M0 0 X 1 1").is_err());
+
+}
+
+#[test]
+fn flatten_curves() {
+
+    let mut path = Path::new();
+    path.circle(Vector2f::new(0.0, 0.0), 10.0);
+    let contours = path.flatten(0.1);
+    assert_eq!(contours.len(), 1);
+    assert!(contours[0].closed);
+    assert!(contours[0].points.len() > 16);
+    assert!(contours[0].points.iter().all(|p| (p.magnitude() - 10.0).abs() < 0.001));
+
+    // A half circle arc through the SVG parameters.
+    let path = Path::from_svg("M0 0 A5 5 0 0 1 10 0").unwrap();
+    let contour = &path.flatten(0.1)[0];
+    assert!(!contour.closed);
+    assert_eq!(*contour.points.last().unwrap(), Vector2f::new(10.0, 0.0));
+    assert!(contour.points.iter().all(|p| ((*p - Vector2f::new(5.0, 0.0)).magnitude() - 5.0).abs() < 0.001));
+
+    let mut path = Path::new();
+    path.move_to(Vector2f::new(0.0, 0.0)).cubic_to(Vector2f::new(0.0, 10.0), Vector2f::new(10.0, 10.0), Vector2f::new(10.0, 0.0));
+    let coarse = path.flatten(1.0)[0].points.len();
+    let fine = path.flatten(0.01)[0].points.len();
+    assert!(fine > coarse);
+
+}
+
+#[test]
+fn fill_rules() {
+
+    // A square with a square hole winding the same way.
+    let mut path = Path::new();
+    square(&mut path, 0.0, 10.0, false);
+    square(&mut path, 3.0, 7.0, false);
+    assert!((area(&path.fill(FillRule::NonZero, 0.1, Color::white())) - 100.0).abs() < 0.01);
+    assert!((area(&path.fill(FillRule::EvenOdd, 0.1, Color::white())) - 84.0).abs() < 0.01);
+
+    // Winding the other way makes it a hole either way.
+    let mut path = Path::new();
+    square(&mut path, 0.0, 10.0, false);
+    square(&mut path, 3.0, 7.0, true);
+    assert!((area(&path.fill(FillRule::NonZero, 0.1, Color::white())) - 84.0).abs() < 0.01);
+
+    // A self-intersecting bow tie.
+    let mut path = Path::new();
+    path.move_to(Vector2f::new(0.0, 0.0)).line_to(Vector2f::new(10.0, 10.0)).line_to(Vector2f::new(10.0, 0.0)).line_to(Vector2f::new(0.0, 10.0)).close();
+    assert!((area(&path.fill(FillRule::EvenOdd, 0.1, Color::white())) - 50.0).abs() < 0.01);
+
+}
+
+#[test]
+fn dashed_stroke() {
+
+    let mut path = Path::new();
+    path.move_to(Vector2f::new(0.0, 0.0)).line_to(Vector2f::new(10.0, 0.0));
+
+    let solid = path.stroke(&StrokeStyle::new(2.0), 0.1, Color::white());
+    assert!((area(&solid) - 20.0).abs() < 0.01);
+
+    // Dashes at 0-2, 4-6 and 8-10.
+    let dashed = path.stroke(&StrokeStyle::new(2.0).with_dashes(&[2.0, 2.0], 0.0), 0.1, Color::white());
+    assert_eq!(dashed.len(), 18);
+    assert!((area(&dashed) - 12.0).abs() < 0.01);
+
+    // Starting one unit into the pattern leaves dashes at 0-1, 3-5 and 7-9.
+    let offset = path.stroke(&StrokeStyle::new(2.0).with_dashes(&[2.0, 2.0], 1.0), 0.1, Color::white());
+    assert!((area(&offset) - 10.0).abs() < 0.01);
+
+    // Square caps extend each dash by half the thickness at both ends.
+    let capped = path.stroke(&StrokeStyle::new(2.0).with_dashes(&[2.0, 2.0], 0.0).with_cap(LineCap::Square), 0.1, Color::white());
+    assert!((area(&capped) - 24.0).abs() < 0.01);
+
+}
@@ -0,0 +1,86 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::core::FlatEngine;
+use flat_engine::render::{Sprite, Texture};
+use flat_engine::node::*;
+use flat_engine::post::{self, PostEffect, PostProcessChain};
+
+fn gradient() -> Texture {
+
+    let mut data: Vec<u8> = Vec::new();
+    for y in 0..8 {
+        for x in 0..8 {
+            data.extend_from_slice(&[x * 32, y * 32, 255 - x * 16, 255]);
+        }
+    }
+    return Texture::from_data(&data, 8, 8);
+
+}
+
+fn draw(engine: &mut FlatEngine, chain: &mut PostProcessChain, sprite: &mut Sprite) -> Texture {
+
+    chain.render(engine, |engine| {
+        engine.clear(Color::black());
+        engine.render(sprite);
+    }).unwrap();
+    return engine.read_pixels().unwrap();
+
+}
+
+fn max_difference(a: &Texture, b: &Texture) -> u8 {
+    return a.data.iter().zip(b.data.iter()).map(|(a, b)| (*a as i16 - *b as i16).abs() as u8).max().unwrap_or(0);
+}
+
+#[test]
+fn toggling() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(32, 32));
+    let mut sprite = Sprite::from_texture(Box::new(gradient()));
+    sprite.set_size(Vector2f::new(32.0, 32.0));
+    engine.load(&mut sprite).unwrap();
+
+    let mut chain = PostProcessChain::new();
+    let plain = draw(&mut engine, &mut chain, &mut sprite);
+
+    chain.add(PostEffect::grayscale(1.0));
+    assert!(chain.is_active());
+    let gray = draw(&mut engine, &mut chain, &mut sprite);
+    assert!(max_difference(&plain, &gray) > 32);
+    let i = (16 * 32 + 16) * 4;
+    assert_eq!(gray.data[i], gray.data[i + 1]);
+    assert_eq!(gray.data[i + 1], gray.data[i + 2]);
+
+    // Turning the effect or the whole chain off shows the scene as it is.
+    assert!(chain.set_enabled("grayscale", false));
+    assert!(!chain.is_active());
+    assert_eq!(draw(&mut engine, &mut chain, &mut sprite).data, plain.data);
+
+    chain.set_enabled("grayscale", true);
+    chain.enabled = false;
+    assert_eq!(draw(&mut engine, &mut chain, &mut sprite).data, plain.data);
+
+    assert!(!chain.set_enabled("missing", true));
+
+}
+
+#[test]
+fn identity_passes() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(32, 32));
+    let mut sprite = Sprite::from_texture(Box::new(gradient()));
+    sprite.set_size(Vector2f::new(32.0, 32.0));
+    engine.load(&mut sprite).unwrap();
+
+    let mut chain = PostProcessChain::new();
+    let plain = draw(&mut engine, &mut chain, &mut sprite);
+
+    // A neutral LUT, a custom shader (drawn as a copy by the software renderer) and a bloom that nothing is bright enough for.
+    chain.add(PostEffect::color_grade(post::identity_lut(16), 1.0));
+    chain.add(PostEffect::custom("custom", b"#version 150 core\nuniform sampler2D t_Input;\nin vec2 v_Uv;\nout vec4 Target0;\nvoid main() { Target0 = texture(t_Input, v_Uv); }"));
+    chain.add(PostEffect::bloom(1.0, 1.0, 1.0));
+    assert!(max_difference(&plain, &draw(&mut engine, &mut chain, &mut sprite)) <= 2);
+
+    chain.destroy(&mut engine);
+
+}
@@ -0,0 +1,135 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::core::{FlatEngine, PipelineKind};
+use flat_engine::error::Error;
+use flat_engine::render::{Sprite, SpriteBatch, Texture};
+use flat_engine::text;
+
+fn texture() -> Texture {
+    return Texture::from_data(&[255, 255, 255, 255], 1, 1);
+}
+
+#[test]
+fn sprites_share_one_shader() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(16, 16));
+
+    let mut first = Sprite::from_texture(Box::new(texture()));
+    let mut second = Sprite::from_texture(Box::new(texture()));
+    engine.load(&mut first).unwrap();
+    engine.load(&mut second).unwrap();
+
+    let shader = first.texture_renderer.as_ref().unwrap().get_shader();
+    assert_eq!(second.texture_renderer.as_ref().unwrap().get_shader(), shader);
+    assert_eq!(engine.shaders.len(), 1);
+
+    // Other sources or pipeline kinds are compiled separately.
+    let other = engine.create_shader(PipelineKind::Texture, b"vertex", b"fragment").unwrap();
+    assert!(other != shader);
+    assert_eq!(engine.create_shader(PipelineKind::Texture, b"vertex", b"fragment").unwrap(), other);
+    assert!(engine.create_shader(PipelineKind::Geometry, b"vertex", b"fragment").unwrap() != other);
+    assert_eq!(engine.shaders.len(), 3);
+
+}
+
+#[test]
+fn identical_textures_batch_into_one_draw() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(16, 16));
+
+    let mut sprites: Vec<Sprite> = Vec::new();
+    for _ in 0..4 {
+        let mut sprite = Sprite::from_texture(Box::new(texture()));
+        engine.load(&mut sprite).unwrap();
+        sprites.push(sprite);
+    }
+    assert_eq!(engine.textures.len(), 1);
+
+    let mut batch = SpriteBatch::new();
+    engine.load(&mut batch).unwrap();
+    for sprite in sprites.iter_mut() {
+        batch.add(sprite, &mut engine).unwrap();
+    }
+    engine.render(&mut batch);
+    assert_eq!(batch.get_draw_count(), 1);
+
+    // The upload is kept until its last sprite is destroyed.
+    for sprite in sprites.iter_mut() {
+        engine.destroy(sprite);
+    }
+    assert_eq!(engine.textures.len(), 0);
+
+}
+
+#[test]
+fn textures_with_the_same_key_are_compared() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(16, 16));
+
+    // Only some pixels of large textures are hashed, so these two only differ in pixels that are not.
+    let mut data = vec![255; 64 * 64 * 4];
+    let first = Texture::from_data(&data, 64, 64);
+    data[4..8].copy_from_slice(&[0, 0, 0, 255]);
+    let second = Texture::from_data(&data, 64, 64);
+
+    let a = engine.create_texture(&first).unwrap();
+    let b = engine.create_texture(&second).unwrap();
+    assert!(a != b);
+    assert_eq!(engine.create_texture(&second).unwrap(), b);
+    assert_eq!(engine.textures.len(), 2);
+
+    engine.destroy_texture(b);
+    assert_eq!(engine.textures.len(), 2);
+    engine.destroy_texture(b);
+    engine.destroy_texture(a);
+    assert_eq!(engine.textures.len(), 0);
+
+}
+
+#[test]
+fn loading_reports_errors() {
+
+    match Sprite::from_image_path("tests/missing.png") {
+        Err(Error::Io(_)) => (),
+        _ => panic!("A missing image should be an I/O error."),
+    }
+    match Texture::load_from_image(b"not an image") {
+        Err(Error::Image(_)) => (),
+        _ => panic!("Bad image data should be an image error."),
+    }
+    match text::load_font(b"not a font".to_vec()) {
+        Err(Error::Font(_)) => (),
+        _ => panic!("Bad font data should be a font error."),
+    }
+
+}
+
+#[test]
+fn failed_texture_updates_are_reported() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(16, 16));
+
+    let mut sprite = Sprite::from_texture(Box::new(texture()));
+    engine.load(&mut sprite).unwrap();
+
+    // Two pixels are missing.
+    sprite.set_texture(Box::new(Texture::from_data(&[255, 255, 255, 255], 1, 3)));
+    match sprite.update(&mut engine) {
+        Err(Error::Texture(_)) => (),
+        _ => panic!("A texture without enough pixels should not upload."),
+    }
+
+    // Drawing skips the sprite rather than panicking, and keeps the error.
+    assert!(sprite.take_error().is_none());
+    engine.render(&mut sprite);
+    match sprite.take_error() {
+        Some(Error::Texture(_)) => (),
+        _ => panic!("The failed update while drawing should be kept."),
+    }
+    assert!(sprite.take_error().is_none());
+
+    sprite.set_texture(Box::new(texture()));
+    sprite.update(&mut engine).unwrap();
+
+}
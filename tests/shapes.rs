@@ -0,0 +1,88 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::core::FlatEngine;
+use flat_engine::geometry::*;
+
+fn area(points: &[Vector2f], triangles: &[[usize; 3]]) -> f32 {
+
+    return triangles.iter().map(|t| {
+        let (a, b, c) = (points[t[0]], points[t[1]], points[t[2]]);
+        (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+    }).sum::<f32>() / 2.0;
+
+}
+
+#[test]
+fn triangulate_concave() {
+
+    // An L shape with an area of 3, listed clockwise.
+    let points = [Vector2f::new(0.0, 0.0), Vector2f::new(0.0, 2.0), Vector2f::new(1.0, 2.0), Vector2f::new(1.0, 1.0), Vector2f::new(2.0, 1.0), Vector2f::new(2.0, 0.0)];
+    let triangles = triangulate(&points);
+    assert_eq!(triangles.len(), 4);
+    // Triangles come out counter-clockwise, so none of them has a negative area.
+    assert!(triangles.iter().all(|t| area(&points, &[*t]) > 0.0));
+    assert!((area(&points, &triangles) - 3.0).abs() < 0.0001);
+
+    assert!(triangulate(&points[0..2]).is_empty());
+
+}
+
+#[test]
+fn shapes_are_batched() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(32, 32));
+
+    engine.shapes.fill_rect(Rect::new(0.0, 0.0, 8.0, 8.0), Color::red());
+    assert_eq!(engine.shapes.get_vertex_count(), 6);
+
+    // Two segments and one mitered corner, filled on both sides of the turn.
+    engine.shapes.polyline(&[Vector2f::new(0.0, 0.0), Vector2f::new(8.0, 0.0), Vector2f::new(8.0, 8.0)], 2.0, LineJoin::Miter, LineCap::Butt, Color::red());
+    assert_eq!(engine.shapes.get_vertex_count(), 6 + 12 + 6 + 6);
+
+    // Repeated points are skipped.
+    engine.shapes.line(Vector2f::new(1.0, 1.0), Vector2f::new(1.0, 1.0), 2.0, Color::red());
+    assert_eq!(engine.shapes.get_vertex_count(), 30);
+
+    // Lower tolerances use more segments.
+    engine.shapes.clear();
+    engine.shapes.fill_circle(Vector2f::new(16.0, 16.0), 10.0, Color::green());
+    let coarse = engine.shapes.get_vertex_count();
+    engine.shapes.clear();
+    engine.shapes.tolerance = 0.01;
+    engine.shapes.fill_circle(Vector2f::new(16.0, 16.0), 10.0, Color::green());
+    assert!(engine.shapes.get_vertex_count() > coarse);
+
+    engine.clear(Color::black());
+    engine.draw_shapes().unwrap();
+    assert_eq!(engine.shapes.get_vertex_count(), 0);
+    assert_eq!(engine.shapes.tolerance, 0.01);
+
+    let pixels = engine.read_pixels().unwrap();
+    let center = (16 * 32 + 16) * 4;
+    assert_eq!(&pixels.data[center..center + 4], &[0, 255, 0, 255]);
+
+}
+
+#[test]
+fn translucent_strokes_cover_pixels_once() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(40, 40));
+    let color = Color { r: 1.0, g: 1.0, b: 1.0, a: 0.5 };
+    let points = [Vector2f::new(4.0, 6.0), Vector2f::new(30.0, 6.0), Vector2f::new(30.0, 34.0), Vector2f::new(12.0, 20.0)];
+
+    for &join in [LineJoin::Miter, LineJoin::Bevel, LineJoin::Round].iter() {
+
+        engine.clear(Color::black());
+        engine.shapes.polyline(&points, 6.0, join, LineCap::Butt, color);
+        engine.draw_shapes().unwrap();
+
+        // Every pixel of the stroke is blended once, so none of them is brighter than the rest beyond rounding.
+        let pixels = engine.read_pixels().unwrap();
+        let covered: Vec<u8> = pixels.data.chunks(4).map(|p| p[0]).filter(|&r| r > 0).collect();
+        assert!(!covered.is_empty());
+        assert!(covered.iter().all(|&r| (r as i32 - covered[0] as i32).abs() <= 1), "{:?} join: {:?}", join, covered.iter().max());
+
+    }
+
+}
@@ -0,0 +1,152 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::animation::PlayMode;
+use flat_engine::core::FlatEngine;
+use flat_engine::node::*;
+use flat_engine::render::Texture;
+use flat_engine::sheet::*;
+
+fn image() -> Texture {
+    return Texture::from_data(&[255; 64 * 32 * 4], 64, 32);
+}
+
+const TEXTURE_PACKER_HASH: &'static str = r#"{
+    "frames": {
+        "idle.png": {
+            "frame": {"x": 0, "y": 0, "w": 16, "h": 16},
+            "rotated": false,
+            "trimmed": false,
+            "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16},
+            "sourceSize": {"w": 16, "h": 16},
+            "pivot": {"x": 0.5, "y": 0.5}
+        },
+        "jump.png": {
+            "frame": {"x": 16, "y": 0, "w": 10, "h": 12},
+            "rotated": false,
+            "trimmed": true,
+            "spriteSourceSize": {"x": 2, "y": 4, "w": 10, "h": 12},
+            "sourceSize": {"w": 16, "h": 16},
+            "pivot": {"x": 0.5, "y": 1.0}
+        }
+    },
+    "meta": {"image": "sheet.png", "size": {"w": 64, "h": 32}}
+}"#;
+
+const ASEPRITE_ARRAY: &'static str = r##"{
+    "frames": [
+        {"filename": "run 0.aseprite", "frame": {"x": 0, "y": 0, "w": 16, "h": 16}, "rotated": false, "trimmed": false, "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16}, "sourceSize": {"w": 16, "h": 16}, "duration": 100},
+        {"filename": "run 1.aseprite", "frame": {"x": 16, "y": 0, "w": 16, "h": 16}, "rotated": false, "trimmed": false, "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16}, "sourceSize": {"w": 16, "h": 16}, "duration": 150},
+        {"filename": "run 2.aseprite", "frame": {"x": 32, "y": 0, "w": 16, "h": 16}, "rotated": false, "trimmed": false, "spriteSourceSize": {"x": 0, "y": 0, "w": 16, "h": 16}, "sourceSize": {"w": 16, "h": 16}, "duration": 200}
+    ],
+    "meta": {
+        "app": "http://www.aseprite.org/",
+        "image": "run.png",
+        "size": {"w": 64, "h": 32},
+        "frameTags": [
+            {"name": "run", "from": 0, "to": 2, "direction": "forward"},
+            {"name": "back", "from": 1, "to": 2, "direction": "reverse", "repeat": "1"},
+            {"name": "sway", "from": 0, "to": 1, "direction": "pingpong"},
+            {"name": "hop", "from": 1, "to": 2, "direction": "forward", "repeat": "3"},
+            {"name": "wave", "from": 0, "to": 2, "direction": "pingpong", "repeat": "3"}
+        ],
+        "slices": [
+            {"name": "feet", "color": "#0000ffff", "keys": [
+                {"frame": 2, "bounds": {"x": 0, "y": 0, "w": 16, "h": 16}, "pivot": {"x": 8, "y": 8}},
+                {"frame": 0, "bounds": {"x": 0, "y": 0, "w": 16, "h": 16}, "pivot": {"x": 8, "y": 16}}
+            ]},
+            {"name": "head", "color": "#ff0000ff", "keys": [{"frame": 0, "bounds": {"x": 0, "y": 0, "w": 16, "h": 16}, "pivot": {"x": 0, "y": 0}}]}
+        ]
+    }
+}"##;
+
+#[test]
+fn texture_packer_hash() {
+
+    let sheet = SpriteSheet::load_from_str(TEXTURE_PACKER_HASH, image()).unwrap();
+    assert_eq!(sheet.frames.len(), 2);
+    assert!(sheet.tags.is_empty());
+
+    let idle = sheet.get_frame("idle.png").unwrap();
+    assert_eq!(idle.region.uv, Rect::new(0.0, 0.0, 0.25, 0.5));
+    assert_eq!(idle.get_origin(), Vector2f::new(8.0, 8.0));
+    assert_eq!(idle.duration, 0.1);
+
+    // The trimmed image starts 2 pixels right and 4 pixels down of the 16x16 frame, whose bottom center is the pivot.
+    let jump = sheet.get_frame("jump.png").unwrap();
+    assert_eq!(jump.region.rect, Rect::new(16.0, 0.0, 10.0, 12.0));
+    assert_eq!(jump.trim_offset, Vector2f::new(2.0, 4.0));
+    assert_eq!(jump.get_origin(), Vector2f::new(6.0, 0.0));
+
+}
+
+#[test]
+fn aseprite_array() {
+
+    let sheet = SpriteSheet::load_from_str(ASEPRITE_ARRAY, image()).unwrap();
+    let names: Vec<&str> = sheet.frames.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, vec!["run 0.aseprite", "run 1.aseprite", "run 2.aseprite"]);
+    assert_eq!(sheet.frames[1].duration, 0.15);
+
+    // The first slice puts the pivot at the bottom center of the frames, up to the key that moves it to the center.
+    assert_eq!(sheet.frames[1].pivot, Vector2f::new(0.5, 1.0));
+    assert_eq!(sheet.frames[1].get_origin(), Vector2f::new(8.0, 0.0));
+    assert_eq!(sheet.frames[2].pivot, Vector2f::new(0.5, 0.5));
+
+    let run = sheet.create_clip("run").unwrap();
+    assert_eq!(run.mode, PlayMode::Loop);
+    assert_eq!(run.frames.iter().map(|f| f.duration).collect::<Vec<f32>>(), vec![0.1, 0.15, 0.2]);
+    assert_eq!(run.frames[0].origin, Some(Vector2f::new(8.0, 0.0)));
+
+    let back = sheet.create_clip("back").unwrap();
+    assert_eq!(back.mode, PlayMode::Once);
+    assert_eq!(back.frames[0].region, sheet.frames[2].region);
+
+    assert_eq!(sheet.create_clip("sway").unwrap().mode, PlayMode::PingPong);
+
+    // Limited repeats play the frames that many times, then stop.
+    let regions = |clip: &str| sheet.create_clip(clip).unwrap().frames.iter().map(|f| sheet.frames.iter().position(|s| s.region == f.region).unwrap()).collect::<Vec<usize>>();
+    assert_eq!(sheet.create_clip("hop").unwrap().mode, PlayMode::Once);
+    assert_eq!(regions("hop"), vec![1, 2, 1, 2, 1, 2]);
+    assert_eq!(sheet.create_clip("wave").unwrap().mode, PlayMode::Once);
+    assert_eq!(regions("wave"), vec![0, 1, 2, 1, 0, 1, 2]);
+    assert!(sheet.create_clip("missing").is_none());
+
+}
+
+#[test]
+fn sprites_from_sheet() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(32, 32));
+
+    let mut sheet = SpriteSheet::load_from_str(ASEPRITE_ARRAY, image()).unwrap();
+    assert!(sheet.create_sprite("run 0.aseprite").is_err());
+    sheet.upload(&mut engine).unwrap();
+
+    let sprite = sheet.create_sprite("run 1.aseprite").unwrap();
+    assert_eq!(sprite.get_origin(), Vector2f::new(8.0, 0.0));
+    assert_eq!(sprite.uv_rect, sheet.frames[1].region.uv);
+
+    let mut animated = sheet.create_animated_sprite().unwrap();
+    assert!(animated.play("run"));
+    engine.load(&mut animated).unwrap();
+    animated.update(0.1);
+    assert_eq!(animated.sprite.uv_rect, sheet.frames[1].region.uv);
+
+}
+
+#[test]
+fn invalid_sheets() {
+
+    let rotated = r#"{"frames": {"a": {"frame": {"x": 0, "y": 0, "w": 4, "h": 4}, "rotated": true}}}"#;
+    assert!(SpriteSheet::load_from_str(rotated, image()).is_err());
+
+    let outside = r#"{"frames": {"a": {"frame": {"x": 60, "y": 0, "w": 8, "h": 4}}}}"#;
+    assert!(SpriteSheet::load_from_str(outside, image()).is_err());
+
+    let bad_tag = r#"{"frames": [{"frame": {"x": 0, "y": 0, "w": 4, "h": 4}}], "meta": {"frameTags": [{"name": "t", "from": 0, "to": 3}]}}"#;
+    assert!(SpriteSheet::load_from_str(bad_tag, image()).is_err());
+
+    assert!(SpriteSheet::load_from_str("not json", image()).is_err());
+
+}
@@ -0,0 +1,529 @@
+extern crate flat_engine;
+
+mod common;
+
+use flat_engine::*;
+use flat_engine::core::FlatEngine;
+use flat_engine::node::*;
+use flat_engine::render::Texture;
+use flat_engine::text::*;
+
+/**
+Every character advances 6 pixels at a size of 10, lines are 10 pixels apart.
+*/
+fn font() -> Font<'static> {
+    return load_font(common::box_font("abcdefghijklmnopqrstuvwxyz ", 600)).unwrap();
+}
+
+fn face() -> FontFace<'static> {
+    return FontFace::new(font());
+}
+
+fn line_text(layout: &TextLayout, line: usize) -> String {
+    let l = &layout.lines[line];
+    return layout.glyphs[l.start..l.end].iter().map(|g| g.character).collect();
+}
+
+#[test]
+fn newlines_and_wrapping() {
+
+    let face = face();
+
+    let layout = TextLayout::new("ab\ncde\r\n", &face, 10.0, &LayoutOptions::new());
+    assert_eq!(layout.lines.len(), 3);
+    assert_eq!(line_text(&layout, 1), "cde");
+    assert_eq!(layout.lines[1].width, 18.0);
+    assert_eq!(layout.lines[2].start, layout.lines[2].end);
+    assert_eq!(layout.size, Vector2f::new(18.0, 30.0));
+
+    let layout = TextLayout::new("aaa bbb ccc", &face, 10.0, &LayoutOptions::new().with_max_width(45.0));
+    assert_eq!(layout.lines.len(), 2);
+    assert_eq!(line_text(&layout, 0), "aaa bbb");
+    assert_eq!(line_text(&layout, 1), "ccc");
+    assert_eq!(layout.lines[0].width, 42.0);
+    assert_eq!(layout.lines[1].baseline, 18.0);
+
+    // The glyphs still point at their characters in the source text.
+    let c = &layout.glyphs[layout.lines[1].start];
+    assert_eq!((c.index, c.line, c.position), (8, 1, Vector2f::new(0.0, 18.0)));
+
+    // Words wider than a line are broken between characters.
+    let layout = TextLayout::new("abcdefghij", &face, 10.0, &LayoutOptions::new().with_max_width(25.0));
+    let lines: Vec<String> = (0..layout.lines.len()).map(|i| line_text(&layout, i)).collect();
+    assert_eq!(lines, vec!["abcd", "efgh", "ij"]);
+
+    // Widths that cannot be wrapped at leave the text on one line, trailing whitespace included.
+    for &width in [-10.0, std::f32::NAN, std::f32::INFINITY].iter() {
+        let layout = TextLayout::new("aaa bbb  ", &face, 10.0, &LayoutOptions::new().with_max_width(width));
+        assert_eq!(layout.lines.len(), 1);
+        assert!(layout.size.x.is_finite() && layout.size.x >= 0.0);
+    }
+
+}
+
+#[test]
+fn horizontal_alignment() {
+
+    let face = face();
+    let options = LayoutOptions::new().with_max_width(40.0);
+
+    let layout = TextLayout::new("aa bb cc dd", &face, 10.0, &options.with_align(HorizontalAlign::Center));
+    assert_eq!(line_text(&layout, 0), "aa bb");
+    assert_eq!(layout.lines[0].x, 5.0);
+    assert_eq!(layout.glyphs[0].position.x, 5.0);
+
+    let layout = TextLayout::new("aa bb cc dd", &face, 10.0, &options.with_align(HorizontalAlign::Right));
+    assert_eq!(layout.lines[1].x, 10.0);
+
+    // Justified lines fill the width, except the last line of the paragraph.
+    let layout = TextLayout::new("aa bb cc dd", &face, 10.0, &options.with_align(HorizontalAlign::Justify));
+    assert_eq!(layout.glyphs[3].position.x, 28.0);
+    assert_eq!(layout.lines[0].width, 40.0);
+    assert_eq!(layout.lines[1].width, 30.0);
+    assert_eq!(layout.lines[1].x, 0.0);
+
+}
+
+#[test]
+fn vertical_alignment_and_spacing() {
+
+    let face = face();
+    let options = LayoutOptions::new().with_height(100.0).with_vertical_align(VerticalAlign::Middle).with_line_height(1.5).with_letter_spacing(2.0);
+
+    let layout = TextLayout::new("ab\nc", &face, 10.0, &options);
+    assert_eq!(layout.size, Vector2f::new(14.0, 100.0));
+    assert_eq!(layout.lines[0].top, 35.0);
+    assert_eq!(layout.lines[0].width, 14.0);
+    assert_eq!(layout.lines[1].top, 50.0);
+    assert_eq!(layout.lines[1].baseline, 60.5);
+    assert_eq!(layout.glyphs[1].position.x, 8.0);
+    assert_eq!(layout.get_content_rect(), Rect::new(0.0, 35.0, 14.0, 30.0));
+
+    let layout = TextLayout::new("ab", &face, 10.0, &options.with_vertical_align(VerticalAlign::Bottom));
+    assert_eq!(layout.lines[0].top, 85.0);
+
+}
+
+#[test]
+fn text_is_sized_to_its_content() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(50, 20));
+    let mut text = Text::new("ab\ncd", font(), 10.0, Color::white()).unwrap();
+    assert_eq!(text.get_fixed_size(), Vector2f::new(12.0, 20.0));
+    assert_eq!(text.get_lines().len(), 2);
+
+    // The box of the first character spans x 1..5 and the 7 pixels above the baseline at y 8.
+    let alpha = |engine: &mut FlatEngine, text: &mut Text, x: usize, y: usize| {
+        engine.clear(Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 });
+        engine.render(text);
+        return engine.read_pixels().unwrap().data[(y * 50 + x) * 4 + 3];
+    };
+
+    engine.load(&mut text).unwrap();
+    assert_eq!(alpha(&mut engine, &mut text, 3, 4), 255);
+    assert_eq!(alpha(&mut engine, &mut text, 0, 4), 0);
+    assert_eq!(alpha(&mut engine, &mut text, 3, 9), 0);
+    assert_eq!(alpha(&mut engine, &mut text, 3, 14), 255);
+
+    text.set_options(LayoutOptions::new().with_max_width(50.0).with_align(HorizontalAlign::Right)).unwrap();
+    assert_eq!(text.get_fixed_size(), Vector2f::new(50.0, 20.0));
+    assert_eq!(alpha(&mut engine, &mut text, 41, 4), 255);
+    assert_eq!(alpha(&mut engine, &mut text, 3, 4), 0);
+
+}
+
+#[test]
+fn glyphs_are_rasterized_once() {
+
+    let face = face();
+    let mut cache = GlyphCache::new(64, 64);
+    let layout = TextLayout::new("abab ba", &face, 10.0, &LayoutOptions::new());
+
+    cache.prepare(&face, &layout.glyphs).unwrap();
+    assert_eq!(cache.len(), 2);
+
+    let a = cache.get(face.id, face.font.glyph('a').id(), 10.0).unwrap();
+    assert_eq!(a.offset, Vector2f::new(1.0, -7.0));
+    assert_eq!(a.size, Vector2f::new(4.0, 7.0));
+    assert!(cache.get(face.id, face.font.glyph(' ').id(), 10.0).is_none());
+
+    // Another font id or size are different glyphs.
+    let other = FontFace::new(font());
+    cache.prepare(&other, &TextLayout::new("abab ba", &other, 10.0, &LayoutOptions::new()).glyphs).unwrap();
+    cache.prepare(&face, &TextLayout::new("a", &face, 20.0, &LayoutOptions::new()).glyphs).unwrap();
+    assert_eq!(cache.len(), 5);
+    assert_eq!(cache.get_generation(), 0);
+
+    // Glyphs of fonts the source does not know about cannot be rasterized.
+    assert!(cache.prepare(&face, &TextLayout::new("c", &other, 10.0, &LayoutOptions::new()).glyphs).is_err());
+
+}
+
+#[test]
+fn least_recently_used_rows_are_evicted() {
+
+    let face = face();
+    let glyphs = |text: &str| TextLayout::new(text, &face, 10.0, &LayoutOptions::new()).glyphs;
+    let cached = |cache: &GlyphCache, c: char| cache.get(face.id, face.font.glyph(c).id(), 10.0).is_some();
+
+    // Glyphs take 6x9 pixels with their border, so the cache has two rows of three glyphs.
+    let mut cache = GlyphCache::new(20, 24);
+    cache.prepare(&face, &glyphs("abc")).unwrap();
+    cache.prepare(&face, &glyphs("def")).unwrap();
+    cache.prepare(&face, &glyphs("a")).unwrap();
+    assert_eq!(cache.get_generation(), 0);
+
+    cache.prepare(&face, &glyphs("g")).unwrap();
+    assert_eq!(cache.get_generation(), 1);
+    assert!(cached(&cache, 'a') && cached(&cache, 'g'));
+    assert!(!cached(&cache, 'd'));
+
+    // Rows used by the same layout are never evicted for it.
+    assert!(cache.prepare(&face, &glyphs("abcdefg")).is_err());
+
+}
+
+#[test]
+fn changing_text_reuses_cached_glyphs() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(32, 16));
+    let mut text = Text::new("ab", font(), 10.0, Color::white()).unwrap();
+    engine.load(&mut text).unwrap();
+    engine.render(&mut text);
+    assert_eq!(engine.glyphs.len(), 2);
+
+    text.set_text(String::from("ba ab")).unwrap();
+    engine.render(&mut text);
+    assert_eq!(engine.glyphs.len(), 2);
+    assert_eq!(text.vertices.len(), 4 * 6);
+
+}
+
+#[test]
+fn glyphs_that_cannot_be_cached_skip_the_text() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(32, 16));
+    let mut text = Text::new("ab", font(), 10.0, Color::white()).unwrap();
+    engine.load(&mut text).unwrap();
+    text.update(&mut engine).unwrap();
+
+    // The new glyphs are larger than the whole cache.
+    engine.glyphs = GlyphCache::new(4, 4);
+    text.set_text(String::from("ba")).unwrap();
+    assert!(text.update(&mut engine).is_err());
+
+    // Drawing skips the text instead of panicking.
+    engine.render(&mut text);
+
+}
+
+/**
+The bold face advances 8 pixels at a size of 10.
+*/
+fn family() -> FontFamily<'static> {
+    return FontFamily::new(font()).with_bold(load_font(common::box_font("abcdefghijklmnopqrstuvwxyz ", 800)).unwrap());
+}
+
+#[test]
+fn markup_is_parsed_into_runs() {
+
+    let base = TextStyle::new(10.0, Color::white());
+
+    let runs = parse_markup("a[b]b[i]c[/i][/b][color=red]d[/color][[e [x] [size=20]f", &base).unwrap();
+    let texts: Vec<&str> = runs.iter().map(|r| r.text.as_str()).collect();
+    assert_eq!(texts, vec!["a", "b", "c", "d", "[e [x] ", "f"]);
+    assert_eq!(runs[0].style, base);
+    assert!(runs[1].style.bold && !runs[1].style.italic);
+    assert!(runs[2].style.bold && runs[2].style.italic);
+    assert_eq!(runs[3].style.color, Color::red());
+    assert_eq!(runs[4].style, base);
+    assert_eq!(runs[5].style.size, 20.0);
+
+    let runs = parse_markup("[u][s][img=star][url=shop]x[/url]", &base).unwrap();
+    assert_eq!(runs[0].icon, Some(String::from("star")));
+    assert_eq!(runs[0].text, "\u{FFFC}");
+    assert!(runs[0].style.underline && runs[0].style.strikethrough);
+    assert_eq!(runs[1].style.link, Some(String::from("shop")));
+
+    let runs = parse_markup("[color=#ff000080]x", &base).unwrap();
+    assert_eq!(runs[0].style.color, Color { r: 1.0, g: 0.0, b: 0.0, a: 128.0 / 255.0 });
+
+    for invalid in ["[b]x[/i]", "[/b]", "[b][i][/b][/i]", "[color=nope]x", "[color=#12]x", "[size=-1]x"].iter() {
+        assert!(parse_markup(invalid, &base).is_err(), "{}", invalid);
+    }
+
+}
+
+#[test]
+fn runs_of_different_sizes_share_a_line() {
+
+    let family = family();
+    assert_eq!(family.get_face(true, true).id, family.bold.as_ref().unwrap().id);
+    assert_eq!(family.get_face(false, true).id, family.regular.id);
+
+    // A size of 20 has an ascent of 16 and a descent of 4, so the line is 20 pixels tall.
+    let runs = [
+        LayoutRun { text: "a", face: &family.regular, fallbacks: &[], size: 10.0, image: None },
+        LayoutRun { text: "b", face: &family.regular, fallbacks: &[], size: 20.0, image: None },
+        LayoutRun { text: "c", face: &family.regular, fallbacks: &[], size: 10.0, image: None },
+    ];
+    let layout = TextLayout::from_runs(&runs, &LayoutOptions::new());
+    assert_eq!(layout.size, Vector2f::new(24.0, 20.0));
+    assert_eq!(layout.lines[0].baseline, 16.0);
+    assert_eq!(layout.glyphs.iter().map(|g| g.position.x).collect::<Vec<f32>>(), vec![0.0, 6.0, 18.0]);
+    assert_eq!((layout.glyphs[2].index, layout.glyphs[2].run), (2, 2));
+    assert_eq!(layout.glyphs[0].get_rect(), Rect::new(0.0, 8.0, 6.0, 10.0));
+    assert_eq!(layout.glyphs[1].get_rect(), Rect::new(6.0, 0.0, 12.0, 20.0));
+
+    let text = Text::from_markup("ab[b]ab[/b]", FontCollection::new(family), 10.0, Color::white(), LayoutOptions::new()).unwrap();
+    assert_eq!(text.layout.glyphs.iter().map(|g| g.position.x).collect::<Vec<f32>>(), vec![0.0, 6.0, 12.0, 20.0]);
+    assert_eq!(text.get_fixed_size(), Vector2f::new(28.0, 10.0));
+
+}
+
+#[test]
+fn glyph_rects_and_links() {
+
+    let mut text = Text::from_markup("go [url=shop]shop[/url]\nx", FontCollection::new(family()), 10.0, Color::white(), LayoutOptions::new()).unwrap();
+    assert_eq!(text.get_glyph_count(), 8);
+
+    // Rects are in the node's space, where the first line is on top.
+    assert_eq!(text.get_glyph_rect(3), Some(Rect::new(18.0, 10.0, 6.0, 10.0)));
+    assert_eq!(text.get_glyph_rect(7), Some(Rect::new(0.0, 0.0, 6.0, 10.0)));
+    assert_eq!(text.get_glyph_rect(8), None);
+
+    assert_eq!(text.get_link_at(Vector2f::new(20.0, 15.0)), Some("shop"));
+    assert_eq!(text.get_link_at(Vector2f::new(2.0, 15.0)), None);
+    assert_eq!(text.get_link_at(Vector2f::new(100.0, 15.0)), None);
+
+    text.set_pos(Vector2f::new(100.0, 0.0));
+    assert_eq!(text.get_glyph_at(Vector2f::new(120.0, 15.0)), Some(3));
+    assert_eq!(text.get_link_at(Vector2f::new(120.0, 15.0)), Some("shop"));
+
+    // Invalid markup leaves the text as it was.
+    assert!(text.set_markup(String::from("[b]x[/i]")).is_err());
+    assert_eq!(text.get_glyph_count(), 8);
+
+}
+
+#[test]
+fn visible_glyphs_are_revealed_in_order() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(50, 20));
+    let mut text = Text::new("ab\ncd", font(), 10.0, Color::white()).unwrap();
+    engine.load(&mut text).unwrap();
+
+    let alphas = |engine: &mut FlatEngine, text: &mut Text| {
+        engine.clear(Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 });
+        engine.render(text);
+        let pixels = engine.read_pixels().unwrap();
+        return [(3, 4), (9, 4), (3, 14), (9, 14)].iter().map(|&(x, y)| pixels.data[(y * 50 + x) * 4 + 3]).collect::<Vec<u8>>();
+    };
+
+    text.set_visible_glyphs(Some(3));
+    assert_eq!(alphas(&mut engine, &mut text), vec![255, 255, 255, 0]);
+    text.set_visible_glyphs(Some(0));
+    assert_eq!(alphas(&mut engine, &mut text), vec![0, 0, 0, 0]);
+    text.set_visible_glyphs(None);
+    assert_eq!(alphas(&mut engine, &mut text), vec![255, 255, 255, 255]);
+
+}
+
+#[test]
+fn icons_and_lines_are_drawn() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(50, 20));
+    let mut text = Text::from_markup("[u]ab[/u][img=dot][img=missing]\n[s]c[/s]", FontCollection::new(family()), 10.0, Color::white(), LayoutOptions::new()).unwrap();
+
+    // Unknown icons take up no room.
+    assert_eq!(text.get_fixed_size(), Vector2f::new(12.0, 20.0));
+    text.add_icon("dot", Texture { data: vec![255, 0, 0, 255].repeat(4), dimensions: Vector2::new(2, 2) }).unwrap();
+    assert_eq!(text.get_fixed_size(), Vector2f::new(22.0, 20.0));
+    assert_eq!(text.get_glyph_rect(2), Some(Rect::new(12.0, 10.0, 10.0, 10.0)));
+
+    engine.load(&mut text).unwrap();
+    engine.clear(Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 });
+    engine.render(&mut text);
+    let pixels = engine.read_pixels().unwrap();
+    let pixel = |x: usize, y: usize| pixels.data[(y * 50 + x) * 4..(y * 50 + x) * 4 + 4].to_vec();
+
+    // The underline is one pixel below the baseline, and runs through the gap between the glyphs.
+    assert_eq!(pixel(0, 9), vec![255, 255, 255, 255]);
+    assert_eq!(pixel(6, 9), vec![255, 255, 255, 255]);
+    assert_eq!(pixel(0, 10)[3], 0);
+    assert_eq!(pixel(17, 5), vec![255, 0, 0, 255]);
+    assert_eq!(pixel(17, 9)[3], 255);
+    assert_eq!(pixel(0, 15), vec![255, 255, 255, 255]);
+    assert_eq!(pixel(0, 14)[3], 0);
+
+}
+
+#[test]
+fn missing_glyphs_come_from_fallback_fonts() {
+
+    let wide = load_font(common::box_font("abcdefghijklmnopqrstuvwxyz ", 800)).unwrap();
+    let cjk = load_font(common::box_font("\u{3042}\u{3044}", 1000)).unwrap();
+    let fonts = FontCollection::new(FontFamily::new(font())).with_family("wide", FontFamily::new(wide)).with_fallback(cjk);
+
+    let mut text = Text::from_markup("a\u{3042}b\u{20AC}[font=wide]a[/font][font=unknown]a", fonts, 10.0, Color::white(), LayoutOptions::new()).unwrap();
+    let glyphs = text.layout.glyphs.clone();
+    assert_eq!(glyphs.iter().map(|g| g.position.x).collect::<Vec<f32>>(), vec![0.0, 6.0, 16.0, 22.0, 28.0, 36.0]);
+    assert_eq!(glyphs[1].font, text.fonts.fallbacks[0].id);
+    assert_eq!(glyphs[4].font, text.fonts.families["wide"].regular.id);
+    assert_eq!(glyphs[5].font, text.fonts.default.regular.id);
+
+    // Characters no font has a glyph for show the missing glyph of the run's own font.
+    assert_eq!((glyphs[3].font, glyphs[3].id.0), (text.fonts.default.regular.id, 0));
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 16));
+    engine.load(&mut text).unwrap();
+    engine.render(&mut text);
+    assert_eq!(engine.glyphs.len(), 4);
+
+}
+
+#[test]
+fn kerning_follows_the_direction_of_the_text() {
+
+    let font = load_font(common::kerned_box_font("av\u{5D0}\u{5D1} ", 600, &[('a', 'v', -100), ('\u{5D1}', '\u{5D0}', -200)])).unwrap();
+    let face = FontFace::new(font);
+    let positions = |text: &str| TextLayout::new(text, &face, 10.0, &LayoutOptions::new()).glyphs.iter().map(|g| g.position.x).collect::<Vec<f32>>();
+
+    assert_eq!(positions("av"), vec![0.0, 5.0]);
+    assert_eq!(positions("va"), vec![0.0, 6.0]);
+    // Alef is on the right of bet, which is the kerned pair.
+    assert_eq!(positions("\u{5D0}\u{5D1}"), vec![4.0, 0.0]);
+    assert_eq!(positions("\u{5D1}\u{5D0}"), vec![6.0, 0.0]);
+
+    let layout = TextLayout::new("av av", &face, 10.0, &LayoutOptions::new());
+    assert_eq!(layout.lines[0].width, 28.0);
+
+}
+
+#[test]
+fn right_to_left_text_is_reordered() {
+
+    let face = FontFace::new(load_font(common::box_font("abcd12()\u{5D0}\u{5D1}\u{5D2} ", 600)).unwrap());
+    let layout = |text: &str, direction: TextDirection| TextLayout::new(text, &face, 10.0, &LayoutOptions::new().with_direction(direction));
+    let positions = |layout: &TextLayout| layout.glyphs.iter().map(|g| g.position.x).collect::<Vec<f32>>();
+
+    // Hebrew within English runs right to left, the spaces around it stay in place.
+    let mixed = layout("ab \u{5D0}\u{5D1}\u{5D2} cd", TextDirection::Auto);
+    assert_eq!(positions(&mixed), vec![0.0, 6.0, 12.0, 30.0, 24.0, 18.0, 36.0, 42.0, 48.0]);
+    assert_eq!(mixed.glyphs.iter().map(|g| g.level).collect::<Vec<u8>>(), vec![0, 0, 0, 1, 1, 1, 0, 0, 0]);
+
+    // A paragraph starting with Hebrew is right to left, but numbers in it still read left to right.
+    let hebrew = layout("\u{5D0}\u{5D1} 12", TextDirection::Auto);
+    assert_eq!(positions(&hebrew), vec![24.0, 18.0, 12.0, 0.0, 6.0]);
+    assert_eq!(positions(&layout("ab", TextDirection::RightToLeft)), vec![0.0, 6.0]);
+
+    // Brackets are mirrored in right to left text.
+    let brackets = layout("\u{5D0}(\u{5D1})", TextDirection::Auto);
+    assert_eq!(brackets.glyphs.iter().map(|g| g.character).collect::<String>(), "\u{5D0})\u{5D1}(");
+    assert_eq!(positions(&brackets), vec![18.0, 12.0, 6.0, 0.0]);
+
+    // Wrapping happens in the order of the text, and every line is reordered by itself.
+    let wrapped = TextLayout::new("\u{5D0}\u{5D1} \u{5D2}", &face, 10.0, &LayoutOptions::new().with_max_width(15.0).with_align(HorizontalAlign::Right));
+    assert_eq!(wrapped.lines.len(), 2);
+    assert_eq!(positions(&wrapped), vec![9.0, 3.0, 9.0]);
+
+}
+
+#[test]
+fn arabic_letters_are_joined() {
+
+    // Beh, lam and alef, and the presentation forms of beh and the lam alef ligature.
+    let face = FontFace::new(load_font(common::box_font("\u{628}\u{644}\u{627}\u{FE8F}\u{FE90}\u{FE91}\u{FE92}\u{FEFB}\u{FEFC} ", 600)).unwrap());
+    let shaped = |text: &str| TextLayout::new(text, &face, 10.0, &LayoutOptions::new()).glyphs.iter().map(|g| g.character).collect::<String>();
+
+    assert_eq!(shaped("\u{628}\u{628}\u{628} \u{628}"), "\u{FE91}\u{FE92}\u{FE90} \u{FE8F}");
+    assert_eq!(shaped("\u{644}\u{627}"), "\u{FEFB}");
+    assert_eq!(shaped("\u{628}\u{644}\u{627}"), "\u{FE91}\u{FEFC}");
+
+    // Forms the font has no glyph for are left as they are.
+    assert_eq!(shaped("\u{644}\u{644}\u{628}"), "\u{644}\u{644}\u{FE90}");
+
+    let layout = TextLayout::new("\u{628}\u{644}\u{627}", &face, 10.0, &LayoutOptions::new());
+    assert_eq!(layout.glyphs.iter().map(|g| (g.index, g.position.x)).collect::<Vec<(usize, f32)>>(), vec![(0, 6.0), (2, 0.0)]);
+
+}
+
+#[test]
+fn distance_fields_are_shared_between_sizes() {
+
+    let face = face();
+    let id = face.font.glyph('a').id();
+
+    // At 32 pixels the box of 'a' spans 3.2 to 16 pixels across, and the field is padded by the spread.
+    let (field, offset) = render_distance_field(&face.font, id).unwrap();
+    let alpha = |x: f32, y: f32| field.data[((y - offset.y) as usize * field.dimensions.x as usize + (x - offset.x) as usize) * 4 + 3];
+    assert!(offset.x <= 3.2 - DISTANCE_FIELD_SPREAD && offset.y <= -22.4 - DISTANCE_FIELD_SPREAD);
+    assert!(alpha(9.0, -11.0) > 200);
+    assert_eq!(alpha(offset.x, offset.y), 0);
+    assert!((alpha(3.0, -11.0) as i32 - 128).abs() <= 12);
+    assert!(alpha(1.0, -11.0) < alpha(3.0, -11.0) && alpha(5.0, -11.0) > alpha(3.0, -11.0));
+    assert!(render_distance_field(&face.font, face.font.glyph(' ').id()).is_none());
+
+    let mut cache = GlyphCache::new(256, 256);
+    cache.prepare_distance(&face, &TextLayout::new("ab a", &face, 10.0, &LayoutOptions::new()).glyphs).unwrap();
+    cache.prepare_distance(&face, &TextLayout::new("ba", &face, 40.0, &LayoutOptions::new()).glyphs).unwrap();
+    assert_eq!(cache.len(), 2);
+    let cached = cache.get_distance(face.id, id).unwrap();
+    assert_eq!(cached.size, Vector2f::new(field.dimensions.x as f32, field.dimensions.y as f32));
+    assert_eq!(cache.get(face.id, id, 10.0), None);
+
+}
+
+#[test]
+fn distance_field_text_stays_sharp_when_scaled() {
+
+    // The number of visibly blurred pixels across a glyph scaled up four times.
+    let edge = |rendering: TextRendering| {
+        let mut engine = FlatEngine::headless(Vector2u::new(32, 40));
+        let mut text = Text::new("a", font(), 10.0, Color::white()).unwrap();
+        text.set_rendering(rendering);
+        text.set_scale(Vector2f::new(4.0, 4.0));
+        engine.load(&mut text).unwrap();
+        engine.clear(Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 });
+        engine.render(&mut text);
+        let pixels = engine.read_pixels().unwrap();
+        return (0..32).filter(|x| pixels.data[(20 * 32 + x) * 4 + 3] > 16 && pixels.data[(20 * 32 + x) * 4 + 3] < 240).count();
+    };
+
+    assert!(edge(TextRendering::Bitmap) >= 4);
+    assert!(edge(TextRendering::DistanceField) <= 2);
+
+}
+
+#[test]
+fn outlines_and_shadows_are_drawn_around_the_glyphs() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(40, 30));
+    let mut text = Text::new("a", font(), 20.0, Color::white()).unwrap();
+    text.set_rendering(TextRendering::DistanceField);
+    let effects = TextEffects::new().with_outline(2.0, Color::red()).with_shadow(Vector2f::new(6.0, 4.0), 0.0, Color::blue());
+    text.set_effects(effects);
+    assert_eq!(text.effects, effects);
+
+    engine.load(&mut text).unwrap();
+    engine.clear(Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 });
+    engine.render(&mut text);
+    let pixels = engine.read_pixels().unwrap();
+    let pixel = |x: usize, y: usize| pixels.data[(y * 40 + x) * 4..(y * 40 + x) * 4 + 4].to_vec();
+
+    // The text is at the bottom of the window, where the box of 'a' spans 2 to 10 pixels across and 12 to 26 down.
+    assert_eq!(pixel(6, 18), vec![255, 255, 255, 255]);
+    assert_eq!(pixel(1, 18), vec![255, 0, 0, 255]);
+    // Right above the glyph its edge is blended into the outline.
+    assert!(pixel(6, 11)[0] == 255 && pixel(6, 11)[1] < 64);
+    assert_eq!(pixel(14, 18), vec![0, 0, 255, 255]);
+    assert_eq!(pixel(14, 28), vec![0, 0, 255, 255]);
+    assert_eq!(pixel(20, 18)[3], 0);
+
+    // Glyphs that are not visible have no shadow either.
+    text.set_visible_glyphs(Some(0));
+    engine.clear(Color { r: 0.0, g: 0.0, b: 0.0, a: 0.0 });
+    engine.render(&mut text);
+    assert!(engine.read_pixels().unwrap().data.iter().all(|&b| b == 0));
+
+}
@@ -0,0 +1,363 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::core::FlatEngine;
+use flat_engine::golden;
+use flat_engine::node::*;
+use flat_engine::render::Texture;
+use flat_engine::tiled::*;
+use flat_engine::tilemap::{Tile, TileFlags, Tilemap};
+use std::fs;
+use std::path::PathBuf;
+
+const TERRAIN_TSX: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="terrain" tilewidth="4" tileheight="4" tilecount="4" columns="4">
+ <image source="terrain.png" width="16" height="4"/>
+ <tile id="0" type="water">
+  <properties>
+   <property name="solid" type="bool" value="false"/>
+  </properties>
+  <animation>
+   <frame tileid="0" duration="100"/>
+   <frame tileid="1" duration="100"/>
+  </animation>
+ </tile>
+ <tile id="3">
+  <objectgroup draworder="index">
+   <object id="1" x="0" y="2" width="4" height="2"/>
+  </objectgroup>
+ </tile>
+</tileset>
+"#;
+
+const TERRAIN_TSJ: &'static str = r#"{
+    "name": "terrain", "tilewidth": 4, "tileheight": 4, "tilecount": 4, "columns": 4,
+    "image": "terrain.png", "imagewidth": 16, "imageheight": 4,
+    "tiles": [
+        {"id": 0, "type": "water", "properties": [{"name": "solid", "type": "bool", "value": false}],
+         "animation": [{"tileid": 0, "duration": 100}, {"tileid": 1, "duration": 100}]},
+        {"id": 3, "objectgroup": {"objects": [{"id": 1, "x": 0, "y": 2, "width": 4, "height": 2}]}}
+    ]
+}"#;
+
+const MAP_TMX: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="4" height="3" tilewidth="4" tileheight="4" infinite="0">
+ <properties>
+  <property name="music" value="cave.ogg"/>
+  <property name="gravity" type="float" value="9.5"/>
+ </properties>
+ <tileset firstgid="1" source="terrain.tsx"/>
+ <tileset firstgid="5" name="props" tilewidth="4" tileheight="4" tilecount="1" columns="0">
+  <tile id="0">
+   <image source="crate.png" width="4" height="4"/>
+  </tile>
+ </tileset>
+ <layer id="1" name="csv" width="4" height="3">
+  <data encoding="csv">
+1,2,3,4,
+0,0,0,0,
+2147483650,0,0,5
+</data>
+ </layer>
+ <layer id="2" name="zlib" width="4" height="3">
+  <data encoding="base64" compression="zlib">
+   eJxjZGBgYAJiZiBmYcAEQLkGGJsViAEIdACS
+  </data>
+ </layer>
+ <group id="3" name="details" offsetx="2" offsety="1" visible="0">
+  <layer id="4" name="gzip" width="4" height="3" offsetx="1">
+   <data encoding="base64" compression="gzip">H4sIAAAAAAACA2NkYGBgAmJmIGZhwARAuQYYmxWIAdQ3kZkwAAAA</data>
+  </layer>
+ </group>
+ <objectgroup id="5" name="spawns">
+  <object id="1" name="player" type="spawn" x="2" y="3" width="4" height="2">
+   <properties>
+    <property name="health" type="int" value="3"/>
+    <property name="note">first
+line</property>
+   </properties>
+  </object>
+  <object id="2" name="wall" x="4" y="4" rotation="90">
+   <polygon points="0,0 4,0 4,2"/>
+  </object>
+  <object id="3" name="marker" x="8" y="8">
+   <point/>
+  </object>
+  <object id="4" name="crate" gid="5" x="12" y="12" width="4" height="4"/>
+ </objectgroup>
+ <imagelayer id="6" name="sky" offsetx="3" offsety="2">
+  <image source="crate.png" width="4" height="4"/>
+ </imagelayer>
+</map>
+"#;
+
+const MAP_JSON: &'static str = r#"{
+    "orientation": "orthogonal", "width": 4, "height": 3, "tilewidth": 4, "tileheight": 4, "infinite": false,
+    "properties": [{"name": "music", "type": "string", "value": "cave.ogg"}, {"name": "gravity", "type": "float", "value": 9.5}],
+    "tilesets": [
+        {"firstgid": 1, "source": "terrain.tsj"},
+        {"firstgid": 5, "name": "props", "tilewidth": 4, "tileheight": 4, "tilecount": 1, "columns": 0,
+         "tiles": [{"id": 0, "image": "crate.png", "imagewidth": 4, "imageheight": 4}]}
+    ],
+    "layers": [
+        {"type": "tilelayer", "name": "csv", "width": 4, "height": 3, "data": [1, 2, 3, 4, 0, 0, 0, 0, 2147483650, 0, 0, 5]},
+        {"type": "tilelayer", "name": "base64", "width": 4, "height": 3, "encoding": "base64", "compression": "",
+         "data": "AQAAAAIAAAADAAAABAAAAAAAAAAAAAAAAAAAAAAAAAACAACAAAAAAAAAAAAFAAAA"},
+        {"type": "group", "name": "details", "offsetx": 2, "offsety": 1, "visible": false, "layers": [
+            {"type": "tilelayer", "name": "zlib", "width": 4, "height": 3, "offsetx": 1, "encoding": "base64", "compression": "zlib",
+             "data": "eJxjZGBgYAJiZiBmYcAEQLkGGJsViAEIdACS"}
+        ]},
+        {"type": "objectgroup", "name": "spawns", "objects": [
+            {"id": 1, "name": "player", "type": "spawn", "x": 2, "y": 3, "width": 4, "height": 2,
+             "properties": [{"name": "health", "type": "int", "value": 3}, {"name": "note", "type": "string", "value": "first\nline"}]},
+            {"id": 2, "name": "wall", "x": 4, "y": 4, "rotation": 90, "polygon": [{"x": 0, "y": 0}, {"x": 4, "y": 0}, {"x": 4, "y": 2}]},
+            {"id": 3, "name": "marker", "x": 8, "y": 8, "point": true},
+            {"id": 4, "name": "crate", "gid": 5, "x": 12, "y": 12, "width": 4, "height": 4}
+        ]},
+        {"type": "imagelayer", "name": "sky", "offsetx": 3, "offsety": 2, "image": "crate.png", "imagewidth": 4, "imageheight": 4}
+    ]
+}"#;
+
+/**
+Writes the map files and the tileset images into a fresh directory.
+*/
+fn fixtures(name: &str) -> PathBuf {
+
+    let dir = std::env::temp_dir().join(format!("flat_engine_tiled_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    // Four tiles, red, green, blue and white.
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 255, 255]];
+    let mut data: Vec<u8> = Vec::new();
+    for _ in 0..4 {
+        for x in 0..16 {
+            data.extend_from_slice(&colors[x / 4]);
+        }
+    }
+    golden::save_png(&Texture::from_data(&data, 16, 4), &dir.join("terrain.png")).unwrap();
+    golden::save_png(&Texture::from_data(&[255, 255, 0, 255].iter().cycle().take(64).cloned().collect::<Vec<u8>>(), 4, 4), &dir.join("crate.png")).unwrap();
+
+    fs::write(dir.join("terrain.tsx"), TERRAIN_TSX).unwrap();
+    fs::write(dir.join("terrain.tsj"), TERRAIN_TSJ).unwrap();
+    fs::write(dir.join("map.tmx"), MAP_TMX).unwrap();
+    fs::write(dir.join("map.json"), MAP_JSON).unwrap();
+    return dir;
+
+}
+
+fn tile_gids(map: &TiledMap, name: &str) -> Vec<u32> {
+
+    return match map.get_layer(name) {
+        Some(&TiledLayer::Tiles(ref layer)) => layer.data.clone(),
+        _ => panic!("No tile layer named {}", name),
+    };
+
+}
+
+/**
+Both formats describe the same map, so they are checked the same way.
+*/
+fn check_map(map: &TiledMap, encoded: &[&str]) {
+
+    assert_eq!((map.width, map.height, map.tile_width, map.tile_height), (4, 3, 4, 4));
+    assert_eq!(map.properties.get("music").and_then(|p| p.as_str()), Some("cave.ogg"));
+    assert_eq!(map.properties.get("gravity").and_then(|p| p.as_float()), Some(9.5));
+
+    // Every encoding decodes to the same tiles.
+    let expected = vec![1, 2, 3, 4, 0, 0, 0, 0, 0x80000002, 0, 0, 5];
+    for layer in encoded {
+        assert_eq!(tile_gids(map, layer), expected);
+    }
+    assert_eq!(split_gid(0x80000002), (2, TileFlags { flip_x: true, flip_y: false, flip_diagonal: false }));
+
+    // The group is flattened into its layer.
+    match map.layers[2] {
+        TiledLayer::Tiles(ref layer) => {
+            assert_eq!(layer.offset, Vector2f::new(3.0, 1.0));
+            assert!(!layer.visible);
+        },
+        _ => panic!("Expected the grouped tile layer"),
+    }
+
+    // The external tileset.
+    let terrain = map.get_tileset(3).unwrap();
+    assert_eq!((terrain.name.as_str(), terrain.first_gid, terrain.tile_count), ("terrain", 1, 4));
+    assert!(terrain.image.as_ref().unwrap().source.ends_with("terrain.png"));
+    let water = terrain.get_tile(0).unwrap();
+    assert_eq!(water.kind, "water");
+    assert_eq!(water.properties.get("solid"), Some(&PropertyValue::Bool(false)));
+    assert_eq!(water.animation, vec![(0, 0.1), (1, 0.1)]);
+    assert_eq!(terrain.get_tile(3).unwrap().objects[0].size, Vector2f::new(4.0, 2.0));
+    assert_eq!(map.get_tileset(5).unwrap().name, "props");
+
+    // Objects.
+    let spawns = map.get_object_layer("spawns").unwrap();
+    assert_eq!(spawns.objects.len(), 4);
+    let player = &spawns.objects[0];
+    assert_eq!((player.name.as_str(), player.kind.as_str(), player.shape.clone()), ("player", "spawn", ObjectShape::Rectangle));
+    assert_eq!(player.properties.get("health").and_then(|p| p.as_int()), Some(3));
+    assert_eq!(player.properties.get("note").and_then(|p| p.as_str()), Some("first\nline"));
+    assert_eq!(spawns.objects[1].shape, ObjectShape::Polygon(vec![Vector2f::new(0.0, 0.0), Vector2f::new(4.0, 0.0), Vector2f::new(4.0, 2.0)]));
+    assert_eq!(spawns.objects[2].shape, ObjectShape::Point);
+    assert_eq!(spawns.objects[3].shape, ObjectShape::Tile(5));
+    assert_eq!(map.get_objects().len(), 4);
+
+    // The image layer.
+    match map.get_layer("sky") {
+        Some(&TiledLayer::Image(ref layer)) => {
+            assert_eq!(layer.offset, Vector2f::new(3.0, 2.0));
+            assert!(layer.image.as_ref().unwrap().source.ends_with("crate.png"));
+        },
+        _ => panic!("Expected the image layer"),
+    }
+
+}
+
+#[test]
+fn load_tmx() {
+
+    let dir = fixtures("tmx");
+    let map = TiledMap::load_from_path(&dir.join("map.tmx").to_string_lossy()).unwrap();
+    check_map(&map, &["csv", "zlib", "gzip"]);
+
+}
+
+#[test]
+fn load_json() {
+
+    let dir = fixtures("json");
+    let map = TiledMap::load_from_path(&dir.join("map.json").to_string_lossy()).unwrap();
+    check_map(&map, &["csv", "base64", "zlib"]);
+
+}
+
+#[test]
+fn invalid_maps() {
+
+    assert!(TiledMap::load_from_str(r#"<map width="2" height="2" tilewidth="4" tileheight="4" infinite="1"></map>"#, "").is_err());
+    assert!(TiledMap::load_from_str(r#"<map width="2" height="2" tilewidth="4" tileheight="4"><layer name="a" width="2" height="2"><data encoding="csv">1,2,3</data></layer></map>"#, "").is_err());
+    assert!(TiledMap::load_from_str(r#"{"width": 2, "height": 2, "tilewidth": 4}"#, "").is_err());
+
+    // Tiles without a size would divide by zero when they are cut out of the image.
+    let xml = r#"<map width="2" height="2" tilewidth="4" tileheight="4"><tileset firstgid="1" name="a" tilewidth="0" tileheight="4" spacing="0"/></map>"#;
+    match TiledMap::load_from_str(xml, "") {
+        Err(Error::Parse(message)) => assert!(message.contains("0x4")),
+        _ => panic!("Expected a parse error"),
+    }
+    let json = r#"{"width": 2, "height": 2, "tilewidth": 4, "tileheight": 4, "tilesets": [{"firstgid": 1, "name": "a", "tilewidth": 4, "tileheight": 0}], "layers": []}"#;
+    assert!(TiledMap::load_from_str(json, "").is_err());
+
+}
+
+#[test]
+fn object_coordinates() {
+
+    let dir = fixtures("objects");
+    let map = TiledMap::load_from_path(&dir.join("map.tmx").to_string_lossy()).unwrap();
+    let spawns = map.get_object_layer("spawns").unwrap();
+
+    // The map is 12 pixels high, y is flipped to point up from the bottom left.
+    assert_eq!(map.to_local(Vector2f::new(1.0, 2.0)), Vector2f::new(1.0, 10.0));
+    assert_eq!(map.get_object_rect(&spawns.objects[0]), Rect::new(2.0, 7.0, 4.0, 2.0));
+    // Tile objects are positioned by their bottom left.
+    assert_eq!(map.get_object_rect(&spawns.objects[3]), Rect::new(12.0, 0.0, 4.0, 4.0));
+    assert_eq!(map.get_object_points(&spawns.objects[2]), vec![Vector2f::new(8.0, 4.0)]);
+
+    // Rotated clockwise by 90 degrees around its position.
+    let wall = map.get_object_points(&spawns.objects[1]);
+    let expected = [Vector2f::new(4.0, 8.0), Vector2f::new(4.0, 4.0), Vector2f::new(2.0, 4.0)];
+    for (point, expected) in wall.iter().zip(expected.iter()) {
+        assert!((point.x - expected.x).abs() < 0.001 && (point.y - expected.y).abs() < 0.001, "{:?} != {:?}", point, expected);
+    }
+
+}
+
+#[test]
+fn create_tilemap() {
+
+    let dir = fixtures("tilemap");
+    let map = TiledMap::load_from_path(&dir.join("map.tmx").to_string_lossy()).unwrap();
+
+    // Only the offset of the hidden layer is lost.
+    let warnings = map.get_tilemap_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].contains("gzip"));
+
+    let mut tilemap = map.create_tilemap().unwrap();
+    assert_eq!(tilemap.layers.len(), 3);
+    assert!(!tilemap.layers[2].visible);
+    assert_eq!(tilemap.tileset.get_tile_count(), 5);
+    assert_eq!(tilemap.get_tile(0, 1, 0), Some(Tile::new(1)));
+    assert_eq!(tilemap.get_tile(0, 0, 2), Some(Tile::with_flags(1, TileFlags { flip_x: true, flip_y: false, flip_diagonal: false })));
+    assert_eq!(tilemap.get_tile(0, 3, 2), Some(Tile::new(4)));
+    assert_eq!(tilemap.get_tile(0, 1, 1), None);
+    assert!(tilemap.tileset.animations.contains_key(&0));
+
+    // Tiles cut from the same image are packed apart, with copies of their own edges around them.
+    for id in 0..tilemap.tileset.get_tile_count() as u32 {
+        let region = tilemap.tileset.get_region(id).unwrap();
+        let page = tilemap.tileset.atlas.get_page(region.page).unwrap();
+        let pixel = |x: f32, y: f32| {
+            let index = (y as usize * page.dimensions.x as usize + x as usize) * 4;
+            return &page.data[index..index + 4];
+        };
+        let (right, bottom) = (region.rect.x + region.rect.width - 1.0, region.rect.y + region.rect.height - 1.0);
+        assert_eq!(pixel(region.rect.x - 1.0, region.rect.y), pixel(region.rect.x, region.rect.y));
+        assert_eq!(pixel(right + 1.0, bottom), pixel(right, bottom));
+    }
+
+    // The tile in the bottom right is the crate image.
+    let mut engine = FlatEngine::headless(Vector2u::new(16, 12));
+    engine.load(&mut tilemap).unwrap();
+    engine.clear(Color::black());
+    engine.render(&mut tilemap);
+    let pixels = engine.read_pixels().unwrap();
+    let index = ((12 - 2) * 16 + 14) * 4;
+    assert_eq!(&pixels.data[index..index + 4], &[255, 255, 0, 255]);
+
+    let sky = match map.get_layer("sky") {
+        Some(&TiledLayer::Image(ref layer)) => map.create_image_sprite(layer).unwrap(),
+        _ => panic!("Expected the image layer"),
+    };
+    assert_eq!(sky.get_pos(), Vector2f::new(3.0, 6.0));
+
+}
+
+#[test]
+fn tilesets_span_pages() {
+
+    let dir = fixtures("pages");
+    let crates = (0..5).map(|id| format!(r#"{{"id": {}, "image": "crate.png", "imagewidth": 4, "imageheight": 4}}"#, id)).collect::<Vec<String>>().join(", ");
+    let source = format!(r#"{{
+        "orientation": "orthogonal", "width": 5, "height": 1, "tilewidth": 4, "tileheight": 4, "infinite": false,
+        "tilesets": [{{"firstgid": 1, "name": "crates", "tilewidth": 4, "tileheight": 4, "tilecount": 5, "columns": 0, "tiles": [{}]}}],
+        "layers": [{{"type": "tilelayer", "name": "row", "width": 5, "height": 1, "data": [1, 2, 3, 4, 5]}}]
+    }}"#, crates);
+    let map = TiledMap::load_from_str(&source, &dir.to_string_lossy()).unwrap();
+
+    // Four crates with a pixel of padding fill a 12x12 page, the fifth starts another one.
+    let (tileset, ids) = map.create_tileset_with_page_size(12, 1).unwrap();
+    assert_eq!(tileset.atlas.get_page_count(), 2);
+    assert!(map.create_tileset_with_page_size(5, 1).is_err());
+
+    let mut tilemap = Tilemap::new(tileset, 5, 1, Vector2f::new(4.0, 4.0));
+    let layer = tilemap.add_layer("row");
+    for x in 0..5 {
+        tilemap.set_tile(layer, x, 0, Some(Tile::new(ids[&(x + 1)])));
+    }
+
+    let mut engine = FlatEngine::headless(Vector2u::new(20, 4));
+    engine.load(&mut tilemap).unwrap();
+    engine.clear(Color::black());
+    engine.render(&mut tilemap);
+    let pixels = engine.read_pixels().unwrap();
+    for x in 0..5 {
+        let index = (2 * 20 + x * 4 + 2) * 4;
+        assert_eq!(&pixels.data[index..index + 4], &[255, 255, 0, 255]);
+    }
+
+    // The default pages fit the whole map.
+    assert_eq!(map.create_tileset().unwrap().0.atlas.get_page_count(), 1);
+
+}
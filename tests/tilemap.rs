@@ -0,0 +1,148 @@
+extern crate flat_engine;
+
+use flat_engine::*;
+use flat_engine::core::FlatEngine;
+use flat_engine::node::*;
+use flat_engine::render::Texture;
+use flat_engine::tilemap::*;
+
+/**
+Four 2x2 tiles in a row, each a different shade of gray.
+*/
+fn tileset() -> Tileset {
+
+    let mut data: Vec<u8> = Vec::new();
+    for _ in 0..2 {
+        for x in 0..8 {
+            let shade = (x / 2) as u8 * 60;
+            data.extend_from_slice(&[shade, shade, shade, 255]);
+        }
+    }
+    return Tileset::from_grid(Texture::from_data(&data, 8, 2), Vector2u::new(2, 2), 0, 0, 1).unwrap();
+
+}
+
+#[test]
+fn set_and_get_tiles() {
+
+    let mut map = Tilemap::new(tileset(), 10, 6, Vector2f::new(8.0, 8.0));
+    assert_eq!(map.tileset.get_tile_count(), 4);
+
+    let ground = map.add_layer("ground");
+    let decoration = map.add_layer("decoration");
+    assert_eq!(map.get_layer_index("decoration"), Some(decoration));
+
+    assert!(map.set_tile(ground, 3, 2, Some(Tile::new(1))));
+    assert!(map.set_tile(decoration, 3, 2, Some(Tile::with_flags(2, TileFlags::rotated(1)))));
+    assert!(!map.set_tile(ground, 10, 0, Some(Tile::new(1))));
+    assert!(!map.set_tile(5, 0, 0, Some(Tile::new(1))));
+
+    assert_eq!(map.get_tile(ground, 3, 2), Some(Tile::new(1)));
+    assert_eq!(map.get_tile(decoration, 3, 2).unwrap().flags, TileFlags { flip_x: true, flip_y: false, flip_diagonal: true });
+    assert_eq!(map.get_tile(ground, 4, 2), None);
+
+    map.set_tile(ground, 3, 2, None);
+    assert_eq!(map.get_tile(ground, 3, 2), None);
+
+}
+
+#[test]
+fn tiles_are_padded_with_their_edges() {
+
+    let tileset = tileset();
+    for id in 0..4 {
+        let region = tileset.get_region(id).unwrap();
+        let page = tileset.atlas.get_page(region.page).unwrap();
+        let pixel = |x: f32, y: f32| {
+            let index = (y as usize * page.dimensions.x as usize + x as usize) * 4;
+            return page.data[index];
+        };
+
+        // Filtering at the edges of a tile samples copies of the tile, never its neighbours.
+        let (left, top) = (region.rect.x, region.rect.y);
+        let (right, bottom) = (left + region.rect.width - 1.0, top + region.rect.height - 1.0);
+        let shade = id as u8 * 60;
+        for &(x, y) in [(left - 1.0, top), (right + 1.0, top), (left, top - 1.0), (left, bottom + 1.0), (left - 1.0, top - 1.0), (right + 1.0, bottom + 1.0)].iter() {
+            assert_eq!(pixel(x, y), shade);
+        }
+    }
+
+}
+
+#[test]
+fn world_to_tile() {
+
+    let mut map = Tilemap::new(tileset(), 10, 6, Vector2f::new(8.0, 8.0));
+    map.set_pos(Vector2f::new(100.0, 50.0));
+
+    // Row 0 is the top row, which starts 5 rows above the bottom of the map.
+    assert_eq!(map.world_to_tile(Vector2f::new(101.0, 50.0 + 8.0 * 5.0 + 1.0)), Vector2i::new(0, 0));
+    assert_eq!(map.world_to_tile(Vector2f::new(100.0 + 8.0 * 3.5, 51.0)), Vector2i::new(3, 5));
+    assert_eq!(map.world_to_tile(Vector2f::new(99.0, 51.0)), Vector2i::new(-1, 5));
+
+    assert_eq!(map.tile_to_world(0, 5), Vector2f::new(104.0, 54.0));
+    assert_eq!(map.world_to_tile(map.tile_to_world(7, 1)), Vector2i::new(7, 1));
+
+}
+
+#[test]
+fn only_visible_chunks_are_drawn() {
+
+    let mut engine = FlatEngine::headless(Vector2u::new(64, 64));
+
+    // 4x4 chunks of 4x4 tiles of 4x4 units, so a 64x64 view shows exactly one chunk.
+    let mut map = Tilemap::new(tileset(), 16, 16, Vector2f::new(4.0, 4.0));
+    map.set_chunk_size(4, &mut engine);
+    let layer = map.add_layer("ground");
+    for y in 0..16 {
+        for x in 0..16 {
+            map.set_tile(layer, x, y, Some(Tile::new(0)));
+        }
+    }
+    engine.load(&mut map).unwrap();
+
+    map.set_scale(Vector2f::new(4.0, 4.0));
+    engine.render(&mut map);
+    assert_eq!(map.get_drawn_chunk_count(), 1);
+
+    map.set_scale(Vector2f::new(1.0, 1.0));
+    engine.render(&mut map);
+    assert_eq!(map.get_drawn_chunk_count(), 16);
+
+    // Moved entirely out of view.
+    map.set_pos(Vector2f::new(-200.0, 0.0));
+    engine.render(&mut map);
+    assert_eq!(map.get_drawn_chunk_count(), 0);
+
+}
+
+#[test]
+fn animated_tiles() {
+
+    let mut set = tileset();
+    set.set_animation(0, TileAnimation { frames: vec![(1, 0.5), (3, 0.25)] });
+
+    let animation = set.animations.get(&0).unwrap().clone();
+    assert_eq!(animation.get_tile_at(0.0), Some(1));
+    assert_eq!(animation.get_tile_at(0.6), Some(3));
+    assert_eq!(animation.get_tile_at(0.8), Some(1));
+
+    let mut engine = FlatEngine::headless(Vector2u::new(8, 8));
+    let mut map = Tilemap::new(set, 1, 1, Vector2f::new(8.0, 8.0));
+    let layer = map.add_layer("water");
+    map.set_tile(layer, 0, 0, Some(Tile::new(0)));
+    engine.load(&mut map).unwrap();
+
+    engine.clear(Color::black());
+    engine.render(&mut map);
+    let first = engine.read_pixels().unwrap();
+    let center = (4 * 8 + 4) * 4;
+    assert_eq!(&first.data[center..center + 4], &[60, 60, 60, 255]);
+
+    map.update(0.6);
+    engine.clear(Color::black());
+    engine.render(&mut map);
+    let second = engine.read_pixels().unwrap();
+    assert_eq!(&second.data[center..center + 4], &[180, 180, 180, 255]);
+
+}